@@ -1,15 +1,20 @@
 pub mod borrow;
 pub mod monomorph;
 pub mod ownership;
+pub mod throws;
 pub mod types;
 pub mod validate;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 use oxc_ast::ast::*;
 use oxc_span::Span;
 
 use borrow::{BorrowChecker, BorrowError};
 use monomorph::Monomorphizer;
 use ownership::{FunctionOwnership, OwnershipAnalyzer, OwnershipError};
+use throws::{ThrowsAnalyzer, ThrowsError};
 use types::{LltsType, TypeRegistry, TypeResolver};
 use validate::{ValidationError, Validator};
 
@@ -45,6 +50,7 @@ pub enum AnalysisError {
     Validation(ValidationError),
     Ownership(OwnershipError),
     Borrow(BorrowError),
+    Throws(ThrowsError),
 }
 
 impl std::fmt::Display for AnalysisError {
@@ -53,6 +59,7 @@ impl std::fmt::Display for AnalysisError {
             AnalysisError::Validation(e) => write!(f, "validation: {e}"),
             AnalysisError::Ownership(e) => write!(f, "ownership: {e}"),
             AnalysisError::Borrow(e) => write!(f, "borrow: {e}"),
+            AnalysisError::Throws(e) => write!(f, "throws: {e}"),
         }
     }
 }
@@ -63,6 +70,7 @@ impl AnalysisError {
             AnalysisError::Validation(e) => e.span,
             AnalysisError::Ownership(e) => e.span,
             AnalysisError::Borrow(e) => e.span,
+            AnalysisError::Throws(e) => e.span,
         }
     }
 }
@@ -94,8 +102,9 @@ pub fn analyze(program: &Program<'_>) -> AnalysisResult {
     {
         let mut resolver = TypeResolver::new(&mut registry);
 
-        // First pass: register all top-level type declarations
-        for stmt in &program.body {
+        // First pass: register all top-level type declarations, parents
+        // before the classes that extend them
+        for (_, stmt) in declaration_order(program) {
             match stmt {
                 Statement::TSInterfaceDeclaration(iface) => {
                     resolver.resolve_interface(iface);
@@ -177,6 +186,81 @@ pub fn analyze(program: &Program<'_>) -> AnalysisResult {
     }
 }
 
+/// Check the throws effects of a whole module graph, `programs` in
+/// dependency order and read from `paths`: every call to a function that can
+/// throw must be inside `try`/`catch` or in a function declaring a
+/// `Result<T, E>` return type.
+///
+/// Returns each error with the index of the program it is in.
+pub fn analyze_throws(programs: &[Program<'_>], paths: &[PathBuf]) -> Vec<(usize, AnalysisError)> {
+    let mut analyzer = ThrowsAnalyzer::new();
+    for (file, (program, path)) in programs.iter().zip(paths).enumerate() {
+        analyzer.add_program(file, path, program);
+    }
+    analyzer
+        .finish()
+        .into_iter()
+        .map(|(file, e)| (file, AnalysisError::Throws(e)))
+        .collect()
+}
+
+/// The top-level statements of `program` with their indices, in source
+/// order except that a class declared before the class it `extends` comes
+/// right after it, so a subclass's layout can start with its parent's.
+pub fn declaration_order<'p, 'a>(program: &'p Program<'a>) -> Vec<(usize, &'p Statement<'a>)> {
+    let classes: HashMap<&str, usize> = program
+        .body
+        .iter()
+        .enumerate()
+        .filter_map(|(i, stmt)| Some((declared_class(stmt)?.id.as_ref()?.name.as_str(), i)))
+        .collect();
+
+    fn place<'p, 'a>(
+        i: usize,
+        program: &'p Program<'a>,
+        classes: &HashMap<&str, usize>,
+        placed: &mut [bool],
+        order: &mut Vec<(usize, &'p Statement<'a>)>,
+    ) {
+        if placed[i] {
+            return;
+        }
+        // Marked first, so an `extends` cycle cannot recurse forever
+        placed[i] = true;
+        let parent = declared_class(&program.body[i]).and_then(|class| match class.super_class.as_ref()? {
+            Expression::Identifier(id) => classes.get(id.name.as_str()).copied(),
+            _ => None,
+        });
+        if let Some(parent) = parent {
+            place(parent, program, classes, placed, order);
+        }
+        order.push((i, &program.body[i]));
+    }
+
+    let mut placed = vec![false; program.body.len()];
+    let mut order = Vec::with_capacity(program.body.len());
+    for i in 0..program.body.len() {
+        place(i, program, &classes, &mut placed, &mut order);
+    }
+    order
+}
+
+/// The class a top-level statement declares, exported or not.
+fn declared_class<'p, 'a>(stmt: &'p Statement<'a>) -> Option<&'p Class<'a>> {
+    match stmt {
+        Statement::ClassDeclaration(class) => Some(class),
+        Statement::ExportNamedDeclaration(export) => match &export.declaration {
+            Some(Declaration::ClassDeclaration(class)) => Some(class),
+            _ => None,
+        },
+        Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+            ExportDefaultDeclarationKind::ClassDeclaration(class) => Some(class),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Internal helpers for type registration
 // ---------------------------------------------------------------------------
@@ -235,7 +319,13 @@ fn resolve_class(resolver: &mut TypeResolver<'_>, class: &Class<'_>) {
         })
         .unwrap_or_default();
 
+    // Single inheritance: the parent's fields come first (prefix layout).
     let mut fields = Vec::new();
+    if let Some(Expression::Identifier(parent)) = &class.super_class
+        && let Some(LltsType::Struct(parent_ty)) = resolver.registry.get(parent.name.as_str())
+    {
+        fields.extend(parent_ty.fields.iter().cloned());
+    }
     for element in &class.body.body {
         if let ClassElement::PropertyDefinition(prop) = element {
             if !prop.computed {
@@ -245,12 +335,17 @@ fn resolve_class(resolver: &mut TypeResolver<'_>, class: &Class<'_>) {
                     .as_ref()
                     .map(|ann| resolver.resolve_ts_type(&ann.type_annotation))
                     .unwrap_or(LltsType::Unknown);
-                fields.push(types::StructField {
+                let field = types::StructField {
                     name: field_name,
                     ty,
                     readonly: prop.readonly,
                     optional: prop.r#override, // Classes don't have `optional` on props directly
-                });
+                };
+                // A redeclared parent field keeps its slot.
+                match fields.iter_mut().find(|f| f.name == field.name) {
+                    Some(existing) => *existing = field,
+                    None => fields.push(field),
+                }
             }
         }
     }
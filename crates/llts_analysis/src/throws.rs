@@ -0,0 +1,1038 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use llts_frontend::resolve::ModuleResolver;
+use oxc_ast::ast::*;
+use oxc_span::Span;
+
+// ---------------------------------------------------------------------------
+// Throws errors
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct ThrowsError {
+    pub span: Span,
+    pub kind: ThrowsErrorKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum ThrowsErrorKind {
+    /// A call to a throwing function outside `try`/`catch`, in a function
+    /// that does not declare a `Result<T, E>` return type.
+    UnhandledCall { callee: String, caller: String },
+    /// A method call outside `try`/`catch` whose receiver's class is not
+    /// known, where some method of that name throws.
+    UnresolvedCall { method: String, candidate: String, caller: String },
+    /// A `throw` escaping a closure, whose callers (through a function value)
+    /// cannot receive it.
+    ClosureThrow,
+}
+
+impl std::fmt::Display for ThrowsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ThrowsErrorKind::UnhandledCall { callee, caller } => write!(
+                f,
+                "`{callee}` can throw, but `{caller}` calls it outside `try`/`catch`; \
+                 handle the error or declare a `Result<T, E>` return type on `{caller}`"
+            ),
+            ThrowsErrorKind::UnresolvedCall { method, candidate, caller } => write!(
+                f,
+                "cannot tell which `{method}` `{caller}` calls, and `{candidate}` can throw; \
+                 call it inside `try`/`catch` or give the receiver a class type"
+            ),
+            ThrowsErrorKind::ClosureThrow => write!(
+                f,
+                "a closure cannot throw to its caller; catch the error inside it \
+                 or declare a `Result<T, E>` return type"
+            ),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Throws effects
+// ---------------------------------------------------------------------------
+
+/// A top-level declaration: the index of the file declaring it and its name
+/// there. Methods are named `Class.method`, constructors `Class.constructor`.
+type Decl = (usize, String);
+
+/// Where the uncaught throws of a function go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Propagation {
+    /// Back to its caller, which must handle them.
+    Throws,
+    /// Into its declared `Result<T, E>` return value, as `Err`.
+    Result,
+    /// Nowhere: `main` and top-level code end the program.
+    Entry,
+    /// Nowhere: a closure is called through a function value.
+    Closure,
+}
+
+/// A function named at a call site, resolved once every file is collected.
+#[derive(Debug, Clone)]
+enum Callee {
+    Function(Decl),
+    /// A method of a value of type `class`: the one it inherits, or an
+    /// override in a subclass. `class` may also name an interface.
+    Method { class: Decl, method: String },
+    /// A method of a receiver whose type is not known: any method of that
+    /// name.
+    AnyMethod(String),
+    Constructor(Decl),
+}
+
+/// What the analysis knows about the value of an expression or variable.
+#[derive(Debug, Clone)]
+enum ValueType {
+    /// An instance of a class or interface.
+    Instance(Decl),
+    /// An array of instances.
+    Array(Decl),
+    /// Anything else (a number, a string, an array of them): its methods
+    /// are built in and never throw.
+    Builtin,
+}
+
+/// What one function body can throw out of itself.
+#[derive(Debug)]
+struct FunctionEffects {
+    /// Index of the file in the module graph.
+    file: usize,
+    /// `f`, `Class.method`, `Class.constructor`, or a description for
+    /// closures and top-level code.
+    name: String,
+    propagation: Propagation,
+    /// `throw` statements outside any `try` with a `catch`.
+    throws: Vec<Span>,
+    /// Calls outside any `try` with a `catch`.
+    calls: Vec<(Callee, Span)>,
+}
+
+impl FunctionEffects {
+    fn new(file: usize, name: String, propagation: Propagation) -> Self {
+        Self { file, name, propagation, throws: Vec::new(), calls: Vec::new() }
+    }
+}
+
+/// The walk through one function body.
+struct Scope {
+    /// Class whose method this is, for `this` and `super`.
+    class: Option<Decl>,
+    /// Local variable -> what it holds, if its annotation or initializer
+    /// tells. Locals shadow the file's top-level declarations.
+    locals: HashMap<String, Option<ValueType>>,
+    /// Number of enclosing `try` blocks with a `catch`.
+    handled: usize,
+    effects: FunctionEffects,
+}
+
+impl Scope {
+    fn new(effects: FunctionEffects, class: Option<Decl>, locals: HashMap<String, Option<ValueType>>) -> Self {
+        Self { class, locals, handled: 0, effects }
+    }
+}
+
+/// Globals whose methods are built in, unless a local shadows them.
+const BUILTIN_NAMESPACES: &[&str] = &["console", "Math", "JSON", "Number", "String", "Array", "Promise", "Object"];
+
+// ---------------------------------------------------------------------------
+// Throws analyzer
+// ---------------------------------------------------------------------------
+
+/// Infers which functions of a module graph throw, transitively through
+/// calls, and reports calls to them that nothing handles.
+///
+/// A call is handled inside a `try` with a `catch`, or in a function that
+/// declares a `Result<T, E>` return type, whose uncaught throws become `Err`
+/// values instead. A non-null assertion (`x!`) is not a checked throw.
+///
+/// Files are added in dependency order, so an import names a file added
+/// before it.
+#[derive(Default)]
+pub struct ThrowsAnalyzer {
+    functions: Vec<FunctionEffects>,
+    /// Path of each file added so far, for resolving imports.
+    paths: HashMap<PathBuf, usize>,
+    /// Per file: local name -> the declaration it imports.
+    imports: HashMap<usize, HashMap<String, Decl>>,
+    /// Classes declared in any file.
+    classes: HashSet<Decl>,
+    /// Interfaces declared in any file.
+    interfaces: HashSet<Decl>,
+    /// Top-level variable -> what it holds.
+    globals: HashMap<Decl, ValueType>,
+    /// Class -> the class it extends.
+    class_parents: HashMap<Decl, Decl>,
+    /// Class -> field name -> what the field holds.
+    class_fields: HashMap<Decl, HashMap<String, ValueType>>,
+    /// Function or `Class.method` -> what it returns.
+    returns: HashMap<Decl, ValueType>,
+}
+
+impl ThrowsAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect the functions, methods and closures of file `file`, read
+    /// from `path`.
+    pub fn add_program(&mut self, file: usize, path: &Path, program: &Program<'_>) {
+        self.paths.insert(path.to_path_buf(), file);
+        self.add_imports(file, path, program);
+        self.add_declarations(file, program);
+
+        let effects = FunctionEffects::new(file, "top-level code".to_string(), Propagation::Entry);
+        let mut top = Scope::new(effects, None, HashMap::new());
+        for stmt in &program.body {
+            match stmt {
+                Statement::FunctionDeclaration(func) => self.add_function(file, None, func, None),
+                Statement::ClassDeclaration(class) => self.add_class(file, class),
+                Statement::VariableDeclaration(decl) => self.add_top_level_vars(decl, &mut top),
+                Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                    Some(Declaration::FunctionDeclaration(func)) => self.add_function(file, None, func, None),
+                    Some(Declaration::ClassDeclaration(class)) => self.add_class(file, class),
+                    Some(Declaration::VariableDeclaration(decl)) => self.add_top_level_vars(decl, &mut top),
+                    _ => {}
+                },
+                _ => self.walk_statement(stmt, &mut top),
+            }
+        }
+        self.functions.push(top.effects);
+    }
+
+    /// Consume the analyzer and return the unhandled throws found, with the
+    /// index of the file each is in.
+    pub fn finish(self) -> Vec<(usize, ThrowsError)> {
+        let index: HashMap<Decl, usize> = self
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| matches!(f.propagation, Propagation::Throws | Propagation::Result))
+            .map(|(i, f)| ((f.file, f.name.clone()), i))
+            .collect();
+
+        // A function throws if a `throw` or a call to a throwing function
+        // escapes it, to a fixed point.
+        let mut throwing = vec![false; self.functions.len()];
+        loop {
+            let mut changed = false;
+            for (i, func) in self.functions.iter().enumerate() {
+                if throwing[i] || func.propagation != Propagation::Throws {
+                    continue;
+                }
+                let calls_throwing = func
+                    .calls
+                    .iter()
+                    .any(|(callee, _)| self.resolve(callee, &index).iter().any(|&j| throwing[j]));
+                if !func.throws.is_empty() || calls_throwing {
+                    throwing[i] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut errors = Vec::new();
+        for func in &self.functions {
+            if func.propagation == Propagation::Result {
+                continue;
+            }
+            for (callee, span) in &func.calls {
+                let Some(&j) = self.resolve(callee, &index).iter().find(|&&j| throwing[j]) else {
+                    continue;
+                };
+                let caller = func.name.clone();
+                let kind = match callee {
+                    Callee::AnyMethod(method) => ThrowsErrorKind::UnresolvedCall {
+                        method: method.clone(),
+                        candidate: self.functions[j].name.clone(),
+                        caller,
+                    },
+                    _ => ThrowsErrorKind::UnhandledCall { callee: self.functions[j].name.clone(), caller },
+                };
+                errors.push((func.file, ThrowsError { span: *span, kind }));
+            }
+            if func.propagation == Propagation::Closure {
+                for span in &func.throws {
+                    errors.push((func.file, ThrowsError { span: *span, kind: ThrowsErrorKind::ClosureThrow }));
+                }
+            }
+        }
+        errors
+    }
+
+    /// The functions a call can reach. A method is the one the class has or
+    /// inherits, or an override of it in a subclass; a method of an
+    /// interface, or of an unknown receiver, is any method of that name.
+    fn resolve(&self, callee: &Callee, index: &HashMap<Decl, usize>) -> Vec<usize> {
+        let any_method = |method: &str| -> Vec<usize> {
+            let suffix = format!(".{method}");
+            let mut reached: Vec<usize> = index
+                .iter()
+                .filter(|((_, name), _)| name.ends_with(&suffix))
+                .map(|(_, &i)| i)
+                .collect();
+            // In declaration order, so errors name the same candidate each run.
+            reached.sort_unstable();
+            reached
+        };
+        match callee {
+            Callee::Function(decl) => index.get(decl).copied().into_iter().collect(),
+            Callee::Constructor(class) => self.inherited(class, "constructor", index).into_iter().collect(),
+            Callee::AnyMethod(method) => any_method(method),
+            Callee::Method { class, method } if !self.classes.contains(class) => any_method(method),
+            Callee::Method { class, method } => {
+                let mut reached: Vec<usize> = self.inherited(class, method, index).into_iter().collect();
+                for sub in &self.classes {
+                    if sub != class && self.ancestors(sub).any(|a| a == class) {
+                        reached.extend(index.get(&(sub.0, format!("{}.{method}", sub.1))));
+                    }
+                }
+                reached
+            }
+        }
+    }
+
+    /// `member` of `class`, or of the nearest parent that has one.
+    fn inherited(&self, class: &Decl, member: &str, index: &HashMap<Decl, usize>) -> Option<usize> {
+        std::iter::once(class)
+            .chain(self.ancestors(class))
+            .find_map(|(file, name)| index.get(&(*file, format!("{name}.{member}"))).copied())
+    }
+
+    /// The classes `class` extends, nearest first.
+    fn ancestors<'s>(&'s self, class: &Decl) -> impl Iterator<Item = &'s Decl> + 's {
+        let mut current = self.class_parents.get(class);
+        let mut steps = 0;
+        std::iter::from_fn(move || {
+            // Cyclic `extends` is a type error elsewhere; don't loop on it.
+            steps += 1;
+            let class = current.filter(|_| steps <= self.class_parents.len())?;
+            current = self.class_parents.get(class);
+            Some(class)
+        })
+    }
+
+    // -----------------------------------------------------------------------
+    // Declarations
+    // -----------------------------------------------------------------------
+
+    /// Record the names file `file` imports from files already added.
+    fn add_imports(&mut self, file: usize, path: &Path, program: &Program<'_>) {
+        let resolver = ModuleResolver::new();
+        let mut imports = HashMap::new();
+        for stmt in &program.body {
+            let Statement::ImportDeclaration(import) = stmt else { continue };
+            let Some(from) = resolver
+                .resolve_from_file(path, import.source.value.as_str())
+                .ok()
+                .and_then(|resolved| self.paths.get(&resolved.into_path_buf()).copied())
+            else {
+                continue;
+            };
+            for specifier in import.specifiers.iter().flatten() {
+                if let ImportDeclarationSpecifier::ImportSpecifier(spec) = specifier {
+                    imports.insert(spec.local.name.to_string(), (from, spec.imported.name().to_string()));
+                }
+            }
+        }
+        self.imports.insert(file, imports);
+    }
+
+    /// Record the classes of file `file`, their parents and fields, and
+    /// what its functions, methods and variables hold, before any body is
+    /// walked.
+    fn add_declarations(&mut self, file: usize, program: &Program<'_>) {
+        let declarations: Vec<&Declaration<'_>> = program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::ExportNamedDeclaration(export) => export.declaration.as_ref(),
+                _ => stmt.as_declaration(),
+            })
+            .collect();
+        // Names first, so annotations can name a class declared further down.
+        for decl in &declarations {
+            match decl {
+                Declaration::ClassDeclaration(class) => {
+                    if let Some(id) = &class.id {
+                        self.classes.insert((file, id.name.to_string()));
+                    }
+                }
+                Declaration::TSInterfaceDeclaration(interface) => {
+                    self.interfaces.insert((file, interface.id.name.to_string()));
+                }
+                _ => {}
+            }
+        }
+        for decl in declarations {
+            match decl {
+                Declaration::FunctionDeclaration(func) => {
+                    if let (Some(id), Some(ty)) = (&func.id, self.return_type(file, func.return_type.as_deref())) {
+                        self.returns.insert((file, id.name.to_string()), ty);
+                    }
+                }
+                Declaration::ClassDeclaration(class) => self.add_class_declaration(file, class),
+                Declaration::VariableDeclaration(vars) => {
+                    for declarator in &vars.declarations {
+                        let annotated = declarator
+                            .type_annotation
+                            .as_ref()
+                            .and_then(|ann| self.value_type_of(file, &ann.type_annotation));
+                        let constructed = match &declarator.init {
+                            Some(Expression::NewExpression(new)) => match &new.callee {
+                                Expression::Identifier(id) => {
+                                    Some(ValueType::Instance(self.lookup(file, id.name.as_str())))
+                                }
+                                _ => None,
+                            },
+                            _ => None,
+                        };
+                        if let Some(ty) = annotated.or(constructed) {
+                            self.globals.insert((file, binding_pattern_name(&declarator.id)), ty);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_class_declaration(&mut self, file: usize, class: &Class<'_>) {
+        let Some(id) = &class.id else { return };
+        let decl = (file, id.name.to_string());
+        self.classes.insert(decl.clone());
+        if let Some(Expression::Identifier(parent)) = &class.super_class {
+            let parent = self.lookup(file, parent.name.as_str());
+            self.class_parents.insert(decl.clone(), parent);
+        }
+        let mut fields = HashMap::new();
+        for element in &class.body.body {
+            match element {
+                ClassElement::PropertyDefinition(prop) => {
+                    if let Some(ty) = prop.type_annotation.as_ref().and_then(|ann| self.value_type_of(file, &ann.type_annotation)) {
+                        fields.insert(property_key_name(&prop.key), ty);
+                    }
+                }
+                ClassElement::MethodDefinition(method) => {
+                    if let Some(ty) = self.return_type(file, method.value.return_type.as_deref()) {
+                        let name = format!("{}.{}", decl.1, property_key_name(&method.key));
+                        self.returns.insert((file, name), ty);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.class_fields.insert(decl, fields);
+    }
+
+    /// A top-level function, or a closure if `outer` gives the locals it
+    /// can see.
+    fn add_function(
+        &mut self,
+        file: usize,
+        class: Option<Decl>,
+        func: &Function<'_>,
+        outer: Option<&HashMap<String, Option<ValueType>>>,
+    ) {
+        let name = match &func.id {
+            Some(id) if outer.is_none() => id.name.to_string(),
+            _ => "closure".to_string(),
+        };
+        let propagation = propagation(&name, func.return_type.as_deref(), outer.is_some());
+        let body = func.body.as_ref().map(|b| &b.statements[..]).unwrap_or_default();
+        let locals = outer.cloned().unwrap_or_default();
+        let scope = Scope::new(FunctionEffects::new(file, name, propagation), class, locals);
+        self.add_body(scope, &func.params, body);
+    }
+
+    fn add_arrow(
+        &mut self,
+        file: usize,
+        name: Option<String>,
+        class: Option<Decl>,
+        outer: HashMap<String, Option<ValueType>>,
+        arrow: &ArrowFunctionExpression<'_>,
+    ) {
+        let closure = name.is_none();
+        let name = name.unwrap_or_else(|| "closure".to_string());
+        let propagation = propagation(&name, arrow.return_type.as_deref(), closure);
+        let scope = Scope::new(FunctionEffects::new(file, name, propagation), class, outer);
+        self.add_body(scope, &arrow.params, &arrow.body.statements);
+    }
+
+    /// Walk a body in `scope`, which its parameters join.
+    fn add_body(&mut self, mut scope: Scope, params: &FormalParameters<'_>, body: &[Statement<'_>]) {
+        let file = scope.effects.file;
+        for param in &params.items {
+            let ty = param.type_annotation.as_ref().and_then(|ann| self.value_type_of(file, &ann.type_annotation));
+            scope.locals.insert(binding_pattern_name(&param.pattern), ty);
+        }
+        self.walk_statements(body, &mut scope);
+        self.functions.push(scope.effects);
+    }
+
+    fn add_class(&mut self, file: usize, class: &Class<'_>) {
+        let Some(name) = class.id.as_ref().map(|id| id.name.to_string()) else {
+            return;
+        };
+        for element in &class.body.body {
+            if let ClassElement::MethodDefinition(method) = element {
+                let method_name = match method.kind {
+                    MethodDefinitionKind::Constructor => "constructor".to_string(),
+                    _ => property_key_name(&method.key),
+                };
+                let effects_name = format!("{name}.{method_name}");
+                let propagation = propagation(&effects_name, method.value.return_type.as_deref(), false);
+                let body = method.value.body.as_ref().map(|b| &b.statements[..]).unwrap_or_default();
+                let effects = FunctionEffects::new(file, effects_name, propagation);
+                let scope = Scope::new(effects, Some((file, name.clone())), HashMap::new());
+                self.add_body(scope, &method.value.params, body);
+            }
+        }
+    }
+
+    /// Top-level `const f = () => ...` is a named function; other
+    /// initializers are top-level code.
+    fn add_top_level_vars(&mut self, decl: &VariableDeclaration<'_>, top: &mut Scope) {
+        for declarator in &decl.declarations {
+            match &declarator.init {
+                Some(Expression::ArrowFunctionExpression(arrow)) => {
+                    let name = binding_pattern_name(&declarator.id);
+                    self.add_arrow(top.effects.file, Some(name), None, HashMap::new(), arrow);
+                }
+                _ => self.walk_declarator(declarator, top),
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Statements
+    // -----------------------------------------------------------------------
+
+    fn walk_statements(&mut self, stmts: &[Statement<'_>], scope: &mut Scope) {
+        for stmt in stmts {
+            self.walk_statement(stmt, scope);
+        }
+    }
+
+    fn walk_statement(&mut self, stmt: &Statement<'_>, scope: &mut Scope) {
+        match stmt {
+            Statement::BlockStatement(block) => self.walk_statements(&block.body, scope),
+            Statement::ExpressionStatement(expr_stmt) => self.walk_expression(&expr_stmt.expression, scope),
+            Statement::ReturnStatement(ret) => {
+                if let Some(arg) = &ret.argument {
+                    self.walk_expression(arg, scope);
+                }
+            }
+            Statement::IfStatement(if_stmt) => {
+                self.walk_expression(&if_stmt.test, scope);
+                self.walk_statement(&if_stmt.consequent, scope);
+                if let Some(alt) = &if_stmt.alternate {
+                    self.walk_statement(alt, scope);
+                }
+            }
+            Statement::WhileStatement(while_stmt) => {
+                self.walk_expression(&while_stmt.test, scope);
+                self.walk_statement(&while_stmt.body, scope);
+            }
+            Statement::DoWhileStatement(do_while) => {
+                self.walk_statement(&do_while.body, scope);
+                self.walk_expression(&do_while.test, scope);
+            }
+            Statement::ForStatement(for_stmt) => {
+                match &for_stmt.init {
+                    Some(ForStatementInit::VariableDeclaration(decl)) => self.walk_declaration(decl, scope),
+                    Some(init) => self.walk_expression(init.to_expression(), scope),
+                    None => {}
+                }
+                if let Some(test) = &for_stmt.test {
+                    self.walk_expression(test, scope);
+                }
+                if let Some(update) = &for_stmt.update {
+                    self.walk_expression(update, scope);
+                }
+                self.walk_statement(&for_stmt.body, scope);
+            }
+            Statement::ForOfStatement(for_of) => {
+                self.walk_expression(&for_of.right, scope);
+                // The loop variable holds an element of the array.
+                if let ForStatementLeft::VariableDeclaration(decl) = &for_of.left {
+                    let elem = match self.value_type(&for_of.right, scope) {
+                        Some(ValueType::Array(class)) => Some(ValueType::Instance(class)),
+                        _ => None,
+                    };
+                    for declarator in &decl.declarations {
+                        scope.locals.insert(binding_pattern_name(&declarator.id), elem.clone());
+                    }
+                }
+                self.walk_statement(&for_of.body, scope);
+            }
+            Statement::ForInStatement(for_in) => {
+                self.walk_expression(&for_in.right, scope);
+                self.walk_statement(&for_in.body, scope);
+            }
+            Statement::SwitchStatement(switch) => {
+                self.walk_expression(&switch.discriminant, scope);
+                for case in &switch.cases {
+                    if let Some(test) = &case.test {
+                        self.walk_expression(test, scope);
+                    }
+                    self.walk_statements(&case.consequent, scope);
+                }
+            }
+            Statement::ThrowStatement(throw) => {
+                self.walk_expression(&throw.argument, scope);
+                if scope.handled == 0 {
+                    scope.effects.throws.push(throw.span);
+                }
+            }
+            Statement::TryStatement(try_stmt) => {
+                // Only a `catch` handles the try block's throws.
+                let catches = usize::from(try_stmt.handler.is_some());
+                scope.handled += catches;
+                self.walk_statements(&try_stmt.block.body, scope);
+                scope.handled -= catches;
+                if let Some(handler) = &try_stmt.handler {
+                    self.walk_statements(&handler.body.body, scope);
+                }
+                if let Some(finalizer) = &try_stmt.finalizer {
+                    self.walk_statements(&finalizer.body, scope);
+                }
+            }
+            Statement::LabeledStatement(labeled) => self.walk_statement(&labeled.body, scope),
+            Statement::VariableDeclaration(decl) => self.walk_declaration(decl, scope),
+            // A nested function is a closure value.
+            Statement::FunctionDeclaration(func) => {
+                let locals = scope.locals.clone();
+                self.add_function(scope.effects.file, scope.class.clone(), func, Some(&locals));
+            }
+            Statement::ClassDeclaration(class) => {
+                self.add_class_declaration(scope.effects.file, class);
+                self.add_class(scope.effects.file, class);
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_declaration(&mut self, decl: &VariableDeclaration<'_>, scope: &mut Scope) {
+        for declarator in &decl.declarations {
+            self.walk_declarator(declarator, scope);
+        }
+    }
+
+    /// Walk a declarator's initializer, noting what the variable holds if
+    /// its annotation or initializer tells.
+    fn walk_declarator(&mut self, declarator: &VariableDeclarator<'_>, scope: &mut Scope) {
+        if let Some(init) = &declarator.init {
+            self.walk_expression(init, scope);
+        }
+        let file = scope.effects.file;
+        let annotated = declarator.type_annotation.as_ref().and_then(|ann| self.value_type_of(file, &ann.type_annotation));
+        let inferred = declarator.init.as_ref().and_then(|init| self.value_type(init, scope));
+        scope.locals.insert(binding_pattern_name(&declarator.id), annotated.or(inferred));
+    }
+
+    // -----------------------------------------------------------------------
+    // Expressions
+    // -----------------------------------------------------------------------
+
+    fn walk_expression(&mut self, expr: &Expression<'_>, scope: &mut Scope) {
+        match expr {
+            Expression::CallExpression(call) => self.walk_call(call, scope),
+            Expression::NewExpression(new) => {
+                if let Expression::Identifier(class) = &new.callee {
+                    let class = self.lookup(scope.effects.file, class.name.as_str());
+                    self.note_call(Callee::Constructor(class), new.span, scope);
+                }
+                self.walk_arguments(&new.arguments, scope);
+            }
+            Expression::ChainExpression(chain) => match &chain.expression {
+                ChainElement::CallExpression(call) => self.walk_call(call, scope),
+                ChainElement::StaticMemberExpression(member) => self.walk_expression(&member.object, scope),
+                ChainElement::ComputedMemberExpression(member) => {
+                    self.walk_expression(&member.object, scope);
+                    self.walk_expression(&member.expression, scope);
+                }
+                _ => {}
+            },
+            Expression::StaticMemberExpression(member) => self.walk_expression(&member.object, scope),
+            Expression::ComputedMemberExpression(member) => {
+                self.walk_expression(&member.object, scope);
+                self.walk_expression(&member.expression, scope);
+            }
+            Expression::AwaitExpression(await_expr) => self.walk_expression(&await_expr.argument, scope),
+            Expression::YieldExpression(yield_expr) => {
+                if let Some(arg) = &yield_expr.argument {
+                    self.walk_expression(arg, scope);
+                }
+            }
+            Expression::UnaryExpression(unary) => self.walk_expression(&unary.argument, scope),
+            Expression::UpdateExpression(update) => self.walk_simple_target(&update.argument, scope),
+            Expression::BinaryExpression(binary) => {
+                self.walk_expression(&binary.left, scope);
+                self.walk_expression(&binary.right, scope);
+            }
+            Expression::LogicalExpression(logical) => {
+                self.walk_expression(&logical.left, scope);
+                self.walk_expression(&logical.right, scope);
+            }
+            Expression::ConditionalExpression(cond) => {
+                self.walk_expression(&cond.test, scope);
+                self.walk_expression(&cond.consequent, scope);
+                self.walk_expression(&cond.alternate, scope);
+            }
+            // `a[f()] = g()`: the target's object and index are evaluated too.
+            Expression::AssignmentExpression(assign) => {
+                self.walk_target(&assign.left, scope);
+                self.walk_expression(&assign.right, scope);
+            }
+            Expression::SequenceExpression(seq) => {
+                for e in &seq.expressions {
+                    self.walk_expression(e, scope);
+                }
+            }
+            Expression::TemplateLiteral(tmpl) => {
+                for e in &tmpl.expressions {
+                    self.walk_expression(e, scope);
+                }
+            }
+            Expression::TaggedTemplateExpression(tagged) => {
+                self.walk_expression(&tagged.tag, scope);
+                for e in &tagged.quasi.expressions {
+                    self.walk_expression(e, scope);
+                }
+            }
+            Expression::ArrayExpression(arr) => {
+                for elem in &arr.elements {
+                    match elem {
+                        ArrayExpressionElement::SpreadElement(spread) => self.walk_expression(&spread.argument, scope),
+                        ArrayExpressionElement::Elision(_) => {}
+                        _ => self.walk_expression(elem.to_expression(), scope),
+                    }
+                }
+            }
+            Expression::ObjectExpression(obj) => {
+                for prop in &obj.properties {
+                    match prop {
+                        ObjectPropertyKind::ObjectProperty(p) => self.walk_expression(&p.value, scope),
+                        ObjectPropertyKind::SpreadProperty(spread) => self.walk_expression(&spread.argument, scope),
+                    }
+                }
+            }
+            Expression::ParenthesizedExpression(paren) => self.walk_expression(&paren.expression, scope),
+            Expression::TSAsExpression(as_expr) => self.walk_expression(&as_expr.expression, scope),
+            Expression::TSSatisfiesExpression(satisfies) => self.walk_expression(&satisfies.expression, scope),
+            Expression::TSNonNullExpression(non_null) => self.walk_expression(&non_null.expression, scope),
+            // Closures get their own effects; `this` inside an arrow is the
+            // enclosing method's.
+            Expression::ArrowFunctionExpression(arrow) => {
+                let locals = scope.locals.clone();
+                self.add_arrow(scope.effects.file, None, scope.class.clone(), locals, arrow);
+            }
+            Expression::FunctionExpression(func) => {
+                let locals = scope.locals.clone();
+                self.add_function(scope.effects.file, None, func, Some(&locals));
+            }
+            _ => {}
+        }
+    }
+
+    /// Walk what an assignment target evaluates: the objects and indices of
+    /// member targets, and the defaults of destructuring ones.
+    fn walk_target(&mut self, target: &AssignmentTarget<'_>, scope: &mut Scope) {
+        match target {
+            AssignmentTarget::ArrayAssignmentTarget(array) => {
+                for elem in array.elements.iter().flatten() {
+                    self.walk_target_maybe_default(elem, scope);
+                }
+                if let Some(rest) = &array.rest {
+                    self.walk_target(&rest.target, scope);
+                }
+            }
+            AssignmentTarget::ObjectAssignmentTarget(object) => {
+                for prop in &object.properties {
+                    match prop {
+                        AssignmentTargetProperty::AssignmentTargetPropertyIdentifier(id) => {
+                            if let Some(init) = &id.init {
+                                self.walk_expression(init, scope);
+                            }
+                        }
+                        AssignmentTargetProperty::AssignmentTargetPropertyProperty(prop) => {
+                            if let Some(key) = prop.name.as_expression() {
+                                self.walk_expression(key, scope);
+                            }
+                            self.walk_target_maybe_default(&prop.binding, scope);
+                        }
+                    }
+                }
+                if let Some(rest) = &object.rest {
+                    self.walk_target(&rest.target, scope);
+                }
+            }
+            _ => {
+                if let Some(simple) = target.as_simple_assignment_target() {
+                    self.walk_simple_target(simple, scope);
+                }
+            }
+        }
+    }
+
+    fn walk_target_maybe_default(&mut self, target: &AssignmentTargetMaybeDefault<'_>, scope: &mut Scope) {
+        match target {
+            AssignmentTargetMaybeDefault::AssignmentTargetWithDefault(with_default) => {
+                self.walk_target(&with_default.binding, scope);
+                self.walk_expression(&with_default.init, scope);
+            }
+            _ => {
+                if let Some(target) = target.as_assignment_target() {
+                    self.walk_target(target, scope);
+                }
+            }
+        }
+    }
+
+    fn walk_simple_target(&mut self, target: &SimpleAssignmentTarget<'_>, scope: &mut Scope) {
+        match target {
+            SimpleAssignmentTarget::StaticMemberExpression(member) => self.walk_expression(&member.object, scope),
+            SimpleAssignmentTarget::ComputedMemberExpression(member) => {
+                self.walk_expression(&member.object, scope);
+                self.walk_expression(&member.expression, scope);
+            }
+            SimpleAssignmentTarget::PrivateFieldExpression(field) => self.walk_expression(&field.object, scope),
+            SimpleAssignmentTarget::TSAsExpression(as_expr) => self.walk_expression(&as_expr.expression, scope),
+            SimpleAssignmentTarget::TSSatisfiesExpression(satisfies) => {
+                self.walk_expression(&satisfies.expression, scope)
+            }
+            SimpleAssignmentTarget::TSNonNullExpression(non_null) => self.walk_expression(&non_null.expression, scope),
+            _ => {}
+        }
+    }
+
+    fn walk_call(&mut self, call: &CallExpression<'_>, scope: &mut Scope) {
+        let file = scope.effects.file;
+        let callee = match &call.callee {
+            Expression::Identifier(id) if scope.locals.contains_key(id.name.as_str()) => None,
+            Expression::Identifier(id) => Some(Callee::Function(self.lookup(file, id.name.as_str()))),
+            Expression::Super(_) => self.parent_of(scope).map(Callee::Constructor),
+            Expression::StaticMemberExpression(member) => {
+                let method = member.property.name.to_string();
+                let callee = match &member.object {
+                    Expression::Super(_) => self.parent_of(scope).map(|class| Callee::Method { class, method }),
+                    object => match self.value_type(object, scope) {
+                        Some(ValueType::Instance(class)) => Some(Callee::Method { class, method }),
+                        Some(ValueType::Array(_) | ValueType::Builtin) => None,
+                        None => match object {
+                            // A static method, or a built-in namespace.
+                            Expression::Identifier(id) => {
+                                let class = self.lookup(file, id.name.as_str());
+                                if self.classes.contains(&class) {
+                                    Some(Callee::Method { class, method })
+                                } else if BUILTIN_NAMESPACES.contains(&id.name.as_str()) {
+                                    None
+                                } else {
+                                    Some(Callee::AnyMethod(method))
+                                }
+                            }
+                            _ => Some(Callee::AnyMethod(method)),
+                        },
+                    },
+                };
+                self.walk_expression(&member.object, scope);
+                callee
+            }
+            other => {
+                self.walk_expression(other, scope);
+                None
+            }
+        };
+        if let Some(callee) = callee {
+            self.note_call(callee, call.span, scope);
+        }
+        self.walk_arguments(&call.arguments, scope);
+    }
+
+    fn walk_arguments(&mut self, args: &[Argument<'_>], scope: &mut Scope) {
+        for arg in args {
+            match arg {
+                Argument::SpreadElement(spread) => self.walk_expression(&spread.argument, scope),
+                _ => self.walk_expression(arg.to_expression(), scope),
+            }
+        }
+    }
+
+    fn note_call(&mut self, callee: Callee, span: Span, scope: &mut Scope) {
+        if scope.handled == 0 {
+            scope.effects.calls.push((callee, span));
+        }
+    }
+
+    fn parent_of(&self, scope: &Scope) -> Option<Decl> {
+        scope.class.as_ref().and_then(|class| self.class_parents.get(class)).cloned()
+    }
+
+    // -----------------------------------------------------------------------
+    // Value types
+    // -----------------------------------------------------------------------
+
+    /// What `expr` evaluates to, as far as declarations tell: `this`, locals,
+    /// `new`, fields and array elements of known values, and the results of
+    /// functions and methods with a declared return type.
+    fn value_type(&self, expr: &Expression<'_>, scope: &Scope) -> Option<ValueType> {
+        let file = scope.effects.file;
+        match expr {
+            Expression::ThisExpression(_) => scope.class.clone().map(ValueType::Instance),
+            Expression::Identifier(id) => match scope.locals.get(id.name.as_str()) {
+                Some(local) => local.clone(),
+                None => self.globals.get(&self.lookup(file, id.name.as_str())).cloned(),
+            },
+            Expression::NewExpression(new) => match &new.callee {
+                Expression::Identifier(id) => Some(ValueType::Instance(self.lookup(file, id.name.as_str()))),
+                _ => None,
+            },
+            Expression::StaticMemberExpression(member) => match self.value_type(&member.object, scope)? {
+                ValueType::Instance(class) => {
+                    let field = member.property.name.as_str();
+                    std::iter::once(&class)
+                        .chain(self.ancestors(&class))
+                        .find_map(|c| self.class_fields.get(c).and_then(|fields| fields.get(field)))
+                        .cloned()
+                }
+                _ => None,
+            },
+            Expression::ComputedMemberExpression(member) => match self.value_type(&member.object, scope)? {
+                ValueType::Array(class) => Some(ValueType::Instance(class)),
+                _ => None,
+            },
+            Expression::CallExpression(call) => match &call.callee {
+                Expression::Identifier(id) => self.returns.get(&self.lookup(file, id.name.as_str())).cloned(),
+                Expression::StaticMemberExpression(member) => match self.value_type(&member.object, scope)? {
+                    ValueType::Instance(class) => {
+                        let method = member.property.name.as_str();
+                        std::iter::once(&class)
+                            .chain(self.ancestors(&class))
+                            .find_map(|(f, c)| self.returns.get(&(*f, format!("{c}.{method}"))))
+                            .cloned()
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            Expression::ParenthesizedExpression(paren) => self.value_type(&paren.expression, scope),
+            Expression::TSNonNullExpression(non_null) => self.value_type(&non_null.expression, scope),
+            Expression::TSAsExpression(as_expr) => self.value_type_of(file, &as_expr.type_annotation),
+            Expression::NumericLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::TemplateLiteral(_)
+            | Expression::BinaryExpression(_) => Some(ValueType::Builtin),
+            _ => None,
+        }
+    }
+
+    /// What a value of type annotation `ty` in file `file` holds.
+    fn value_type_of(&self, file: usize, ty: &TSType<'_>) -> Option<ValueType> {
+        match ty {
+            // A class or interface, a numeric type, or an array; type
+            // parameters and aliases are not known.
+            TSType::TSTypeReference(reference) => {
+                let decl = self.lookup(file, class_name_of(&reference.type_name)?);
+                if self.classes.contains(&decl) || self.interfaces.contains(&decl) {
+                    return Some(ValueType::Instance(decl));
+                }
+                match (decl.1.as_str(), reference.type_arguments.as_ref().map(|args| &args.params[..])) {
+                    ("i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "f32" | "f64", None) => {
+                        Some(ValueType::Builtin)
+                    }
+                    ("Array", Some([elem])) => match self.value_type_of(file, elem) {
+                        Some(ValueType::Instance(class)) => Some(ValueType::Array(class)),
+                        other => other.map(|_| ValueType::Builtin),
+                    },
+                    _ => None,
+                }
+            }
+            // `C | null` holds a `C` wherever a method can be called on it.
+            TSType::TSUnionType(union) => {
+                let mut types = union
+                    .types
+                    .iter()
+                    .filter(|ty| !matches!(ty, TSType::TSNullKeyword(_) | TSType::TSUndefinedKeyword(_)));
+                match (types.next(), types.next()) {
+                    (Some(ty), None) => self.value_type_of(file, ty),
+                    _ => None,
+                }
+            }
+            TSType::TSArrayType(array) => match self.value_type_of(file, &array.element_type) {
+                Some(ValueType::Instance(class)) => Some(ValueType::Array(class)),
+                Some(_) => Some(ValueType::Builtin),
+                None => None,
+            },
+            TSType::TSNumberKeyword(_)
+            | TSType::TSStringKeyword(_)
+            | TSType::TSBooleanKeyword(_)
+            | TSType::TSVoidKeyword(_) => Some(ValueType::Builtin),
+            _ => None,
+        }
+    }
+
+    fn return_type(&self, file: usize, ann: Option<&TSTypeAnnotation<'_>>) -> Option<ValueType> {
+        ann.and_then(|ann| self.value_type_of(file, &ann.type_annotation))
+    }
+
+    /// The declaration `name` refers to at the top level of file `file`:
+    /// an import, or the file's own.
+    fn lookup(&self, file: usize, name: &str) -> Decl {
+        self.imports
+            .get(&file)
+            .and_then(|imports| imports.get(name))
+            .cloned()
+            .unwrap_or_else(|| (file, name.to_string()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Where the throws of function `name` go, given its return type.
+fn propagation(name: &str, return_type: Option<&TSTypeAnnotation<'_>>, closure: bool) -> Propagation {
+    let declares_result = matches!(
+        return_type.map(|ann| &ann.type_annotation),
+        Some(TSType::TSTypeReference(ty)) if class_name_of(&ty.type_name) == Some("Result")
+    );
+    if declares_result {
+        Propagation::Result
+    } else if closure {
+        Propagation::Closure
+    } else if name == "main" {
+        Propagation::Entry
+    } else {
+        Propagation::Throws
+    }
+}
+
+fn class_name_of<'a>(name: &'a TSTypeName<'_>) -> Option<&'a str> {
+    match name {
+        TSTypeName::IdentifierReference(id) => Some(id.name.as_str()),
+        _ => None,
+    }
+}
+
+fn binding_pattern_name(pattern: &BindingPattern<'_>) -> String {
+    match pattern {
+        BindingPattern::BindingIdentifier(id) => id.name.to_string(),
+        _ => "_".to_string(),
+    }
+}
+
+fn property_key_name(key: &PropertyKey<'_>) -> String {
+    match key {
+        PropertyKey::StaticIdentifier(id) => id.name.to_string(),
+        _ => "<computed>".to_string(),
+    }
+}
@@ -39,11 +39,12 @@ pub enum ValidationErrorKind {
     ReflectUsage,
     /// Prototype manipulation.
     PrototypeManipulation,
-    /// `async`/`await` not supported in v1.
+    /// `async` arrow, function expression or generator, or `for await` (only
+    /// `async function` declarations and methods).
     AsyncAwait,
-    /// Generator function not supported in v1.
+    /// Generator method or function expression (only `function*` declarations).
     GeneratorFunction,
-    /// `yield` expression not supported in v1.
+    /// `yield*` delegation.
     YieldExpression,
     /// `typeof` on arbitrary value (only known unions allowed).
     ArbitraryTypeof,
@@ -83,13 +84,13 @@ impl std::fmt::Display for ValidationError {
             ValidationErrorKind::PrototypeManipulation => {
                 "prototype manipulation is not allowed in LLTS"
             }
-            ValidationErrorKind::AsyncAwait => "`async`/`await` is not supported in LLTS v1",
-            ValidationErrorKind::GeneratorFunction => {
-                "generator functions are not supported in LLTS v1"
+            ValidationErrorKind::AsyncAwait => {
+                "only `async function` declarations and async methods are supported; async arrows, function expressions and generators, and `for await`, are not"
             }
-            ValidationErrorKind::YieldExpression => {
-                "`yield` expressions are not supported in LLTS v1"
+            ValidationErrorKind::GeneratorFunction => {
+                "generators must be `function*` declarations; generator methods and expressions are not supported"
             }
+            ValidationErrorKind::YieldExpression => "`yield*` delegation is not supported in LLTS",
             ValidationErrorKind::ArbitraryTypeof => {
                 "`typeof` on arbitrary values is not supported; use on known union types only"
             }
@@ -181,6 +182,9 @@ impl Validator {
                 self.validate_statement(&for_stmt.body);
             }
             Statement::ForOfStatement(for_of) => {
+                if for_of.r#await {
+                    self.error(for_of.span, ValidationErrorKind::AsyncAwait);
+                }
                 self.validate_for_in_of_left(&for_of.left);
                 self.validate_expression(&for_of.right);
                 self.validate_statement(&for_of.body);
@@ -282,16 +286,11 @@ impl Validator {
     // -----------------------------------------------------------------------
 
     fn validate_function(&mut self, func: &Function<'_>) {
-        // Reject async
-        if func.r#async {
+        // Reject async generators
+        if func.r#async && func.generator {
             self.error(func.span, ValidationErrorKind::AsyncAwait);
         }
 
-        // Reject generators
-        if func.generator {
-            self.error(func.span, ValidationErrorKind::GeneratorFunction);
-        }
-
         // Check decorators (on function expressions)
         // (Note: function declarations don't have decorators, but we check anyway)
 
@@ -344,6 +343,9 @@ impl Validator {
         for element in &class.body.body {
             match element {
                 ClassElement::MethodDefinition(method) => {
+                    if method.value.generator {
+                        self.error(method.span, ValidationErrorKind::GeneratorFunction);
+                    }
                     self.validate_function(&method.value);
                 }
                 ClassElement::PropertyDefinition(prop) => {
@@ -435,14 +437,19 @@ impl Validator {
                 self.error(ident.span, ValidationErrorKind::ReflectUsage);
             }
 
-            // -- Reject await --
+            // -- await (async functions) --
             Expression::AwaitExpression(await_expr) => {
-                self.error(await_expr.span, ValidationErrorKind::AsyncAwait);
+                self.validate_expression(&await_expr.argument);
             }
 
-            // -- Reject yield --
+            // -- yield (generators); delegation is rejected --
             Expression::YieldExpression(yield_expr) => {
-                self.error(yield_expr.span, ValidationErrorKind::YieldExpression);
+                if yield_expr.delegate {
+                    self.error(yield_expr.span, ValidationErrorKind::YieldExpression);
+                }
+                if let Some(arg) = &yield_expr.argument {
+                    self.validate_expression(arg);
+                }
             }
 
             // -- Call expressions: check for eval, Proxy, Reflect --
@@ -515,6 +522,12 @@ impl Validator {
 
             // -- Function expressions --
             Expression::FunctionExpression(func) => {
+                if func.generator {
+                    self.error(func.span, ValidationErrorKind::GeneratorFunction);
+                }
+                if func.r#async {
+                    self.error(func.span, ValidationErrorKind::AsyncAwait);
+                }
                 self.validate_function(func);
             }
 
@@ -12,6 +12,7 @@ use crate::types::{LltsType, TypeRegistry};
 /// - Direct calls to known functions.
 /// - Method calls (`obj.method()` → `Class_method(obj, args)`).
 /// - Indirect calls through fat pointers (function values).
+/// - Dynamic dispatch through vtables (interface / base-class values).
 /// - Constructor calls (`new Foo()` → `Foo_new(args)`).
 pub struct CallCodegen;
 
@@ -184,4 +185,75 @@ impl CallCodegen {
             .into_struct_value();
         val.into()
     }
+
+    /// Create a dynamically dispatched value from a data pointer and a vtable.
+    pub fn build_dyn_fat_ptr<'ctx>(
+        builder: &Builder<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        data_ptr: PointerValue<'ctx>,
+        vtable_ptr: PointerValue<'ctx>,
+    ) -> BasicValueEnum<'ctx> {
+        let val = registry.dyn_type().get_undef();
+        let val = builder
+            .build_insert_value(val, data_ptr, 0, "dyn_data")
+            .unwrap()
+            .into_struct_value();
+        let val = builder
+            .build_insert_value(val, vtable_ptr, 1, "dyn_vtable")
+            .unwrap()
+            .into_struct_value();
+        val.into()
+    }
+
+    /// Call slot `slot` of a `{ data_ptr, vtable_ptr }` value.
+    ///
+    /// Vtable entries use the closure calling convention with the data
+    /// pointer in place of the env pointer: `fn(data_ptr, args...)`.
+    pub fn build_dyn_call<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        registry: &mut TypeRegistry<'ctx>,
+        dyn_val: BasicValueEnum<'ctx>,
+        slot: u32,
+        args: &[BasicValueEnum<'ctx>],
+        param_types: &[LltsType],
+        ret_type: &LltsType,
+        name: &str,
+    ) -> Option<BasicValueEnum<'ctx>> {
+        let fat = dyn_val.into_struct_value();
+        let data_ptr = builder.build_extract_value(fat, 0, "data_ptr").unwrap();
+        let vtable_ptr = builder
+            .build_extract_value(fat, 1, "vtable_ptr")
+            .unwrap()
+            .into_pointer_value();
+
+        let ptr_ty = context.ptr_type(AddressSpace::default());
+        let slot_idx = context.i64_type().const_int(slot as u64, false);
+        let slot_ptr = unsafe {
+            builder
+                .build_gep(ptr_ty, vtable_ptr, &[slot_idx], "vt_slot")
+                .unwrap()
+        };
+        let fn_ptr = builder
+            .build_load(ptr_ty, slot_ptr, "vt_fn")
+            .unwrap()
+            .into_pointer_value();
+
+        let mut all_param_types: Vec<LltsType> = Vec::with_capacity(param_types.len() + 1);
+        all_param_types.push(LltsType::Ptr); // data_ptr
+        all_param_types.extend_from_slice(param_types);
+        let fn_type = registry.fn_type(&all_param_types, ret_type);
+
+        let mut all_args: Vec<BasicMetadataValueEnum<'ctx>> =
+            Vec::with_capacity(args.len() + 1);
+        all_args.push(data_ptr.into());
+        for a in args {
+            all_args.push((*a).into());
+        }
+
+        let call = builder
+            .build_indirect_call(fn_type, fn_ptr, &all_args, name)
+            .unwrap();
+        call.try_as_basic_value().basic()
+    }
 }
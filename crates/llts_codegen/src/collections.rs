@@ -0,0 +1,1127 @@
+use inkwell::builder::Builder;
+use inkwell::context::Context;
+use inkwell::module::{Linkage, Module};
+use inkwell::types::{BasicType, BasicTypeEnum, FunctionType, StructType};
+use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
+
+use crate::memory::MemoryManager;
+use crate::types::{LltsType, TypeRegistry};
+
+/// Which part of each entry a `keys()` / `values()` / `entries()` snapshot holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectKind {
+    Keys,
+    Values,
+    Entries,
+}
+
+/// Hash tables backing `Map<K, V>` and `Set<T>` (a set is a map whose
+/// values are unused `bool`s).
+///
+/// A table value is a pointer to a heap header, so copies share the table
+/// like JS references do:
+///
+/// ```text
+/// { entries: ptr, len: i64, cap: i64, count: i64, index: ptr, index_cap: i64 }
+/// ```
+///
+/// `entries` is a growable array of `{ hash: i64, live: i1, key: K, value: V }`
+/// in insertion order; deleting only clears `live`, so iteration order is
+/// kept. `index` is an open-addressing table (linear probing, power-of-two
+/// size) of `entry index + 1`, 0 marking an empty slot. When the entries
+/// would fill three quarters of the index, dead entries are compacted away
+/// and the index is rebuilt, doubling if needed.
+///
+/// The table operations are emitted once per key/value type as internal
+/// helper functions (`__llts_map_insert.string.i32`, ...); hashing and
+/// equality are emitted per key type and recurse into struct fields.
+pub struct CollectionCodegen;
+
+impl CollectionCodegen {
+    /// `new Map()` / `new Set()` — allocate an empty table.
+    pub fn build_new<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+    ) -> BasicValueEnum<'ctx> {
+        let header_ty = header_type(context);
+        let size = header_ty.size_of().unwrap();
+        let table = memory.build_heap_alloc(builder, module, size, "table");
+        builder.build_store(table, header_ty.const_zero()).unwrap();
+        table.into()
+    }
+
+    /// `map.set(key, value)` / `set.add(key)` — insert or overwrite.
+    pub fn build_insert<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+        key: BasicValueEnum<'ctx>,
+        value: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+    ) {
+        let mut cx = Cx { builder, context, module, registry, memory };
+        let insert = cx.insert_fn(key_type, value_type);
+        builder
+            .build_call(insert, &[table.into(), key.into(), value.into()], "")
+            .unwrap();
+    }
+
+    /// `map.get(key)` — the value as an `Option<V>`.
+    pub fn build_get<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+        key: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+    ) -> BasicValueEnum<'ctx> {
+        let mut cx = Cx { builder, context, module, registry, memory };
+        let get = cx.get_fn(key_type, value_type);
+        builder
+            .build_call(get, &[table.into(), key.into()], "map_get")
+            .unwrap()
+            .try_as_basic_value()
+            .unwrap_basic()
+    }
+
+    /// `map.has(key)` / `set.has(key)`.
+    pub fn build_has<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+        key: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+    ) -> BasicValueEnum<'ctx> {
+        let mut cx = Cx { builder, context, module, registry, memory };
+        let index = cx.call_lookup(table.into_pointer_value(), key, key_type, value_type);
+        builder
+            .build_int_compare(IntPredicate::SGE, index, context.i64_type().const_zero(), "has")
+            .unwrap()
+            .into()
+    }
+
+    /// `map.delete(key)` / `set.delete(key)` — true if the key was present.
+    pub fn build_delete<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+        key: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+    ) -> BasicValueEnum<'ctx> {
+        let mut cx = Cx { builder, context, module, registry, memory };
+        let delete = cx.delete_fn(key_type, value_type);
+        builder
+            .build_call(delete, &[table.into(), key.into()], "deleted")
+            .unwrap()
+            .try_as_basic_value()
+            .unwrap_basic()
+    }
+
+    /// `map.size` / `set.size` — the number of live entries, as `i64`.
+    pub fn build_size<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        table: BasicValueEnum<'ctx>,
+    ) -> BasicValueEnum<'ctx> {
+        load_header(builder, context, table.into_pointer_value(), COUNT, "size").into()
+    }
+
+    /// `map.clear()` / `set.clear()` — drop every entry, keeping the entry
+    /// buffer for reuse. The index is freed and rebuilt on the next insert.
+    pub fn build_clear<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+    ) {
+        let table = table.into_pointer_value();
+        let zero = context.i64_type().const_zero();
+        let index = load_header_ptr(builder, context, table, INDEX, "old_index");
+        memory.build_heap_free(builder, module, index);
+        store_header(builder, context, table, LEN, zero.into());
+        store_header(builder, context, table, COUNT, zero.into());
+        store_header(builder, context, table, INDEX, context.ptr_type(AddressSpace::default()).const_null().into());
+        store_header(builder, context, table, INDEX_CAP, zero.into());
+    }
+
+    /// `keys()` / `values()` / `entries()` — a fresh array of the live
+    /// entries in insertion order. `elem_type` is the array's element type
+    /// (for entries, a `{ key, value }` struct).
+    pub fn build_collect<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        table: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+        kind: CollectKind,
+        elem_type: &LltsType,
+    ) -> BasicValueEnum<'ctx> {
+        let mut cx = Cx { builder, context, module, registry, memory };
+        let collect = cx.collect_fn(key_type, value_type, kind, elem_type);
+        builder
+            .build_call(collect, &[table.into()], "collected")
+            .unwrap()
+            .try_as_basic_value()
+            .unwrap_basic()
+    }
+}
+
+// Header field indices.
+const ENTRIES: u32 = 0;
+const LEN: u32 = 1;
+const CAP: u32 = 2;
+const COUNT: u32 = 3;
+const INDEX: u32 = 4;
+const INDEX_CAP: u32 = 5;
+
+// Entry field indices.
+const HASH: u32 = 0;
+const LIVE: u32 = 1;
+const KEY: u32 = 2;
+const VALUE: u32 = 3;
+
+/// Initial size of the entry buffer and the index.
+const INITIAL_CAP: u64 = 8;
+
+fn header_type(context: &Context) -> StructType<'_> {
+    let ptr_ty = context.ptr_type(AddressSpace::default()).into();
+    let i64_ty = context.i64_type().into();
+    context.struct_type(&[ptr_ty, i64_ty, i64_ty, i64_ty, ptr_ty, i64_ty], false)
+}
+
+fn load_header<'ctx>(
+    builder: &Builder<'ctx>,
+    context: &'ctx Context,
+    table: PointerValue<'ctx>,
+    field: u32,
+    name: &str,
+) -> IntValue<'ctx> {
+    let field_ptr = builder.build_struct_gep(header_type(context), table, field, name).unwrap();
+    builder
+        .build_load(context.i64_type(), field_ptr, name)
+        .unwrap()
+        .into_int_value()
+}
+
+fn load_header_ptr<'ctx>(
+    builder: &Builder<'ctx>,
+    context: &'ctx Context,
+    table: PointerValue<'ctx>,
+    field: u32,
+    name: &str,
+) -> PointerValue<'ctx> {
+    let field_ptr = builder.build_struct_gep(header_type(context), table, field, name).unwrap();
+    builder
+        .build_load(context.ptr_type(AddressSpace::default()), field_ptr, name)
+        .unwrap()
+        .into_pointer_value()
+}
+
+fn store_header<'ctx>(
+    builder: &Builder<'ctx>,
+    context: &'ctx Context,
+    table: PointerValue<'ctx>,
+    field: u32,
+    value: BasicValueEnum<'ctx>,
+) {
+    let field_ptr = builder.build_struct_gep(header_type(context), table, field, "hdr_field").unwrap();
+    builder.build_store(field_ptr, value).unwrap();
+}
+
+/// A short, symbol-safe name for `ty`, used to key the generated helpers.
+fn type_tag(ty: &LltsType) -> String {
+    match ty {
+        LltsType::I8 => "i8".to_string(),
+        LltsType::I16 => "i16".to_string(),
+        LltsType::I32 => "i32".to_string(),
+        LltsType::I64 => "i64".to_string(),
+        LltsType::U8 => "u8".to_string(),
+        LltsType::U16 => "u16".to_string(),
+        LltsType::U32 => "u32".to_string(),
+        LltsType::U64 => "u64".to_string(),
+        LltsType::F32 => "f32".to_string(),
+        LltsType::F64 => "f64".to_string(),
+        LltsType::Bool => "bool".to_string(),
+        LltsType::Void => "void".to_string(),
+        LltsType::Never => "never".to_string(),
+        LltsType::String => "string".to_string(),
+        LltsType::Ptr => "ptr".to_string(),
+        LltsType::Struct { name, .. } | LltsType::Union { name, .. } | LltsType::Dyn { name } => name.clone(),
+        LltsType::Array(elem) => format!("arr_{}", type_tag(elem)),
+        LltsType::Option(inner) => format!("opt_{}", type_tag(inner)),
+        LltsType::Result { ok, err } => format!("res_{}_{}", type_tag(ok), type_tag(err)),
+        LltsType::Function { .. } => "fn".to_string(),
+        LltsType::Map { key, value } => format!("map_{}_{}", type_tag(key), type_tag(value)),
+        LltsType::Set(elem) => format!("set_{}", type_tag(elem)),
+    }
+}
+
+/// Everything needed to emit helper functions.
+struct Cx<'a, 'ctx> {
+    builder: &'a Builder<'ctx>,
+    context: &'ctx Context,
+    module: &'a Module<'ctx>,
+    registry: &'a mut TypeRegistry<'ctx>,
+    memory: &'a mut MemoryManager<'ctx>,
+}
+
+impl<'ctx> Cx<'_, 'ctx> {
+    /// Return helper `name`, first declaring it as `fn_type` and emitting
+    /// its body with `build` (which receives the function, positioned at its
+    /// entry block). The builder's position is restored afterwards.
+    fn helper(
+        &mut self,
+        name: &str,
+        fn_type: FunctionType<'ctx>,
+        build: impl FnOnce(&mut Self, FunctionValue<'ctx>),
+    ) -> FunctionValue<'ctx> {
+        if let Some(f) = self.module.get_function(name) {
+            return f;
+        }
+        let function = self.module.add_function(name, fn_type, Some(Linkage::Internal));
+        let saved = self.builder.get_insert_block();
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+        build(self, function);
+        if let Some(block) = saved {
+            self.builder.position_at_end(block);
+        }
+        function
+    }
+
+    fn entry_type(&mut self, key_type: &LltsType, value_type: &LltsType) -> StructType<'ctx> {
+        let i64_ty = self.context.i64_type().into();
+        let bool_ty = self.context.bool_type().into();
+        let key_ty = self.registry.llvm_type(key_type);
+        let value_ty = self.registry.llvm_type(value_type);
+        self.context.struct_type(&[i64_ty, bool_ty, key_ty, value_ty], false)
+    }
+
+    fn ptr_type(&self) -> BasicTypeEnum<'ctx> {
+        self.context.ptr_type(AddressSpace::default()).into()
+    }
+
+    fn i64_const(&self, value: u64) -> IntValue<'ctx> {
+        self.context.i64_type().const_int(value, false)
+    }
+
+    /// Pointer to entry `index` of the table's entry buffer.
+    fn entry_ptr(&self, entry_ty: StructType<'ctx>, table: PointerValue<'ctx>, index: IntValue<'ctx>) -> PointerValue<'ctx> {
+        let entries = load_header_ptr(self.builder, self.context, table, ENTRIES, "entries");
+        unsafe { self.builder.build_gep(entry_ty, entries, &[index], "entry").unwrap() }
+    }
+
+    fn load_entry_field(
+        &self,
+        entry_ty: StructType<'ctx>,
+        entry: PointerValue<'ctx>,
+        field: u32,
+        name: &str,
+    ) -> BasicValueEnum<'ctx> {
+        let field_ty = entry_ty.get_field_type_at_index(field).unwrap();
+        let field_ptr = self.builder.build_struct_gep(entry_ty, entry, field, name).unwrap();
+        self.builder.build_load(field_ty, field_ptr, name).unwrap()
+    }
+
+    fn store_entry_field(
+        &self,
+        entry_ty: StructType<'ctx>,
+        entry: PointerValue<'ctx>,
+        field: u32,
+        value: BasicValueEnum<'ctx>,
+    ) {
+        let field_ptr = self.builder.build_struct_gep(entry_ty, entry, field, "entry_field").unwrap();
+        self.builder.build_store(field_ptr, value).unwrap();
+    }
+
+    /// Call the lookup helper: the entry index of `key`, or -1.
+    fn call_lookup(
+        &mut self,
+        table: PointerValue<'ctx>,
+        key: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+        value_type: &LltsType,
+    ) -> IntValue<'ctx> {
+        let hash_fn = self.hash_fn(key_type);
+        let lookup = self.lookup_fn(key_type, value_type);
+        let hash = self
+            .builder
+            .build_call(hash_fn, &[key.into()], "hash")
+            .unwrap()
+            .try_as_basic_value()
+            .unwrap_basic();
+        self.builder
+            .build_call(lookup, &[table.into(), key.into(), hash.into()], "index")
+            .unwrap()
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value()
+    }
+
+    /// Probe the index from `hash` for the first empty slot and store
+    /// `entry_index + 1` there. Leaves the builder in a fresh block.
+    fn build_index_insert(
+        &self,
+        function: FunctionValue<'ctx>,
+        index: PointerValue<'ctx>,
+        mask: IntValue<'ctx>,
+        hash: IntValue<'ctx>,
+        entry_index: IntValue<'ctx>,
+    ) {
+        let i64_ty = self.context.i64_type();
+        let start_bb = self.builder.get_insert_block().unwrap();
+        let probe_bb = self.context.append_basic_block(function, "probe");
+        let next_bb = self.context.append_basic_block(function, "probe_next");
+        let place_bb = self.context.append_basic_block(function, "probe_place");
+        let start = self.builder.build_and(hash, mask, "start").unwrap();
+        self.builder.build_unconditional_branch(probe_bb).unwrap();
+
+        self.builder.position_at_end(probe_bb);
+        let slot = self.builder.build_phi(i64_ty, "slot").unwrap();
+        let slot_val = slot.as_basic_value().into_int_value();
+        let slot_ptr = unsafe { self.builder.build_gep(i64_ty, index, &[slot_val], "slot_ptr").unwrap() };
+        let occupant = self.builder.build_load(i64_ty, slot_ptr, "occupant").unwrap().into_int_value();
+        let empty = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, occupant, i64_ty.const_zero(), "empty")
+            .unwrap();
+        self.builder.build_conditional_branch(empty, place_bb, next_bb).unwrap();
+
+        self.builder.position_at_end(next_bb);
+        let bumped = self.builder.build_int_add(slot_val, self.i64_const(1), "bumped").unwrap();
+        let next = self.builder.build_and(bumped, mask, "next_slot").unwrap();
+        self.builder.build_unconditional_branch(probe_bb).unwrap();
+        slot.add_incoming(&[(&start, start_bb), (&next, next_bb)]);
+
+        self.builder.position_at_end(place_bb);
+        let stored = self.builder.build_int_add(entry_index, self.i64_const(1), "stored").unwrap();
+        self.builder.build_store(slot_ptr, stored).unwrap();
+    }
+
+    // ---- Hashing and equality ----
+
+    /// `__llts_hash.{K}(key) -> i64`.
+    fn hash_fn(&mut self, key_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_hash.{}", type_tag(key_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let fn_type = self.context.i64_type().fn_type(&[key_ty.into()], false);
+        let key_type = key_type.clone();
+        self.helper(&name, fn_type, move |cx, function| {
+            let key = function.get_nth_param(0).unwrap();
+            let hash = cx.build_hash(function, key, &key_type);
+            cx.builder.build_return(Some(&hash)).unwrap();
+        })
+    }
+
+    fn build_hash(&mut self, function: FunctionValue<'ctx>, key: BasicValueEnum<'ctx>, key_type: &LltsType) -> IntValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        match key_type {
+            LltsType::F32 | LltsType::F64 => {
+                // -0.0 hashes like 0.0 and every NaN alike, matching equality.
+                let value = key.into_float_value();
+                let float_ty = value.get_type();
+                let is_zero = self
+                    .builder
+                    .build_float_compare(FloatPredicate::OEQ, value, float_ty.const_zero(), "is_zero")
+                    .unwrap();
+                let is_nan = self
+                    .builder
+                    .build_float_compare(FloatPredicate::UNO, value, value, "is_nan")
+                    .unwrap();
+                let value = self
+                    .builder
+                    .build_select(is_zero, float_ty.const_zero(), value, "canon")
+                    .unwrap()
+                    .into_float_value();
+                let value = self
+                    .builder
+                    .build_select(is_nan, float_ty.const_float(f64::NAN), value, "canon")
+                    .unwrap()
+                    .into_float_value();
+                let bits = if matches!(key_type, LltsType::F32) {
+                    let bits = self
+                        .builder
+                        .build_bit_cast(value, self.context.i32_type(), "bits")
+                        .unwrap()
+                        .into_int_value();
+                    self.builder.build_int_z_extend(bits, i64_ty, "bits64").unwrap()
+                } else {
+                    self.builder.build_bit_cast(value, i64_ty, "bits").unwrap().into_int_value()
+                };
+                self.build_mix(bits)
+            }
+            LltsType::String => {
+                // FNV-1a over the bytes.
+                let s = key.into_struct_value();
+                let data = self.builder.build_extract_value(s, 0, "str_data").unwrap().into_pointer_value();
+                let len = self.builder.build_extract_value(s, 1, "str_len").unwrap().into_int_value();
+                let entry_bb = self.builder.get_insert_block().unwrap();
+                let loop_bb = self.context.append_basic_block(function, "fnv_loop");
+                let body_bb = self.context.append_basic_block(function, "fnv_body");
+                let done_bb = self.context.append_basic_block(function, "fnv_done");
+                self.builder.build_unconditional_branch(loop_bb).unwrap();
+
+                self.builder.position_at_end(loop_bb);
+                let i = self.builder.build_phi(i64_ty, "i").unwrap();
+                let h = self.builder.build_phi(i64_ty, "h").unwrap();
+                let i_val = i.as_basic_value().into_int_value();
+                let h_val = h.as_basic_value().into_int_value();
+                let done = self.builder.build_int_compare(IntPredicate::UGE, i_val, len, "done").unwrap();
+                self.builder.build_conditional_branch(done, done_bb, body_bb).unwrap();
+
+                self.builder.position_at_end(body_bb);
+                let byte_ptr = unsafe {
+                    self.builder
+                        .build_gep(self.context.i8_type(), data, &[i_val], "byte_ptr")
+                        .unwrap()
+                };
+                let byte = self
+                    .builder
+                    .build_load(self.context.i8_type(), byte_ptr, "byte")
+                    .unwrap()
+                    .into_int_value();
+                let byte = self.builder.build_int_z_extend(byte, i64_ty, "byte64").unwrap();
+                let mixed = self.builder.build_xor(h_val, byte, "fnv_xor").unwrap();
+                let next_h = self
+                    .builder
+                    .build_int_mul(mixed, self.i64_const(0x100000001b3), "fnv_mul")
+                    .unwrap();
+                let next_i = self.builder.build_int_add(i_val, self.i64_const(1), "next_i").unwrap();
+                self.builder.build_unconditional_branch(loop_bb).unwrap();
+
+                i.add_incoming(&[(&self.i64_const(0), entry_bb), (&next_i, body_bb)]);
+                h.add_incoming(&[(&self.i64_const(0xcbf29ce484222325), entry_bb), (&next_h, body_bb)]);
+
+                self.builder.position_at_end(done_bb);
+                h_val
+            }
+            LltsType::Struct { fields, .. } => {
+                // h = h * 31 + hash(field), from 17.
+                let s = key.into_struct_value();
+                let mut h = self.i64_const(17);
+                for (i, (_, field_type)) in fields.iter().enumerate() {
+                    let field = self.builder.build_extract_value(s, i as u32, "field").unwrap();
+                    let field_hash = self.hash_fn(field_type);
+                    let fh = self
+                        .builder
+                        .build_call(field_hash, &[field.into()], "field_hash")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .unwrap_basic()
+                        .into_int_value();
+                    let scaled = self.builder.build_int_mul(h, self.i64_const(31), "h31").unwrap();
+                    h = self.builder.build_int_add(scaled, fh, "h").unwrap();
+                }
+                h
+            }
+            _ if key.is_int_value() => {
+                let value = key.into_int_value();
+                let wide = if value.get_type().get_bit_width() == 64 {
+                    value
+                } else if TypeRegistry::is_signed(key_type) {
+                    self.builder.build_int_s_extend(value, i64_ty, "wide").unwrap()
+                } else {
+                    self.builder.build_int_z_extend(value, i64_ty, "wide").unwrap()
+                };
+                self.build_mix(wide)
+            }
+            // Other key types are rejected by the front end.
+            _ => self.i64_const(0),
+        }
+    }
+
+    /// The murmur3 64-bit finalizer, spreading integer keys over the index.
+    fn build_mix(&self, x: IntValue<'ctx>) -> IntValue<'ctx> {
+        let shift = self.i64_const(33);
+        let mut x = x;
+        for (i, multiplier) in [0xff51afd7ed558ccd_u64, 0xc4ceb9fe1a85ec53].into_iter().enumerate() {
+            let shifted = self.builder.build_right_shift(x, shift, false, "mix_shr").unwrap();
+            x = self.builder.build_xor(x, shifted, "mix_xor").unwrap();
+            x = self.builder.build_int_mul(x, self.i64_const(multiplier), &format!("mix_mul{i}")).unwrap();
+        }
+        let shifted = self.builder.build_right_shift(x, shift, false, "mix_shr").unwrap();
+        self.builder.build_xor(x, shifted, "mixed").unwrap()
+    }
+
+    /// `__llts_eq.{K}(a, b) -> i1`.
+    fn eq_fn(&mut self, key_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_eq.{}", type_tag(key_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let fn_type = self.context.bool_type().fn_type(&[key_ty.into(), key_ty.into()], false);
+        let key_type = key_type.clone();
+        self.helper(&name, fn_type, move |cx, function| {
+            let a = function.get_nth_param(0).unwrap();
+            let b = function.get_nth_param(1).unwrap();
+            let eq = cx.build_eq(function, a, b, &key_type);
+            cx.builder.build_return(Some(&eq)).unwrap();
+        })
+    }
+
+    fn build_eq(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+        key_type: &LltsType,
+    ) -> IntValue<'ctx> {
+        match key_type {
+            LltsType::F32 | LltsType::F64 => {
+                // SameValueZero: NaN equals NaN.
+                let (a, b) = (a.into_float_value(), b.into_float_value());
+                let eq = self.builder.build_float_compare(FloatPredicate::OEQ, a, b, "feq").unwrap();
+                let a_nan = self.builder.build_float_compare(FloatPredicate::UNO, a, a, "a_nan").unwrap();
+                let b_nan = self.builder.build_float_compare(FloatPredicate::UNO, b, b, "b_nan").unwrap();
+                let both_nan = self.builder.build_and(a_nan, b_nan, "both_nan").unwrap();
+                self.builder.build_or(eq, both_nan, "eq").unwrap()
+            }
+            LltsType::String => {
+                let (a, b) = (a.into_struct_value(), b.into_struct_value());
+                let a_len = self.builder.build_extract_value(a, 1, "a_len").unwrap().into_int_value();
+                let b_len = self.builder.build_extract_value(b, 1, "b_len").unwrap().into_int_value();
+                let same_len = self.builder.build_int_compare(IntPredicate::EQ, a_len, b_len, "same_len").unwrap();
+                let entry_bb = self.builder.get_insert_block().unwrap();
+                let cmp_bb = self.context.append_basic_block(function, "str_cmp");
+                let done_bb = self.context.append_basic_block(function, "str_eq_done");
+                self.builder.build_conditional_branch(same_len, cmp_bb, done_bb).unwrap();
+
+                self.builder.position_at_end(cmp_bb);
+                let a_data = self.builder.build_extract_value(a, 0, "a_data").unwrap();
+                let b_data = self.builder.build_extract_value(b, 0, "b_data").unwrap();
+                let memcmp = self.memcmp_fn();
+                let diff = self
+                    .builder
+                    .build_call(memcmp, &[a_data.into(), b_data.into(), a_len.into()], "memcmp")
+                    .unwrap()
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_int_value();
+                let bytes_eq = self
+                    .builder
+                    .build_int_compare(IntPredicate::EQ, diff, self.context.i32_type().const_zero(), "bytes_eq")
+                    .unwrap();
+                self.builder.build_unconditional_branch(done_bb).unwrap();
+
+                self.builder.position_at_end(done_bb);
+                let phi = self.builder.build_phi(self.context.bool_type(), "str_eq").unwrap();
+                phi.add_incoming(&[(&self.context.bool_type().const_zero(), entry_bb), (&bytes_eq, cmp_bb)]);
+                phi.as_basic_value().into_int_value()
+            }
+            LltsType::Struct { fields, .. } => {
+                let (a, b) = (a.into_struct_value(), b.into_struct_value());
+                let mut eq = self.context.bool_type().const_int(1, false);
+                for (i, (_, field_type)) in fields.iter().enumerate() {
+                    let fa = self.builder.build_extract_value(a, i as u32, "fa").unwrap();
+                    let fb = self.builder.build_extract_value(b, i as u32, "fb").unwrap();
+                    let field_eq = self.eq_fn(field_type);
+                    let feq = self
+                        .builder
+                        .build_call(field_eq, &[fa.into(), fb.into()], "field_eq")
+                        .unwrap()
+                        .try_as_basic_value()
+                        .unwrap_basic()
+                        .into_int_value();
+                    eq = self.builder.build_and(eq, feq, "eq").unwrap();
+                }
+                eq
+            }
+            _ if a.is_int_value() => self
+                .builder
+                .build_int_compare(IntPredicate::EQ, a.into_int_value(), b.into_int_value(), "eq")
+                .unwrap(),
+            _ => self.context.bool_type().const_zero(),
+        }
+    }
+
+    fn memcmp_fn(&self) -> FunctionValue<'ctx> {
+        if let Some(f) = self.module.get_function("memcmp") {
+            return f;
+        }
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+        let fn_type = self
+            .context
+            .i32_type()
+            .fn_type(&[ptr_ty.into(), ptr_ty.into(), self.context.i64_type().into()], false);
+        self.module.add_function("memcmp", fn_type, None)
+    }
+
+    fn calloc_fn(&self) -> FunctionValue<'ctx> {
+        if let Some(f) = self.module.get_function("calloc") {
+            return f;
+        }
+        let i64_ty = self.context.i64_type();
+        let fn_type = self
+            .context
+            .ptr_type(AddressSpace::default())
+            .fn_type(&[i64_ty.into(), i64_ty.into()], false);
+        self.module.add_function("calloc", fn_type, None)
+    }
+
+    // ---- Table operations ----
+
+    /// `__llts_map_lookup.{K}.{V}(table, key, hash) -> i64`: the index of
+    /// the live entry holding `key`, or -1.
+    fn lookup_fn(&mut self, key_type: &LltsType, value_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_map_lookup.{}.{}", type_tag(key_type), type_tag(value_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let i64_ty = self.context.i64_type();
+        let fn_type = i64_ty.fn_type(&[self.ptr_type().into(), key_ty.into(), i64_ty.into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        let eq_fn = self.eq_fn(key_type);
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let key = function.get_nth_param(1).unwrap();
+            let hash = function.get_nth_param(2).unwrap().into_int_value();
+            let b = cx.builder;
+
+            let init_bb = cx.context.append_basic_block(function, "init");
+            let probe_bb = cx.context.append_basic_block(function, "probe");
+            let check_bb = cx.context.append_basic_block(function, "check");
+            let cmp_key_bb = cx.context.append_basic_block(function, "cmp_key");
+            let next_bb = cx.context.append_basic_block(function, "next");
+            let found_bb = cx.context.append_basic_block(function, "found");
+            let missing_bb = cx.context.append_basic_block(function, "missing");
+
+            let index_cap = load_header(b, cx.context, table, INDEX_CAP, "index_cap");
+            let no_index = b.build_int_compare(IntPredicate::EQ, index_cap, i64_ty.const_zero(), "no_index").unwrap();
+            b.build_conditional_branch(no_index, missing_bb, init_bb).unwrap();
+
+            b.position_at_end(init_bb);
+            let mask = b.build_int_sub(index_cap, cx.i64_const(1), "mask").unwrap();
+            let start = b.build_and(hash, mask, "start").unwrap();
+            let index = load_header_ptr(b, cx.context, table, INDEX, "index");
+            b.build_unconditional_branch(probe_bb).unwrap();
+
+            b.position_at_end(probe_bb);
+            let slot = b.build_phi(i64_ty, "slot").unwrap();
+            let slot_val = slot.as_basic_value().into_int_value();
+            let slot_ptr = unsafe { b.build_gep(i64_ty, index, &[slot_val], "slot_ptr").unwrap() };
+            let occupant = b.build_load(i64_ty, slot_ptr, "occupant").unwrap().into_int_value();
+            let empty = b.build_int_compare(IntPredicate::EQ, occupant, i64_ty.const_zero(), "empty").unwrap();
+            b.build_conditional_branch(empty, missing_bb, check_bb).unwrap();
+
+            b.position_at_end(check_bb);
+            let entry_index = b.build_int_sub(occupant, cx.i64_const(1), "entry_index").unwrap();
+            let entry = cx.entry_ptr(entry_ty, table, entry_index);
+            let live = cx.load_entry_field(entry_ty, entry, LIVE, "live").into_int_value();
+            let entry_hash = cx.load_entry_field(entry_ty, entry, HASH, "entry_hash").into_int_value();
+            let same_hash = b.build_int_compare(IntPredicate::EQ, entry_hash, hash, "same_hash").unwrap();
+            let candidate = b.build_and(live, same_hash, "candidate").unwrap();
+            b.build_conditional_branch(candidate, cmp_key_bb, next_bb).unwrap();
+
+            b.position_at_end(cmp_key_bb);
+            let entry_key = cx.load_entry_field(entry_ty, entry, KEY, "entry_key");
+            let same_key = b
+                .build_call(eq_fn, &[entry_key.into(), key.into()], "same_key")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            b.build_conditional_branch(same_key, found_bb, next_bb).unwrap();
+
+            b.position_at_end(next_bb);
+            let bumped = b.build_int_add(slot_val, cx.i64_const(1), "bumped").unwrap();
+            let next = b.build_and(bumped, mask, "next_slot").unwrap();
+            b.build_unconditional_branch(probe_bb).unwrap();
+            slot.add_incoming(&[(&start, init_bb), (&next, next_bb)]);
+
+            b.position_at_end(found_bb);
+            b.build_return(Some(&entry_index)).unwrap();
+
+            b.position_at_end(missing_bb);
+            b.build_return(Some(&i64_ty.const_all_ones())).unwrap();
+        })
+    }
+
+    /// `__llts_map_rehash.{K}.{V}(table)`: compact away dead entries and
+    /// rebuild the index, doubling it if it would be over half full.
+    fn rehash_fn(&mut self, key_type: &LltsType, value_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_map_rehash.{}.{}", type_tag(key_type), type_tag(value_type));
+        let fn_type = self.context.void_type().fn_type(&[self.ptr_type().into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let b = cx.builder;
+            let i64_ty = cx.context.i64_type();
+            let entry_bb = b.get_insert_block().unwrap();
+
+            // Compact: move live entries down over dead ones.
+            let len = load_header(b, cx.context, table, LEN, "len");
+            let entries = load_header_ptr(b, cx.context, table, ENTRIES, "entries");
+            let compact_bb = cx.context.append_basic_block(function, "compact");
+            let visit_bb = cx.context.append_basic_block(function, "compact_visit");
+            let keep_bb = cx.context.append_basic_block(function, "compact_keep");
+            let advance_bb = cx.context.append_basic_block(function, "compact_advance");
+            let reindex_bb = cx.context.append_basic_block(function, "reindex");
+            b.build_unconditional_branch(compact_bb).unwrap();
+
+            b.position_at_end(compact_bb);
+            let i = b.build_phi(i64_ty, "i").unwrap();
+            let j = b.build_phi(i64_ty, "j").unwrap();
+            let i_val = i.as_basic_value().into_int_value();
+            let j_val = j.as_basic_value().into_int_value();
+            let done = b.build_int_compare(IntPredicate::UGE, i_val, len, "compact_done").unwrap();
+            b.build_conditional_branch(done, reindex_bb, visit_bb).unwrap();
+
+            b.position_at_end(visit_bb);
+            let src = unsafe { b.build_gep(entry_ty, entries, &[i_val], "src").unwrap() };
+            let live = cx.load_entry_field(entry_ty, src, LIVE, "live").into_int_value();
+            b.build_conditional_branch(live, keep_bb, advance_bb).unwrap();
+
+            b.position_at_end(keep_bb);
+            let entry = b.build_load(entry_ty, src, "moved").unwrap();
+            let dst = unsafe { b.build_gep(entry_ty, entries, &[j_val], "dst").unwrap() };
+            b.build_store(dst, entry).unwrap();
+            let kept = b.build_int_add(j_val, cx.i64_const(1), "kept").unwrap();
+            b.build_unconditional_branch(advance_bb).unwrap();
+
+            b.position_at_end(advance_bb);
+            let j_next = b.build_phi(i64_ty, "j_next").unwrap();
+            j_next.add_incoming(&[(&j_val, visit_bb), (&kept, keep_bb)]);
+            let i_next = b.build_int_add(i_val, cx.i64_const(1), "i_next").unwrap();
+            b.build_unconditional_branch(compact_bb).unwrap();
+            i.add_incoming(&[(&cx.i64_const(0), entry_bb), (&i_next, advance_bb)]);
+            j.add_incoming(&[(&cx.i64_const(0), entry_bb), (&j_next.as_basic_value(), advance_bb)]);
+
+            // Rebuild the index over the `j` live entries.
+            b.position_at_end(reindex_bb);
+            store_header(b, cx.context, table, LEN, j_val.into());
+            let old_cap = load_header(b, cx.context, table, INDEX_CAP, "old_cap");
+            let was_empty = b.build_int_compare(IntPredicate::EQ, old_cap, i64_ty.const_zero(), "was_empty").unwrap();
+            let base = b
+                .build_select(was_empty, cx.i64_const(INITIAL_CAP), old_cap, "base_cap")
+                .unwrap()
+                .into_int_value();
+            let wanted = b.build_int_add(j_val, cx.i64_const(1), "wanted").unwrap();
+            let wanted = b.build_int_mul(wanted, cx.i64_const(2), "wanted2").unwrap();
+            let crowded = b.build_int_compare(IntPredicate::UGT, wanted, base, "crowded").unwrap();
+            let doubled = b.build_int_mul(base, cx.i64_const(2), "doubled").unwrap();
+            let new_cap = b.build_select(crowded, doubled, base, "new_cap").unwrap().into_int_value();
+
+            let old_index = load_header_ptr(b, cx.context, table, INDEX, "old_index");
+            cx.memory.build_heap_free(b, cx.module, old_index);
+            let calloc = cx.calloc_fn();
+            let index = b
+                .build_call(calloc, &[new_cap.into(), cx.i64_const(8).into()], "index")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value();
+            store_header(b, cx.context, table, INDEX, index.into());
+            store_header(b, cx.context, table, INDEX_CAP, new_cap.into());
+            let mask = b.build_int_sub(new_cap, cx.i64_const(1), "mask").unwrap();
+
+            let fill_bb = cx.context.append_basic_block(function, "fill");
+            let fill_body_bb = cx.context.append_basic_block(function, "fill_body");
+            let done_bb = cx.context.append_basic_block(function, "done");
+            b.build_unconditional_branch(fill_bb).unwrap();
+
+            b.position_at_end(fill_bb);
+            let k = b.build_phi(i64_ty, "k").unwrap();
+            let k_val = k.as_basic_value().into_int_value();
+            let filled = b.build_int_compare(IntPredicate::UGE, k_val, j_val, "filled").unwrap();
+            b.build_conditional_branch(filled, done_bb, fill_body_bb).unwrap();
+
+            b.position_at_end(fill_body_bb);
+            let entry = unsafe { b.build_gep(entry_ty, entries, &[k_val], "entry").unwrap() };
+            let hash = cx.load_entry_field(entry_ty, entry, HASH, "hash").into_int_value();
+            cx.build_index_insert(function, index, mask, hash, k_val);
+            let k_next = b.build_int_add(k_val, cx.i64_const(1), "k_next").unwrap();
+            let placed_bb = b.get_insert_block().unwrap();
+            b.build_unconditional_branch(fill_bb).unwrap();
+            k.add_incoming(&[(&cx.i64_const(0), reindex_bb), (&k_next, placed_bb)]);
+
+            b.position_at_end(done_bb);
+            b.build_return(None).unwrap();
+        })
+    }
+
+    /// `__llts_map_insert.{K}.{V}(table, key, value)`.
+    fn insert_fn(&mut self, key_type: &LltsType, value_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_map_insert.{}.{}", type_tag(key_type), type_tag(value_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let value_ty = self.registry.llvm_type(value_type);
+        let fn_type = self
+            .context
+            .void_type()
+            .fn_type(&[self.ptr_type().into(), key_ty.into(), value_ty.into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        let hash_fn = self.hash_fn(key_type);
+        let lookup_fn = self.lookup_fn(key_type, value_type);
+        let rehash_fn = self.rehash_fn(key_type, value_type);
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let key = function.get_nth_param(1).unwrap();
+            let value = function.get_nth_param(2).unwrap();
+            let b = cx.builder;
+            let i64_ty = cx.context.i64_type();
+
+            let update_bb = cx.context.append_basic_block(function, "update");
+            let append_bb = cx.context.append_basic_block(function, "append");
+            let rehash_bb = cx.context.append_basic_block(function, "rehash");
+            let reserve_bb = cx.context.append_basic_block(function, "reserve");
+            let grow_bb = cx.context.append_basic_block(function, "grow");
+            let store_bb = cx.context.append_basic_block(function, "store");
+
+            let hash = b
+                .build_call(hash_fn, &[key.into()], "hash")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            let existing = b
+                .build_call(lookup_fn, &[table.into(), key.into(), hash.into()], "existing")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            let found = b.build_int_compare(IntPredicate::SGE, existing, i64_ty.const_zero(), "found").unwrap();
+            b.build_conditional_branch(found, update_bb, append_bb).unwrap();
+
+            // Existing key: overwrite the value in place.
+            b.position_at_end(update_bb);
+            let entry = cx.entry_ptr(entry_ty, table, existing);
+            cx.store_entry_field(entry_ty, entry, VALUE, value);
+            b.build_return(None).unwrap();
+
+            // New key: keep the index at most three quarters full.
+            b.position_at_end(append_bb);
+            let len = load_header(b, cx.context, table, LEN, "len");
+            let index_cap = load_header(b, cx.context, table, INDEX_CAP, "index_cap");
+            let next_len = b.build_int_add(len, cx.i64_const(1), "next_len").unwrap();
+            let load = b.build_int_mul(next_len, cx.i64_const(4), "load").unwrap();
+            let limit = b.build_int_mul(index_cap, cx.i64_const(3), "limit").unwrap();
+            let over = b.build_int_compare(IntPredicate::UGT, load, limit, "over").unwrap();
+            b.build_conditional_branch(over, rehash_bb, reserve_bb).unwrap();
+
+            b.position_at_end(rehash_bb);
+            b.build_call(rehash_fn, &[table.into()], "").unwrap();
+            b.build_unconditional_branch(reserve_bb).unwrap();
+
+            // Grow the entry buffer when full.
+            b.position_at_end(reserve_bb);
+            let len = load_header(b, cx.context, table, LEN, "len");
+            let cap = load_header(b, cx.context, table, CAP, "cap");
+            let full = b.build_int_compare(IntPredicate::EQ, len, cap, "full").unwrap();
+            b.build_conditional_branch(full, grow_bb, store_bb).unwrap();
+
+            b.position_at_end(grow_bb);
+            let empty = b.build_int_compare(IntPredicate::EQ, cap, i64_ty.const_zero(), "empty").unwrap();
+            let doubled = b.build_int_mul(cap, cx.i64_const(2), "doubled").unwrap();
+            let new_cap = b
+                .build_select(empty, cx.i64_const(INITIAL_CAP), doubled, "new_cap")
+                .unwrap()
+                .into_int_value();
+            let bytes = b.build_int_mul(new_cap, entry_ty.size_of().unwrap(), "bytes").unwrap();
+            let entries = load_header_ptr(b, cx.context, table, ENTRIES, "entries");
+            let realloc = cx.memory.get_or_declare_realloc(cx.module);
+            let grown = b
+                .build_call(realloc, &[entries.into(), bytes.into()], "grown")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic();
+            store_header(b, cx.context, table, ENTRIES, grown);
+            store_header(b, cx.context, table, CAP, new_cap.into());
+            b.build_unconditional_branch(store_bb).unwrap();
+
+            b.position_at_end(store_bb);
+            let entry = cx.entry_ptr(entry_ty, table, len);
+            cx.store_entry_field(entry_ty, entry, HASH, hash.into());
+            cx.store_entry_field(entry_ty, entry, LIVE, cx.context.bool_type().const_int(1, false).into());
+            cx.store_entry_field(entry_ty, entry, KEY, key);
+            cx.store_entry_field(entry_ty, entry, VALUE, value);
+            let new_len = b.build_int_add(len, cx.i64_const(1), "new_len").unwrap();
+            store_header(b, cx.context, table, LEN, new_len.into());
+            let count = load_header(b, cx.context, table, COUNT, "count");
+            let new_count = b.build_int_add(count, cx.i64_const(1), "new_count").unwrap();
+            store_header(b, cx.context, table, COUNT, new_count.into());
+
+            let index = load_header_ptr(b, cx.context, table, INDEX, "index");
+            let index_cap = load_header(b, cx.context, table, INDEX_CAP, "index_cap");
+            let mask = b.build_int_sub(index_cap, cx.i64_const(1), "mask").unwrap();
+            cx.build_index_insert(function, index, mask, hash, len);
+            b.build_return(None).unwrap();
+        })
+    }
+
+    /// `__llts_map_get.{K}.{V}(table, key) -> Option<V>`.
+    fn get_fn(&mut self, key_type: &LltsType, value_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_map_get.{}.{}", type_tag(key_type), type_tag(value_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let opt_ty = self.registry.option_type(value_type);
+        let fn_type = opt_ty.fn_type(&[self.ptr_type().into(), key_ty.into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        let key_type = key_type.clone();
+        let value_type = value_type.clone();
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let key = function.get_nth_param(1).unwrap();
+            let b = cx.builder;
+
+            let index = cx.call_lookup(table, key, &key_type, &value_type);
+            let found = b
+                .build_int_compare(IntPredicate::SGE, index, cx.context.i64_type().const_zero(), "found")
+                .unwrap();
+            let some_bb = cx.context.append_basic_block(function, "some");
+            let none_bb = cx.context.append_basic_block(function, "none");
+            b.build_conditional_branch(found, some_bb, none_bb).unwrap();
+
+            b.position_at_end(some_bb);
+            let entry = cx.entry_ptr(entry_ty, table, index);
+            let value = cx.load_entry_field(entry_ty, entry, VALUE, "value");
+            let tag = cx.context.bool_type().const_int(1, false);
+            let some = b
+                .build_insert_value(opt_ty.get_undef(), tag, 0, "some_tag")
+                .unwrap()
+                .into_struct_value();
+            let some = b.build_insert_value(some, value, 1, "some").unwrap().into_struct_value();
+            b.build_return(Some(&some)).unwrap();
+
+            b.position_at_end(none_bb);
+            b.build_return(Some(&opt_ty.const_zero())).unwrap();
+        })
+    }
+
+    /// `__llts_map_delete.{K}.{V}(table, key) -> i1`.
+    fn delete_fn(&mut self, key_type: &LltsType, value_type: &LltsType) -> FunctionValue<'ctx> {
+        let name = format!("__llts_map_delete.{}.{}", type_tag(key_type), type_tag(value_type));
+        let key_ty = self.registry.llvm_type(key_type);
+        let fn_type = self.context.bool_type().fn_type(&[self.ptr_type().into(), key_ty.into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        let key_type = key_type.clone();
+        let value_type = value_type.clone();
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let key = function.get_nth_param(1).unwrap();
+            let b = cx.builder;
+            let bool_ty = cx.context.bool_type();
+
+            let index = cx.call_lookup(table, key, &key_type, &value_type);
+            let found = b
+                .build_int_compare(IntPredicate::SGE, index, cx.context.i64_type().const_zero(), "found")
+                .unwrap();
+            let kill_bb = cx.context.append_basic_block(function, "kill");
+            let missing_bb = cx.context.append_basic_block(function, "missing");
+            b.build_conditional_branch(found, kill_bb, missing_bb).unwrap();
+
+            b.position_at_end(kill_bb);
+            let entry = cx.entry_ptr(entry_ty, table, index);
+            cx.store_entry_field(entry_ty, entry, LIVE, bool_ty.const_zero().into());
+            let count = load_header(b, cx.context, table, COUNT, "count");
+            let new_count = b.build_int_sub(count, cx.i64_const(1), "new_count").unwrap();
+            store_header(b, cx.context, table, COUNT, new_count.into());
+            b.build_return(Some(&bool_ty.const_int(1, false))).unwrap();
+
+            b.position_at_end(missing_bb);
+            b.build_return(Some(&bool_ty.const_zero())).unwrap();
+        })
+    }
+
+    /// `__llts_map_{keys,values,entries}.{K}.{V}(table) -> Array`.
+    fn collect_fn(
+        &mut self,
+        key_type: &LltsType,
+        value_type: &LltsType,
+        kind: CollectKind,
+        elem_type: &LltsType,
+    ) -> FunctionValue<'ctx> {
+        let what = match kind {
+            CollectKind::Keys => "keys",
+            CollectKind::Values => "values",
+            CollectKind::Entries => "entries",
+        };
+        let name = format!("__llts_map_{what}.{}.{}", type_tag(key_type), type_tag(value_type));
+        let array_ty = self.registry.array_type(elem_type);
+        let elem_ty = self.registry.llvm_type(elem_type);
+        let fn_type = array_ty.fn_type(&[self.ptr_type().into()], false);
+        let entry_ty = self.entry_type(key_type, value_type);
+        self.helper(&name, fn_type, move |cx, function| {
+            let table = function.get_nth_param(0).unwrap().into_pointer_value();
+            let b = cx.builder;
+            let i64_ty = cx.context.i64_type();
+            let entry_bb = b.get_insert_block().unwrap();
+
+            let count = load_header(b, cx.context, table, COUNT, "count");
+            let len = load_header(b, cx.context, table, LEN, "len");
+            let entries = load_header_ptr(b, cx.context, table, ENTRIES, "entries");
+            let bytes = b.build_int_mul(count, elem_ty.size_of().unwrap(), "bytes").unwrap();
+            let data = cx.memory.build_heap_alloc(b, cx.module, bytes, "data");
+
+            let loop_bb = cx.context.append_basic_block(function, "walk");
+            let visit_bb = cx.context.append_basic_block(function, "walk_visit");
+            let take_bb = cx.context.append_basic_block(function, "walk_take");
+            let advance_bb = cx.context.append_basic_block(function, "walk_advance");
+            let done_bb = cx.context.append_basic_block(function, "walk_done");
+            b.build_unconditional_branch(loop_bb).unwrap();
+
+            b.position_at_end(loop_bb);
+            let i = b.build_phi(i64_ty, "i").unwrap();
+            let j = b.build_phi(i64_ty, "j").unwrap();
+            let i_val = i.as_basic_value().into_int_value();
+            let j_val = j.as_basic_value().into_int_value();
+            let done = b.build_int_compare(IntPredicate::UGE, i_val, len, "walked").unwrap();
+            b.build_conditional_branch(done, done_bb, visit_bb).unwrap();
+
+            b.position_at_end(visit_bb);
+            let entry = unsafe { b.build_gep(entry_ty, entries, &[i_val], "entry").unwrap() };
+            let live = cx.load_entry_field(entry_ty, entry, LIVE, "live").into_int_value();
+            b.build_conditional_branch(live, take_bb, advance_bb).unwrap();
+
+            b.position_at_end(take_bb);
+            let elem: BasicValueEnum<'ctx> = match kind {
+                CollectKind::Keys => cx.load_entry_field(entry_ty, entry, KEY, "key"),
+                CollectKind::Values => cx.load_entry_field(entry_ty, entry, VALUE, "value"),
+                CollectKind::Entries => {
+                    let key = cx.load_entry_field(entry_ty, entry, KEY, "key");
+                    let value = cx.load_entry_field(entry_ty, entry, VALUE, "value");
+                    let pair = elem_ty.into_struct_type().get_undef();
+                    let pair = b.build_insert_value(pair, key, 0, "pair_key").unwrap().into_struct_value();
+                    let pair = b.build_insert_value(pair, value, 1, "pair").unwrap().into_struct_value();
+                    pair.into()
+                }
+            };
+            let slot = unsafe { b.build_gep(elem_ty, data, &[j_val], "slot").unwrap() };
+            b.build_store(slot, elem).unwrap();
+            let taken = b.build_int_add(j_val, cx.i64_const(1), "taken").unwrap();
+            b.build_unconditional_branch(advance_bb).unwrap();
+
+            b.position_at_end(advance_bb);
+            let j_next = b.build_phi(i64_ty, "j_next").unwrap();
+            j_next.add_incoming(&[(&j_val, visit_bb), (&taken, take_bb)]);
+            let i_next = b.build_int_add(i_val, cx.i64_const(1), "i_next").unwrap();
+            b.build_unconditional_branch(loop_bb).unwrap();
+            i.add_incoming(&[(&cx.i64_const(0), entry_bb), (&i_next, advance_bb)]);
+            j.add_incoming(&[(&cx.i64_const(0), entry_bb), (&j_next.as_basic_value(), advance_bb)]);
+
+            b.position_at_end(done_bb);
+            let array = b
+                .build_insert_value(array_ty.get_undef(), data, 0, "arr_ptr")
+                .unwrap()
+                .into_struct_value();
+            let array = b.build_insert_value(array, count, 1, "arr_len").unwrap().into_struct_value();
+            let array = b.build_insert_value(array, count, 2, "arr_cap").unwrap().into_struct_value();
+            b.build_return(Some(&array)).unwrap();
+        })
+    }
+}
@@ -0,0 +1,371 @@
+use inkwell::builder::Builder;
+use inkwell::context::Context;
+use inkwell::module::{Linkage, Module};
+use inkwell::types::{FunctionType, StructType};
+use inkwell::values::{FunctionValue, GlobalValue, IntValue, PointerValue};
+use inkwell::{AddressSpace, IntPredicate};
+
+use crate::memory::MemoryManager;
+
+/// Runtime of the single-threaded `async` executor.
+///
+/// Promises are state machines the driver builds; polling one runs it until
+/// it finishes or waits. A waiting timer records when it wants to be polled
+/// again, and the executor loop emitted into `main` parks between polls of
+/// the top-level promise:
+///
+/// ```text
+/// while (main_promise.poll() === null) __llts_park();
+/// ```
+///
+/// The helpers, each emitted on first use:
+///
+/// - `__llts_now_ms() -> i64` — monotonic clock in milliseconds.
+/// - `__llts_wake_at(deadline: i64)` — ask to be polled again at `deadline`.
+/// - `__llts_park()` — sleep until the earliest requested wake-up. With none
+///   requested nothing can make progress, so it reports a deadlock and exits.
+/// - `__llts_read_file(path: string) -> string | null` — read a whole file,
+///   `null` if it cannot be opened.
+pub struct ExecutorCodegen;
+
+const NOW_MS: &str = "__llts_now_ms";
+const WAKE_AT: &str = "__llts_wake_at";
+const PARK: &str = "__llts_park";
+const READ_FILE: &str = "__llts_read_file";
+
+/// Global holding the earliest requested wake-up, `i64::MAX` for none.
+const NEXT_WAKE: &str = "__llts_next_wake";
+
+const CLOCK_MONOTONIC: u64 = if cfg!(target_os = "macos") { 6 } else { 1 };
+const SEEK_SET: u64 = 0;
+const SEEK_END: u64 = 2;
+
+const DEADLOCK_MESSAGE: &str = "fatal: async main is waiting, but no timer is pending (deadlock)\n";
+
+impl ExecutorCodegen {
+    /// Runtime helper `name`, emitted on first use; `None` if `name` is not
+    /// one of them.
+    pub fn runtime_fn<'ctx>(
+        name: &str,
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+    ) -> Option<FunctionValue<'ctx>> {
+        let mut cx = Cx { builder, context, module, memory };
+        match name {
+            NOW_MS => Some(cx.now_ms_fn()),
+            WAKE_AT => Some(cx.wake_at_fn()),
+            PARK => Some(cx.park_fn()),
+            READ_FILE => Some(cx.read_file_fn()),
+            _ => None,
+        }
+    }
+}
+
+struct Cx<'a, 'ctx> {
+    builder: &'a Builder<'ctx>,
+    context: &'ctx Context,
+    module: &'a Module<'ctx>,
+    memory: &'a mut MemoryManager<'ctx>,
+}
+
+impl<'ctx> Cx<'_, 'ctx> {
+    /// Return helper `name`, first declaring it as `fn_type` and emitting
+    /// its body with `build` (which receives the function, positioned at its
+    /// entry block). The builder's position is restored afterwards.
+    fn helper(
+        &mut self,
+        name: &str,
+        fn_type: FunctionType<'ctx>,
+        build: impl FnOnce(&mut Self, FunctionValue<'ctx>),
+    ) -> FunctionValue<'ctx> {
+        if let Some(f) = self.module.get_function(name) {
+            return f;
+        }
+        let function = self.module.add_function(name, fn_type, Some(Linkage::Internal));
+        let saved = self.builder.get_insert_block();
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+        build(self, function);
+        if let Some(block) = saved {
+            self.builder.position_at_end(block);
+        }
+        function
+    }
+
+    /// libc function `name`, declared on first use.
+    fn libc_fn(&self, name: &str, fn_type: FunctionType<'ctx>) -> FunctionValue<'ctx> {
+        self.module
+            .get_function(name)
+            .unwrap_or_else(|| self.module.add_function(name, fn_type, None))
+    }
+
+    fn ptr_type(&self) -> inkwell::types::PointerType<'ctx> {
+        self.context.ptr_type(AddressSpace::default())
+    }
+
+    fn i64_const(&self, value: u64) -> IntValue<'ctx> {
+        self.context.i64_type().const_int(value, false)
+    }
+
+    /// `struct timespec { tv_sec: i64, tv_nsec: i64 }`.
+    fn timespec_type(&self) -> StructType<'ctx> {
+        let i64_ty = self.context.i64_type().into();
+        self.context.struct_type(&[i64_ty, i64_ty], false)
+    }
+
+    fn next_wake(&self) -> GlobalValue<'ctx> {
+        self.module.get_global(NEXT_WAKE).unwrap_or_else(|| {
+            let global = self.module.add_global(self.context.i64_type(), None, NEXT_WAKE);
+            global.set_linkage(Linkage::Internal);
+            global.set_initializer(&self.i64_const(i64::MAX as u64));
+            global
+        })
+    }
+
+    /// `__llts_now_ms() -> i64`, from `clock_gettime(CLOCK_MONOTONIC)`.
+    fn now_ms_fn(&mut self) -> FunctionValue<'ctx> {
+        let fn_type = self.context.i64_type().fn_type(&[], false);
+        self.helper(NOW_MS, fn_type, |cx, _| {
+            let b = cx.builder;
+            let i32_ty = cx.context.i32_type();
+            let clock_gettime = cx.libc_fn(
+                "clock_gettime",
+                i32_ty.fn_type(&[i32_ty.into(), cx.ptr_type().into()], false),
+            );
+            let timespec_ty = cx.timespec_type();
+            let ts = b.build_alloca(timespec_ty, "ts").unwrap();
+            b.build_call(clock_gettime, &[i32_ty.const_int(CLOCK_MONOTONIC, false).into(), ts.into()], "")
+                .unwrap();
+            let (sec, nsec) = cx.load_timespec(ts);
+            let ms = b.build_int_mul(sec, cx.i64_const(1000), "ms").unwrap();
+            let sub_ms = b.build_int_signed_div(nsec, cx.i64_const(1_000_000), "sub_ms").unwrap();
+            let total = b.build_int_add(ms, sub_ms, "now").unwrap();
+            b.build_return(Some(&total)).unwrap();
+        })
+    }
+
+    fn load_timespec(&self, ts: PointerValue<'ctx>) -> (IntValue<'ctx>, IntValue<'ctx>) {
+        let b = self.builder;
+        let i64_ty = self.context.i64_type();
+        let timespec_ty = self.timespec_type();
+        let sec_ptr = b.build_struct_gep(timespec_ty, ts, 0, "sec_ptr").unwrap();
+        let nsec_ptr = b.build_struct_gep(timespec_ty, ts, 1, "nsec_ptr").unwrap();
+        let sec = b.build_load(i64_ty, sec_ptr, "sec").unwrap().into_int_value();
+        let nsec = b.build_load(i64_ty, nsec_ptr, "nsec").unwrap().into_int_value();
+        (sec, nsec)
+    }
+
+    /// `__llts_wake_at(deadline)` — keep the earliest requested wake-up.
+    fn wake_at_fn(&mut self) -> FunctionValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let fn_type = self.context.void_type().fn_type(&[i64_ty.into()], false);
+        self.helper(WAKE_AT, fn_type, |cx, function| {
+            let b = cx.builder;
+            let deadline = function.get_nth_param(0).unwrap().into_int_value();
+            let next_wake = cx.next_wake().as_pointer_value();
+            let current = b.build_load(i64_ty, next_wake, "current").unwrap().into_int_value();
+            let earlier = b.build_int_compare(IntPredicate::SLT, deadline, current, "earlier").unwrap();
+            let next = b.build_select(earlier, deadline, current, "next").unwrap();
+            b.build_store(next_wake, next).unwrap();
+            b.build_return(None).unwrap();
+        })
+    }
+
+    /// `__llts_park()` — `nanosleep` until the earliest requested wake-up,
+    /// then clear it; the promises polled next ask again if still waiting.
+    fn park_fn(&mut self) -> FunctionValue<'ctx> {
+        let fn_type = self.context.void_type().fn_type(&[], false);
+        let now_ms = self.now_ms_fn();
+        self.helper(PARK, fn_type, move |cx, function| {
+            let b = cx.builder;
+            let i32_ty = cx.context.i32_type();
+            let i64_ty = cx.context.i64_type();
+            let timespec_ty = cx.timespec_type();
+            let ts = b.build_alloca(timespec_ty, "ts").unwrap();
+
+            let deadlock_bb = cx.context.append_basic_block(function, "deadlock");
+            let wait_bb = cx.context.append_basic_block(function, "wait");
+            let sleep_bb = cx.context.append_basic_block(function, "sleep");
+            let done_bb = cx.context.append_basic_block(function, "done");
+
+            let next_wake = cx.next_wake().as_pointer_value();
+            let wake = b.build_load(i64_ty, next_wake, "wake").unwrap().into_int_value();
+            let none = b
+                .build_int_compare(IntPredicate::EQ, wake, cx.i64_const(i64::MAX as u64), "none")
+                .unwrap();
+            b.build_conditional_branch(none, deadlock_bb, wait_bb).unwrap();
+
+            // Nothing to wait for: the top-level promise can never finish.
+            b.position_at_end(deadlock_bb);
+            let write = cx.libc_fn(
+                "write",
+                i64_ty.fn_type(&[i32_ty.into(), cx.ptr_type().into(), i64_ty.into()], false),
+            );
+            let exit = cx.libc_fn("exit", cx.context.void_type().fn_type(&[i32_ty.into()], false));
+            let message = b.build_global_string_ptr(DEADLOCK_MESSAGE, "deadlock_msg").unwrap();
+            b.build_call(
+                write,
+                &[
+                    i32_ty.const_int(2, false).into(),
+                    message.as_pointer_value().into(),
+                    cx.i64_const(DEADLOCK_MESSAGE.len() as u64).into(),
+                ],
+                "",
+            )
+            .unwrap();
+            b.build_call(exit, &[i32_ty.const_int(1, false).into()], "").unwrap();
+            b.build_unreachable().unwrap();
+
+            b.position_at_end(wait_bb);
+            b.build_store(next_wake, cx.i64_const(i64::MAX as u64)).unwrap();
+            let now = b.build_call(now_ms, &[], "now").unwrap().try_as_basic_value().unwrap_basic().into_int_value();
+            let delay = b.build_int_sub(wake, now, "delay").unwrap();
+            let positive = b.build_int_compare(IntPredicate::SGT, delay, cx.i64_const(0), "positive").unwrap();
+            b.build_conditional_branch(positive, sleep_bb, done_bb).unwrap();
+
+            b.position_at_end(sleep_bb);
+            let sec = b.build_int_signed_div(delay, cx.i64_const(1000), "sec").unwrap();
+            let rem = b.build_int_signed_rem(delay, cx.i64_const(1000), "rem").unwrap();
+            let nsec = b.build_int_mul(rem, cx.i64_const(1_000_000), "nsec").unwrap();
+            let sec_ptr = b.build_struct_gep(timespec_ty, ts, 0, "sec_ptr").unwrap();
+            let nsec_ptr = b.build_struct_gep(timespec_ty, ts, 1, "nsec_ptr").unwrap();
+            b.build_store(sec_ptr, sec).unwrap();
+            b.build_store(nsec_ptr, nsec).unwrap();
+            let nanosleep = cx.libc_fn(
+                "nanosleep",
+                i32_ty.fn_type(&[cx.ptr_type().into(), cx.ptr_type().into()], false),
+            );
+            b.build_call(nanosleep, &[ts.into(), cx.ptr_type().const_null().into()], "").unwrap();
+            b.build_unconditional_branch(done_bb).unwrap();
+
+            b.position_at_end(done_bb);
+            b.build_return(None).unwrap();
+        })
+    }
+
+    /// `__llts_read_file(path) -> string | null` — `open`, size with
+    /// `lseek`, then `read` until the whole file is in a fresh buffer.
+    fn read_file_fn(&mut self) -> FunctionValue<'ctx> {
+        let i32_ty = self.context.i32_type();
+        let i64_ty = self.context.i64_type();
+        let ptr_ty = self.ptr_type();
+        let string_ty = self.context.struct_type(&[ptr_ty.into(), i64_ty.into()], false);
+        let option_ty = self.context.struct_type(&[self.context.bool_type().into(), string_ty.into()], false);
+        let fn_type = option_ty.fn_type(&[string_ty.into()], false);
+        let malloc = self.memory.get_or_declare_malloc(self.module);
+        let free = self.memory.get_or_declare_free(self.module);
+        self.helper(READ_FILE, fn_type, move |cx, function| {
+            let b = cx.builder;
+            let open = cx.libc_fn("open", i32_ty.fn_type(&[ptr_ty.into(), i32_ty.into()], true));
+            let lseek = cx.libc_fn("lseek", i64_ty.fn_type(&[i32_ty.into(), i64_ty.into(), i32_ty.into()], false));
+            let read = cx.libc_fn("read", i64_ty.fn_type(&[i32_ty.into(), ptr_ty.into(), i64_ty.into()], false));
+            let close = cx.libc_fn("close", i32_ty.fn_type(&[i32_ty.into()], false));
+            let memcpy = cx.libc_fn("memcpy", ptr_ty.fn_type(&[ptr_ty.into(), ptr_ty.into(), i64_ty.into()], false));
+
+            let size_bb = cx.context.append_basic_block(function, "size");
+            let close_fail_bb = cx.context.append_basic_block(function, "close_fail");
+            let alloc_bb = cx.context.append_basic_block(function, "alloc");
+            let loop_bb = cx.context.append_basic_block(function, "loop");
+            let body_bb = cx.context.append_basic_block(function, "body");
+            let advance_bb = cx.context.append_basic_block(function, "advance");
+            let finish_bb = cx.context.append_basic_block(function, "finish");
+            let fail_bb = cx.context.append_basic_block(function, "fail");
+
+            let total_slot = b.build_alloca(i64_ty, "total_slot").unwrap();
+            b.build_store(total_slot, cx.i64_const(0)).unwrap();
+
+            // NUL-terminated copy of the path for open().
+            let path = function.get_nth_param(0).unwrap().into_struct_value();
+            let path_ptr = b.build_extract_value(path, 0, "path_ptr").unwrap().into_pointer_value();
+            let path_len = b.build_extract_value(path, 1, "path_len").unwrap().into_int_value();
+            let cpath_len = b.build_int_add(path_len, cx.i64_const(1), "cpath_len").unwrap();
+            let cpath = b
+                .build_call(malloc, &[cpath_len.into()], "cpath")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value();
+            b.build_call(memcpy, &[cpath.into(), path_ptr.into(), path_len.into()], "").unwrap();
+            let nul = unsafe { b.build_gep(cx.context.i8_type(), cpath, &[path_len], "nul").unwrap() };
+            b.build_store(nul, cx.context.i8_type().const_zero()).unwrap();
+            let fd = b
+                .build_call(open, &[cpath.into(), i32_ty.const_zero().into()], "fd")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            b.build_call(free, &[cpath.into()], "").unwrap();
+            let opened = b.build_int_compare(IntPredicate::SGE, fd, i32_ty.const_zero(), "opened").unwrap();
+            b.build_conditional_branch(opened, size_bb, fail_bb).unwrap();
+
+            b.position_at_end(size_bb);
+            let size = b
+                .build_call(lseek, &[fd.into(), cx.i64_const(0).into(), i32_ty.const_int(SEEK_END, false).into()], "size")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            b.build_call(lseek, &[fd.into(), cx.i64_const(0).into(), i32_ty.const_int(SEEK_SET, false).into()], "")
+                .unwrap();
+            let seekable = b.build_int_compare(IntPredicate::SGE, size, cx.i64_const(0), "seekable").unwrap();
+            b.build_conditional_branch(seekable, alloc_bb, close_fail_bb).unwrap();
+
+            b.position_at_end(close_fail_bb);
+            b.build_call(close, &[fd.into()], "").unwrap();
+            b.build_unconditional_branch(fail_bb).unwrap();
+
+            b.position_at_end(alloc_bb);
+            let buf_size = b.build_int_add(size, cx.i64_const(1), "buf_size").unwrap();
+            let buf = b
+                .build_call(malloc, &[buf_size.into()], "buf")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value();
+            b.build_unconditional_branch(loop_bb).unwrap();
+
+            // read() may return less than asked; stop at the end or an error.
+            b.position_at_end(loop_bb);
+            let total = b.build_load(i64_ty, total_slot, "total").unwrap().into_int_value();
+            let more = b.build_int_compare(IntPredicate::SLT, total, size, "more").unwrap();
+            b.build_conditional_branch(more, body_bb, finish_bb).unwrap();
+
+            b.position_at_end(body_bb);
+            let dest = unsafe { b.build_gep(cx.context.i8_type(), buf, &[total], "dest").unwrap() };
+            let want = b.build_int_sub(size, total, "want").unwrap();
+            let got = b
+                .build_call(read, &[fd.into(), dest.into(), want.into()], "got")
+                .unwrap()
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_int_value();
+            let progressed = b.build_int_compare(IntPredicate::SGT, got, cx.i64_const(0), "progressed").unwrap();
+            b.build_conditional_branch(progressed, advance_bb, finish_bb).unwrap();
+
+            b.position_at_end(advance_bb);
+            let advanced = b.build_int_add(total, got, "advanced").unwrap();
+            b.build_store(total_slot, advanced).unwrap();
+            b.build_unconditional_branch(loop_bb).unwrap();
+
+            b.position_at_end(finish_bb);
+            b.build_call(close, &[fd.into()], "").unwrap();
+            let len = b.build_load(i64_ty, total_slot, "len").unwrap().into_int_value();
+            let contents = b
+                .build_insert_value(string_ty.get_undef(), buf, 0, "contents")
+                .unwrap()
+                .into_struct_value();
+            let contents = b.build_insert_value(contents, len, 1, "contents").unwrap().into_struct_value();
+            let some = b
+                .build_insert_value(option_ty.get_undef(), cx.context.bool_type().const_int(1, false), 0, "some")
+                .unwrap()
+                .into_struct_value();
+            let some = b.build_insert_value(some, contents, 1, "some").unwrap().into_struct_value();
+            b.build_return(Some(&some)).unwrap();
+
+            b.position_at_end(fail_bb);
+            b.build_return(Some(&option_ty.const_zero())).unwrap();
+        })
+    }
+}
@@ -313,8 +313,8 @@ impl ExprCodegen {
 
     /// Emit array element access with bounds checking.
     ///
-    /// `array_ptr` points to an `{ ptr, len, cap }` struct. We extract the
-    /// data pointer, check `index < len`, then GEP to the element.
+    /// `array_val` is an `{ ptr, len, cap }` struct; see
+    /// [`Self::build_array_element_ptr`].
     pub fn build_array_index<'ctx>(
         builder: &Builder<'ctx>,
         context: &'ctx Context,
@@ -325,6 +325,25 @@ impl ExprCodegen {
         elem_type: &LltsType,
         name: &str,
     ) -> BasicValueEnum<'ctx> {
+        let elem_ptr =
+            Self::build_array_element_ptr(builder, context, registry, function, array_val, index, elem_type);
+        let elem_llvm_ty = registry.llvm_type(elem_type);
+        builder.build_load(elem_llvm_ty, elem_ptr, name).unwrap()
+    }
+
+    /// Address of an array element, with bounds checking.
+    ///
+    /// `array_val` is an `{ ptr, len, cap }` struct. We extract the data
+    /// pointer, check `index < len`, then GEP to the element.
+    pub fn build_array_element_ptr<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        registry: &mut TypeRegistry<'ctx>,
+        function: FunctionValue<'ctx>,
+        array_val: BasicValueEnum<'ctx>,
+        index: IntValue<'ctx>,
+        elem_type: &LltsType,
+    ) -> PointerValue<'ctx> {
         let arr = array_val.into_struct_value();
 
         // Extract data pointer and length.
@@ -357,12 +376,11 @@ impl ExprCodegen {
         // In-bounds: GEP to element.
         builder.position_at_end(access_bb);
         let elem_llvm_ty = registry.llvm_type(elem_type);
-        let elem_ptr = unsafe {
+        unsafe {
             builder
                 .build_gep(elem_llvm_ty, data_ptr, &[index], "elem_ptr")
                 .unwrap()
-        };
-        builder.build_load(elem_llvm_ty, elem_ptr, name).unwrap()
+        }
     }
 
     // ---- Type casts ----
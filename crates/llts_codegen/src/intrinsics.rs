@@ -32,8 +32,6 @@ impl<'ctx> Intrinsics<'ctx> {
         self.declare_math_intrinsics(module);
         self.declare_puts(module);
         self.declare_exit(module);
-        self.declare_setjmp(module);
-        self.declare_longjmp(module);
         self.declare_memcpy(module);
     }
 
@@ -616,7 +614,7 @@ impl<'ctx> Intrinsics<'ctx> {
         module.add_function("memcpy", fn_type, None)
     }
 
-    // ---- Error handling intrinsics (setjmp/longjmp/exit) ----
+    // ---- Error handling intrinsics (exit) ----
 
     /// `exit(status: i32) -> void` — libc exit.
     fn declare_exit(&mut self, module: &Module<'ctx>) -> FunctionValue<'ctx> {
@@ -631,33 +629,6 @@ impl<'ctx> Intrinsics<'ctx> {
         f
     }
 
-    /// `setjmp(buf: *i8) -> i32` — POSIX setjmp.
-    fn declare_setjmp(&mut self, module: &Module<'ctx>) -> FunctionValue<'ctx> {
-        if let Some(&f) = self.cache.get("setjmp") {
-            return f;
-        }
-        let i32_ty = self.context.i32_type();
-        let ptr_ty = self.context.ptr_type(AddressSpace::default());
-        let fn_type = i32_ty.fn_type(&[ptr_ty.into()], false);
-        let f = module.add_function("setjmp", fn_type, None);
-        self.cache.insert("setjmp".to_string(), f);
-        f
-    }
-
-    /// `longjmp(buf: *i8, val: i32) -> void` — POSIX longjmp.
-    fn declare_longjmp(&mut self, module: &Module<'ctx>) -> FunctionValue<'ctx> {
-        if let Some(&f) = self.cache.get("longjmp") {
-            return f;
-        }
-        let i32_ty = self.context.i32_type();
-        let ptr_ty = self.context.ptr_type(AddressSpace::default());
-        let void_ty = self.context.void_type();
-        let fn_type = void_ty.fn_type(&[ptr_ty.into(), i32_ty.into()], false);
-        let f = module.add_function("longjmp", fn_type, None);
-        self.cache.insert("longjmp".to_string(), f);
-        f
-    }
-
     /// Emit a write to stderr (fd=2). `str_val` must be a string fat pointer `{ ptr, len }`.
     pub fn build_write_stderr(
         &mut self,
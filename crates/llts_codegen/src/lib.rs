@@ -1,4 +1,6 @@
 pub mod call;
+pub mod collections;
+pub mod executor;
 pub mod expr;
 pub mod intrinsics;
 pub mod memory;
@@ -12,11 +14,12 @@ use std::collections::HashMap;
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
-use inkwell::module::Module;
-use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::module::{Linkage, Module};
+use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
 use inkwell::AddressSpace;
 
 use crate::call::CallCodegen;
+use crate::executor::ExecutorCodegen;
 use crate::expr::{BinOp, ExprCodegen, LogicalOp, UnaryOp};
 use crate::intrinsics::Intrinsics;
 use crate::memory::MemoryManager;
@@ -70,13 +73,6 @@ pub enum Stmt {
         target: String,
         value: Expr,
     },
-    /// `obj.field = value;`
-    FieldAssign {
-        object_name: String,
-        object_type: LltsType,
-        field_index: u32,
-        value: Expr,
-    },
     /// `if (cond) { then } else { else }`
     If {
         condition: Expr,
@@ -117,13 +113,36 @@ pub enum Stmt {
     Break,
     /// `continue;`
     Continue,
-    /// `throw expr;`
+    /// `throw expr;`, with the thrown value wrapped in an error struct (see
+    /// [`types::error_type`]).
     Throw(Expr),
-    /// `try { ... } catch (e) { ... }`
+    /// `try { ... } catch (e) { ... } finally { ... }`. `catch_param` is
+    /// bound to the error struct of the caught throw. Without `catch_body`,
+    /// throws pass through. `finally_body` runs on every exit from the try
+    /// and catch bodies: falling through, `return`, `break`, `continue` and
+    /// throws.
     TryCatch {
         try_body: Vec<Stmt>,
         catch_param: Option<String>,
-        catch_body: Vec<Stmt>,
+        catch_body: Option<Vec<Stmt>>,
+        finally_body: Option<Vec<Stmt>>,
+    },
+    /// Bind a captured variable inside a closure body. When `by_ref` is set,
+    /// `name` resolves to the pointer stored in slot `field_index` of the
+    /// `__env` parameter; otherwise the slot itself holds a copy of the value.
+    CaptureBind {
+        name: String,
+        ty: LltsType,
+        env_type: LltsType,
+        field_index: u32,
+        by_ref: bool,
+    },
+    /// `let name: ty = init;` for a variable captured mutably by an escaping
+    /// closure: storage is a refcounted heap box instead of a stack slot.
+    BoxDecl {
+        name: String,
+        ty: LltsType,
+        init: Option<Expr>,
     },
 }
 
@@ -186,6 +205,32 @@ pub enum Expr {
         index: Box<Expr>,
         elem_type: LltsType,
     },
+    /// Assignment used anywhere: store into the place `target` and yield
+    /// the stored value. With `op`, `target op= value`, loading the current
+    /// value through the same address. `ty` is the place's type.
+    ///
+    /// With `current`, the place's value before the store is bound to that
+    /// name while `value` is evaluated (`??=`, `||=`, `&&=`), so the place
+    /// is still computed once.
+    ///
+    /// `target` is a place expression: a `Var`, a `FieldAccess` of a place,
+    /// or an `ArrayIndex` of any array value (elements live on the heap).
+    Assign {
+        target: Box<Expr>,
+        op: Option<BinOp>,
+        current: Option<String>,
+        value: Box<Expr>,
+        ty: LltsType,
+    },
+    /// `++target` / `target++` (`op` Add) and `--target` / `target--` (Sub)
+    /// on the place `target`, yielding the new value if `prefix`, else the
+    /// old one.
+    Update {
+        target: Box<Expr>,
+        op: BinOp,
+        prefix: bool,
+        ty: LltsType,
+    },
     /// Type cast: `value as TargetType`.
     Cast {
         value: Box<Expr>,
@@ -202,6 +247,12 @@ pub enum Expr {
         elem_type: LltsType,
         elements: Vec<Expr>,
     },
+    /// New array holding the elements of each array in `parts` in order:
+    /// `[...a, b, ...c]` with `[b]` as an `ArrayLit` part.
+    ArrayConcat {
+        elem_type: LltsType,
+        parts: Vec<Expr>,
+    },
     /// Fat pointer call (call through function value).
     IndirectCall {
         callee: Box<Expr>,
@@ -235,12 +286,75 @@ pub enum Expr {
     OptionIsSome { value: Box<Expr>, inner_type: LltsType },
     /// Unwrap an Option<T>, extracting the inner T value.
     OptionUnwrap { value: Box<Expr>, inner_type: LltsType },
+    /// Checked unwrap (`x!`): the inner T value, or a thrown `TypeError` if
+    /// the option is None.
+    OptionExpect { value: Box<Expr>, inner_type: LltsType },
+    /// Optional chaining step (`x?.…`): if `value` is Some, bind its inner
+    /// value to `binding` and evaluate `body`, an `Option<ty>`; otherwise None.
+    /// With `ty` Void, `body` is only evaluated for its effect.
+    OptionAndThen {
+        value: Box<Expr>,
+        inner_type: LltsType,
+        binding: String,
+        body: Box<Expr>,
+        ty: LltsType,
+    },
+    /// Nullish coalescing (`value ?? fallback`): the inner value of `value`
+    /// if it is Some, else `fallback`, which is only evaluated then. With `ty`
+    /// an Option (nullable fallback), `value` itself is the result when Some.
+    Coalesce {
+        value: Box<Expr>,
+        fallback: Box<Expr>,
+        ty: LltsType,
+    },
+    /// `Ok` result of type `result_type`, holding `value` (`None` when the
+    /// Ok type is Void).
+    ResultOk { value: Option<Box<Expr>>, result_type: LltsType },
+    /// Checked unwrap of a call to a throwing function, whose `Result` has
+    /// the error struct as its error type: the Ok value, or the error thrown
+    /// on as if by `throw`.
+    ResultPropagate { value: Box<Expr>, ok_type: LltsType },
     /// Discriminated union literal: construct a tagged union value from a tag and payload fields.
     UnionLit {
         tag: u32,
         payload: Box<Expr>,
         union_type: LltsType,
     },
+    /// Copy `value` into a refcounted heap cell, yielding a `Ptr` to it (the
+    /// payload of a thrown error).
+    BoxValue { value: Box<Expr>, ty: LltsType },
+    /// Load the `ty` value held by the heap cell `ptr` points to.
+    UnboxValue { ptr: Box<Expr>, ty: LltsType },
+    /// Closure value: `{ fn_ptr, env_ptr }` where the env is a struct of
+    /// pointers to the captured variables (empty `captures` → null env).
+    /// Escaping closures get a refcounted heap env instead: `boxed` tells,
+    /// per capture, whether its slot points at a capture box or holds a copy
+    /// of the value. The env is released with the last copy of the closure.
+    Closure {
+        function: String,
+        captures: Vec<String>,
+        env_type: LltsType,
+        ty: LltsType,
+        boxed: Option<Vec<bool>>,
+    },
+    /// Class value viewed through an interface or base-class type: the value
+    /// is copied into a refcounted heap cell and paired with the global
+    /// vtable `vtable`, whose slots are the functions named in `slots`.
+    DynCoerce {
+        value: Box<Expr>,
+        vtable: String,
+        slots: Vec<String>,
+        ty: LltsType,
+    },
+    /// Dynamic dispatch: call vtable slot `slot` of a `Dyn` value, passing its
+    /// data pointer as the hidden first argument.
+    DynCall {
+        receiver: Box<Expr>,
+        slot: u32,
+        args: Vec<Expr>,
+        param_types: Vec<LltsType>,
+        ret_type: LltsType,
+    },
 }
 
 /// Top-level program IR — the full compilation unit.
@@ -251,6 +365,16 @@ pub struct ProgramIR {
     pub functions: Vec<FunctionDecl>,
 }
 
+/// A `finally` body being run on exit from its try block, with the depths of
+/// the catch, break and continue target stacks outside that block.
+#[derive(Clone)]
+struct FinallyScope {
+    body: Vec<Stmt>,
+    catch_depth: usize,
+    break_depth: usize,
+    continue_depth: usize,
+}
+
 /// The main code generator. Holds LLVM context, module, builder, and all
 /// sub-systems (type registry, memory manager, intrinsics).
 ///
@@ -278,8 +402,23 @@ pub struct CodeGenerator<'ctx> {
     break_targets: Vec<BasicBlock<'ctx>>,
     /// Continue target stack (for nested loops).
     continue_targets: Vec<BasicBlock<'ctx>>,
-    /// Stack of jmp_buf pointers for try/catch (setjmp/longjmp).
-    jmp_buf_stack: Vec<PointerValue<'ctx>>,
+    /// Stack of (catch block, error slot) of the enclosing try blocks: a
+    /// throw stores its error struct in the innermost slot and branches to
+    /// the handler.
+    catch_targets: Vec<(BasicBlock<'ctx>, PointerValue<'ctx>)>,
+    /// `finally` bodies of the enclosing try blocks, outermost first.
+    finally_scopes: Vec<FinallyScope>,
+    /// `Result` type the current function returns, if any: its uncaught
+    /// throws go back to the caller as `Err` (see [`Expr::ResultPropagate`]),
+    /// holding the error struct or, for a declared `Result<T, E>`, the
+    /// thrown value itself.
+    throws_to_caller: Option<LltsType>,
+    /// Capture boxes owned by the current frame: variable name → entry-block
+    /// slot holding the box pointer (null until the declaration runs).
+    box_slots: HashMap<String, PointerValue<'ctx>>,
+    /// Slots of the current frame holding closures (locals and parameters
+    /// whose type [holds closures](Self::holds_closures)), released at exit.
+    frame_values: Vec<(PointerValue<'ctx>, LltsType)>,
 }
 
 impl<'ctx> CodeGenerator<'ctx> {
@@ -303,7 +442,11 @@ impl<'ctx> CodeGenerator<'ctx> {
             current_function: None,
             break_targets: Vec::new(),
             continue_targets: Vec::new(),
-            jmp_buf_stack: Vec::new(),
+            catch_targets: Vec::new(),
+            finally_scopes: Vec::new(),
+            throws_to_caller: None,
+            box_slots: HashMap::new(),
+            frame_values: Vec::new(),
         }
     }
 
@@ -389,6 +532,10 @@ impl<'ctx> CodeGenerator<'ctx> {
         for func in &program.functions {
             let function = self.functions[&func.name];
             self.current_function = Some(function);
+            self.throws_to_caller = match &func.ret_type {
+                LltsType::Result { .. } => Some(func.ret_type.clone()),
+                _ => None,
+            };
 
             // Save outer variable scope.
             let outer_vars = self.variables.clone();
@@ -406,6 +553,11 @@ impl<'ctx> CodeGenerator<'ctx> {
                 ).unwrap();
                 self.builder.build_store(alloca, param_val).unwrap();
                 self.variables.insert(name.clone(), (alloca, ty.clone()));
+                // The frame holds its own reference to closure arguments.
+                if Self::holds_closures(ty) {
+                    self.build_value_retain(param_val, ty);
+                    self.frame_values.push((alloca, ty.clone()));
+                }
             }
 
             // Emit body.
@@ -415,6 +567,7 @@ impl<'ctx> CodeGenerator<'ctx> {
 
             // If there's no terminator, add one.
             if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                self.release_frame();
                 if func.name == "main" && matches!(func.ret_type, LltsType::Void) {
                     // main() returns i32 0 to the C runtime.
                     let zero = self.context.i32_type().const_int(0, false);
@@ -429,7 +582,10 @@ impl<'ctx> CodeGenerator<'ctx> {
 
             // Restore outer variable scope.
             self.variables = outer_vars;
+            self.box_slots.clear();
+            self.frame_values.clear();
             self.current_function = None;
+            self.throws_to_caller = None;
         }
     }
 
@@ -437,6 +593,20 @@ impl<'ctx> CodeGenerator<'ctx> {
 
     fn emit_stmt(&mut self, stmt: &Stmt) {
         match stmt {
+            Stmt::VarDecl { name, ty, init } if Self::holds_closures(ty) => {
+                // A slot in the entry block, so it can be released at any
+                // exit. A declaration re-run by a loop drops the previous value.
+                let init_val = init.as_ref().map(|e| self.emit_owned(e));
+                let llvm_ty = self.registry.llvm_type(ty);
+                let slot = self.build_entry_slot(llvm_ty.const_zero(), name);
+                let old = self.builder.build_load(llvm_ty, slot, "old").unwrap();
+                self.builder
+                    .build_store(slot, init_val.unwrap_or_else(|| llvm_ty.const_zero()))
+                    .unwrap();
+                self.build_value_release(old, ty);
+                self.frame_values.push((slot, ty.clone()));
+                self.variables.insert(name.clone(), (slot, ty.clone()));
+            }
             Stmt::VarDecl { name, ty, init } => {
                 let init_val = init.as_ref().map(|e| self.emit_expr(e));
                 let alloca = StmtCodegen::build_var_decl(
@@ -449,17 +619,9 @@ impl<'ctx> CodeGenerator<'ctx> {
                 self.variables.insert(name.clone(), (alloca, ty.clone()));
             }
             Stmt::Assign { target, value } => {
-                let val = self.emit_expr(value);
-                let (ptr, _) = self.variables[target].clone();
-                StmtCodegen::build_assignment(&self.builder, ptr, val);
-            }
-            Stmt::FieldAssign { object_name, object_type, field_index, value } => {
-                let val = self.emit_expr(value);
-                let (ptr, _) = self.variables[object_name].clone();
-                let field_ptr = ExprCodegen::build_struct_field_access(
-                    &self.builder, &mut self.registry, ptr, object_type, *field_index, "field_store",
-                );
-                StmtCodegen::build_assignment(&self.builder, field_ptr, val);
+                let val = self.emit_owned(value);
+                let (ptr, ty) = self.variables[target].clone();
+                self.build_store_replacing(ptr, val, &ty);
             }
             Stmt::If {
                 condition,
@@ -688,11 +850,15 @@ impl<'ctx> CodeGenerator<'ctx> {
                 self.builder.position_at_end(end_bb);
             }
             Stmt::Return(expr) => {
-                if let Some(e) = expr {
-                    let val = self.emit_expr(e);
-                    self.builder.build_return(Some(&val)).unwrap();
-                } else {
-                    self.builder.build_return(None).unwrap();
+                // The value is computed before any `finally` body runs.
+                let val = expr.as_ref().map(|e| self.emit_owned(e));
+                self.emit_finally_from(0);
+                if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                    self.release_frame();
+                    match val {
+                        Some(val) => self.builder.build_return(Some(&val)).unwrap(),
+                        None => self.builder.build_return(None).unwrap(),
+                    };
                 }
             }
             Stmt::Block(stmts) => {
@@ -703,7 +869,12 @@ impl<'ctx> CodeGenerator<'ctx> {
                 self.variables = outer_vars;
             }
             Stmt::Expr(expr) => {
-                self.emit_expr(expr);
+                let val = self.emit_expr(expr);
+                // Nothing keeps a new closure that is not stored.
+                let ty = self.infer_expr_type(expr);
+                if Self::holds_closures(&ty) && Self::is_fresh(expr) {
+                    self.build_value_release(val, &ty);
+                }
             }
             Stmt::Switch {
                 discriminant,
@@ -826,149 +997,543 @@ impl<'ctx> CodeGenerator<'ctx> {
             }
             Stmt::Break => {
                 if let Some(&target) = self.break_targets.last() {
-                    self.builder.build_unconditional_branch(target).unwrap();
+                    let depth = self.break_targets.len();
+                    self.emit_finally_from(self.finally_start(|scope| scope.break_depth >= depth));
+                    if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                        self.builder.build_unconditional_branch(target).unwrap();
+                    }
                 }
             }
             Stmt::Continue => {
                 if let Some(&target) = self.continue_targets.last() {
-                    self.builder.build_unconditional_branch(target).unwrap();
+                    let depth = self.continue_targets.len();
+                    self.emit_finally_from(self.finally_start(|scope| scope.continue_depth >= depth));
+                    if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                        self.builder.build_unconditional_branch(target).unwrap();
+                    }
                 }
             }
-            Stmt::Throw(expr) => {
-                let val = self.emit_expr(expr);
-
-                // Write the thrown value to stderr as a string.
-                let ty = self.infer_expr_type(expr);
-                let str_val = if matches!(ty, LltsType::String) {
-                    val
-                } else {
-                    // Convert to "[object]\n" fallback.
-                    ExprCodegen::const_string(
-                        &self.builder,
-                        &self.module,
-                        self.context,
-                        &self.registry,
-                        "[thrown]",
-                        "thrown_str",
-                    )
-                };
-
-                // Write error message to stderr.
-                self.intrinsics.build_write_stderr(&self.builder, &self.module, str_val);
-                // Write newline to stderr.
-                let newline = ExprCodegen::const_string(
-                    &self.builder,
-                    &self.module,
-                    self.context,
-                    &self.registry,
-                    "\n",
-                    "newline",
-                );
-                self.intrinsics.build_write_stderr(&self.builder, &self.module, newline);
-
-                if let Some(&jmp_buf_ptr) = self.jmp_buf_stack.last() {
-                    // Inside a try block: longjmp back to the catch handler.
-                    let longjmp = self.intrinsics.get("longjmp").expect("longjmp not declared");
-                    let one = self.context.i32_type().const_int(1, false);
-                    self.builder
-                        .build_call(longjmp, &[jmp_buf_ptr.into(), one.into()], "")
-                        .unwrap();
-                    self.builder.build_unreachable().unwrap();
-                } else {
-                    // No enclosing try block: call exit(1).
-                    let exit_fn = self.intrinsics.get("exit").expect("exit not declared");
-                    let one = self.context.i32_type().const_int(1, false);
-                    self.builder
-                        .build_call(exit_fn, &[one.into()], "")
-                        .unwrap();
-                    self.builder.build_unreachable().unwrap();
-                }
+            Stmt::Throw(error) => {
+                let error_val = self.emit_expr(error);
+                self.emit_throw(error_val);
             }
             Stmt::TryCatch {
                 try_body,
                 catch_param,
                 catch_body,
+                finally_body,
             } => {
                 let function = self.current_function.unwrap();
                 let try_stmts = try_body.clone();
                 let catch_stmts = catch_body.clone();
                 let catch_param = catch_param.clone();
+                let finally_stmts = finally_body.clone();
+
+                // Pushed outside the catch target: throws the catch handles
+                // skip it, while throws out of the catch body run it.
+                if let Some(body) = &finally_stmts {
+                    self.finally_scopes.push(FinallyScope {
+                        body: body.clone(),
+                        catch_depth: self.catch_targets.len(),
+                        break_depth: self.break_targets.len(),
+                        continue_depth: self.continue_targets.len(),
+                    });
+                }
 
-                // Allocate a jmp_buf on the stack (200 bytes, enough for any platform).
-                let i8_ty = self.context.i8_type();
-                let jmp_buf_ty = i8_ty.array_type(200);
-                let jmp_buf_alloca = self.builder.build_alloca(jmp_buf_ty, "jmp_buf").unwrap();
-                let ptr_ty = self.context.ptr_type(AddressSpace::default());
-                let jmp_buf_ptr = self.builder
-                    .build_pointer_cast(jmp_buf_alloca, ptr_ty, "jmp_buf_ptr")
-                    .unwrap();
-
-                // Call setjmp(jmp_buf). Returns 0 on first call, non-zero from longjmp.
-                let setjmp = self.intrinsics.get("setjmp").expect("setjmp not declared");
-                let setjmp_result = self.builder
-                    .build_call(setjmp, &[jmp_buf_ptr.into()], "setjmp_res")
-                    .unwrap()
-                    .try_as_basic_value()
-                    .unwrap_basic()
-                    .into_int_value();
-
-                let zero = self.context.i32_type().const_int(0, false);
-                let is_normal = self.builder
-                    .build_int_compare(inkwell::IntPredicate::EQ, setjmp_result, zero, "is_normal")
-                    .unwrap();
+                // The error slot a throw in the try body fills before
+                // branching to the handler.
+                let error_type = types::error_type();
+                let handler = catch_stmts.as_ref().map(|_| {
+                    let error_llvm_ty = self.registry.llvm_type(&error_type);
+                    let error_slot = self.builder.build_alloca(error_llvm_ty, "error_slot").unwrap();
+                    let catch_bb = self.context.append_basic_block(function, "catch_body");
+                    (catch_bb, error_slot)
+                });
 
                 let try_bb = self.context.append_basic_block(function, "try_body");
-                let catch_bb = self.context.append_basic_block(function, "catch_body");
                 let merge_bb = self.context.append_basic_block(function, "try_merge");
+                self.builder.build_unconditional_branch(try_bb).unwrap();
 
-                self.builder
-                    .build_conditional_branch(is_normal, try_bb, catch_bb)
-                    .unwrap();
-
-                // Try body: push jmp_buf so throw can find it.
+                // Try body: throws inside it branch to the handler.
                 self.builder.position_at_end(try_bb);
-                self.jmp_buf_stack.push(jmp_buf_ptr);
+                if let Some(target) = handler {
+                    self.catch_targets.push(target);
+                }
                 for s in &try_stmts {
                     if self.builder.get_insert_block().unwrap().get_terminator().is_some() {
                         break;
                     }
                     self.emit_stmt(s);
                 }
-                self.jmp_buf_stack.pop();
+                if handler.is_some() {
+                    self.catch_targets.pop();
+                }
                 if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
                     self.builder.build_unconditional_branch(merge_bb).unwrap();
                 }
 
                 // Catch body.
-                self.builder.position_at_end(catch_bb);
-                if let Some(param_name) = &catch_param {
-                    // Bind catch parameter as a string with value "error".
-                    // (In v1, we don't carry the thrown value through longjmp.)
-                    let err_str = ExprCodegen::const_string(
+                if let (Some((catch_bb, error_slot)), Some(catch_stmts)) = (handler, &catch_stmts) {
+                    self.builder.position_at_end(catch_bb);
+                    if let Some(param_name) = &catch_param {
+                        // The catch parameter is the error slot itself.
+                        self.variables.insert(param_name.clone(), (error_slot, error_type));
+                    }
+                    for s in catch_stmts {
+                        if self.builder.get_insert_block().unwrap().get_terminator().is_some() {
+                            break;
+                        }
+                        self.emit_stmt(s);
+                    }
+                    if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
+                        self.builder.build_unconditional_branch(merge_bb).unwrap();
+                    }
+                }
+
+                // Finally body, on the fall-through path.
+                self.builder.position_at_end(merge_bb);
+                if let Some(body) = finally_stmts {
+                    self.finally_scopes.pop();
+                    self.emit_stmt(&Stmt::Block(body));
+                }
+            }
+            Stmt::CaptureBind {
+                name,
+                ty,
+                env_type,
+                field_index,
+                by_ref,
+            } => {
+                // Load the env pointer, then the captured variable's address
+                // from its slot. The variable aliases the captured storage, so
+                // reads and writes go straight through to the enclosing frame
+                // (or to the capture box, for escaping closures).
+                let (env_slot, _) = self.variables["__env"].clone();
+                let ptr_ty = self.context.ptr_type(AddressSpace::default());
+                let env_ptr = self.builder
+                    .build_load(ptr_ty, env_slot, "env")
+                    .unwrap()
+                    .into_pointer_value();
+                let env_ptr = self.memory.build_env_address(&self.builder, env_ptr);
+                let var_ptr = if *by_ref {
+                    ExprCodegen::build_load_struct_field(
                         &self.builder,
-                        &self.module,
-                        self.context,
-                        &self.registry,
-                        "error",
-                        "catch_err",
-                    );
-                    let str_ty = self.registry.llvm_type(&LltsType::String);
-                    let alloca = self.builder.build_alloca(str_ty, param_name).unwrap();
-                    self.builder.build_store(alloca, err_str).unwrap();
-                    self.variables.insert(param_name.clone(), (alloca, LltsType::String));
+                        &mut self.registry,
+                        env_ptr,
+                        env_type,
+                        *field_index,
+                        &LltsType::Ptr,
+                        name,
+                    )
+                    .into_pointer_value()
+                } else {
+                    // Copied capture: the value lives in the env slot itself.
+                    ExprCodegen::build_struct_field_access(
+                        &self.builder,
+                        &mut self.registry,
+                        env_ptr,
+                        env_type,
+                        *field_index,
+                        name,
+                    )
+                };
+                self.variables.insert(name.clone(), (var_ptr, ty.clone()));
+            }
+            Stmt::BoxDecl { name, ty, init } => {
+                let init_val = init.as_ref().map(|e| self.emit_owned(e));
+                let box_ptr = self.memory.build_rc_alloc(
+                    &self.builder,
+                    &self.module,
+                    &mut self.registry,
+                    ty,
+                    name,
+                );
+                if let Some(val) = init_val {
+                    self.builder.build_store(box_ptr, val).unwrap();
                 }
-                for s in &catch_stmts {
-                    if self.builder.get_insert_block().unwrap().get_terminator().is_some() {
-                        break;
-                    }
-                    self.emit_stmt(s);
+
+                // The frame holds one reference, dropped at function exit. A
+                // declaration re-run by a loop drops the previous iteration's box.
+                let slot = self.box_slots.get(name).copied().unwrap_or_else(|| {
+                    let slot = self.build_entry_null_ptr(&format!("{name}_box"));
+                    self.box_slots.insert(name.clone(), slot);
+                    slot
+                });
+                self.build_release_slot(slot);
+                self.builder.build_store(slot, box_ptr).unwrap();
+
+                self.variables.insert(name.clone(), (box_ptr, ty.clone()));
+            }
+        }
+    }
+
+    // ---- Capture boxes ----
+
+    /// Emit a pointer-sized alloca at the top of the entry block, initialized
+    /// to null, so it dominates every use regardless of where it is requested.
+    fn build_entry_null_ptr(&mut self, name: &str) -> PointerValue<'ctx> {
+        let function = self.current_function.unwrap();
+        let entry = function.get_first_basic_block().unwrap();
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        let slot = entry_builder.build_alloca(ptr_ty, name).unwrap();
+        entry_builder.build_store(slot, ptr_ty.const_null()).unwrap();
+        slot
+    }
+
+    /// Release the box held in `slot`, if any.
+    fn build_release_slot(&mut self, slot: PointerValue<'ctx>) {
+        let function = self.current_function.unwrap();
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+        let box_ptr = self.builder
+            .build_load(ptr_ty, slot, "box_ptr")
+            .unwrap()
+            .into_pointer_value();
+        let is_set = self.builder.build_is_not_null(box_ptr, "box_set").unwrap();
+
+        let release_bb = self.context.append_basic_block(function, "box_release");
+        let cont_bb = self.context.append_basic_block(function, "box_cont");
+        self.builder
+            .build_conditional_branch(is_set, release_bb, cont_bb)
+            .unwrap();
+
+        self.builder.position_at_end(release_bb);
+        self.memory.build_release(&self.builder, &self.module, function, box_ptr);
+        self.builder.build_unconditional_branch(cont_bb).unwrap();
+
+        self.builder.position_at_end(cont_bb);
+    }
+
+    /// Drop the frame's references, to closure values and capture boxes,
+    /// before returning.
+    fn release_frame(&mut self) {
+        for (slot, ty) in self.frame_values.clone() {
+            let llvm_ty = self.registry.llvm_type(&ty);
+            let val = self.builder.build_load(llvm_ty, slot, "frame_value").unwrap();
+            self.build_value_release(val, &ty);
+        }
+        let slots: Vec<PointerValue<'ctx>> = self.box_slots.values().copied().collect();
+        for slot in slots {
+            self.build_release_slot(slot);
+        }
+    }
+
+    // ---- Closure values ----
+
+    /// Whether values of `ty` can hold a closure with a counted env: a
+    /// function, or a struct or option containing one. Array elements are
+    /// owned by the (never freed) buffer and are not counted.
+    fn holds_closures(ty: &LltsType) -> bool {
+        match ty {
+            LltsType::Function { .. } => true,
+            LltsType::Struct { fields, .. } => fields.iter().any(|(_, t)| Self::holds_closures(t)),
+            LltsType::Option(inner) => Self::holds_closures(inner),
+            _ => false,
+        }
+    }
+
+    /// Whether `expr` evaluates to a new reference, which the consumer
+    /// takes over, rather than a copy of one held elsewhere.
+    fn is_fresh(expr: &Expr) -> bool {
+        match expr {
+            Expr::Closure { .. }
+            | Expr::Call { .. }
+            | Expr::IndirectCall { .. }
+            | Expr::DynCall { .. }
+            | Expr::ConstructorCall { .. }
+            | Expr::StructLit { .. }
+            | Expr::OptionSome { .. }
+            | Expr::ResultOk { .. } => true,
+            Expr::MethodCall { class_name, method_name, .. } => {
+                !class_name.starts_with("__") || (class_name == "__Array" && method_name == "pop")
+            }
+            Expr::ResultPropagate { value, .. }
+            | Expr::OptionUnwrap { value, .. }
+            | Expr::OptionExpect { value, .. } => Self::is_fresh(value),
+            _ => false,
+        }
+    }
+
+    /// Emit `expr` as a value about to be stored: a copy of a value held
+    /// elsewhere takes its own reference to the closures it contains.
+    fn emit_owned(&mut self, expr: &Expr) -> BasicValueEnum<'ctx> {
+        let val = self.emit_expr(expr);
+        let ty = self.infer_expr_type(expr);
+        if Self::holds_closures(&ty) && !Self::is_fresh(expr) {
+            self.build_value_retain(val, &ty);
+        }
+        val
+    }
+
+    /// Store the owned `val` at `ptr`, releasing the value it replaces.
+    fn build_store_replacing(&mut self, ptr: PointerValue<'ctx>, val: BasicValueEnum<'ctx>, ty: &LltsType) {
+        if !Self::holds_closures(ty) {
+            StmtCodegen::build_assignment(&self.builder, ptr, val);
+            return;
+        }
+        let llvm_ty = self.registry.llvm_type(ty);
+        let old = self.builder.build_load(llvm_ty, ptr, "replaced").unwrap();
+        StmtCodegen::build_assignment(&self.builder, ptr, val);
+        self.build_value_release(old, ty);
+    }
+
+    /// Emit an alloca of `init`'s type at the top of the entry block,
+    /// initialized to `init`.
+    fn build_entry_slot(&mut self, init: BasicValueEnum<'ctx>, name: &str) -> PointerValue<'ctx> {
+        let function = self.current_function.unwrap();
+        let entry = function.get_first_basic_block().unwrap();
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        let slot = entry_builder.build_alloca(init.get_type(), name).unwrap();
+        entry_builder.build_store(slot, init).unwrap();
+        slot
+    }
+
+    /// Retain every closure env held by `val`, of type `ty`.
+    fn build_value_retain(&mut self, val: BasicValueEnum<'ctx>, ty: &LltsType) {
+        self.build_value_refs(val, ty, true);
+    }
+
+    /// Release every closure env held by `val`, of type `ty`.
+    fn build_value_release(&mut self, val: BasicValueEnum<'ctx>, ty: &LltsType) {
+        self.build_value_refs(val, ty, false);
+    }
+
+    fn build_value_refs(&mut self, val: BasicValueEnum<'ctx>, ty: &LltsType, retain: bool) {
+        if !Self::holds_closures(ty) || !val.is_struct_value() {
+            return;
+        }
+        let function = self.current_function.unwrap();
+        let agg = val.into_struct_value();
+        match ty {
+            LltsType::Function { .. } => {
+                let env = self.builder
+                    .build_extract_value(agg, 1, "env")
+                    .unwrap()
+                    .into_pointer_value();
+                if retain {
+                    self.memory.build_closure_retain(&self.builder, function, env);
+                } else {
+                    self.memory.build_closure_release(&self.builder, &self.module, function, env);
                 }
-                if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
-                    self.builder.build_unconditional_branch(merge_bb).unwrap();
+            }
+            LltsType::Struct { fields, .. } => {
+                for (i, (_, field_ty)) in fields.iter().enumerate() {
+                    if Self::holds_closures(field_ty) {
+                        let field = self.builder.build_extract_value(agg, i as u32, "field").unwrap();
+                        self.build_value_refs(field, field_ty, retain);
+                    }
                 }
+            }
+            LltsType::Option(inner) => {
+                let tag = self.builder
+                    .build_extract_value(agg, 0, "tag")
+                    .unwrap()
+                    .into_int_value();
+                let some_bb = self.context.append_basic_block(function, "refs_some");
+                let cont_bb = self.context.append_basic_block(function, "refs_cont");
+                self.builder.build_conditional_branch(tag, some_bb, cont_bb).unwrap();
+                self.builder.position_at_end(some_bb);
+                let payload = self.builder.build_extract_value(agg, 1, "payload").unwrap();
+                self.build_value_refs(payload, inner, retain);
+                self.builder.build_unconditional_branch(cont_bb).unwrap();
+                self.builder.position_at_end(cont_bb);
+            }
+            _ => {}
+        }
+    }
 
-                self.builder.position_at_end(merge_bb);
+    /// Retain the closures held by each element of array `arr`.
+    fn build_elements_retain(&mut self, arr: BasicValueEnum<'ctx>, elem_type: &LltsType) {
+        let function = self.current_function.unwrap();
+        let i64_ty = self.context.i64_type();
+        let elem_llvm_ty = self.registry.llvm_type(elem_type);
+        let arr = arr.into_struct_value();
+        let data = self.builder.build_extract_value(arr, 0, "data").unwrap().into_pointer_value();
+        let len = self.builder.build_extract_value(arr, 1, "len").unwrap().into_int_value();
+
+        let pre_bb = self.builder.get_insert_block().unwrap();
+        let cond_bb = self.context.append_basic_block(function, "retain_cond");
+        let body_bb = self.context.append_basic_block(function, "retain_body");
+        let done_bb = self.context.append_basic_block(function, "retain_done");
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(cond_bb);
+        let index = self.builder.build_phi(i64_ty, "index").unwrap();
+        index.add_incoming(&[(&i64_ty.const_zero(), pre_bb)]);
+        let i = index.as_basic_value().into_int_value();
+        let more = self.builder
+            .build_int_compare(inkwell::IntPredicate::ULT, i, len, "more")
+            .unwrap();
+        self.builder.build_conditional_branch(more, body_bb, done_bb).unwrap();
+
+        self.builder.position_at_end(body_bb);
+        let elem_ptr = unsafe {
+            self.builder.build_gep(elem_llvm_ty, data, &[i], "elem_ptr").unwrap()
+        };
+        let elem = self.builder.build_load(elem_llvm_ty, elem_ptr, "elem").unwrap();
+        self.build_value_retain(elem, elem_type);
+        let next = self.builder.build_int_add(i, i64_ty.const_int(1, false), "next").unwrap();
+        let latch_bb = self.builder.get_insert_block().unwrap();
+        index.add_incoming(&[(&next, latch_bb)]);
+        self.builder.build_unconditional_branch(cond_bb).unwrap();
+
+        self.builder.position_at_end(done_bb);
+    }
+
+    /// The function releasing what the env of closure `function` holds:
+    /// its reference to each capture box in `boxed` slots, and the closures
+    /// held by the copied values.
+    fn closure_env_drop(&mut self, function: &str, env_type: &LltsType, boxed: &[bool]) -> FunctionValue<'ctx> {
+        let name = format!("{function}_env_drop");
+        if let Some(existing) = self.module.get_function(&name) {
+            return existing;
+        }
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+        let fn_type = self.context.void_type().fn_type(&[ptr_ty.into()], false);
+        let drop = self.module.add_function(&name, fn_type, Some(Linkage::Internal));
+
+        let saved_block = self.builder.get_insert_block();
+        let saved_function = self.current_function.replace(drop);
+        self.builder.position_at_end(self.context.append_basic_block(drop, "entry"));
+
+        let env_ptr = drop.get_nth_param(0).unwrap().into_pointer_value();
+        let slot_types: Vec<LltsType> = match env_type {
+            LltsType::Struct { fields, .. } => fields.iter().map(|(_, t)| t.clone()).collect(),
+            _ => Vec::new(),
+        };
+        for (i, slot_ty) in slot_types.iter().enumerate() {
+            let is_box = boxed.get(i).copied().unwrap_or(false);
+            if !is_box && !Self::holds_closures(slot_ty) {
+                continue;
+            }
+            let slot = ExprCodegen::build_struct_field_access(
+                &self.builder,
+                &mut self.registry,
+                env_ptr,
+                env_type,
+                i as u32,
+                "env_slot",
+            );
+            if is_box {
+                let box_ptr = self.builder.build_load(ptr_ty, slot, "box_ptr").unwrap().into_pointer_value();
+                self.memory.build_release(&self.builder, &self.module, drop, box_ptr);
+            } else {
+                let llvm_ty = self.registry.llvm_type(slot_ty);
+                let val = self.builder.build_load(llvm_ty, slot, "captured").unwrap();
+                self.build_value_release(val, slot_ty);
+            }
+        }
+        self.builder.build_return(None).unwrap();
+
+        self.current_function = saved_function;
+        if let Some(block) = saved_block {
+            self.builder.position_at_end(block);
+        }
+        drop
+    }
+
+    /// Index of the first `finally` scope that an exit leaves, given which
+    /// scopes it leaves (those entered inside its target). Scopes nest, so
+    /// every later one is left too.
+    fn finally_start(&self, leaves: impl Fn(&FinallyScope) -> bool) -> usize {
+        self.finally_scopes
+            .iter()
+            .position(leaves)
+            .unwrap_or(self.finally_scopes.len())
+    }
+
+    /// Run the `finally` bodies of the scopes from index `from` on,
+    /// innermost first, for an exit leaving them. Each body runs with the
+    /// targets outside its try block, and a body that exits itself (e.g.
+    /// returns) ends the chain.
+    fn emit_finally_from(&mut self, from: usize) {
+        for index in (from..self.finally_scopes.len()).rev() {
+            if self.builder.get_insert_block().unwrap().get_terminator().is_some() {
+                return;
             }
+            let scope = self.finally_scopes[index].clone();
+            let finally_scopes = self.finally_scopes.split_off(index);
+            let catch_targets = self.catch_targets.split_off(scope.catch_depth);
+            let break_targets = self.break_targets.split_off(scope.break_depth);
+            let continue_targets = self.continue_targets.split_off(scope.continue_depth);
+            self.emit_stmt(&Stmt::Block(scope.body));
+            self.finally_scopes.extend(finally_scopes);
+            self.catch_targets.extend(catch_targets);
+            self.break_targets.extend(break_targets);
+            self.continue_targets.extend(continue_targets);
+        }
+    }
+
+    /// Throw the error struct `error_val`: to the innermost catch handler,
+    /// else back to the caller as an `Err` result, else (in `main` and
+    /// functions called through pointers) print its message to stderr and
+    /// exit(1). The `finally` bodies on the way run first.
+    fn emit_throw(&mut self, error_val: BasicValueEnum<'ctx>) {
+        use crate::narrowing::NarrowingCodegen;
+
+        let depth = self.catch_targets.len();
+        self.emit_finally_from(self.finally_start(|scope| scope.catch_depth >= depth));
+        if self.builder.get_insert_block().unwrap().get_terminator().is_some() {
+            return;
+        }
+
+        if let Some(&(catch_bb, error_slot)) = self.catch_targets.last() {
+            self.builder.build_store(error_slot, error_val).unwrap();
+            self.builder.build_unconditional_branch(catch_bb).unwrap();
+        } else if let Some(result_type) = self.throws_to_caller.clone() {
+            // A declared error type gets the thrown value, which the driver
+            // checked is an `E`.
+            let LltsType::Result { err: err_type, .. } = &result_type else { unreachable!() };
+            let error_val = if **err_type == types::error_type() {
+                error_val
+            } else {
+                let payload = self.builder
+                    .build_extract_value(error_val.into_struct_value(), 1, "error_payload")
+                    .unwrap()
+                    .into_pointer_value();
+                let llvm_ty = self.registry.llvm_type(err_type);
+                self.builder.build_load(llvm_ty, payload, "thrown_value").unwrap()
+            };
+            let err = NarrowingCodegen::build_union_value(
+                &self.builder,
+                self.context,
+                &mut self.registry,
+                &result_type,
+                types::RESULT_ERR_TAG,
+                error_val,
+            );
+            self.release_frame();
+            self.builder.build_return(Some(&err)).unwrap();
+        } else {
+            let message = self.builder
+                .build_extract_value(error_val.into_struct_value(), 2, "error_message")
+                .unwrap();
+            self.intrinsics.build_write_stderr(&self.builder, &self.module, message);
+            let newline = ExprCodegen::const_string(
+                &self.builder,
+                &self.module,
+                self.context,
+                &self.registry,
+                "\n",
+                "newline",
+            );
+            self.intrinsics.build_write_stderr(&self.builder, &self.module, newline);
+
+            let exit_fn = self.intrinsics.get("exit").expect("exit not declared");
+            let one = self.context.i32_type().const_int(1, false);
+            self.builder
+                .build_call(exit_fn, &[one.into()], "")
+                .unwrap();
+            self.builder.build_unreachable().unwrap();
         }
     }
 
@@ -1126,11 +1691,19 @@ impl<'ctx> CodeGenerator<'ctx> {
                 let arg_vals: Vec<BasicValueEnum<'ctx>> =
                     args.iter().map(|a| self.emit_expr(a)).collect();
 
-                let function = self.functions.get(callee).copied().unwrap_or_else(|| {
-                    self.module
-                        .get_function(callee)
-                        .unwrap_or_else(|| panic!("function not found: {callee}"))
-                });
+                // Runtime helpers of the async executor are emitted on first use.
+                let function = self
+                    .functions
+                    .get(callee)
+                    .copied()
+                    .or_else(|| {
+                        ExecutorCodegen::runtime_fn(callee, &self.builder, self.context, &self.module, &mut self.memory)
+                    })
+                    .unwrap_or_else(|| {
+                        self.module
+                            .get_function(callee)
+                            .unwrap_or_else(|| panic!("function not found: {callee}"))
+                    });
 
                 match CallCodegen::build_direct_call(
                     &self.builder,
@@ -1150,19 +1723,92 @@ impl<'ctx> CodeGenerator<'ctx> {
                 method_name,
                 receiver,
                 args,
-                ret_type: _,
+                ret_type,
             } => {
                 use crate::stdlib::StdlibCodegen;
 
-                if class_name == "__Array" {
-                    let recv_type = self.infer_expr_type(receiver);
-                    let elem_type = match &recv_type {
-                        LltsType::Array(e) => *e.clone(),
-                        _ => LltsType::F64,
+                if class_name == "__Map" || class_name == "__Set" {
+                    use crate::collections::{CollectKind, CollectionCodegen};
+
+                    let (key_type, value_type) = match self.infer_expr_type(receiver) {
+                        LltsType::Map { key, value } => (*key, *value),
+                        LltsType::Set(elem) => (*elem, LltsType::Bool),
+                        _ => (LltsType::F64, LltsType::Bool),
                     };
                     let recv = self.emit_expr(receiver);
-                    let arg_vals: Vec<BasicValueEnum<'ctx>> =
-                        args.iter().map(|a| self.emit_expr(a)).collect();
+                    let arg_vals: Vec<BasicValueEnum<'ctx>> = match method_name.as_str() {
+                        "set" | "add" => args.iter().map(|a| self.emit_owned(a)).collect(),
+                        _ => args.iter().map(|a| self.emit_expr(a)).collect(),
+                    };
+
+                    return match method_name.as_str() {
+                        "set" | "add" => {
+                            // A set stores `true` as each key's value.
+                            let value = arg_vals
+                                .get(1)
+                                .copied()
+                                .unwrap_or_else(|| self.context.bool_type().const_int(1, false).into());
+                            CollectionCodegen::build_insert(
+                                &self.builder, self.context, &self.module,
+                                &mut self.registry, &mut self.memory,
+                                recv, arg_vals[0], value, &key_type, &value_type,
+                            );
+                            // `set`/`add` return the collection for chaining
+                            recv
+                        }
+                        "get" => CollectionCodegen::build_get(
+                            &self.builder, self.context, &self.module,
+                            &mut self.registry, &mut self.memory,
+                            recv, arg_vals[0], &key_type, &value_type,
+                        ),
+                        "has" => CollectionCodegen::build_has(
+                            &self.builder, self.context, &self.module,
+                            &mut self.registry, &mut self.memory,
+                            recv, arg_vals[0], &key_type, &value_type,
+                        ),
+                        "delete" => CollectionCodegen::build_delete(
+                            &self.builder, self.context, &self.module,
+                            &mut self.registry, &mut self.memory,
+                            recv, arg_vals[0], &key_type, &value_type,
+                        ),
+                        "size" => CollectionCodegen::build_size(&self.builder, self.context, recv),
+                        "clear" => {
+                            CollectionCodegen::build_clear(
+                                &self.builder, self.context, &self.module, &mut self.memory, recv,
+                            );
+                            self.context.i8_type().const_int(0, false).into()
+                        }
+                        "keys" | "values" | "entries" => {
+                            let kind = match method_name.as_str() {
+                                "values" if class_name == "__Map" => CollectKind::Values,
+                                "entries" => CollectKind::Entries,
+                                _ => CollectKind::Keys,
+                            };
+                            let elem_type = match ret_type {
+                                LltsType::Array(elem) => *elem.clone(),
+                                _ => key_type.clone(),
+                            };
+                            CollectionCodegen::build_collect(
+                                &self.builder, self.context, &self.module,
+                                &mut self.registry, &mut self.memory,
+                                recv, &key_type, &value_type, kind, &elem_type,
+                            )
+                        }
+                        other => panic!("unknown {class_name} method: {other}"),
+                    };
+                }
+
+                if class_name == "__Array" {
+                    let recv_type = self.infer_expr_type(receiver);
+                    let elem_type = match &recv_type {
+                        LltsType::Array(e) => *e.clone(),
+                        _ => LltsType::F64,
+                    };
+                    let recv = self.emit_expr(receiver);
+                    let arg_vals: Vec<BasicValueEnum<'ctx>> = match method_name.as_str() {
+                        "push" => args.iter().map(|a| self.emit_owned(a)).collect(),
+                        _ => args.iter().map(|a| self.emit_expr(a)).collect(),
+                    };
                     let function = self.current_function.unwrap();
 
                     return match method_name.as_str() {
@@ -1346,6 +1992,12 @@ impl<'ctx> CodeGenerator<'ctx> {
                 args,
                 ret_type: _,
             } => {
+                if class_name == "__Map" || class_name == "__Set" {
+                    return crate::collections::CollectionCodegen::build_new(
+                        &self.builder, self.context, &self.module, &mut self.memory,
+                    );
+                }
+
                 let arg_vals: Vec<BasicValueEnum<'ctx>> =
                     args.iter().map(|a| self.emit_expr(a)).collect();
 
@@ -1377,6 +2029,11 @@ impl<'ctx> CodeGenerator<'ctx> {
                         field_type,
                         "field",
                     )
+                } else if matches!(object_type, LltsType::Union { .. } | LltsType::Result { .. }) && *field_index == 1 {
+                    // Union or Result payload: the slot has the type of the
+                    // largest member, so reinterpret it as the one asked for.
+                    use crate::narrowing::NarrowingCodegen;
+                    NarrowingCodegen::build_union_extract(&self.builder, &mut self.registry, obj, field_type)
                 } else {
                     // Direct struct value — extract.
                     self.builder
@@ -1394,26 +2051,7 @@ impl<'ctx> CodeGenerator<'ctx> {
                 elem_type,
             } => {
                 let arr = self.emit_expr(array);
-                let i64_ty = self.context.i64_type();
-                let index_type = self.infer_expr_type(index);
-                let raw = self.emit_expr(index);
-                let idx = if TypeRegistry::is_float(&index_type) {
-                    // f64/f32 index → fptosi to i64
-                    self.builder
-                        .build_float_to_signed_int(raw.into_float_value(), i64_ty, "idx_cast")
-                        .unwrap()
-                } else if raw.is_int_value() {
-                    let iv = raw.into_int_value();
-                    if iv.get_type().get_bit_width() == 64 {
-                        iv
-                    } else if TypeRegistry::is_signed(&index_type) {
-                        self.builder.build_int_s_extend(iv, i64_ty, "idx_sext").unwrap()
-                    } else {
-                        self.builder.build_int_z_extend(iv, i64_ty, "idx_zext").unwrap()
-                    }
-                } else {
-                    raw.into_int_value() // fallback — will panic if not int
-                };
+                let idx = self.emit_index(index);
                 let function = self.current_function.unwrap();
 
                 ExprCodegen::build_array_index(
@@ -1427,6 +2065,55 @@ impl<'ctx> CodeGenerator<'ctx> {
                     "arr_elem",
                 )
             }
+            Expr::Assign { target, op, current: Some(binding), value, ty } if op.is_none() => {
+                let ptr = self.emit_place(target);
+                let llvm_ty = self.registry.llvm_type(ty);
+                let current = self.builder.build_load(llvm_ty, ptr, "current").unwrap();
+                let alloca = StmtCodegen::build_var_decl(
+                    &self.builder,
+                    &mut self.registry,
+                    ty,
+                    binding,
+                    Some(current),
+                );
+                let shadowed = self.variables.insert(binding.clone(), (alloca, ty.clone()));
+                let stored = self.emit_owned(value);
+                match shadowed {
+                    Some(outer) => self.variables.insert(binding.clone(), outer),
+                    None => self.variables.remove(binding),
+                };
+                self.build_store_replacing(ptr, stored, ty);
+                stored
+            }
+            Expr::Assign { target, op, value, ty, .. } => {
+                let ptr = self.emit_place(target);
+                let stored = match op {
+                    None => self.emit_owned(value),
+                    Some(op) => {
+                        let llvm_ty = self.registry.llvm_type(ty);
+                        let current = self.builder.build_load(llvm_ty, ptr, "current").unwrap();
+                        let value_ty = self.infer_expr_type(value);
+                        let rhs = self.emit_expr(value);
+                        let (l, r, effective_ty) = self.coerce_binary_operands(current, rhs, ty, &value_ty, ty);
+                        ExprCodegen::build_binary(&self.builder, self.context, *op, l, r, &effective_ty, "compound")
+                    }
+                };
+                self.build_store_replacing(ptr, stored, ty);
+                stored
+            }
+            Expr::Update { target, op, prefix, ty } => {
+                let ptr = self.emit_place(target);
+                let llvm_ty = self.registry.llvm_type(ty);
+                let old = self.builder.build_load(llvm_ty, ptr, "old").unwrap();
+                let one: BasicValueEnum<'ctx> = if TypeRegistry::is_float(ty) {
+                    llvm_ty.into_float_type().const_float(1.0).into()
+                } else {
+                    llvm_ty.into_int_type().const_int(1, false).into()
+                };
+                let new = ExprCodegen::build_binary(&self.builder, self.context, *op, old, one, ty, "update");
+                StmtCodegen::build_assignment(&self.builder, ptr, new);
+                if *prefix { new } else { old }
+            }
             Expr::Cast { value, from, to } => {
                 let v = self.emit_expr(value);
                 ExprCodegen::build_cast(&self.builder, self.context, v, from, to, "cast")
@@ -1436,7 +2123,7 @@ impl<'ctx> CodeGenerator<'ctx> {
                 fields,
             } => {
                 let field_vals: Vec<BasicValueEnum<'ctx>> =
-                    fields.iter().map(|f| self.emit_expr(f)).collect();
+                    fields.iter().map(|f| self.emit_owned(f)).collect();
                 let ptr = ExprCodegen::build_struct_literal(
                     &self.builder,
                     &mut self.registry,
@@ -1455,7 +2142,7 @@ impl<'ctx> CodeGenerator<'ctx> {
                 elements,
             } => {
                 let elem_vals: Vec<BasicValueEnum<'ctx>> =
-                    elements.iter().map(|e| self.emit_expr(e)).collect();
+                    elements.iter().map(|e| self.emit_owned(e)).collect();
                 ExprCodegen::build_array_literal(
                     &self.builder,
                     self.context,
@@ -1466,6 +2153,36 @@ impl<'ctx> CodeGenerator<'ctx> {
                     "arr_lit",
                 )
             }
+            Expr::ArrayConcat { elem_type, parts } => {
+                use crate::stdlib::{ConcatPart, StdlibCodegen};
+                // Literal parts are stored element by element, without a
+                // temporary array.
+                let part_vals: Vec<ConcatPart<'ctx>> = parts
+                    .iter()
+                    .map(|part| match part {
+                        Expr::ArrayLit { elements, .. } => {
+                            ConcatPart::Elements(elements.iter().map(|e| self.emit_owned(e)).collect())
+                        }
+                        array => {
+                            // The new array holds its own copy of each element.
+                            let arr = self.emit_expr(array);
+                            if Self::holds_closures(elem_type) {
+                                self.build_elements_retain(arr, elem_type);
+                            }
+                            ConcatPart::Array(arr)
+                        }
+                    })
+                    .collect();
+                StdlibCodegen::build_array_concat(
+                    &self.builder,
+                    self.context,
+                    &self.module,
+                    &mut self.registry,
+                    &mut self.memory,
+                    &part_vals,
+                    elem_type,
+                )
+            }
             Expr::IndirectCall {
                 callee,
                 args,
@@ -1618,7 +2335,7 @@ impl<'ctx> CodeGenerator<'ctx> {
             }
             Expr::OptionSome { value, inner_type } => {
                 use crate::narrowing::NarrowingCodegen;
-                let val = self.emit_expr(value);
+                let val = self.emit_owned(value);
                 NarrowingCodegen::build_option_some(
                     &self.builder,
                     self.context,
@@ -1637,9 +2354,131 @@ impl<'ctx> CodeGenerator<'ctx> {
                 let val = self.emit_expr(value);
                 NarrowingCodegen::build_option_unwrap(&self.builder, val)
             }
+            Expr::OptionExpect { value, inner_type: _ } => {
+                use crate::narrowing::NarrowingCodegen;
+                let val = self.emit_expr(value);
+                let function = self.current_function.unwrap();
+                let is_some = NarrowingCodegen::build_option_is_some(&self.builder, val);
+
+                let null_bb = self.context.append_basic_block(function, "expect_null");
+                let some_bb = self.context.append_basic_block(function, "expect_some");
+                self.builder
+                    .build_conditional_branch(is_some, some_bb, null_bb)
+                    .unwrap();
+
+                self.builder.position_at_end(null_bb);
+                let message = Expr::StringLit("TypeError: non-null assertion on a null value".to_string());
+                self.emit_stmt(&Stmt::Throw(Expr::StructLit {
+                    struct_type: types::error_type(),
+                    fields: vec![
+                        Expr::IntLit { value: types::STRING_ERROR_TAG, ty: LltsType::I32 },
+                        Expr::BoxValue { value: Box::new(message.clone()), ty: LltsType::String },
+                        message,
+                    ],
+                }));
+
+                self.builder.position_at_end(some_bb);
+                NarrowingCodegen::build_option_unwrap(&self.builder, val)
+            }
+            Expr::OptionAndThen {
+                value,
+                inner_type,
+                binding,
+                body,
+                ty,
+            } => {
+                use crate::narrowing::NarrowingCodegen;
+                let val = self.emit_expr(value);
+                let function = self.current_function.unwrap();
+                let is_some = NarrowingCodegen::build_option_is_some(&self.builder, val);
+
+                let some_bb = self.context.append_basic_block(function, "chain_some");
+                let none_bb = self.context.append_basic_block(function, "chain_none");
+                let merge_bb = self.context.append_basic_block(function, "chain_merge");
+                self.builder
+                    .build_conditional_branch(is_some, some_bb, none_bb)
+                    .unwrap();
+
+                // Some: bind the inner value and evaluate the rest of the chain.
+                self.builder.position_at_end(some_bb);
+                let inner = NarrowingCodegen::build_option_unwrap(&self.builder, val);
+                let alloca = StmtCodegen::build_var_decl(
+                    &self.builder,
+                    &mut self.registry,
+                    inner_type,
+                    binding,
+                    Some(inner),
+                );
+                let shadowed = self.variables.insert(binding.clone(), (alloca, inner_type.clone()));
+                let some_val = self.emit_expr(body);
+                match shadowed {
+                    Some(outer) => self.variables.insert(binding.clone(), outer),
+                    None => self.variables.remove(binding),
+                };
+                let some_bb_end = self.builder.get_insert_block().unwrap();
+                self.builder.build_unconditional_branch(merge_bb).unwrap();
+
+                // None: the whole chain is None.
+                self.builder.position_at_end(none_bb);
+                if matches!(ty, LltsType::Void) {
+                    // Evaluated for its effect only.
+                    self.builder.build_unconditional_branch(merge_bb).unwrap();
+                    self.builder.position_at_end(merge_bb);
+                    return self.context.i8_type().const_int(0, false).into();
+                }
+                let none_val = NarrowingCodegen::build_option_none(
+                    &self.builder,
+                    self.context,
+                    &mut self.registry,
+                    ty,
+                );
+                self.builder.build_unconditional_branch(merge_bb).unwrap();
+
+                self.builder.position_at_end(merge_bb);
+                let phi = self
+                    .builder
+                    .build_phi(some_val.get_type(), "chain_result")
+                    .unwrap();
+                phi.add_incoming(&[(&some_val, some_bb_end), (&none_val, none_bb)]);
+                phi.as_basic_value()
+            }
+            Expr::Coalesce { value, fallback, ty } => {
+                use crate::narrowing::NarrowingCodegen;
+                let val = self.emit_expr(value);
+                let function = self.current_function.unwrap();
+                let is_some = NarrowingCodegen::build_option_is_some(&self.builder, val);
+                // Some: the value, unwrapped unless the result is nullable. The
+                // payload of a None is read too, but only used when Some.
+                let some_val = if matches!(ty, LltsType::Option(_)) {
+                    val
+                } else {
+                    NarrowingCodegen::build_option_unwrap(&self.builder, val)
+                };
+                let value_bb = self.builder.get_insert_block().unwrap();
+
+                let fallback_bb = self.context.append_basic_block(function, "coalesce_fallback");
+                let merge_bb = self.context.append_basic_block(function, "coalesce_merge");
+                self.builder
+                    .build_conditional_branch(is_some, merge_bb, fallback_bb)
+                    .unwrap();
+
+                // None: evaluate the fallback.
+                self.builder.position_at_end(fallback_bb);
+                let fallback_val = self.emit_expr(fallback);
+                let fallback_bb_end = self.builder.get_insert_block().unwrap();
+                self.builder.build_unconditional_branch(merge_bb).unwrap();
+
+                self.builder.position_at_end(merge_bb);
+                let phi = self
+                    .builder
+                    .build_phi(fallback_val.get_type(), "coalesce_result")
+                    .unwrap();
+                phi.add_incoming(&[(&some_val, value_bb), (&fallback_val, fallback_bb_end)]);
+                phi.as_basic_value()
+            }
             Expr::UnionLit { tag, payload, union_type } => {
                 use crate::narrowing::NarrowingCodegen;
-                let payload_val = self.emit_expr(payload);
+                let payload_val = self.emit_owned(payload);
                 NarrowingCodegen::build_union_value(
                     &self.builder,
                     self.context,
@@ -1649,7 +2488,245 @@ impl<'ctx> CodeGenerator<'ctx> {
                     payload_val,
                 )
             }
+            Expr::ResultOk { value, result_type } => {
+                use crate::narrowing::NarrowingCodegen;
+                // A Void Ok value only sets the tag.
+                let payload_val = match value {
+                    Some(v) => self.emit_owned(v),
+                    None => self.context.i8_type().const_int(0, false).into(),
+                };
+                NarrowingCodegen::build_union_value(
+                    &self.builder,
+                    self.context,
+                    &mut self.registry,
+                    result_type,
+                    types::RESULT_OK_TAG,
+                    payload_val,
+                )
+            }
+            Expr::ResultPropagate { value, ok_type } => {
+                use crate::narrowing::NarrowingCodegen;
+                let result = self.emit_expr(value);
+                let function = self.current_function.unwrap();
+                let tag = self.builder
+                    .build_extract_value(result.into_struct_value(), 0, "result_tag")
+                    .unwrap()
+                    .into_int_value();
+                let ok_tag = self.context.i32_type().const_int(types::RESULT_OK_TAG as u64, false);
+                let is_ok = self.builder
+                    .build_int_compare(inkwell::IntPredicate::EQ, tag, ok_tag, "is_ok")
+                    .unwrap();
+
+                let err_bb = self.context.append_basic_block(function, "propagate_err");
+                let ok_bb = self.context.append_basic_block(function, "propagate_ok");
+                self.builder
+                    .build_conditional_branch(is_ok, ok_bb, err_bb)
+                    .unwrap();
+
+                self.builder.position_at_end(err_bb);
+                let error = NarrowingCodegen::build_union_extract(
+                    &self.builder,
+                    &mut self.registry,
+                    result,
+                    &types::error_type(),
+                );
+                self.emit_throw(error);
+
+                self.builder.position_at_end(ok_bb);
+                if matches!(ok_type, LltsType::Void) {
+                    // Void return — return a dummy value.
+                    self.context.i8_type().const_int(0, false).into()
+                } else {
+                    NarrowingCodegen::build_union_extract(&self.builder, &mut self.registry, result, ok_type)
+                }
+            }
+            Expr::BoxValue { value, ty } => {
+                let val = self.emit_owned(value);
+                let cell = self.memory.build_rc_alloc(
+                    &self.builder,
+                    &self.module,
+                    &mut self.registry,
+                    ty,
+                    "boxed",
+                );
+                self.builder.build_store(cell, val).unwrap();
+                cell.into()
+            }
+            Expr::UnboxValue { ptr, ty } => {
+                let cell = self.emit_expr(ptr).into_pointer_value();
+                let llvm_ty = self.registry.llvm_type(ty);
+                self.builder.build_load(llvm_ty, cell, "unboxed").unwrap()
+            }
+            Expr::Closure {
+                function,
+                captures,
+                env_type,
+                ty,
+                boxed,
+            } => {
+                let (params, ret) = match ty {
+                    LltsType::Function { params, ret } => (params.clone(), *ret.clone()),
+                    _ => (Vec::new(), LltsType::Void),
+                };
+                let fn_val = self.functions.get(function).copied().unwrap_or_else(|| {
+                    self.module
+                        .get_function(function)
+                        .unwrap_or_else(|| panic!("closure function not found: {function}"))
+                });
+
+                if captures.is_empty() {
+                    return CallCodegen::build_fn_fat_ptr(
+                        &self.builder,
+                        self.context,
+                        &mut self.registry,
+                        fn_val,
+                        &params,
+                        &ret,
+                    );
+                }
+
+                if let Some(boxed) = boxed {
+                    // Escaping capture: the env is a counted heap cell. Box
+                    // slots share the variable's storage and take a reference
+                    // to the box; other slots hold their own copy of the value.
+                    let drop = self.closure_env_drop(function, env_type, boxed);
+                    let env_ptr = self.memory.build_closure_env_alloc(
+                        &self.builder,
+                        &self.module,
+                        &mut self.registry,
+                        env_type,
+                        drop,
+                    );
+                    let env_addr = self.memory.build_env_address(&self.builder, env_ptr);
+                    for (i, name) in captures.iter().enumerate() {
+                        let (var_ptr, var_ty) = self.variables[name].clone();
+                        let slot = ExprCodegen::build_struct_field_access(
+                            &self.builder,
+                            &mut self.registry,
+                            env_addr,
+                            env_type,
+                            i as u32,
+                            "env_slot",
+                        );
+                        if boxed.get(i).copied().unwrap_or(false) {
+                            self.builder.build_store(slot, var_ptr).unwrap();
+                            self.memory.build_retain(&self.builder, var_ptr);
+                        } else {
+                            let llvm_ty = self.registry.llvm_type(&var_ty);
+                            let val = self.builder.build_load(llvm_ty, var_ptr, name).unwrap();
+                            self.build_value_retain(val, &var_ty);
+                            self.builder.build_store(slot, val).unwrap();
+                        }
+                    }
+                    return CallCodegen::build_closure_fat_ptr(
+                        &self.builder,
+                        &mut self.registry,
+                        fn_val,
+                        env_ptr,
+                        &params,
+                        &ret,
+                    );
+                }
+
+                // Non-escaping capture: the env lives on the stack and holds
+                // the address of each captured variable.
+                let env_llvm_ty = self.registry.llvm_type(env_type);
+                let env_ptr = self.builder.build_alloca(env_llvm_ty, "closure_env").unwrap();
+                for (i, name) in captures.iter().enumerate() {
+                    let (var_ptr, _) = self.variables[name].clone();
+                    let slot = ExprCodegen::build_struct_field_access(
+                        &self.builder,
+                        &mut self.registry,
+                        env_ptr,
+                        env_type,
+                        i as u32,
+                        "env_slot",
+                    );
+                    self.builder.build_store(slot, var_ptr).unwrap();
+                }
+                CallCodegen::build_closure_fat_ptr(
+                    &self.builder,
+                    &mut self.registry,
+                    fn_val,
+                    env_ptr,
+                    &params,
+                    &ret,
+                )
+            }
+            Expr::DynCoerce {
+                value,
+                vtable,
+                slots,
+                ty: _,
+            } => {
+                let value_ty = self.infer_expr_type(value);
+                let val = self.emit_owned(value);
+                let data_ptr = self.memory.build_rc_alloc(
+                    &self.builder,
+                    &self.module,
+                    &mut self.registry,
+                    &value_ty,
+                    "dyn_data",
+                );
+                self.builder.build_store(data_ptr, val).unwrap();
+                let vtable_ptr = self.vtable_global(vtable, slots);
+                CallCodegen::build_dyn_fat_ptr(
+                    &self.builder,
+                    &mut self.registry,
+                    data_ptr,
+                    vtable_ptr,
+                )
+            }
+            Expr::DynCall {
+                receiver,
+                slot,
+                args,
+                param_types,
+                ret_type,
+            } => {
+                let dyn_val = self.emit_expr(receiver);
+                let arg_vals: Vec<BasicValueEnum<'ctx>> =
+                    args.iter().map(|a| self.emit_expr(a)).collect();
+
+                CallCodegen::build_dyn_call(
+                    &self.builder,
+                    self.context,
+                    &mut self.registry,
+                    dyn_val,
+                    *slot,
+                    &arg_vals,
+                    param_types,
+                    ret_type,
+                    "dyn_call",
+                )
+                .unwrap_or_else(|| self.context.i8_type().const_int(0, false).into())
+            }
+        }
+    }
+
+    /// Get or create the constant vtable global `name`: an array of pointers
+    /// to the functions named in `slots`, in slot order.
+    fn vtable_global(&mut self, name: &str, slots: &[String]) -> PointerValue<'ctx> {
+        if let Some(global) = self.module.get_global(name) {
+            return global.as_pointer_value();
         }
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+        let entries: Vec<PointerValue<'ctx>> = slots
+            .iter()
+            .map(|f| {
+                let fn_val = self.functions.get(f).copied().unwrap_or_else(|| {
+                    self.module
+                        .get_function(f)
+                        .unwrap_or_else(|| panic!("vtable function not found: {f}"))
+                });
+                fn_val.as_global_value().as_pointer_value()
+            })
+            .collect();
+        let table = ptr_ty.const_array(&entries);
+        let global = self.module.add_global(table.get_type(), None, name);
+        global.set_initializer(&table);
+        global.set_constant(true);
+        global.as_pointer_value()
     }
 
     /// Emit an expression and convert the result to a string fat pointer.
@@ -2108,6 +3185,58 @@ impl<'ctx> CodeGenerator<'ctx> {
         self.context.i8_type().const_int(0, false).into()
     }
 
+    /// An array index as an i64: float indices are truncated, narrower
+    /// integers extended.
+    fn emit_index(&mut self, index: &Expr) -> IntValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let index_type = self.infer_expr_type(index);
+        let raw = self.emit_expr(index);
+        if TypeRegistry::is_float(&index_type) {
+            // f64/f32 index → fptosi to i64
+            self.builder
+                .build_float_to_signed_int(raw.into_float_value(), i64_ty, "idx_cast")
+                .unwrap()
+        } else if raw.is_int_value() {
+            let iv = raw.into_int_value();
+            if iv.get_type().get_bit_width() == 64 {
+                iv
+            } else if TypeRegistry::is_signed(&index_type) {
+                self.builder.build_int_s_extend(iv, i64_ty, "idx_sext").unwrap()
+            } else {
+                self.builder.build_int_z_extend(iv, i64_ty, "idx_zext").unwrap()
+            }
+        } else {
+            raw.into_int_value() // fallback — will panic if not int
+        }
+    }
+
+    /// The address of place expression `place` (see [`Expr::Assign`]),
+    /// evaluating its objects and indices once.
+    fn emit_place(&mut self, place: &Expr) -> PointerValue<'ctx> {
+        match place {
+            Expr::Var { name, .. } => self.variables[name].0,
+            Expr::FieldAccess { object, object_type, field_index, .. } => {
+                // A `Ptr` object already holds the struct's address.
+                let object_ptr = match self.infer_expr_type(object) {
+                    LltsType::Ptr => self.emit_expr(object).into_pointer_value(),
+                    _ => self.emit_place(object),
+                };
+                ExprCodegen::build_struct_field_access(
+                    &self.builder, &mut self.registry, object_ptr, object_type, *field_index, "field_ptr",
+                )
+            }
+            Expr::ArrayIndex { array, index, elem_type } => {
+                let arr = self.emit_expr(array);
+                let idx = self.emit_index(index);
+                let function = self.current_function.unwrap();
+                ExprCodegen::build_array_element_ptr(
+                    &self.builder, self.context, &mut self.registry, function, arr, idx, elem_type,
+                )
+            }
+            other => panic!("internal error: {other:?} is not a place"),
+        }
+    }
+
     /// Implicit widening for binary operations with mismatched types.
     /// Returns (coerced_lhs, coerced_rhs, common_type).
     ///
@@ -2225,9 +3354,12 @@ impl<'ctx> CodeGenerator<'ctx> {
             Expr::ConstructorCall { ret_type, .. } => ret_type.clone(),
             Expr::FieldAccess { field_type, .. } => field_type.clone(),
             Expr::ArrayIndex { elem_type, .. } => elem_type.clone(),
+            Expr::Assign { ty, .. } | Expr::Update { ty, .. } => ty.clone(),
             Expr::Cast { to, .. } => to.clone(),
             Expr::StructLit { struct_type, .. } => struct_type.clone(),
-            Expr::ArrayLit { elem_type, .. } => LltsType::Array(Box::new(elem_type.clone())),
+            Expr::ArrayLit { elem_type, .. } | Expr::ArrayConcat { elem_type, .. } => {
+                LltsType::Array(Box::new(elem_type.clone()))
+            }
             Expr::IndirectCall { ret_type, .. } => ret_type.clone(),
             Expr::Ternary { ty, .. } => ty.clone(),
             Expr::StringConcat { .. } => LltsType::String,
@@ -2236,7 +3368,18 @@ impl<'ctx> CodeGenerator<'ctx> {
             Expr::OptionSome { inner_type, .. } => LltsType::Option(Box::new(inner_type.clone())),
             Expr::OptionIsSome { .. } => LltsType::Bool,
             Expr::OptionUnwrap { inner_type, .. } => inner_type.clone(),
+            Expr::OptionExpect { inner_type, .. } => inner_type.clone(),
+            Expr::OptionAndThen { ty: LltsType::Void, .. } => LltsType::Void,
+            Expr::OptionAndThen { ty, .. } => LltsType::Option(Box::new(ty.clone())),
+            Expr::Coalesce { ty, .. } => ty.clone(),
             Expr::UnionLit { union_type, .. } => union_type.clone(),
+            Expr::ResultOk { result_type, .. } => result_type.clone(),
+            Expr::ResultPropagate { ok_type, .. } => ok_type.clone(),
+            Expr::BoxValue { .. } => LltsType::Ptr,
+            Expr::UnboxValue { ty, .. } => ty.clone(),
+            Expr::Closure { ty, .. } => ty.clone(),
+            Expr::DynCoerce { ty, .. } => ty.clone(),
+            Expr::DynCall { ret_type, .. } => ret_type.clone(),
         }
     }
 }
@@ -2579,4 +3722,404 @@ mod tests {
         codegen.compile(&program);
         assert!(codegen.module().verify().is_ok());
     }
+
+    #[test]
+    fn test_closure_captures_by_reference() {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test");
+
+        // function outer(): f64 {
+        //   let total: f64 = 1;
+        //   const add = (n: f64): void => { total = total + n; };
+        //   add(2);
+        //   return total;
+        // }
+        let env_type = LltsType::Struct {
+            name: "__lambda_0_env".to_string(),
+            fields: vec![("total".to_string(), LltsType::Ptr)],
+        };
+        let fn_type = LltsType::Function {
+            params: vec![LltsType::F64],
+            ret: Box::new(LltsType::Void),
+        };
+        let program = ProgramIR {
+            structs: vec![],
+            enums: vec![],
+            functions: vec![
+                FunctionDecl {
+                    name: "__lambda_0".to_string(),
+                    params: vec![
+                        ("__env".to_string(), LltsType::Ptr),
+                        ("n".to_string(), LltsType::F64),
+                    ],
+                    ret_type: LltsType::Void,
+                    body: vec![
+                        Stmt::CaptureBind {
+                            name: "total".to_string(),
+                            ty: LltsType::F64,
+                            env_type: env_type.clone(),
+                            field_index: 0,
+                            by_ref: true,
+                        },
+                        Stmt::Assign {
+                            target: "total".to_string(),
+                            value: Expr::Binary {
+                                op: BinOp::Add,
+                                lhs: Box::new(Expr::Var {
+                                    name: "total".to_string(),
+                                    ty: LltsType::F64,
+                                }),
+                                rhs: Box::new(Expr::Var {
+                                    name: "n".to_string(),
+                                    ty: LltsType::F64,
+                                }),
+                                ty: LltsType::F64,
+                            },
+                        },
+                    ],
+                },
+                FunctionDecl {
+                    name: "outer".to_string(),
+                    params: vec![],
+                    ret_type: LltsType::F64,
+                    body: vec![
+                        Stmt::VarDecl {
+                            name: "total".to_string(),
+                            ty: LltsType::F64,
+                            init: Some(Expr::FloatLit {
+                                value: 1.0,
+                                ty: LltsType::F64,
+                            }),
+                        },
+                        Stmt::VarDecl {
+                            name: "add".to_string(),
+                            ty: fn_type.clone(),
+                            init: Some(Expr::Closure {
+                                function: "__lambda_0".to_string(),
+                                captures: vec!["total".to_string()],
+                                env_type,
+                                ty: fn_type.clone(),
+                                boxed: None,
+                            }),
+                        },
+                        Stmt::Expr(Expr::IndirectCall {
+                            callee: Box::new(Expr::Var {
+                                name: "add".to_string(),
+                                ty: fn_type,
+                            }),
+                            args: vec![Expr::FloatLit {
+                                value: 2.0,
+                                ty: LltsType::F64,
+                            }],
+                            param_types: vec![LltsType::F64],
+                            ret_type: LltsType::Void,
+                        }),
+                        Stmt::Return(Some(Expr::Var {
+                            name: "total".to_string(),
+                            ty: LltsType::F64,
+                        })),
+                    ],
+                },
+            ],
+        };
+
+        codegen.compile(&program);
+        assert!(codegen.module().verify().is_ok());
+    }
+
+    #[test]
+    fn test_result_propagation() {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test");
+
+        let result_type = LltsType::Result {
+            ok: Box::new(LltsType::I32),
+            err: Box::new(types::error_type()),
+        };
+        let message = Expr::StringLit("negative".to_string());
+        let check_call = Expr::ResultPropagate {
+            value: Box::new(Expr::Call {
+                callee: "check".to_string(),
+                args: vec![Expr::IntLit {
+                    value: -1,
+                    ty: LltsType::I32,
+                }],
+                ret_type: result_type.clone(),
+            }),
+            ok_type: LltsType::I32,
+        };
+
+        let program = ProgramIR {
+            structs: vec![],
+            enums: vec![],
+            functions: vec![
+                // Throws back to the caller as an Err result.
+                FunctionDecl {
+                    name: "check".to_string(),
+                    params: vec![("n".to_string(), LltsType::I32)],
+                    ret_type: result_type.clone(),
+                    body: vec![
+                        Stmt::If {
+                            condition: Expr::Binary {
+                                op: BinOp::Lt,
+                                lhs: Box::new(Expr::Var {
+                                    name: "n".to_string(),
+                                    ty: LltsType::I32,
+                                }),
+                                rhs: Box::new(Expr::IntLit {
+                                    value: 0,
+                                    ty: LltsType::I32,
+                                }),
+                                ty: LltsType::I32,
+                            },
+                            then_body: vec![Stmt::Throw(Expr::StructLit {
+                                struct_type: types::error_type(),
+                                fields: vec![
+                                    Expr::IntLit {
+                                        value: types::STRING_ERROR_TAG,
+                                        ty: LltsType::I32,
+                                    },
+                                    Expr::BoxValue {
+                                        value: Box::new(message.clone()),
+                                        ty: LltsType::String,
+                                    },
+                                    message,
+                                ],
+                            })],
+                            else_body: None,
+                        },
+                        Stmt::Return(Some(Expr::ResultOk {
+                            value: Some(Box::new(Expr::Var {
+                                name: "n".to_string(),
+                                ty: LltsType::I32,
+                            })),
+                            result_type,
+                        })),
+                    ],
+                },
+                // Catches the error in a try block, and exits on it outside one.
+                FunctionDecl {
+                    name: "main".to_string(),
+                    params: vec![],
+                    ret_type: LltsType::Void,
+                    body: vec![
+                        Stmt::TryCatch {
+                            try_body: vec![Stmt::Expr(check_call.clone())],
+                            catch_param: Some("e".to_string()),
+                            catch_body: Some(vec![]),
+                            finally_body: None,
+                        },
+                        Stmt::Expr(check_call),
+                    ],
+                },
+            ],
+        };
+
+        codegen.compile(&program);
+        assert!(codegen.module().verify().is_ok());
+    }
+
+    #[test]
+    fn test_declared_result_error() {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test");
+
+        let thrown_type = LltsType::Result {
+            ok: Box::new(LltsType::I32),
+            err: Box::new(types::error_type()),
+        };
+        let declared_type = LltsType::Result {
+            ok: Box::new(LltsType::I32),
+            err: Box::new(LltsType::String),
+        };
+        let message = Expr::StringLit("failed".to_string());
+
+        let program = ProgramIR {
+            structs: vec![],
+            enums: vec![],
+            functions: vec![
+                FunctionDecl {
+                    name: "fail".to_string(),
+                    params: vec![],
+                    ret_type: thrown_type.clone(),
+                    body: vec![Stmt::Throw(Expr::StructLit {
+                        struct_type: types::error_type(),
+                        fields: vec![
+                            Expr::IntLit {
+                                value: types::STRING_ERROR_TAG,
+                                ty: LltsType::I32,
+                            },
+                            Expr::BoxValue {
+                                value: Box::new(message.clone()),
+                                ty: LltsType::String,
+                            },
+                            message,
+                        ],
+                    })],
+                },
+                // Declares `Result<i32, string>`: the thrown string comes
+                // back as its Err value.
+                FunctionDecl {
+                    name: "load".to_string(),
+                    params: vec![],
+                    ret_type: declared_type.clone(),
+                    body: vec![Stmt::Return(Some(Expr::ResultOk {
+                        value: Some(Box::new(Expr::ResultPropagate {
+                            value: Box::new(Expr::Call {
+                                callee: "fail".to_string(),
+                                args: vec![],
+                                ret_type: thrown_type,
+                            }),
+                            ok_type: LltsType::I32,
+                        })),
+                        result_type: declared_type,
+                    }))],
+                },
+            ],
+        };
+
+        codegen.compile(&program);
+        assert!(codegen.module().verify().is_ok());
+    }
+
+    #[test]
+    fn test_array_concat() {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test");
+
+        let array_type = LltsType::Array(Box::new(LltsType::I32));
+        let int = |value: i64| Expr::IntLit { value, ty: LltsType::I32 };
+        let a = Expr::Var { name: "a".to_string(), ty: array_type.clone() };
+
+        let program = ProgramIR {
+            structs: vec![],
+            enums: vec![],
+            functions: vec![FunctionDecl {
+                name: "main".to_string(),
+                params: vec![],
+                ret_type: LltsType::Void,
+                body: vec![
+                    Stmt::VarDecl {
+                        name: "a".to_string(),
+                        ty: array_type.clone(),
+                        init: Some(Expr::ArrayLit { elem_type: LltsType::I32, elements: vec![int(1), int(2)] }),
+                    },
+                    // [...a, 3, ...a]
+                    Stmt::VarDecl {
+                        name: "b".to_string(),
+                        ty: array_type,
+                        init: Some(Expr::ArrayConcat {
+                            elem_type: LltsType::I32,
+                            parts: vec![
+                                a.clone(),
+                                Expr::ArrayLit { elem_type: LltsType::I32, elements: vec![int(3)] },
+                                a,
+                            ],
+                        }),
+                    },
+                ],
+            }],
+        };
+
+        codegen.compile(&program);
+        assert!(codegen.module().verify().is_ok());
+    }
+
+    #[test]
+    fn test_assign_through_place() {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test");
+
+        let array_type = LltsType::Array(Box::new(LltsType::I32));
+        let point_type = LltsType::Struct {
+            name: "Point".to_string(),
+            fields: vec![("x".to_string(), LltsType::F64), ("y".to_string(), LltsType::F64)],
+        };
+        let int = |value: i64| Expr::IntLit { value, ty: LltsType::I32 };
+        let a = Expr::Var { name: "a".to_string(), ty: array_type.clone() };
+        let p = Expr::Var { name: "p".to_string(), ty: point_type.clone() };
+        let current = Expr::Var { name: "current".to_string(), ty: LltsType::F64 };
+
+        let program = ProgramIR {
+            structs: vec![StructDecl {
+                name: "Point".to_string(),
+                fields: vec![("x".to_string(), LltsType::F64), ("y".to_string(), LltsType::F64)],
+            }],
+            enums: vec![],
+            functions: vec![FunctionDecl {
+                name: "main".to_string(),
+                params: vec![("p".to_string(), point_type.clone())],
+                ret_type: LltsType::I32,
+                body: vec![
+                    Stmt::VarDecl {
+                        name: "a".to_string(),
+                        ty: array_type,
+                        init: Some(Expr::ArrayLit { elem_type: LltsType::I32, elements: vec![int(1), int(2)] }),
+                    },
+                    // a[1] += 3
+                    Stmt::Expr(Expr::Assign {
+                        target: Box::new(Expr::ArrayIndex {
+                            array: Box::new(a.clone()),
+                            index: Box::new(int(1)),
+                            elem_type: LltsType::I32,
+                        }),
+                        op: Some(BinOp::Add),
+                        current: None,
+                        value: Box::new(int(3)),
+                        ty: LltsType::I32,
+                    }),
+                    // p.y++
+                    Stmt::Expr(Expr::Update {
+                        target: Box::new(Expr::FieldAccess {
+                            object: Box::new(p.clone()),
+                            object_type: point_type.clone(),
+                            field_index: 1,
+                            field_type: LltsType::F64,
+                        }),
+                        op: BinOp::Add,
+                        prefix: false,
+                        ty: LltsType::F64,
+                    }),
+                    // p.x = (current > 0 ? current : 1), reading p.x through the same address
+                    Stmt::Expr(Expr::Assign {
+                        target: Box::new(Expr::FieldAccess {
+                            object: Box::new(p),
+                            object_type: point_type,
+                            field_index: 0,
+                            field_type: LltsType::F64,
+                        }),
+                        op: None,
+                        current: Some("current".to_string()),
+                        value: Box::new(Expr::Ternary {
+                            condition: Box::new(Expr::Binary {
+                                op: BinOp::Gt,
+                                lhs: Box::new(current.clone()),
+                                rhs: Box::new(Expr::FloatLit { value: 0.0, ty: LltsType::F64 }),
+                                ty: LltsType::F64,
+                            }),
+                            then_expr: Box::new(current),
+                            else_expr: Box::new(Expr::FloatLit { value: 1.0, ty: LltsType::F64 }),
+                            ty: LltsType::F64,
+                        }),
+                        ty: LltsType::F64,
+                    }),
+                    // return a[0] = 5
+                    Stmt::Return(Some(Expr::Assign {
+                        target: Box::new(Expr::ArrayIndex {
+                            array: Box::new(a),
+                            index: Box::new(int(0)),
+                            elem_type: LltsType::I32,
+                        }),
+                        op: None,
+                        current: None,
+                        value: Box::new(int(5)),
+                        ty: LltsType::I32,
+                    })),
+                ],
+            }],
+        };
+
+        codegen.compile(&program);
+        assert!(codegen.module().verify().is_ok());
+    }
 }
@@ -1,6 +1,7 @@
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
+use inkwell::types::BasicType;
 use inkwell::values::{BasicValueEnum, FunctionValue, IntValue, PointerValue};
 use inkwell::AddressSpace;
 
@@ -211,7 +212,7 @@ impl<'ctx> MemoryManager<'ctx> {
         // Free the original allocation (ptr - 8 for RC header).
         let alloc_ptr = unsafe {
             builder
-                .build_gep(self.context.i8_type(), ptr, &[neg_one], "alloc_ptr")
+                .build_gep(i64_ty, ptr, &[neg_one], "alloc_ptr")
                 .unwrap()
         };
         self.build_heap_free(builder, module, alloc_ptr);
@@ -221,6 +222,184 @@ impl<'ctx> MemoryManager<'ctx> {
         builder.position_at_end(cont_bb);
     }
 
+    /// Allocate a refcounted heap cell large enough for `ty`.
+    ///
+    /// Lays out `[rc: i64][data...]`, initializes the count to 1, and returns
+    /// the pointer to `data` (the convention expected by [`Self::build_retain`]
+    /// and [`Self::build_release`]).
+    pub fn build_rc_alloc(
+        &mut self,
+        builder: &Builder<'ctx>,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        ty: &LltsType,
+        name: &str,
+    ) -> PointerValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let data_size = registry
+            .llvm_type(ty)
+            .size_of()
+            .unwrap_or(i64_ty.const_int(8, false));
+        let header = i64_ty.const_int(8, false);
+        let total = builder.build_int_add(data_size, header, "rc_size").unwrap();
+        let base = self.build_heap_alloc(builder, module, total, name);
+        builder.build_store(base, i64_ty.const_int(1, false)).unwrap();
+        unsafe {
+            builder
+                .build_gep(i64_ty, base, &[i64_ty.const_int(1, false)], "rc_data")
+                .unwrap()
+        }
+    }
+
+    // ---- Closure environments ----
+
+    /// Allocate the heap environment of an escaping closure.
+    ///
+    /// Lays out `[rc: i64][drop: ptr][env...]` with the count at 1, where
+    /// `drop` releases what the env holds, and returns the pointer to `env`
+    /// with its low bit set. The bit tells a counted env from a stack env or
+    /// the null env of a capture-free closure; clear it with
+    /// [`Self::build_env_address`] before reading the env.
+    pub fn build_closure_env_alloc(
+        &mut self,
+        builder: &Builder<'ctx>,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        env_type: &LltsType,
+        drop: FunctionValue<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let data_size = registry
+            .llvm_type(env_type)
+            .size_of()
+            .unwrap_or(i64_ty.const_int(8, false));
+        let header = i64_ty.const_int(16, false);
+        let total = builder.build_int_add(data_size, header, "env_size").unwrap();
+        let base = self.build_heap_alloc(builder, module, total, "closure_env");
+        builder.build_store(base, i64_ty.const_int(1, false)).unwrap();
+        let drop_slot = unsafe {
+            builder
+                .build_gep(i64_ty, base, &[i64_ty.const_int(1, false)], "env_drop")
+                .unwrap()
+        };
+        builder
+            .build_store(drop_slot, drop.as_global_value().as_pointer_value())
+            .unwrap();
+        let env = unsafe {
+            builder
+                .build_gep(i64_ty, base, &[i64_ty.const_int(2, false)], "env")
+                .unwrap()
+        };
+        let addr = builder.build_ptr_to_int(env, i64_ty, "env_addr").unwrap();
+        let tagged = builder.build_or(addr, i64_ty.const_int(1, false), "env_tagged").unwrap();
+        builder
+            .build_int_to_ptr(tagged, self.context.ptr_type(AddressSpace::default()), "env")
+            .unwrap()
+    }
+
+    /// The address of a closure's env, with the counted-env bit cleared.
+    pub fn build_env_address(
+        &self,
+        builder: &Builder<'ctx>,
+        env: PointerValue<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let addr = builder.build_ptr_to_int(env, i64_ty, "env_addr").unwrap();
+        let cleared = builder
+            .build_and(addr, i64_ty.const_int(!1u64, false), "env_untagged")
+            .unwrap();
+        builder
+            .build_int_to_ptr(cleared, self.context.ptr_type(AddressSpace::default()), "env")
+            .unwrap()
+    }
+
+    /// Branch on whether `env` is a counted env, positioning the builder in
+    /// the counted case. Returns the block both cases continue in.
+    fn build_if_counted_env(
+        &self,
+        builder: &Builder<'ctx>,
+        function: FunctionValue<'ctx>,
+        env: PointerValue<'ctx>,
+    ) -> inkwell::basic_block::BasicBlock<'ctx> {
+        let i64_ty = self.context.i64_type();
+        let addr = builder.build_ptr_to_int(env, i64_ty, "env_addr").unwrap();
+        let bit = builder.build_and(addr, i64_ty.const_int(1, false), "env_bit").unwrap();
+        let counted = builder
+            .build_int_compare(inkwell::IntPredicate::NE, bit, i64_ty.const_zero(), "env_counted")
+            .unwrap();
+        let counted_bb = self.context.append_basic_block(function, "env_counted");
+        let cont_bb = self.context.append_basic_block(function, "env_cont");
+        builder.build_conditional_branch(counted, counted_bb, cont_bb).unwrap();
+        builder.position_at_end(counted_bb);
+        cont_bb
+    }
+
+    /// Retain the env of a closure value, if it is counted.
+    pub fn build_closure_retain(
+        &self,
+        builder: &Builder<'ctx>,
+        function: FunctionValue<'ctx>,
+        env: PointerValue<'ctx>,
+    ) {
+        let cont_bb = self.build_if_counted_env(builder, function, env);
+        let i64_ty = self.context.i64_type();
+        let addr = self.build_env_address(builder, env);
+        let rc_ptr = unsafe {
+            builder
+                .build_gep(i64_ty, addr, &[i64_ty.const_int((-2i64) as u64, false)], "rc_ptr")
+                .unwrap()
+        };
+        let rc = builder.build_load(i64_ty, rc_ptr, "rc").unwrap().into_int_value();
+        let new_rc = builder.build_int_add(rc, i64_ty.const_int(1, false), "rc_inc").unwrap();
+        builder.build_store(rc_ptr, new_rc).unwrap();
+        builder.build_unconditional_branch(cont_bb).unwrap();
+        builder.position_at_end(cont_bb);
+    }
+
+    /// Release the env of a closure value, if it is counted. When the count
+    /// reaches zero, its `drop` function releases what it holds and the
+    /// cell is freed.
+    pub fn build_closure_release(
+        &mut self,
+        builder: &Builder<'ctx>,
+        module: &Module<'ctx>,
+        function: FunctionValue<'ctx>,
+        env: PointerValue<'ctx>,
+    ) {
+        let cont_bb = self.build_if_counted_env(builder, function, env);
+        let i64_ty = self.context.i64_type();
+        let ptr_ty = self.context.ptr_type(AddressSpace::default());
+        let addr = self.build_env_address(builder, env);
+        let rc_ptr = unsafe {
+            builder
+                .build_gep(i64_ty, addr, &[i64_ty.const_int((-2i64) as u64, false)], "rc_ptr")
+                .unwrap()
+        };
+        let rc = builder.build_load(i64_ty, rc_ptr, "rc").unwrap().into_int_value();
+        let new_rc = builder.build_int_sub(rc, i64_ty.const_int(1, false), "rc_dec").unwrap();
+        builder.build_store(rc_ptr, new_rc).unwrap();
+
+        let is_zero = builder
+            .build_int_compare(inkwell::IntPredicate::EQ, new_rc, i64_ty.const_zero(), "rc_is_zero")
+            .unwrap();
+        let free_bb = self.context.append_basic_block(function, "env_free");
+        builder.build_conditional_branch(is_zero, free_bb, cont_bb).unwrap();
+
+        builder.position_at_end(free_bb);
+        let drop_slot = unsafe {
+            builder
+                .build_gep(i64_ty, addr, &[i64_ty.const_int(u64::MAX, false)], "env_drop")
+                .unwrap()
+        };
+        let drop = builder.build_load(ptr_ty, drop_slot, "drop").unwrap().into_pointer_value();
+        let drop_ty = self.context.void_type().fn_type(&[ptr_ty.into()], false);
+        builder.build_indirect_call(drop_ty, drop, &[addr.into()], "").unwrap();
+        self.build_heap_free(builder, module, rc_ptr);
+        builder.build_unconditional_branch(cont_bb).unwrap();
+
+        builder.position_at_end(cont_bb);
+    }
+
     // ---- String allocation ----
 
     /// Allocate a string on the heap: malloc(len), memcpy data, return { ptr, len }.
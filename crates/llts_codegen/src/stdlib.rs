@@ -12,6 +12,14 @@ use crate::types::{LltsType, TypeRegistry};
 /// These are emitted inline as LLVM IR rather than as function calls.
 pub struct StdlibCodegen;
 
+/// One part of an array concatenation (see [`StdlibCodegen::build_array_concat`]).
+pub enum ConcatPart<'ctx> {
+    /// Elements appended one by one.
+    Elements(Vec<BasicValueEnum<'ctx>>),
+    /// An array whose elements are appended.
+    Array(BasicValueEnum<'ctx>),
+}
+
 impl StdlibCodegen {
     // ========================================================================
     // Array methods
@@ -110,6 +118,76 @@ impl StdlibCodegen {
         arr_val.into()
     }
 
+    /// `[...a, x, ...b]` — a new array holding the elements of `parts` in
+    /// order. The total length is summed first, so the data is allocated
+    /// once at that capacity and the parts are appended without growing.
+    pub fn build_array_concat<'ctx>(
+        builder: &Builder<'ctx>,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        registry: &mut TypeRegistry<'ctx>,
+        memory: &mut MemoryManager<'ctx>,
+        parts: &[ConcatPart<'ctx>],
+        elem_type: &LltsType,
+    ) -> BasicValueEnum<'ctx> {
+        let i64_ty = context.i64_type();
+        let part_len = |part: &ConcatPart<'ctx>| match part {
+            ConcatPart::Elements(elems) => i64_ty.const_int(elems.len() as u64, false),
+            ConcatPart::Array(arr) => builder
+                .build_extract_value(arr.into_struct_value(), 1, "concat_part_len")
+                .unwrap()
+                .into_int_value(),
+        };
+        let lens: Vec<IntValue<'ctx>> = parts.iter().map(part_len).collect();
+        let total = lens.iter().fold(i64_ty.const_int(0, false), |sum, len| {
+            builder.build_int_add(sum, *len, "concat_len").unwrap()
+        });
+
+        let elem_llvm_ty = registry.llvm_type(elem_type);
+        let elem_size_val = elem_llvm_ty.size_of().unwrap_or(i64_ty.const_int(8, false));
+        let bytes = builder.build_int_mul(total, elem_size_val, "concat_bytes").unwrap();
+        let data_ptr = memory.build_heap_alloc(builder, module, bytes, "concat_data");
+
+        // Append each part at the running length.
+        let memcpy = Self::get_or_declare_memcpy(context, module);
+        let mut len = i64_ty.const_int(0, false);
+        for (part, part_len) in parts.iter().zip(&lens) {
+            let dest = unsafe { builder.build_gep(elem_llvm_ty, data_ptr, &[len], "concat_dest").unwrap() };
+            match part {
+                ConcatPart::Elements(elems) => {
+                    for (i, val) in elems.iter().enumerate() {
+                        let idx = i64_ty.const_int(i as u64, false);
+                        let elem_ptr = unsafe {
+                            builder.build_gep(elem_llvm_ty, dest, &[idx], "concat_elem_ptr").unwrap()
+                        };
+                        builder.build_store(elem_ptr, *val).unwrap();
+                    }
+                }
+                ConcatPart::Array(arr) => {
+                    let src = builder
+                        .build_extract_value(arr.into_struct_value(), 0, "concat_src")
+                        .unwrap()
+                        .into_pointer_value();
+                    let part_bytes = builder
+                        .build_int_mul(*part_len, elem_size_val, "concat_part_bytes")
+                        .unwrap();
+                    builder
+                        .build_call(memcpy, &[dest.into(), src.into(), part_bytes.into()], "")
+                        .unwrap();
+                }
+            }
+            len = builder.build_int_add(len, *part_len, "concat_pos").unwrap();
+        }
+
+        // Build { ptr, len, cap } with cap == len.
+        let arr_ty = registry.array_type(elem_type);
+        let arr_val = arr_ty.get_undef();
+        let arr_val = builder.build_insert_value(arr_val, data_ptr, 0, "arr_ptr").unwrap().into_struct_value();
+        let arr_val = builder.build_insert_value(arr_val, total, 1, "arr_len").unwrap().into_struct_value();
+        let arr_val = builder.build_insert_value(arr_val, total, 2, "arr_cap").unwrap().into_struct_value();
+        arr_val.into()
+    }
+
     /// `arr.pop()` — remove and return last element.
     pub fn build_array_pop<'ctx>(
         builder: &Builder<'ctx>,
@@ -706,6 +784,19 @@ impl StdlibCodegen {
 
     // ---- Helpers ----
 
+    fn get_or_declare_memcpy<'ctx>(
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+    ) -> FunctionValue<'ctx> {
+        if let Some(f) = module.get_function("memcpy") {
+            return f;
+        }
+        let ptr_ty = context.ptr_type(AddressSpace::default());
+        let i64_ty = context.i64_type();
+        let fn_type = ptr_ty.fn_type(&[ptr_ty.into(), ptr_ty.into(), i64_ty.into()], false);
+        module.add_function("memcpy", fn_type, None)
+    }
+
     fn get_or_declare_memcmp<'ctx>(
         context: &'ctx Context,
         module: &Module<'ctx>,
@@ -35,7 +35,8 @@ pub enum LltsType {
     Array(Box<LltsType>),
     /// Optional value: { i1, T }.
     Option(Box<LltsType>),
-    /// Result type: { i32_tag, union(T, E) }.
+    /// Result type: { i32_tag, union(T, E) }, tagged [`RESULT_OK_TAG`] or
+    /// [`RESULT_ERR_TAG`].
     Result {
         ok: Box<LltsType>,
         err: Box<LltsType>,
@@ -52,6 +53,42 @@ pub enum LltsType {
     },
     /// Raw pointer (used internally for env_ptr, data pointers, etc.).
     Ptr,
+    /// Interface- or base-class-typed value (fat pointer): { data_ptr, vtable_ptr }.
+    /// The slot layout of the vtable is known to the front end only.
+    Dyn { name: String },
+    /// Hash map (pointer to a shared table header), see [`crate::collections`].
+    Map {
+        key: Box<LltsType>,
+        value: Box<LltsType>,
+    },
+    /// Hash set (pointer to a shared table header), see [`crate::collections`].
+    Set(Box<LltsType>),
+}
+
+/// Tag of an `Ok` result.
+pub const RESULT_OK_TAG: u32 = 0;
+
+/// Tag of an `Err` result.
+pub const RESULT_ERR_TAG: u32 = 1;
+
+/// Tag of a thrown string. The front end numbers the other thrown types from
+/// `STRING_ERROR_TAG + 1`.
+pub const STRING_ERROR_TAG: i64 = 1;
+
+/// A thrown value in flight: `{ tag, payload, message }`.
+///
+/// `tag` identifies the type of the value, `payload` points to a refcounted
+/// cell holding it, and `message` is what an uncaught throw prints. Every
+/// throw carries the same struct, whatever it throws.
+pub fn error_type() -> LltsType {
+    LltsType::Struct {
+        name: "__Error".to_string(),
+        fields: vec![
+            ("tag".to_string(), LltsType::I32),
+            ("payload".to_string(), LltsType::Ptr),
+            ("message".to_string(), LltsType::String),
+        ],
+    }
 }
 
 /// Registry that maps [`LltsType`] values to LLVM types for a given context.
@@ -100,8 +137,11 @@ impl<'ctx> TypeRegistry<'ctx> {
             LltsType::Option(inner) => self.option_type(inner).into(),
             LltsType::Result { ok, err } => self.result_type(ok, err).into(),
             LltsType::Function { params, ret } => self.fat_fn_type(params, ret).into(),
+            LltsType::Dyn { .. } => self.dyn_type().into(),
             LltsType::Union { name, variants } => self.union_type(name, variants).into(),
-            LltsType::Ptr => self.context.ptr_type(AddressSpace::default()).into(),
+            LltsType::Ptr | LltsType::Map { .. } | LltsType::Set(_) => {
+                self.context.ptr_type(AddressSpace::default()).into()
+            }
         }
     }
 
@@ -234,6 +274,12 @@ impl<'ctx> TypeRegistry<'ctx> {
         self.context.struct_type(&[ptr_ty, ptr_ty], false)
     }
 
+    /// Dynamically dispatched value (fat pointer): `{ data_ptr, vtable_ptr }`.
+    pub fn dyn_type(&self) -> StructType<'ctx> {
+        let ptr_ty = self.context.ptr_type(AddressSpace::default()).into();
+        self.context.struct_type(&[ptr_ty, ptr_ty], false)
+    }
+
     /// Tagged union: `{ i32, max_variant_bytes }`.
     pub fn union_type(
         &mut self,
@@ -264,6 +310,8 @@ impl<'ctx> TypeRegistry<'ctx> {
             LltsType::String | LltsType::Ptr => 16, // ptr + len or just ptr
             LltsType::Array(_) => 24,                // ptr + len + cap
             LltsType::Function { .. } => 16,         // fn_ptr + env_ptr
+            LltsType::Dyn { .. } => 16,              // data_ptr + vtable_ptr
+            LltsType::Map { .. } | LltsType::Set(_) => 8, // table ptr
             LltsType::Option(_) => 16,               // conservative
             LltsType::Result { .. } => 16,            // conservative
             LltsType::Struct { fields, .. } => {
@@ -290,7 +338,8 @@ impl<'ctx> TypeRegistry<'ctx> {
             LltsType::I64 | LltsType::U64 | LltsType::F64 => 8,
             LltsType::String | LltsType::Ptr => 16,
             LltsType::Array(_) => 24,
-            LltsType::Function { .. } => 16,
+            LltsType::Function { .. } | LltsType::Dyn { .. } => 16,
+            LltsType::Map { .. } | LltsType::Set(_) => 8,
             LltsType::Option(_) => 16,
             LltsType::Result { .. } => 16,
             LltsType::Struct { fields, .. } => {
@@ -0,0 +1,536 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{
+    Expr, FunctionDecl, Stmt,
+    expr::{BinOp, UnaryOp},
+    types::{LltsType, TypeRegistry},
+};
+
+use super::closures::ENV_PARAM;
+use super::context::LowerCtx;
+use super::utils::{coerce_to_type, ir_expr_type};
+use super::{
+    MachineKind, STATE_FIELD, bind_state_fields, build_state_machine, codegen_type_suffix, lower_expr, lower_stmts,
+    resumable_type, resumable_type_arg, slot_payload,
+};
+
+// ---------------------------------------------------------------------------
+// async/await: poll-based promises on a single-threaded executor
+// ---------------------------------------------------------------------------
+//
+// `async function f(params): Promise<T>` is lowered like a generator (see
+// `generators`): `f` returns a `Promise$T` value, a `Dyn` whose single
+// `poll(): T | null` slot runs the body until it returns (`T`) or has to
+// wait (null). `await p` polls `p`; while `p` is pending the enclosing
+// `poll()` returns null and resumes at the same `await` next time.
+//
+// Promises are lazy: a body starts running on its first `poll()`. The leaf
+// promises `sleep(ms)` and `readFile(path)` and the `Promise.all` /
+// `Promise.race` combinators are hand-built state machines; a pending
+// `sleep` asks the runtime to wake the executor at its deadline (see
+// `llts_codegen::executor`). `async function main()` becomes `__main_async`,
+// driven by a generated `main`:
+//
+//     while (__main_async().poll() === null) __llts_park();
+
+/// Callee of the marker call `await p` lowers to before the state machine
+/// transform.
+pub(crate) const AWAIT_MARKER: &str = "__await";
+
+/// `var_types` key holding the result type of the async function being
+/// lowered.
+const ASYNC_RESULT_TYPE: &str = "__async_result_type__";
+
+/// Name `async function main()` is lowered under, next to the generated `main`.
+const MAIN_FACTORY: &str = "__main_async";
+
+/// The `Promise<value>` type.
+pub(crate) fn promise_type(value: &LltsType, ctx: &mut LowerCtx) -> LltsType {
+    resumable_type("Promise", value, ctx)
+}
+
+/// The value type of values of `ty` (`T` of `Promise<T>`), if it is a
+/// promise type.
+pub(crate) fn promise_value(ty: &LltsType, ctx: &LowerCtx) -> Option<LltsType> {
+    let LltsType::Dyn { name } = ty else { return None };
+    match resumable_type_arg(name, ctx)? {
+        ("Promise", LltsType::Void) => Some(LltsType::Void),
+        ("Promise", _) => match ctx.lookup_dyn_slot(name, "poll")?.1.ret {
+            LltsType::Option(value) => Some(*value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Call `poll()` on `promise`, whose value is carried as `payload` (see
+/// [`slot_payload`]).
+pub(crate) fn poll_call(promise: Expr, payload: &LltsType) -> Expr {
+    Expr::DynCall {
+        receiver: Box::new(promise),
+        slot: 0,
+        args: Vec::new(),
+        param_types: Vec::new(),
+        ret_type: LltsType::Option(Box::new(payload.clone())),
+    }
+}
+
+/// Lower `await p` to the marker call the state machine transform suspends
+/// at; its type is the promise's value type.
+pub(crate) fn lower_await(await_expr: &AwaitExpression<'_>, ctx: &mut LowerCtx) -> Expr {
+    if !ctx.var_types.contains_key(ASYNC_RESULT_TYPE) {
+        ctx.errors.push("`await` outside an async function".to_string());
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    }
+    let promise = lower_expr(&await_expr.argument, ctx);
+    let promise_ty = ir_expr_type(&promise);
+    let Some(value) = promise_value(&promise_ty, ctx) else {
+        ctx.errors.push(format!(
+            "`await` needs a Promise, got {}",
+            codegen_type_suffix(&promise_ty)
+        ));
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    };
+    Expr::Call {
+        callee: AWAIT_MARKER.to_string(),
+        args: vec![promise],
+        ret_type: value,
+    }
+}
+
+/// Lower async function `name` (see the module comment): returns the factory
+/// and queues its `poll` function. `ret_type` must be a promise type, and
+/// `prologue` (see `bind_params`) runs before the body. For `main`, returns
+/// the generated executor loop and queues the factory.
+pub(crate) fn lower_async_function(
+    func: &Function<'_>,
+    name: String,
+    params: Vec<(String, LltsType)>,
+    prologue: Vec<Stmt>,
+    ret_type: LltsType,
+    ctx: &mut LowerCtx,
+) -> Option<FunctionDecl> {
+    let Some(value) = promise_value(&ret_type, ctx) else {
+        ctx.errors.push(format!("async function '{name}' must declare its return type as Promise<T>"));
+        return None;
+    };
+    // `return x` resolves the promise with `x`.
+    ctx.var_types.insert("__fn_return_type__".to_string(), value.clone());
+    ctx.var_types.insert(ASYNC_RESULT_TYPE.to_string(), value.clone());
+    let mut body = prologue;
+    if let Some(b) = &func.body {
+        body.extend(lower_stmts(&b.statements, ctx));
+    }
+    let kind = MachineKind::Async { void: value == LltsType::Void };
+    let payload = slot_payload(&value);
+    if name != "main" {
+        return Some(build_state_machine(kind, name, params, ret_type, payload, body, ctx));
+    }
+    let mut factory = build_state_machine(kind, name, params, ret_type.clone(), payload.clone(), body, ctx);
+    factory.name = MAIN_FACTORY.to_string();
+    ctx.pending_functions.push(factory);
+    Some(executor_main(ret_type, &payload))
+}
+
+/// The `main` that drives `async function main()` to completion: poll it,
+/// parking until the next timer is due while it is pending.
+fn executor_main(promise_ty: LltsType, payload: &LltsType) -> FunctionDecl {
+    let promise_name = "__main_promise".to_string();
+    let promise = Expr::Var { name: promise_name.clone(), ty: promise_ty.clone() };
+    let pending = Expr::Unary {
+        op: UnaryOp::Not,
+        operand: Box::new(Expr::OptionIsSome {
+            value: Box::new(poll_call(promise, payload)),
+            inner_type: payload.clone(),
+        }),
+        ty: LltsType::Bool,
+    };
+    FunctionDecl {
+        name: "main".to_string(),
+        params: Vec::new(),
+        ret_type: LltsType::Void,
+        body: vec![
+            Stmt::VarDecl {
+                name: promise_name,
+                ty: promise_ty.clone(),
+                init: Some(Expr::Call { callee: MAIN_FACTORY.to_string(), args: Vec::new(), ret_type: promise_ty }),
+            },
+            Stmt::While {
+                condition: pending,
+                body: vec![Stmt::Expr(call("__llts_park", Vec::new(), LltsType::Void))],
+            },
+        ],
+    }
+}
+
+/// Whether a call of `callee` is one of the built-in promise functions
+/// (`sleep`, `readFile`) rather than a declared function or variable.
+pub(crate) fn is_promise_builtin(callee: &str, ctx: &LowerCtx) -> bool {
+    matches!(callee, "sleep" | "readFile")
+        && !ctx.fn_ret_types.contains_key(callee)
+        && !ctx.var_types.contains_key(callee)
+}
+
+/// Lower a call of built-in promise function `callee` (see
+/// [`is_promise_builtin`]):
+///
+/// - `sleep(ms): Promise<void>` resolves once `ms` milliseconds have passed
+///   since its first poll.
+/// - `readFile(path): Promise<string | null>` resolves to the contents of
+///   the file, or null if it cannot be read. The read itself blocks.
+pub(crate) fn lower_promise_builtin(callee: &str, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    if args.len() != 1 {
+        ctx.errors.push(format!("{callee}() takes 1 argument, got {}", args.len()));
+        args.resize(1, Expr::IntLit { value: 0, ty: LltsType::I64 });
+    }
+    let arg = args.remove(0);
+    match callee {
+        "sleep" => {
+            let ms = match coerce_to_type(arg, &LltsType::I64) {
+                ms if ir_expr_type(&ms) == LltsType::I64 => ms,
+                ms if TypeRegistry::is_numeric(&ir_expr_type(&ms)) => {
+                    let from = ir_expr_type(&ms);
+                    Expr::Cast { value: Box::new(ms), from, to: LltsType::I64 }
+                }
+                ms => {
+                    ctx.errors.push(format!("sleep() takes a number of milliseconds, got {}", codegen_type_suffix(&ir_expr_type(&ms))));
+                    ms
+                }
+            };
+            let fields = vec![("ms".to_string(), LltsType::I64), ("deadline".to_string(), LltsType::I64)];
+            let values = vec![ms, Expr::IntLit { value: 0, ty: LltsType::I64 }];
+            builtin_promise("__sleep", &LltsType::Void, fields, values, sleep_poll, ctx)
+        }
+        _ => {
+            if ir_expr_type(&arg) != LltsType::String {
+                ctx.errors.push(format!("readFile() takes a path string, got {}", codegen_type_suffix(&ir_expr_type(&arg))));
+            }
+            let value = LltsType::Option(Box::new(LltsType::String));
+            let fields = vec![("path".to_string(), LltsType::String)];
+            let poll = {
+                let value = value.clone();
+                let contents = call("__llts_read_file", vec![var("path", &LltsType::String)], value.clone());
+                move || resolve(contents, &value)
+            };
+            builtin_promise("__read_file", &value, fields, vec![arg], poll, ctx)
+        }
+    }
+}
+
+/// Lower `Promise.method(args)`:
+///
+/// - `Promise.all(promises)` resolves to the values of all of them, in
+///   order, once every one has resolved (a `Promise<void>` for void promises).
+/// - `Promise.race(promises)` resolves to the value of the first one to
+///   resolve, polling them in order.
+///
+/// `promises` is an array of promises of one type; each is polled until it
+/// resolves, so they make progress together.
+pub(crate) fn lower_promise_combinator(method: &str, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    let array_type = args.first().map(ir_expr_type);
+    let promise_ty = match &array_type {
+        Some(LltsType::Array(elem)) if args.len() == 1 => Some(*elem.clone()),
+        _ => None,
+    };
+    let value = promise_ty.as_ref().and_then(|ty| promise_value(ty, ctx));
+    let (Some(array_type), Some(promise_ty), Some(value)) = (array_type, promise_ty, value) else {
+        ctx.errors.push(format!("Promise.{method}() takes one array of promises"));
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    };
+    let payload = slot_payload(&value);
+    let suffix = codegen_type_suffix(&value);
+    let fields = vec![("promises".to_string(), array_type.clone())];
+    let promises = args.remove(0);
+    match method {
+        "all" => {
+            let slots_type = LltsType::Array(Box::new(LltsType::Option(Box::new(payload.clone()))));
+            let result = match &value {
+                LltsType::Void => LltsType::Void,
+                value => LltsType::Array(Box::new(value.clone())),
+            };
+            let mut fields = fields;
+            fields.push(("slots".to_string(), slots_type.clone()));
+            let values = vec![promises, Expr::ArrayLit { elem_type: LltsType::Option(Box::new(payload.clone())), elements: Vec::new() }];
+            let poll = {
+                let result = result.clone();
+                move || all_poll(&array_type, &promise_ty, &payload, &slots_type, &result)
+            };
+            builtin_promise(&format!("__promise_all${suffix}"), &result, fields, values, poll, ctx)
+        }
+        "race" => {
+            let poll = move || race_poll(&array_type, &promise_ty, &payload);
+            builtin_promise(&format!("__promise_race${suffix}"), &value, fields, vec![promises], poll, ctx)
+        }
+        _ => {
+            ctx.errors.push(format!("unsupported Promise method '{method}'; only Promise.all and Promise.race are"));
+            Expr::IntLit { value: 0, ty: LltsType::I32 }
+        }
+    }
+}
+
+/// A `Promise<value>` whose `poll()` runs `poll_body` over the state struct
+/// `state_struct`: `__gen_state` (0 until the first poll changes it)
+/// followed by `fields`, which start out as `values` and are visible to the
+/// body as variables. The poll function is generated once per state struct.
+fn builtin_promise(
+    state_struct: &str,
+    value: &LltsType,
+    fields: Vec<(String, LltsType)>,
+    values: Vec<Expr>,
+    poll_body: impl FnOnce() -> Vec<Stmt>,
+    ctx: &mut LowerCtx,
+) -> Expr {
+    let promise_ty = promise_type(value, ctx);
+    let mut state_fields = vec![(STATE_FIELD.to_string(), LltsType::I32)];
+    state_fields.extend(fields);
+    let state_type = LltsType::Struct { name: state_struct.to_string(), fields: state_fields };
+    let poll_name = format!("{state_struct}_poll");
+    if ctx.fn_thunks.insert(poll_name.clone()) {
+        let mut body = bind_state_fields(&state_type);
+        body.extend(poll_body());
+        ctx.pending_functions.push(FunctionDecl {
+            name: poll_name.clone(),
+            params: vec![(ENV_PARAM.to_string(), LltsType::Ptr)],
+            ret_type: LltsType::Option(Box::new(slot_payload(value))),
+            body,
+        });
+    }
+    let mut initial = vec![Expr::IntLit { value: 0, ty: LltsType::I32 }];
+    initial.extend(values);
+    let LltsType::Dyn { name: dyn_name } = &promise_ty else { unreachable!("promise types are Dyn") };
+    Expr::DynCoerce {
+        value: Box::new(Expr::StructLit { struct_type: state_type, fields: initial }),
+        vtable: format!("__vtable_{state_struct}_as_{dyn_name}"),
+        slots: vec![poll_name],
+        ty: promise_ty.clone(),
+    }
+}
+
+/// `poll()` of `sleep(ms)`:
+///
+///     if (state == 0) { deadline = now() + ms; state = 1; }
+///     if (now() >= deadline) { state = -1; return true; }
+///     wake_at(deadline);
+///     return null;
+fn sleep_poll() -> Vec<Stmt> {
+    let now = || call("__llts_now_ms", Vec::new(), LltsType::I64);
+    let deadline = var("deadline", &LltsType::I64);
+    vec![
+        Stmt::If {
+            condition: binary(BinOp::Eq, var(STATE_FIELD, &LltsType::I32), int(0, LltsType::I32), LltsType::I32),
+            then_body: vec![
+                Stmt::Assign {
+                    target: "deadline".to_string(),
+                    value: binary(BinOp::Add, now(), var("ms", &LltsType::I64), LltsType::I64),
+                },
+                Stmt::Assign { target: STATE_FIELD.to_string(), value: int(1, LltsType::I32) },
+            ],
+            else_body: None,
+        },
+        Stmt::If {
+            condition: binary(BinOp::Ge, now(), deadline.clone(), LltsType::I64),
+            then_body: resolve(Expr::BoolLit(true), &LltsType::Bool),
+            else_body: None,
+        },
+        Stmt::Expr(call("__llts_wake_at", vec![deadline], LltsType::Void)),
+        Stmt::Return(Some(Expr::OptionNone { inner_type: LltsType::Bool })),
+    ]
+}
+
+/// `poll()` of `Promise.all(promises)`; `slots` holds each promise's value
+/// once it has resolved:
+///
+///     if (state == 0) { for (...) slots.push(null); state = 1; }
+///     let next = [], pending = false, slot;
+///     for (let i = 0; i < promises.length; i++) {
+///         if (slots[i] !== null) slot = slots[i]; else slot = promises[i].poll();
+///         if (slot === null) pending = true;
+///         next.push(slot);
+///     }
+///     slots = next;
+///     if (pending) return null;
+///     const results = []; for (...) results.push(slots[j]!);
+///     state = -1; return results;
+fn all_poll(
+    array_type: &LltsType,
+    promise_ty: &LltsType,
+    payload: &LltsType,
+    slots_type: &LltsType,
+    result: &LltsType,
+) -> Vec<Stmt> {
+    let opt_type = LltsType::Option(Box::new(payload.clone()));
+    let promises = var("promises", array_type);
+    let slots = var("slots", slots_type);
+    let slot = var("slot", &opt_type);
+    let index = |name: &str, array: &Expr, elem: &LltsType| Expr::ArrayIndex {
+        array: Box::new(array.clone()),
+        index: Box::new(var(name, &LltsType::I64)),
+        elem_type: elem.clone(),
+    };
+    let push = |array: &Expr, value: Expr| {
+        Stmt::Expr(Expr::MethodCall {
+            class_name: "__Array".to_string(),
+            method_name: "push".to_string(),
+            receiver: Box::new(array.clone()),
+            args: vec![value],
+            ret_type: LltsType::Void,
+        })
+    };
+    let is_some = |value: Expr| Expr::OptionIsSome { value: Box::new(value), inner_type: payload.clone() };
+
+    let next = var("next", slots_type);
+    let mut body = vec![
+        Stmt::If {
+            condition: binary(BinOp::Eq, var(STATE_FIELD, &LltsType::I32), int(0, LltsType::I32), LltsType::I32),
+            then_body: vec![
+                count_loop("i", &promises, array_type, vec![push(&slots, Expr::OptionNone { inner_type: payload.clone() })]),
+                Stmt::Assign { target: STATE_FIELD.to_string(), value: int(1, LltsType::I32) },
+            ],
+            else_body: None,
+        },
+        Stmt::VarDecl { name: "next".to_string(), ty: slots_type.clone(), init: Some(Expr::ArrayLit { elem_type: opt_type.clone(), elements: Vec::new() }) },
+        Stmt::VarDecl { name: "pending".to_string(), ty: LltsType::Bool, init: Some(Expr::BoolLit(false)) },
+        Stmt::VarDecl { name: "slot".to_string(), ty: opt_type.clone(), init: None },
+        count_loop(
+            "j",
+            &promises,
+            array_type,
+            vec![
+                Stmt::If {
+                    condition: is_some(index("j", &slots, &opt_type)),
+                    then_body: vec![Stmt::Assign { target: "slot".to_string(), value: index("j", &slots, &opt_type) }],
+                    else_body: Some(vec![Stmt::Assign {
+                        target: "slot".to_string(),
+                        value: poll_call(index("j", &promises, promise_ty), payload),
+                    }]),
+                },
+                Stmt::If {
+                    condition: not(is_some(slot.clone())),
+                    then_body: vec![Stmt::Assign { target: "pending".to_string(), value: Expr::BoolLit(true) }],
+                    else_body: None,
+                },
+                push(&next, slot),
+            ],
+        ),
+        Stmt::Assign { target: "slots".to_string(), value: next },
+        Stmt::If {
+            condition: var("pending", &LltsType::Bool),
+            then_body: vec![Stmt::Return(Some(Expr::OptionNone { inner_type: slot_payload(result) }))],
+            else_body: None,
+        },
+    ];
+    if *result == LltsType::Void {
+        body.extend(resolve(Expr::BoolLit(true), &LltsType::Bool));
+        return body;
+    }
+    let results = var("results", result);
+    body.push(Stmt::VarDecl {
+        name: "results".to_string(),
+        ty: result.clone(),
+        init: Some(Expr::ArrayLit { elem_type: payload.clone(), elements: Vec::new() }),
+    });
+    body.push(count_loop(
+        "k",
+        &slots,
+        slots_type,
+        vec![push(
+            &results,
+            Expr::OptionUnwrap { value: Box::new(index("k", &slots, &opt_type)), inner_type: payload.clone() },
+        )],
+    ));
+    body.extend(resolve(results, result));
+    body
+}
+
+/// `poll()` of `Promise.race(promises)`:
+///
+///     for (let i = 0; i < promises.length; i++) {
+///         const r = promises[i].poll();
+///         if (r !== null) { state = -1; return r; }
+///     }
+///     return null;
+fn race_poll(array_type: &LltsType, promise_ty: &LltsType, payload: &LltsType) -> Vec<Stmt> {
+    let opt_type = LltsType::Option(Box::new(payload.clone()));
+    let ready = var("ready", &opt_type);
+    let promise = Expr::ArrayIndex {
+        array: Box::new(var("promises", array_type)),
+        index: Box::new(var("i", &LltsType::I64)),
+        elem_type: promise_ty.clone(),
+    };
+    vec![
+        Stmt::VarDecl { name: "ready".to_string(), ty: opt_type, init: None },
+        count_loop(
+            "i",
+            &var("promises", array_type),
+            array_type,
+            vec![
+                Stmt::Assign { target: "ready".to_string(), value: poll_call(promise, payload) },
+                Stmt::If {
+                    condition: Expr::OptionIsSome { value: Box::new(ready.clone()), inner_type: payload.clone() },
+                    then_body: vec![
+                        Stmt::Assign { target: STATE_FIELD.to_string(), value: int(-1, LltsType::I32) },
+                        Stmt::Return(Some(ready)),
+                    ],
+                    else_body: None,
+                },
+            ],
+        ),
+        Stmt::Return(Some(Expr::OptionNone { inner_type: payload.clone() })),
+    ]
+}
+
+/// Statements resolving a hand-built promise with `value` (of `payload`).
+fn resolve(value: Expr, payload: &LltsType) -> Vec<Stmt> {
+    vec![
+        Stmt::Assign { target: STATE_FIELD.to_string(), value: int(-1, LltsType::I32) },
+        Stmt::Return(Some(Expr::OptionSome { value: Box::new(value), inner_type: payload.clone() })),
+    ]
+}
+
+/// `for (let index = 0; index < array.length; index++) body`.
+fn count_loop(index: &str, array: &Expr, array_type: &LltsType, body: Vec<Stmt>) -> Stmt {
+    let i = var(index, &LltsType::I64);
+    Stmt::For {
+        init: Some(Box::new(Stmt::VarDecl {
+            name: index.to_string(),
+            ty: LltsType::I64,
+            init: Some(int(0, LltsType::I64)),
+        })),
+        condition: Some(binary(
+            BinOp::Lt,
+            i.clone(),
+            Expr::FieldAccess {
+                object: Box::new(array.clone()),
+                object_type: array_type.clone(),
+                field_index: 1,
+                field_type: LltsType::I64,
+            },
+            LltsType::I64,
+        )),
+        update: Some(Box::new(Stmt::Assign {
+            target: index.to_string(),
+            value: binary(BinOp::Add, i, int(1, LltsType::I64), LltsType::I64),
+        })),
+        body,
+    }
+}
+
+fn var(name: &str, ty: &LltsType) -> Expr {
+    Expr::Var { name: name.to_string(), ty: ty.clone() }
+}
+
+fn int(value: i64, ty: LltsType) -> Expr {
+    Expr::IntLit { value, ty }
+}
+
+fn call(callee: &str, args: Vec<Expr>, ret_type: LltsType) -> Expr {
+    Expr::Call { callee: callee.to_string(), args, ret_type }
+}
+
+fn binary(op: BinOp, lhs: Expr, rhs: Expr, ty: LltsType) -> Expr {
+    Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty }
+}
+
+fn not(operand: Expr) -> Expr {
+    Expr::Unary { op: UnaryOp::Not, operand: Box::new(operand), ty: LltsType::Bool }
+}
@@ -0,0 +1,179 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, types::LltsType};
+
+use super::context::LowerCtx;
+use super::utils::{expr_to_name, ir_expr_type};
+use super::{
+    indirect_call, lower_argument, lower_expr, lower_index_access, lower_member_access, lower_method_call,
+    method_target, spread_call_args,
+};
+
+/// One link of an optional chain, applied to the value of the links before it.
+enum Link<'b, 'a> {
+    /// `.name`
+    Field(&'b str),
+    /// `[index]`
+    Index(&'b Expression<'a>),
+    /// `.name(args)`
+    Method(&'b str, &'b [Argument<'a>]),
+    /// `(args)` on a function value
+    Call(&'b [Argument<'a>]),
+    /// `!`
+    NonNull,
+}
+
+/// A link, whether it is optional (`?.`), and the expression it applies to.
+type ChainLink<'b, 'a> = (Link<'b, 'a>, bool, &'b Expression<'a>);
+
+/// Lower an optional chain such as `a?.b.c`, `a?.method()`, `a?.[i]` or
+/// `f?.()`.
+///
+/// The expression under the innermost `?.` is lowered as usual and the links
+/// above it are applied one by one. A `?.` on an `Option` value becomes an
+/// `OptionAndThen` that evaluates the rest of the chain on the unwrapped value,
+/// so `null` short-circuits the whole chain: `a?.b.c` is `Option<C>`, not
+/// `Option<Option<C>>`. A `?.` on a value that cannot be null is a plain access.
+pub(crate) fn lower_chain(chain: &ChainExpression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let Some(outermost) = chain_element_link(&chain.expression) else {
+        ctx.errors.push("private fields cannot be accessed with `?.`".to_string());
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    };
+
+    // Links from the outermost inwards, down to the base of the chain.
+    let mut links = vec![outermost];
+    while let Some(link) = expression_link(links[links.len() - 1].2) {
+        links.push(link);
+    }
+
+    // Links below the innermost `?.` are ordinary accesses.
+    let innermost = links.iter().rposition(|(_, optional, _)| *optional).unwrap_or(links.len() - 1);
+    links.truncate(innermost + 1);
+    links.reverse();
+
+    let base = links[0].2;
+    let receiver = lower_expr(base, ctx);
+    let id = chain.span.start;
+    lower_links(receiver, &expr_to_name(base, ctx), &links, id, ctx)
+}
+
+/// Lower a non-null assertion `value!`: a checked unwrap of an `Option`, which
+/// throws a `TypeError` on `null`. Other values pass through unchanged.
+pub(crate) fn non_null_assertion(value: Expr) -> Expr {
+    match ir_expr_type(&value) {
+        LltsType::Option(inner) => Expr::OptionExpect {
+            value: Box::new(value),
+            inner_type: *inner,
+        },
+        _ => value,
+    }
+}
+
+/// Apply `links` to `receiver`, whose variable name is `obj_name` if it is one.
+fn lower_links(receiver: Expr, obj_name: &str, links: &[ChainLink<'_, '_>], id: u32, ctx: &mut LowerCtx) -> Expr {
+    let Some(((link, optional, _), rest)) = links.split_first() else {
+        return receiver;
+    };
+
+    if *optional {
+        if let LltsType::Option(inner) = ir_expr_type(&receiver) {
+            let binding = format!("__chain_{id}_{}", rest.len());
+            let unwrapped = Expr::Var { name: binding.clone(), ty: (*inner).clone() };
+            let applied = apply_link(unwrapped, "<expr>", link, ctx);
+            let body = lower_links(applied, "<expr>", rest, id, ctx);
+            let (body, ty) = match ir_expr_type(&body) {
+                // `null` at any later link is the chain's `null` too.
+                LltsType::Option(ty) => (body, *ty),
+                // A call made only for its effect.
+                LltsType::Void => (body, LltsType::Void),
+                ty => (
+                    Expr::OptionSome {
+                        value: Box::new(body),
+                        inner_type: ty.clone(),
+                    },
+                    ty,
+                ),
+            };
+            return Expr::OptionAndThen {
+                value: Box::new(receiver),
+                inner_type: *inner,
+                binding,
+                body: Box::new(body),
+                ty,
+            };
+        }
+    }
+
+    let applied = apply_link(receiver, obj_name, link, ctx);
+    lower_links(applied, "<expr>", rest, id, ctx)
+}
+
+fn apply_link(receiver: Expr, obj_name: &str, link: &Link<'_, '_>, ctx: &mut LowerCtx) -> Expr {
+    match link {
+        Link::Field(name) => lower_member_access(receiver, obj_name, name, ctx),
+        Link::Index(index) => {
+            let index = lower_expr(index, ctx);
+            lower_index_access(receiver, index)
+        }
+        Link::Method(name, arguments) => {
+            let args = arguments.iter().map(|a| lower_argument(a, ctx)).collect();
+            let target = match ir_expr_type(&receiver) {
+                LltsType::Struct { name: class, .. } => method_target(&format!("{class}_{name}"), ctx),
+                _ => None,
+            };
+            let args = spread_call_args(target.as_ref(), arguments, args, ctx);
+            lower_method_call(receiver, obj_name, name, args, ctx)
+        }
+        Link::Call(arguments) => {
+            let args: Vec<Expr> = arguments.iter().map(|a| lower_argument(a, ctx)).collect();
+            if !matches!(ir_expr_type(&receiver), LltsType::Function { .. }) {
+                ctx.errors.push("only functions can be called with `?.()`".to_string());
+            }
+            indirect_call(receiver, args)
+        }
+        Link::NonNull => non_null_assertion(receiver),
+    }
+}
+
+/// The link at the top of a chain expression; `None` for private fields.
+fn chain_element_link<'b, 'a>(element: &'b ChainElement<'a>) -> Option<ChainLink<'b, 'a>> {
+    match element {
+        ChainElement::CallExpression(call) => Some(call_link(call)),
+        ChainElement::StaticMemberExpression(member) => {
+            Some((Link::Field(member.property.name.as_str()), member.optional, &member.object))
+        }
+        ChainElement::ComputedMemberExpression(member) => {
+            Some((Link::Index(&member.expression), member.optional, &member.object))
+        }
+        ChainElement::TSNonNullExpression(non_null) => Some((Link::NonNull, false, &non_null.expression)),
+        _ => None,
+    }
+}
+
+/// The link `expr` adds to the chain, if it continues the chain.
+fn expression_link<'b, 'a>(expr: &'b Expression<'a>) -> Option<ChainLink<'b, 'a>> {
+    match expr {
+        Expression::CallExpression(call) => Some(call_link(call)),
+        Expression::StaticMemberExpression(member) => {
+            Some((Link::Field(member.property.name.as_str()), member.optional, &member.object))
+        }
+        Expression::ComputedMemberExpression(member) => {
+            Some((Link::Index(&member.expression), member.optional, &member.object))
+        }
+        Expression::TSNonNullExpression(non_null) => Some((Link::NonNull, false, &non_null.expression)),
+        _ => None,
+    }
+}
+
+/// A call is one link: `a?.method()` calls `method` on `a` when it is not
+/// null. Methods themselves are never null, so `a.method?.()` is a plain call.
+fn call_link<'b, 'a>(call: &'b CallExpression<'a>) -> ChainLink<'b, 'a> {
+    match &call.callee {
+        Expression::StaticMemberExpression(member) => (
+            Link::Method(member.property.name.as_str(), &call.arguments),
+            member.optional,
+            &member.object,
+        ),
+        callee => (Link::Call(&call.arguments), call.optional, callee),
+    }
+}
@@ -0,0 +1,741 @@
+use std::collections::{HashMap, HashSet};
+
+use llts_codegen::{Expr, FunctionDecl, Stmt, types::LltsType};
+
+use super::context::LowerCtx;
+use super::visit::{expr_children, for_each_expr, for_each_expr_mut, for_each_stmt, for_each_stmt_mut, stmt_exprs};
+use super::record_param_shape;
+
+/// Name of the hidden environment parameter every lambda takes first.
+pub(crate) const ENV_PARAM: &str = "__env";
+
+// ---------------------------------------------------------------------------
+// Free-variable analysis
+// ---------------------------------------------------------------------------
+
+/// Collect the variables a lambda body captures from its enclosing scope.
+///
+/// A name is captured when it is read or written before being declared
+/// locally, is not a parameter, and is a variable of the enclosing scope
+/// (`outer`). Captures of nested closures count as references, so an inner
+/// lambda's captures propagate outward. Returns names in first-use order.
+pub(crate) fn collect_captures(
+    body: &[Stmt],
+    params: &[(String, LltsType)],
+    outer: &HashMap<String, LltsType>,
+) -> Vec<(String, LltsType)> {
+    let mut bound: HashSet<String> = params.iter().map(|(n, _)| n.clone()).collect();
+    let mut refs: Vec<String> = Vec::new();
+    walk_stmts(body, &mut bound, &mut refs);
+
+    let mut captures: Vec<(String, LltsType)> = Vec::new();
+    for name in refs {
+        if name.starts_with("__") || captures.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        if let Some(ty) = outer.get(&name) {
+            captures.push((name, ty.clone()));
+        }
+    }
+    captures
+}
+
+fn note_ref(name: &str, bound: &HashSet<String>, refs: &mut Vec<String>) {
+    if !bound.contains(name) {
+        refs.push(name.to_string());
+    }
+}
+
+fn walk_block(stmts: &[Stmt], bound: &HashSet<String>, refs: &mut Vec<String>) {
+    let mut inner = bound.clone();
+    walk_stmts(stmts, &mut inner, refs);
+}
+
+fn walk_stmts(stmts: &[Stmt], bound: &mut HashSet<String>, refs: &mut Vec<String>) {
+    for stmt in stmts {
+        walk_stmt(stmt, bound, refs);
+    }
+}
+
+fn walk_stmt(stmt: &Stmt, bound: &mut HashSet<String>, refs: &mut Vec<String>) {
+    match stmt {
+        Stmt::VarDecl { name, init, .. } | Stmt::BoxDecl { name, init, .. } => {
+            if let Some(e) = init {
+                walk_expr(e, bound, refs);
+            }
+            bound.insert(name.clone());
+        }
+        Stmt::Assign { target, value } => {
+            walk_expr(value, bound, refs);
+            note_ref(target, bound, refs);
+        }
+        Stmt::CaptureBind { name, .. } => {
+            bound.insert(name.clone());
+        }
+        Stmt::If { condition, then_body, else_body } => {
+            walk_expr(condition, bound, refs);
+            walk_block(then_body, bound, refs);
+            if let Some(els) = else_body {
+                walk_block(els, bound, refs);
+            }
+        }
+        Stmt::While { condition, body } => {
+            walk_expr(condition, bound, refs);
+            walk_block(body, bound, refs);
+        }
+        Stmt::For { init, condition, update, body } => {
+            let mut inner = bound.clone();
+            if let Some(i) = init {
+                walk_stmt(i, &mut inner, refs);
+            }
+            if let Some(c) = condition {
+                walk_expr(c, &inner, refs);
+            }
+            if let Some(u) = update {
+                walk_stmt(u, &mut inner, refs);
+            }
+            walk_block(body, &inner, refs);
+        }
+        Stmt::ForOf { elem_name, iterable, body, .. } => {
+            walk_expr(iterable, bound, refs);
+            let mut inner = bound.clone();
+            inner.insert(elem_name.clone());
+            walk_stmts(body, &mut inner, refs);
+        }
+        Stmt::Return(e) => {
+            if let Some(e) = e {
+                walk_expr(e, bound, refs);
+            }
+        }
+        Stmt::Block(stmts) => walk_block(stmts, bound, refs),
+        Stmt::Expr(e) | Stmt::Throw(e) => walk_expr(e, bound, refs),
+        Stmt::Switch { discriminant, cases } => {
+            walk_expr(discriminant, bound, refs);
+            for (test, body) in cases {
+                if let Some(t) = test {
+                    walk_expr(t, bound, refs);
+                }
+                walk_block(body, bound, refs);
+            }
+        }
+        Stmt::Break | Stmt::Continue => {}
+        Stmt::TryCatch { try_body, catch_param, catch_body, finally_body } => {
+            walk_block(try_body, bound, refs);
+            if let Some(catch_body) = catch_body {
+                let mut inner = bound.clone();
+                if let Some(p) = catch_param {
+                    inner.insert(p.clone());
+                }
+                walk_stmts(catch_body, &mut inner, refs);
+            }
+            if let Some(finally_body) = finally_body {
+                walk_block(finally_body, bound, refs);
+            }
+        }
+    }
+}
+
+fn walk_expr(expr: &Expr, bound: &HashSet<String>, refs: &mut Vec<String>) {
+    match expr {
+        Expr::IntLit { .. }
+        | Expr::FloatLit { .. }
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::OptionNone { .. } => {}
+        Expr::Var { name, .. } => note_ref(name, bound, refs),
+        Expr::Binary { lhs, rhs, .. }
+        | Expr::Logical { lhs, rhs, .. }
+        | Expr::Coalesce { value: lhs, fallback: rhs, .. } => {
+            walk_expr(lhs, bound, refs);
+            walk_expr(rhs, bound, refs);
+        }
+        Expr::Unary { operand, .. } => walk_expr(operand, bound, refs),
+        Expr::Call { args, .. } | Expr::ConstructorCall { args, .. } => {
+            for a in args {
+                walk_expr(a, bound, refs);
+            }
+        }
+        Expr::MethodCall { receiver, args, .. } => {
+            walk_expr(receiver, bound, refs);
+            for a in args {
+                walk_expr(a, bound, refs);
+            }
+        }
+        Expr::IndirectCall { callee, args, .. } => {
+            walk_expr(callee, bound, refs);
+            for a in args {
+                walk_expr(a, bound, refs);
+            }
+        }
+        Expr::DynCall { receiver, args, .. } => {
+            walk_expr(receiver, bound, refs);
+            for a in args {
+                walk_expr(a, bound, refs);
+            }
+        }
+        Expr::FieldAccess { object, .. } | Expr::Update { target: object, .. } => walk_expr(object, bound, refs),
+        Expr::Assign { target, current, value, .. } => {
+            walk_expr(target, bound, refs);
+            let mut inner = bound.clone();
+            inner.extend(current.clone());
+            walk_expr(value, &inner, refs);
+        }
+        Expr::ArrayIndex { array, index, .. } => {
+            walk_expr(array, bound, refs);
+            walk_expr(index, bound, refs);
+        }
+        Expr::Cast { value, .. }
+        | Expr::OptionSome { value, .. }
+        | Expr::OptionIsSome { value, .. }
+        | Expr::OptionUnwrap { value, .. }
+        | Expr::OptionExpect { value, .. }
+        | Expr::BoxValue { value, .. }
+        | Expr::UnboxValue { ptr: value, .. }
+        | Expr::ResultPropagate { value, .. }
+        | Expr::DynCoerce { value, .. } => walk_expr(value, bound, refs),
+        Expr::OptionAndThen { value, binding, body, .. } => {
+            walk_expr(value, bound, refs);
+            let mut inner = bound.clone();
+            inner.insert(binding.clone());
+            walk_expr(body, &inner, refs);
+        }
+        Expr::StructLit { fields: items, .. }
+        | Expr::ArrayLit { elements: items, .. }
+        | Expr::ArrayConcat { parts: items, .. }
+        | Expr::StringConcat { parts: items } => {
+            for e in items {
+                walk_expr(e, bound, refs);
+            }
+        }
+        Expr::Ternary { condition, then_expr, else_expr, .. } => {
+            walk_expr(condition, bound, refs);
+            walk_expr(then_expr, bound, refs);
+            walk_expr(else_expr, bound, refs);
+        }
+        Expr::UnionLit { payload, .. } => walk_expr(payload, bound, refs),
+        Expr::ResultOk { value, .. } => {
+            if let Some(v) = value {
+                walk_expr(v, bound, refs);
+            }
+        }
+        Expr::Closure { captures, .. } => {
+            for c in captures {
+                note_ref(c, bound, refs);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Closure binding helpers
+// ---------------------------------------------------------------------------
+
+/// Build the environment struct type for a lambda: one pointer slot per
+/// captured variable, in capture order.
+pub(crate) fn closure_env_type(lambda_name: &str, captures: &[(String, LltsType)]) -> LltsType {
+    LltsType::Struct {
+        name: format!("{lambda_name}_env"),
+        fields: captures
+            .iter()
+            .map(|(n, _)| (n.clone(), LltsType::Ptr))
+            .collect(),
+    }
+}
+
+/// Turn a reference to the named function `name` into a function value.
+///
+/// Function values are always called with the env pointer first, so the
+/// value points at a generated wrapper (`__fnref_{name}`) that takes and
+/// ignores the env before forwarding to `name`. Returns `None` if `name` is
+/// not a known function.
+pub(crate) fn function_value(name: &str, ctx: &mut LowerCtx) -> Option<Expr> {
+    let ret = ctx.fn_ret_types.get(name)?.clone();
+    let params = ctx.fn_param_types.get(name).cloned().unwrap_or_default();
+    let thunk = format!("__fnref_{name}");
+
+    if ctx.fn_thunks.insert(thunk.clone()) {
+        let args: Vec<(String, LltsType)> = params
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (format!("__arg{i}"), t.clone()))
+            .collect();
+        let call = Expr::Call {
+            callee: name.to_string(),
+            args: args
+                .iter()
+                .map(|(n, t)| Expr::Var { name: n.clone(), ty: t.clone() })
+                .collect(),
+            ret_type: ret.clone(),
+        };
+        let body = if ret == LltsType::Void {
+            vec![Stmt::Expr(call)]
+        } else {
+            vec![Stmt::Return(Some(call))]
+        };
+        let mut all_params = vec![(ENV_PARAM.to_string(), LltsType::Ptr)];
+        all_params.extend(args);
+        let shape = ctx.param_shapes.get(name).cloned();
+        record_param_shape(&thunk, shape, ctx);
+        ctx.pending_functions.push(FunctionDecl {
+            name: thunk.clone(),
+            params: all_params,
+            ret_type: ret.clone(),
+            body,
+        });
+    }
+
+    Some(Expr::Closure {
+        env_type: closure_env_type(&thunk, &[]),
+        function: thunk,
+        captures: Vec::new(),
+        ty: LltsType::Function { params, ret: Box::new(ret) },
+        boxed: None,
+    })
+}
+
+/// When a capture-free lambda is bound with `const name = (...) => ...`,
+/// turn it into a plain named function so call sites stay direct calls.
+///
+/// The hidden env parameter is dropped since nothing reads it. Returns true
+/// if the binding was absorbed (no `VarDecl` should be emitted).
+pub(crate) fn try_name_lambda(init: &Option<Expr>, name: &str, ctx: &mut LowerCtx) -> bool {
+    // Calls through `name` fill the parameters of the arrow function it holds.
+    let shape = match init {
+        Some(Expr::Closure { function, .. }) => ctx.param_shapes.get(function).cloned(),
+        _ => None,
+    };
+    match &shape {
+        Some(shape) => ctx.closure_shapes.insert(name.to_string(), shape.clone()),
+        None => ctx.closure_shapes.remove(name),
+    };
+    let Some(Expr::Closure { function, captures, .. }) = init else {
+        return false;
+    };
+    if !captures.is_empty() || !function.starts_with("__lambda_") {
+        return false;
+    }
+    ctx.closure_shapes.remove(name);
+    record_param_shape(name, shape, ctx);
+    if let Some(func) = ctx.pending_functions.iter_mut().find(|f| f.name == *function) {
+        func.name = name.to_string();
+        if func.params.first().is_some_and(|(p, _)| p == ENV_PARAM) {
+            func.params.remove(0);
+        }
+        let param_types: Vec<LltsType> = func.params.iter().map(|(_, t)| t.clone()).collect();
+        ctx.fn_param_types.insert(name.to_string(), param_types);
+    }
+    if let Some(ret) = ctx.fn_ret_types.get(function).cloned() {
+        ctx.fn_ret_types.insert(name.to_string(), ret);
+    }
+    ctx.var_types.remove(name);
+    true
+}
+
+// ---------------------------------------------------------------------------
+// Escaping closures
+// ---------------------------------------------------------------------------
+
+/// Where a value flows: out of the current frame, into a named local, or
+/// nowhere that outlives the expression.
+#[derive(Clone, Copy)]
+enum Flow<'a> {
+    Escape,
+    Into(&'a str),
+    Local,
+}
+
+/// Per-function value-flow facts gathered by [`collect_flows`].
+#[derive(Default)]
+struct FlowFacts {
+    /// Variables whose value flows out of the frame.
+    escaping_vars: HashSet<String>,
+    /// Lambdas whose closure value flows out of the frame.
+    escaping_lambdas: HashSet<String>,
+    /// `(target, source)`: the value of variable `source` is stored in `target`.
+    var_edges: Vec<(String, String)>,
+    /// `(target, lambda)`: a closure over `lambda` is stored in `target`.
+    lambda_edges: Vec<(String, String)>,
+}
+
+impl FlowFacts {
+    fn note_var(&mut self, name: &str, flow: Flow<'_>) {
+        match flow {
+            Flow::Escape => {
+                self.escaping_vars.insert(name.to_string());
+            }
+            Flow::Into(target) => self.var_edges.push((target.to_string(), name.to_string())),
+            Flow::Local => {}
+        }
+    }
+
+    /// All variables that escape, directly or through a variable that does.
+    fn escaping_names(&self) -> HashSet<String> {
+        let mut out = self.escaping_vars.clone();
+        loop {
+            let mut changed = false;
+            for (target, source) in &self.var_edges {
+                if out.contains(target) && !out.contains(source) {
+                    out.insert(source.clone());
+                    changed = true;
+                }
+            }
+            if !changed {
+                return out;
+            }
+        }
+    }
+}
+
+/// Parameters of each function that may outlive the call (stored, returned,
+/// or passed on to another owning parameter), indexed like `params`.
+type OwningParams = HashMap<String, Vec<bool>>;
+
+fn collect_flows(body: &[Stmt], owning: &OwningParams, facts: &mut FlowFacts) {
+    for_each_stmt(body, &mut |stmt| match stmt {
+        Stmt::VarDecl { name, init: Some(e), .. } | Stmt::BoxDecl { name, init: Some(e), .. } => {
+            flow_expr(e, Flow::Into(name), owning, facts)
+        }
+        Stmt::Assign { target, value } => flow_expr(value, Flow::Into(target), owning, facts),
+        Stmt::Return(Some(e)) | Stmt::Throw(e) => {
+            flow_expr(e, Flow::Escape, owning, facts)
+        }
+        other => {
+            for e in stmt_exprs(other) {
+                flow_expr(e, Flow::Local, owning, facts);
+            }
+        }
+    });
+}
+
+fn flow_expr(expr: &Expr, flow: Flow<'_>, owning: &OwningParams, facts: &mut FlowFacts) {
+    // Unknown callees (builtins, externs) are assumed to keep their arguments.
+    let arg_flow = |callee: &str, i: usize| -> Flow<'static> {
+        match owning.get(callee).and_then(|o| o.get(i)) {
+            Some(false) => Flow::Local,
+            _ => Flow::Escape,
+        }
+    };
+    match expr {
+        Expr::Var { name, .. } => facts.note_var(name, flow),
+        Expr::Closure { function, captures, .. } => {
+            match flow {
+                Flow::Escape => {
+                    facts.escaping_lambdas.insert(function.clone());
+                }
+                Flow::Into(target) => {
+                    facts.lambda_edges.push((target.to_string(), function.clone()))
+                }
+                Flow::Local => {}
+            }
+            // The env carries the captured values along with the closure.
+            for c in captures {
+                facts.note_var(c, flow);
+            }
+        }
+        Expr::Ternary { condition, then_expr, else_expr, .. } => {
+            flow_expr(condition, Flow::Local, owning, facts);
+            flow_expr(then_expr, flow, owning, facts);
+            flow_expr(else_expr, flow, owning, facts);
+        }
+        Expr::Logical { lhs, rhs, .. } | Expr::Coalesce { value: lhs, fallback: rhs, .. } => {
+            flow_expr(lhs, flow, owning, facts);
+            flow_expr(rhs, flow, owning, facts);
+        }
+        Expr::Assign { target, value, .. } => {
+            // A value stored in a field or element, or also used, escapes.
+            let into = match (target.as_ref(), flow) {
+                (Expr::Var { name, .. }, Flow::Local) => Flow::Into(name),
+                _ => Flow::Escape,
+            };
+            flow_expr(value, into, owning, facts);
+            for child in expr_children(target) {
+                flow_expr(child, Flow::Local, owning, facts);
+            }
+        }
+        Expr::Cast { value, .. }
+        | Expr::OptionSome { value, .. }
+        | Expr::OptionUnwrap { value, .. }
+        | Expr::OptionExpect { value, .. }
+        | Expr::ResultPropagate { value, .. } => flow_expr(value, flow, owning, facts),
+        Expr::OptionAndThen { value, binding, body, .. } => {
+            flow_expr(value, Flow::Into(binding), owning, facts);
+            flow_expr(body, flow, owning, facts);
+        }
+        Expr::StructLit { fields: items, .. }
+        | Expr::ArrayLit { elements: items, .. }
+        | Expr::ArrayConcat { parts: items, .. } => {
+            for e in items {
+                flow_expr(e, Flow::Escape, owning, facts);
+            }
+        }
+        Expr::UnionLit { payload, .. }
+        | Expr::DynCoerce { value: payload, .. }
+        | Expr::BoxValue { value: payload, .. } => flow_expr(payload, Flow::Escape, owning, facts),
+        Expr::Call { callee, args, .. } => {
+            for (i, a) in args.iter().enumerate() {
+                flow_expr(a, arg_flow(callee, i), owning, facts);
+            }
+        }
+        Expr::MethodCall { class_name, method_name, receiver, args, .. } => {
+            flow_expr(receiver, Flow::Local, owning, facts);
+            let mangled = format!("{class_name}_{method_name}");
+            for (i, a) in args.iter().enumerate() {
+                // Methods take `self` as their first parameter.
+                flow_expr(a, arg_flow(&mangled, i + 1), owning, facts);
+            }
+        }
+        Expr::ConstructorCall { args, .. } => {
+            for a in args {
+                flow_expr(a, Flow::Escape, owning, facts);
+            }
+        }
+        Expr::IndirectCall { callee: receiver, args, .. } | Expr::DynCall { receiver, args, .. } => {
+            flow_expr(receiver, Flow::Local, owning, facts);
+            for a in args {
+                flow_expr(a, Flow::Escape, owning, facts);
+            }
+        }
+        other => {
+            for child in expr_children(other) {
+                flow_expr(child, Flow::Local, owning, facts);
+            }
+        }
+    }
+}
+
+/// Names bound by the `CaptureBind` prologue of a lambda body.
+fn captured_names(body: &[Stmt]) -> Vec<&str> {
+    body.iter()
+        .filter_map(|s| match s {
+            Stmt::CaptureBind { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `func` is `ancestor` or a lambda defined (transitively) inside it.
+fn nested_in<'a>(parents: &'a HashMap<String, String>, func: &'a str, ancestor: &str) -> bool {
+    let mut cur = func;
+    loop {
+        if cur == ancestor {
+            return true;
+        }
+        match parents.get(cur) {
+            Some(p) => cur = p.as_str(),
+            None => return false,
+        }
+    }
+}
+
+/// Variables a body reassigns or mutates in place.
+fn mutated_names(body: &[Stmt]) -> HashSet<String> {
+    let mut out = HashSet::new();
+    for_each_stmt(body, &mut |stmt| match stmt {
+        Stmt::Assign { target, .. } => {
+            out.insert(target.clone());
+        }
+        _ => {}
+    });
+    for_each_expr(body, &mut |expr| match expr {
+        Expr::MethodCall { class_name, method_name, receiver, .. }
+            if class_name == "__Array" && matches!(method_name.as_str(), "push" | "pop") =>
+        {
+            if let Expr::Var { name, .. } = receiver.as_ref() {
+                out.insert(name.clone());
+            }
+        }
+        Expr::Assign { target, .. } | Expr::Update { target, .. } => {
+            if let Some(name) = place_variable(target) {
+                out.insert(name.to_string());
+            }
+        }
+        _ => {}
+    });
+    out
+}
+
+/// The variable whose storage holds place `place`: the variable itself or
+/// one whose fields it is. Array elements live on the heap instead.
+fn place_variable(place: &Expr) -> Option<&str> {
+    match place {
+        Expr::Var { name, .. } => Some(name),
+        Expr::FieldAccess { object, .. } => place_variable(object),
+        _ => None,
+    }
+}
+
+/// Rewrite closures that may outlive their defining frame so they no longer
+/// point into it.
+///
+/// A closure escapes when it is returned, stored in a struct, array or field,
+/// passed to a parameter that itself escapes, or bound to a variable that
+/// does. Its env moves to the heap; each capture is either copied into the
+/// env (never mutated) or, when some function sharing the variable mutates
+/// it, moved into a refcounted box at its declaration so the defining frame
+/// and every closure observe the same storage.
+pub(crate) fn box_escaping_captures(functions: &mut [FunctionDecl]) {
+    let index: HashMap<String, usize> = functions
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.name.clone(), i))
+        .collect();
+    let mut parents: HashMap<String, String> = HashMap::new();
+    for f in functions.iter() {
+        for_each_expr(&f.body, &mut |expr| {
+            if let Expr::Closure { function, .. } = expr {
+                parents.insert(function.clone(), f.name.clone());
+            }
+        });
+    }
+
+    // Owning parameters and escaping variables depend on each other, so
+    // iterate until the owning set stops growing.
+    let mut owning: OwningParams = functions
+        .iter()
+        .map(|f| (f.name.clone(), vec![false; f.params.len()]))
+        .collect();
+    let (facts, escaping) = loop {
+        let mut facts: Vec<FlowFacts> = functions
+            .iter()
+            .map(|f| {
+                let mut facts = FlowFacts::default();
+                collect_flows(&f.body, &owning, &mut facts);
+                facts
+            })
+            .collect();
+
+        // A captured variable that escapes from a lambda escapes its parent too.
+        let escaping = loop {
+            let escaping: Vec<HashSet<String>> = facts.iter().map(FlowFacts::escaping_names).collect();
+            let mut changed = false;
+            for (i, f) in functions.iter().enumerate() {
+                let Some(&pi) = parents.get(&f.name).and_then(|p| index.get(p)) else {
+                    continue;
+                };
+                for name in captured_names(&f.body) {
+                    if escaping[i].contains(name) && facts[pi].escaping_vars.insert(name.to_string()) {
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break escaping;
+            }
+        };
+
+        let next: OwningParams = functions
+            .iter()
+            .zip(&escaping)
+            .map(|(f, esc)| (f.name.clone(), f.params.iter().map(|(p, _)| esc.contains(p)).collect()))
+            .collect();
+        if next == owning {
+            break (facts, escaping);
+        }
+        owning = next;
+    };
+
+    let mut escaping_lambdas: Vec<String> = Vec::new();
+    for (fa, esc) in facts.iter().zip(&escaping) {
+        escaping_lambdas.extend(fa.escaping_lambdas.iter().cloned());
+        for (target, lambda) in &fa.lambda_edges {
+            if esc.contains(target) {
+                escaping_lambdas.push(lambda.clone());
+            }
+        }
+    }
+    escaping_lambdas.sort();
+    escaping_lambdas.dedup();
+
+    let mutated: Vec<HashSet<String>> = functions.iter().map(|f| mutated_names(&f.body)).collect();
+    // Plan: which variables to box where, and each escaping lambda's layout.
+    let mut boxes: HashSet<(usize, String)> = HashSet::new();
+    let mut layouts: Vec<(usize, usize, Vec<bool>)> = Vec::new();
+    for lambda in &escaping_lambdas {
+        let Some(&li) = index.get(lambda) else { continue };
+        let Some(&pi) = parents.get(lambda).and_then(|p| index.get(p)) else {
+            continue;
+        };
+        let mut by_ref = Vec::new();
+        for name in captured_names(&functions[li].body) {
+            // Walk up to the function that declares the variable.
+            let mut di = pi;
+            while captured_names(&functions[di].body).contains(&name) {
+                match parents.get(&functions[di].name).and_then(|p| index.get(p)) {
+                    Some(&up) => di = up,
+                    None => break,
+                }
+            }
+            let decl = &functions[di];
+            let is_mutated = functions
+                .iter()
+                .zip(&mutated)
+                .any(|(f, m)| m.contains(name) && nested_in(&parents, &f.name, &decl.name));
+            let boxable = decl.params.iter().any(|(p, _)| p == name) || {
+                let mut found = false;
+                for_each_stmt(&decl.body, &mut |s| {
+                    if matches!(s, Stmt::VarDecl { name: n, .. } if n == name) {
+                        found = true;
+                    }
+                });
+                found
+            };
+            if is_mutated && boxable {
+                boxes.insert((di, name.to_string()));
+                by_ref.push(true);
+            } else {
+                by_ref.push(false);
+            }
+        }
+        layouts.push((li, pi, by_ref));
+    }
+
+    for (di, name) in boxes {
+        let func = &mut functions[di];
+        for_each_stmt_mut(&mut func.body, &mut |s| {
+            if let Stmt::VarDecl { name: n, ty, init } = s {
+                if *n == name {
+                    *s = Stmt::BoxDecl { name: n.clone(), ty: ty.clone(), init: init.take() };
+                }
+            }
+        });
+        if let Some((_, ty)) = func.params.iter().find(|(p, _)| *p == name) {
+            let ty = ty.clone();
+            func.body.insert(0, Stmt::BoxDecl {
+                name: name.clone(),
+                ty: ty.clone(),
+                init: Some(Expr::Var { name, ty }),
+            });
+        }
+    }
+
+    for (li, pi, by_ref) in layouts {
+        let lambda_name = functions[li].name.clone();
+        let mut fields = Vec::new();
+        let mut slot = 0;
+        for stmt in functions[li].body.iter() {
+            if let Stmt::CaptureBind { name, ty, .. } = stmt {
+                let field_ty = if by_ref[slot] { LltsType::Ptr } else { ty.clone() };
+                fields.push((name.clone(), field_ty));
+                slot += 1;
+            }
+        }
+        let new_env = LltsType::Struct { name: format!("{lambda_name}_env"), fields };
+
+        let mut slot = 0;
+        for stmt in functions[li].body.iter_mut() {
+            if let Stmt::CaptureBind { env_type, by_ref: r, .. } = stmt {
+                *env_type = new_env.clone();
+                *r = by_ref[slot];
+                slot += 1;
+            }
+        }
+        for_each_expr_mut(&mut functions[pi].body, &mut |expr| {
+            if let Expr::Closure { function, env_type, boxed, .. } = expr {
+                if *function == lambda_name {
+                    *env_type = new_env.clone();
+                    *boxed = Some(by_ref.clone());
+                }
+            }
+        });
+    }
+}
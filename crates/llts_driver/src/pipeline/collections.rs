@@ -0,0 +1,213 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, types::{LltsType, TypeRegistry}};
+
+use super::context::LowerCtx;
+use super::utils::{coerce_to_type, expr_to_name, ir_expr_type};
+use super::{codegen_type_suffix, coerce_to_dyn, lower_type_annotation, mangle_generic_name};
+
+// ---------------------------------------------------------------------------
+// Map<K, V> and Set<T>
+// ---------------------------------------------------------------------------
+//
+// Both lower to `__Map` / `__Set` builtin calls on a shared hash table (see
+// `llts_codegen::collections`). Iteration works on an insertion-ordered
+// snapshot array: `keys()`, `values()`, `entries()`, and `for...of`, which
+// walks `entries()` of a map and `values()` of a set.
+
+/// Lower `new Map<K, V>()` / `new Set<T>()`. Without type arguments they are
+/// taken from `expected`, the type the context wants. `None` if the callee
+/// is not `Map` or `Set`.
+pub(crate) fn lower_new_collection(
+    new_expr: &NewExpression<'_>,
+    expected: Option<&LltsType>,
+    ctx: &mut LowerCtx,
+) -> Option<Expr> {
+    let callee = expr_to_name(&new_expr.callee, ctx);
+    if callee != "Map" && callee != "Set" {
+        return None;
+    }
+    let ty = match &new_expr.type_arguments {
+        Some(type_args) => {
+            let args: Vec<LltsType> = type_args.params.iter().map(|t| lower_type_annotation(t, ctx)).collect();
+            let arg = |i: usize| Box::new(args.get(i).cloned().unwrap_or(LltsType::F64));
+            if callee == "Set" {
+                LltsType::Set(arg(0))
+            } else {
+                LltsType::Map { key: arg(0), value: arg(1) }
+            }
+        }
+        None => match expected {
+            Some(ty @ LltsType::Map { .. }) if callee == "Map" => ty.clone(),
+            Some(ty @ LltsType::Set(_)) if callee == "Set" => ty.clone(),
+            _ => {
+                ctx.errors.push(format!(
+                    "cannot infer the type arguments of new {callee}(); write them out or annotate the binding"
+                ));
+                LltsType::Set(Box::new(LltsType::F64))
+            }
+        },
+    };
+    if !new_expr.arguments.is_empty() {
+        ctx.errors.push(format!("new {callee}() does not take initial entries; add them with set/add"));
+    }
+    if let LltsType::Map { key, .. } | LltsType::Set(key) = &ty {
+        if !is_hashable(key) {
+            ctx.errors.push(format!(
+                "{callee} key type {} is not hashable; keys must be numbers, booleans, strings or structs of those",
+                codegen_type_suffix(key)
+            ));
+        }
+    }
+    Some(Expr::ConstructorCall {
+        class_name: format!("__{callee}"),
+        args: Vec::new(),
+        ret_type: ty,
+    })
+}
+
+/// Whether `ty` can be a map key or set element.
+fn is_hashable(ty: &LltsType) -> bool {
+    match ty {
+        LltsType::Struct { fields, .. } => !fields.is_empty() && fields.iter().all(|(_, f)| is_hashable(f)),
+        LltsType::String | LltsType::Bool => true,
+        other => TypeRegistry::is_numeric(other),
+    }
+}
+
+/// Whether values of `ty` are a `Map` or `Set`.
+pub(crate) fn is_collection(ty: &LltsType) -> bool {
+    matches!(ty, LltsType::Map { .. } | LltsType::Set(_))
+}
+
+/// Lower `method(args)` called on `receiver`, a `Map` or `Set`.
+pub(crate) fn collection_method_call(receiver: Expr, method: &str, args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    let receiver_type = ir_expr_type(&receiver);
+    let (class_name, key, value) = match &receiver_type {
+        LltsType::Map { key, value } => ("__Map", *key.clone(), Some(*value.clone())),
+        LltsType::Set(elem) => ("__Set", *elem.clone(), None),
+        _ => unreachable!("collection_method_call on {receiver_type:?}"),
+    };
+    let arg_types: Vec<LltsType> = match (method, &value) {
+        ("set", Some(value)) => vec![key.clone(), value.clone()],
+        ("add", None) | ("get", Some(_)) | ("has" | "delete", _) => vec![key.clone()],
+        _ => Vec::new(),
+    };
+    let ret_type = match (method, &value) {
+        ("set", Some(_)) | ("add", None) => receiver_type.clone(),
+        ("get", Some(value)) => LltsType::Option(Box::new(value.clone())),
+        ("has" | "delete", _) => LltsType::Bool,
+        ("clear", _) => LltsType::Void,
+        ("keys", _) | ("values", None) => LltsType::Array(Box::new(key.clone())),
+        ("values", Some(value)) => LltsType::Array(Box::new(value.clone())),
+        ("entries", Some(value)) => LltsType::Array(Box::new(entry_type(&key, value))),
+        _ => {
+            let type_name = if value.is_some() { "Map" } else { "Set" };
+            ctx.errors.push(format!("unsupported {type_name} method '{method}'"));
+            LltsType::Void
+        }
+    };
+    if args.len() != arg_types.len() {
+        ctx.errors.push(format!(
+            "{method}() on a {} takes {} argument(s), got {}",
+            if value.is_some() { "Map" } else { "Set" },
+            arg_types.len(),
+            args.len()
+        ));
+    }
+    let args = args
+        .into_iter()
+        .zip(&arg_types)
+        .map(|(arg, ty)| coerce_element(arg, ty, ctx))
+        .collect();
+    Expr::MethodCall {
+        class_name: class_name.to_string(),
+        method_name: method.to_string(),
+        receiver: Box::new(receiver),
+        args,
+        ret_type,
+    }
+}
+
+/// `collection.size`, as an `i64` like `.length`.
+pub(crate) fn collection_size(receiver: Expr) -> Expr {
+    let class_name = match ir_expr_type(&receiver) {
+        LltsType::Map { .. } => "__Map",
+        _ => "__Set",
+    };
+    Expr::MethodCall {
+        class_name: class_name.to_string(),
+        method_name: "size".to_string(),
+        receiver: Box::new(receiver),
+        args: Vec::new(),
+        ret_type: LltsType::I64,
+    }
+}
+
+/// The array a `for...of` loop walks: a map's entries, a set's values, or
+/// `iterable` itself.
+pub(crate) fn iteration_source(iterable: Expr, ctx: &mut LowerCtx) -> Expr {
+    match ir_expr_type(&iterable) {
+        LltsType::Map { .. } => collection_method_call(iterable, "entries", Vec::new(), ctx),
+        LltsType::Set(_) => collection_method_call(iterable, "values", Vec::new(), ctx),
+        _ => iterable,
+    }
+}
+
+/// The `[key, value]` pair type of `entries()`, a struct with fields `0` and `1`.
+pub(crate) fn entry_type(key: &LltsType, value: &LltsType) -> LltsType {
+    LltsType::Struct {
+        name: mangle_generic_name("__Entry", &[key.clone(), value.clone()]),
+        fields: vec![("0".to_string(), key.clone()), ("1".to_string(), value.clone())],
+    }
+}
+
+/// Element `index` of `source` for array destructuring (`const [k, v] = e`):
+/// a field of a map entry, otherwise an array element. Returns the element
+/// and its type.
+pub(crate) fn destructure_element(source: Expr, index: usize) -> (Expr, LltsType) {
+    match ir_expr_type(&source) {
+        LltsType::Struct { name, fields } if name.starts_with("__Entry$") && index < fields.len() => {
+            let field_type = fields[index].1.clone();
+            let object_type = LltsType::Struct { name, fields };
+            let access = Expr::FieldAccess {
+                object: Box::new(source),
+                object_type,
+                field_index: index as u32,
+                field_type: field_type.clone(),
+            };
+            (access, field_type)
+        }
+        source_type => {
+            let elem_type = match source_type {
+                LltsType::Array(elem) => *elem,
+                _ => LltsType::F64,
+            };
+            let access = Expr::ArrayIndex {
+                array: Box::new(source),
+                index: Box::new(Expr::IntLit { value: index as i64, ty: LltsType::I64 }),
+                elem_type: elem_type.clone(),
+            };
+            (access, elem_type)
+        }
+    }
+}
+
+/// Coerce a key or value argument to the collection's element type,
+/// filling in the type of struct literals.
+fn coerce_element(arg: Expr, ty: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    let arg = match (arg, ty) {
+        (Expr::StructLit { struct_type: LltsType::Struct { name, .. }, fields }, LltsType::Struct { fields: type_fields, .. })
+            if name.is_empty() =>
+        {
+            let fields = fields
+                .into_iter()
+                .zip(type_fields)
+                .map(|(field, (_, field_ty))| coerce_to_type(field, field_ty))
+                .collect();
+            Expr::StructLit { struct_type: ty.clone(), fields }
+        }
+        (arg, _) => arg,
+    };
+    coerce_to_dyn(coerce_to_type(arg, ty), ty, ctx)
+}
@@ -10,14 +10,15 @@ use inkwell::passes::PassBuilderOptions;
 use inkwell::OptimizationLevel;
 use oxc_allocator::Allocator;
 use oxc_ast::ast::*;
+use oxc_semantic::Scoping;
 
 use llts_codegen::{CodeGenerator, ProgramIR};
 use llts_frontend::parse;
 use llts_frontend::resolve::ModuleResolver;
 use llts_frontend::semantic;
 
-use super::context::LowerCtx;
-use super::lower_program_with_ctx;
+use super::context::{LowerCtx, ModuleSymbols};
+use super::{lower_program_with_ctx, propagate_throws, register_builtin_error};
 
 /// Compilation options.
 #[derive(Debug, Clone)]
@@ -89,8 +90,8 @@ impl std::fmt::Display for CompileError {
 ///
 /// This runs the full pipeline:
 /// 1. Resolve module graph (walk imports recursively)
-/// 2. For each file (dependencies first): parse → analyze → lower
-/// 3. Merge all IR into a single ProgramIR
+/// 2. Parse → analyze every file
+/// 3. Lower each file (dependencies first) and merge all IR into a single ProgramIR
 /// 4. LLVM IR generation (llts_codegen)
 /// 5. LLVM optimization
 /// 6. Object emission + linking
@@ -101,22 +102,57 @@ pub fn compile_file(path: &Path, options: &CompileOptions) -> Result<(), Compile
     // Stage 1: Resolve module graph (entry + all transitive imports)
     let file_order = resolve_module_graph(&abs_path)?;
 
-    // Stage 2-4: Parse, analyze, and lower each file with shared context
+    // Stage 2-3: Parse and analyze every file. The ASTs stay alive until all
+    // files are lowered, so generics declared in one file can be specialized
+    // while lowering any file that imports them.
+    let sources = file_order
+        .iter()
+        .map(|file_path| std::fs::read_to_string(file_path).map_err(CompileError::Io))
+        .collect::<Result<Vec<_>, _>>()?;
+    let allocator = Allocator::default();
+    let (programs, scopings): (Vec<_>, Vec<_>) = file_order
+        .iter()
+        .zip(&sources)
+        .map(|(file_path, source)| parse_file(&allocator, source, file_path))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip();
+
+    // Calls to throwing functions must be handled, across the module graph
+    let throws_errors = llts_analysis::analyze_throws(&programs, &file_order);
+    if !throws_errors.is_empty() {
+        return Err(CompileError::Analysis(
+            throws_errors
+                .iter()
+                .map(|(file, e)| format!("{}: {e}", file_order[*file].display()))
+                .collect(),
+        ));
+    }
+
+    // Stage 4: Lower each file (dependencies first) with shared context
     let mut ctx = LowerCtx::new();
+    ctx.module_symbols = scopings.into_iter().map(ModuleSymbols::new).collect();
+    register_builtin_error(&mut ctx);
     let mut merged_ir = ProgramIR {
         structs: Vec::new(),
         enums: Vec::new(),
         functions: Vec::new(),
     };
 
-    for file_path in &file_order {
+    for (index, file_path) in file_order.iter().enumerate() {
         let is_entry = file_path == &abs_path;
-        let ir = compile_single_file(file_path, &mut ctx, is_entry)?;
+        let ir = lower_file(&programs, index, file_path, &mut ctx, is_entry)?;
         merged_ir.structs.extend(ir.structs);
         merged_ir.enums.extend(ir.enums);
         merged_ir.functions.extend(ir.functions);
     }
 
+    // Functions whose throws can escape them return a Result from here on
+    propagate_throws(&mut merged_ir.functions, &mut ctx);
+    if !ctx.errors.is_empty() {
+        return Err(CompileError::Analysis(std::mem::take(&mut ctx.errors)));
+    }
+
     // Stage 5: LLVM IR generation
     let context = Context::create();
     let mut codegen = CodeGenerator::new(&context, "main");
@@ -139,19 +175,14 @@ pub fn compile_file(path: &Path, options: &CompileOptions) -> Result<(), Compile
     emit_and_link(&module, options)
 }
 
-/// Parse, analyze, and lower a single file to ProgramIR.
-/// Uses the shared `LowerCtx` so imported types/functions are available.
-/// If `is_entry` is false, the `main` function is excluded.
-pub(crate) fn compile_single_file(
+/// Parse and analyze a single file, returning its AST and symbol table.
+pub(crate) fn parse_file<'a>(
+    allocator: &'a Allocator,
+    source_text: &'a str,
     path: &Path,
-    ctx: &mut LowerCtx,
-    is_entry: bool,
-) -> Result<ProgramIR, CompileError> {
-    let source_text = std::fs::read_to_string(path).map_err(CompileError::Io)?;
-
+) -> Result<(Program<'a>, Scoping), CompileError> {
     // Parse
-    let allocator = Allocator::default();
-    let parse_result = parse::parse_source(&allocator, &source_text, path);
+    let parse_result = parse::parse_source(allocator, source_text, path);
     if !parse_result.is_ok() {
         return Err(CompileError::Parse(
             parse_result
@@ -161,9 +192,10 @@ pub(crate) fn compile_single_file(
                 .collect(),
         ));
     }
+    let program = parse_result.program;
 
     // Semantic analysis
-    let sem_result = semantic::analyze_semantics(&parse_result.program);
+    let sem_result = semantic::analyze_semantics(&program);
     if !sem_result.is_ok() {
         return Err(CompileError::Semantic(
             sem_result
@@ -173,9 +205,10 @@ pub(crate) fn compile_single_file(
                 .collect(),
         ));
     }
+    let scoping = sem_result.semantic.into_scoping();
 
     // Subset validation + type resolution
-    let analysis_result = llts_analysis::analyze(&parse_result.program);
+    let analysis_result = llts_analysis::analyze(&program);
     if analysis_result.has_errors() {
         return Err(CompileError::Analysis(
             analysis_result
@@ -185,9 +218,28 @@ pub(crate) fn compile_single_file(
                 .collect(),
         ));
     }
+    Ok((program, scoping))
+}
 
-    // Lower AST → codegen IR with shared context
-    let ir = lower_program_with_ctx(&parse_result.program, ctx, is_entry);
+/// Lower `modules[index]` to ProgramIR.
+/// Uses the shared `LowerCtx` so imported types/functions are available.
+/// If `is_entry` is false, the `main` function is excluded.
+pub(crate) fn lower_file(
+    modules: &[Program<'_>],
+    index: usize,
+    path: &Path,
+    ctx: &mut LowerCtx,
+    is_entry: bool,
+) -> Result<ProgramIR, CompileError> {
+    let ir = lower_program_with_ctx(modules, index, ctx, is_entry);
+    if !ctx.errors.is_empty() {
+        return Err(CompileError::Analysis(
+            ctx.errors
+                .drain(..)
+                .map(|e| format!("{}: {e}", path.display()))
+                .collect(),
+        ));
+    }
     Ok(ir)
 }
 
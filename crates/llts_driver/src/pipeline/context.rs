@@ -1,5 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
+use oxc_ast::ast::{BindingIdentifier, IdentifierReference};
+use oxc_semantic::{ScopeId, Scoping, SymbolFlags, SymbolId};
+
 use llts_codegen::{FunctionDecl, types::LltsType};
 
 /// Definition of a discriminated (tagged) union detected from type aliases.
@@ -15,6 +18,91 @@ pub(crate) struct DiscriminatedUnionDef {
     pub(crate) union_type: LltsType,
 }
 
+/// One vtable slot of an interface or polymorphic base class.
+#[derive(Debug, Clone)]
+pub(crate) struct DynSlot {
+    /// Member name.
+    pub(crate) name: String,
+    /// Method parameter types (without `self`); `None` for a field, which is
+    /// read through a getter slot.
+    pub(crate) params: Option<Vec<LltsType>>,
+    /// Method return type, or the field's type.
+    pub(crate) ret: LltsType,
+}
+
+/// How the arguments of a call fill the parameters of a function that does
+/// not take exactly one argument per parameter. Counts leave out a method's
+/// receiver.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ParamShape {
+    /// Number of leading parameters an argument must be given for. Later
+    /// ones are optional (`x?: T`) or have a default, and receive `null`
+    /// when left out.
+    pub(crate) required: usize,
+    /// Whether the last parameter is a rest parameter (`...xs: T[]`), taking
+    /// the trailing arguments as an array.
+    pub(crate) rest: bool,
+}
+
+/// The symbol table of one module, with the names its variables are lowered
+/// under.
+///
+/// Lowering keeps variables in maps keyed by name, so each symbol that may
+/// be in one of them at the same time needs a name of its own. A local that
+/// shadows a binding of an enclosing scope, or shares its name with an
+/// earlier local of the same function (`let x` in two sibling blocks), is
+/// lowered as `x#1`, `x#2`, ...; `#` cannot appear in an identifier, so these
+/// never collide with a source name. Other bindings keep their names.
+pub(crate) struct ModuleSymbols {
+    scoping: Scoping,
+    names: HashMap<SymbolId, String>,
+}
+
+impl ModuleSymbols {
+    pub(crate) fn new(scoping: Scoping) -> Self {
+        Self { names: lowered_names(&scoping), scoping }
+    }
+}
+
+/// Lowered names of the symbols of `scoping` that do not keep their source
+/// name.
+fn lowered_names(scoping: &Scoping) -> HashMap<SymbolId, String> {
+    let root = scoping.root_scope_id();
+    let mut seen: HashSet<(ScopeId, &str)> = HashSet::new();
+    let mut counters: HashMap<&str, usize> = HashMap::new();
+    let mut names = HashMap::new();
+
+    for symbol in scoping.symbol_ids() {
+        let scope = scoping.symbol_scope_id(symbol);
+        let flags = scoping.symbol_flags(symbol);
+        if scope == root || !flags.intersects(SymbolFlags::Variable | SymbolFlags::CatchVariable) {
+            continue;
+        }
+        let name = scoping.symbol_name(symbol);
+        let shadows = scoping
+            .scope_parent_id(scope)
+            .and_then(|parent| scoping.find_binding(parent, name.into()))
+            .is_some_and(|outer| scoping.symbol_flags(outer).intersects(SymbolFlags::Value | SymbolFlags::Import));
+        let function = scoping
+            .scope_ancestors(scope)
+            .find(|&s| scoping.scope_flags(s).is_function())
+            .unwrap_or(root);
+        if seen.insert((function, name)) && !shadows {
+            continue;
+        }
+        let counter = counters.entry(name).or_default();
+        *counter += 1;
+        names.insert(symbol, format!("{name}#{counter}"));
+    }
+    names
+}
+
+/// The source name of a variable lowered under `name` (see [`ModuleSymbols`]),
+/// for diagnostics.
+pub(crate) fn source_name(name: &str) -> &str {
+    name.split_once('#').map_or(name, |(source, _)| source)
+}
+
 /// Lower an oxc Program AST into the codegen ProgramIR.
 /// Lowering context: tracks struct definitions, variable types, and function signatures.
 pub(crate) struct LowerCtx {
@@ -28,10 +116,28 @@ pub(crate) struct LowerCtx {
     pub(crate) fn_ret_types: HashMap<String, LltsType>,
     /// Function name -> parameter types (for coercing arguments at call sites).
     pub(crate) fn_param_types: HashMap<String, Vec<LltsType>>,
+    /// Function, method (`{Class}_{method}`) or constructor (`{Class}_new`)
+    /// name -> its parameter shape, if it has optional, defaulted or rest
+    /// parameters.
+    pub(crate) param_shapes: HashMap<String, ParamShape>,
+    /// Variable name -> parameter shape of the arrow function it holds, for
+    /// closures called through the variable.
+    pub(crate) closure_shapes: HashMap<String, ParamShape>,
+    /// Variable name -> length of its tuple type (`[T, T]`), for spreading
+    /// it into separate arguments.
+    pub(crate) tuple_lengths: HashMap<String, usize>,
     /// Counter for generating unique lambda function names.
     pub(crate) lambda_counter: usize,
     /// Lambda functions generated from arrow expressions, to be appended after lowering.
     pub(crate) pending_functions: Vec<FunctionDecl>,
+    /// Names of the helper functions generated once per program: env-taking
+    /// wrappers of named functions used as values (see `function_value`) and
+    /// `poll` functions of built-in promises (see `async_fns`).
+    pub(crate) fn_thunks: HashSet<String>,
+    /// Expected parameter types and, if known, return type for the next arrow
+    /// function lowered, taken from its context (annotated binding, call
+    /// parameter, return type).
+    pub(crate) lambda_signature_hint: Option<(Vec<LltsType>, Option<LltsType>)>,
     /// String literal union type name -> (string_value -> integer_tag).
     /// e.g. `type Status = "pending" | "active" | "done"` -> {"Status": {"pending": 0, "active": 1, "done": 2}}
     pub(crate) string_literal_unions: HashMap<String, HashMap<String, i64>>,
@@ -40,13 +146,28 @@ pub(crate) struct LowerCtx {
     /// Type alias name -> individual union member types (pre-widening).
     /// e.g. `type Num = i8 | i16 | i32 | i64` -> [I8, I16, I32, I64]
     pub(crate) type_alias_members: HashMap<String, Vec<LltsType>>,
-    /// Generic function name -> index into the program body where the FunctionDeclaration lives.
-    /// These functions have TSTypeParameterDeclaration and are not lowered directly.
-    pub(crate) generic_fn_indices: HashMap<String, usize>,
+    /// Generic function name -> (module index, index into that module's program body)
+    /// where the FunctionDeclaration lives. These functions have
+    /// TSTypeParameterDeclaration and are not lowered directly; the module
+    /// index lets a file specialize generics imported from another.
+    pub(crate) generic_fn_indices: HashMap<String, (usize, usize)>,
     /// Generic function name -> list of (param_name, default_type, constraint_types).
     /// default_type is the lowered default (e.g. `T = f64` -> Some(F64)).
     /// constraint_types is the set of allowed types from `extends` (e.g. `T extends i32 | f64` -> [I32, F64]).
     pub(crate) generic_fn_params: HashMap<String, Vec<(String, Option<LltsType>, Vec<LltsType>)>>,
+    /// Generic function name -> (param types, return type), with type parameters
+    /// left as placeholder structs named after them (e.g. `T` -> `Struct { name: "T" }`).
+    pub(crate) generic_fn_sigs: HashMap<String, (Vec<LltsType>, LltsType)>,
+    /// Type parameter name -> concrete type while a specialization is lowered.
+    pub(crate) type_params_in_scope: HashMap<String, LltsType>,
+    /// Generic interface/type alias/class name -> (type parameter names, fields),
+    /// with type parameters left as placeholder structs. Instances are stamped
+    /// out per type argument list under their mangled name (`Pair$i32$string`).
+    pub(crate) generic_type_defs: HashMap<String, (Vec<String>, Vec<(String, LltsType)>)>,
+    /// Instantiated generic type (mangled name) -> (generic name, concrete type arguments).
+    pub(crate) type_instances: HashMap<String, (String, Vec<LltsType>)>,
+    /// Instances whose struct declaration (and, for classes, methods) are still to be emitted.
+    pub(crate) pending_type_instances: Vec<String>,
     /// Set of already-monomorphized specializations (mangled names) to avoid duplicates.
     pub(crate) monomorphized: HashSet<String>,
     /// Pending monomorphization requests: (generic_fn_name, type_param_names, concrete_types, mangled_name).
@@ -54,9 +175,38 @@ pub(crate) struct LowerCtx {
     /// Discriminated union type name -> definition.
     /// e.g. `type Shape = Circle | Rectangle` where Circle and Rectangle share a `kind` field.
     pub(crate) discriminated_unions: HashMap<String, DiscriminatedUnionDef>,
+    /// Class name -> name of the class it `extends`.
+    pub(crate) class_parents: HashMap<String, String>,
+    /// Class name -> names of its methods, inherited ones included.
+    pub(crate) class_methods: HashMap<String, Vec<String>>,
+    /// Interface/base-class name -> vtable slots, for types whose values are
+    /// dynamically dispatched (interfaces with methods, classes that are
+    /// extended). Annotations naming them lower to `LltsType::Dyn`.
+    pub(crate) dyn_types: HashMap<String, Vec<DynSlot>>,
+    /// Thrown type (by `codegen_type_suffix`) -> tag of its error struct,
+    /// shared by every file (see `errors`).
+    pub(crate) error_tags: HashMap<String, i64>,
+    /// Function name -> (tag, type) of each value it can throw, recorded as
+    /// functions are lowered.
+    pub(crate) fn_throws: HashMap<String, Vec<(i64, LltsType)>>,
+    /// Counter for naming the caught error variable of each catch block.
+    pub(crate) catch_counter: usize,
+    /// Caught error variable (`__caught_N`) -> tags its catch parameter's
+    /// binding expects.
+    pub(crate) catch_tags: HashMap<String, Vec<i64>>,
+    /// Counter for naming the temporaries that hold destructured values.
+    pub(crate) destructure_counter: usize,
+    /// Errors found while lowering (e.g. type arguments that cannot be
+    /// inferred), reported once the file is lowered.
+    pub(crate) errors: Vec<String>,
     /// (struct_name, field_name) -> string literal value.
     /// Tracks fields with string literal types for discriminated union detection.
     pub(crate) string_literal_fields: HashMap<(String, String), String>,
+    /// Symbol tables of the modules being compiled, by module index.
+    pub(crate) module_symbols: Vec<ModuleSymbols>,
+    /// Index of the module whose code is being lowered, whose symbols
+    /// identifiers are resolved against.
+    pub(crate) current_module: usize,
 }
 
 impl LowerCtx {
@@ -67,20 +217,62 @@ impl LowerCtx {
             var_types: HashMap::new(),
             fn_ret_types: HashMap::new(),
             fn_param_types: HashMap::new(),
+            param_shapes: HashMap::new(),
+            closure_shapes: HashMap::new(),
+            tuple_lengths: HashMap::new(),
             lambda_counter: 0,
             pending_functions: Vec::new(),
+            fn_thunks: HashSet::new(),
+            lambda_signature_hint: None,
             string_literal_unions: HashMap::new(),
             type_aliases: HashMap::new(),
             type_alias_members: HashMap::new(),
             generic_fn_indices: HashMap::new(),
             generic_fn_params: HashMap::new(),
+            generic_fn_sigs: HashMap::new(),
+            type_params_in_scope: HashMap::new(),
+            generic_type_defs: HashMap::new(),
+            type_instances: HashMap::new(),
+            pending_type_instances: Vec::new(),
             monomorphized: HashSet::new(),
             pending_monomorphizations: Vec::new(),
             discriminated_unions: HashMap::new(),
             string_literal_fields: HashMap::new(),
+            class_parents: HashMap::new(),
+            class_methods: HashMap::new(),
+            dyn_types: HashMap::new(),
+            error_tags: HashMap::new(),
+            fn_throws: HashMap::new(),
+            catch_counter: 0,
+            catch_tags: HashMap::new(),
+            destructure_counter: 0,
+            errors: Vec::new(),
+            module_symbols: Vec::new(),
+            current_module: 0,
         }
     }
 
+    /// The name the variable `ident` refers to is lowered under.
+    pub(crate) fn var_name(&self, ident: &IdentifierReference<'_>) -> String {
+        self.module_symbols
+            .get(self.current_module)
+            .and_then(|module| {
+                let symbol = module.scoping.get_reference(ident.reference_id.get()?).symbol_id()?;
+                module.names.get(&symbol)
+            })
+            .cloned()
+            .unwrap_or_else(|| ident.name.to_string())
+    }
+
+    /// The name the variable `ident` declares is lowered under.
+    pub(crate) fn binding_var_name(&self, ident: &BindingIdentifier<'_>) -> String {
+        self.module_symbols
+            .get(self.current_module)
+            .and_then(|module| module.names.get(&ident.symbol_id.get()?))
+            .cloned()
+            .unwrap_or_else(|| ident.name.to_string())
+    }
+
     /// Look up a struct field by name, returning (field_index, field_type).
     pub(crate) fn lookup_field(&self, struct_name: &str, field_name: &str) -> Option<(u32, LltsType)> {
         let fields = self.struct_defs.get(struct_name)?;
@@ -91,6 +283,17 @@ impl LowerCtx {
             .map(|(i, (_, ty))| (i as u32, ty.clone()))
     }
 
+    /// Look up a vtable slot of a dynamically dispatched type by member name,
+    /// returning (slot_index, slot).
+    pub(crate) fn lookup_dyn_slot(&self, dyn_name: &str, member: &str) -> Option<(u32, DynSlot)> {
+        let slots = self.dyn_types.get(dyn_name)?;
+        slots
+            .iter()
+            .enumerate()
+            .find(|(_, slot)| slot.name == member)
+            .map(|(i, slot)| (i as u32, slot.clone()))
+    }
+
     /// Look up an enum variant by enum name and variant name, returning the numeric value.
     pub(crate) fn lookup_enum_variant(&self, enum_name: &str, variant_name: &str) -> Option<i64> {
         let variants = self.enum_defs.get(enum_name)?;
@@ -105,6 +308,53 @@ impl LowerCtx {
         self.enum_defs.keys().cloned().collect()
     }
 
+    /// Resolve a type annotation naming a struct: type parameters in scope
+    /// become their concrete type, discriminated unions their union type,
+    /// dynamically dispatched types `Dyn`, and other structs get their full
+    /// field list.
+    pub(crate) fn resolve_named_type(&self, name: &str) -> LltsType {
+        if let Some(concrete) = self.type_params_in_scope.get(name) {
+            concrete.clone()
+        } else if let Some(du) = self.discriminated_unions.get(name) {
+            du.union_type.clone()
+        } else if self.dyn_types.contains_key(name) {
+            LltsType::Dyn { name: name.to_string() }
+        } else {
+            self.full_struct_type(name)
+        }
+    }
+
+    /// Resolve a lowered type annotation (see [`Self::resolve_named_type`]).
+    /// Arrays and options of type parameters hold the concrete type, and of
+    /// dynamically dispatched types `Dyn` values; map and set elements are
+    /// resolved fully.
+    pub(crate) fn resolve_annotation(&self, ty: LltsType) -> LltsType {
+        let elem = |ty: LltsType| match ty {
+            LltsType::Struct { name, fields } if fields.is_empty() => match self.type_params_in_scope.get(&name) {
+                Some(concrete) => concrete.clone(),
+                None if self.dyn_types.contains_key(&name) => LltsType::Dyn { name },
+                None => LltsType::Struct { name, fields },
+            },
+            other => other,
+        };
+        match ty {
+            LltsType::Struct { name, fields } if fields.is_empty() => self.resolve_named_type(&name),
+            LltsType::Array(elem_ty) => LltsType::Array(Box::new(elem(*elem_ty))),
+            LltsType::Option(inner) => LltsType::Option(Box::new(elem(*inner))),
+            // Keys are hashed field by field, so collections hold full struct types.
+            LltsType::Map { key, value } => LltsType::Map {
+                key: Box::new(self.resolve_annotation(*key)),
+                value: Box::new(self.resolve_annotation(*value)),
+            },
+            LltsType::Set(elem) => LltsType::Set(Box::new(self.resolve_annotation(*elem))),
+            LltsType::Result { ok, err } => LltsType::Result {
+                ok: Box::new(self.resolve_annotation(*ok)),
+                err: Box::new(self.resolve_annotation(*err)),
+            },
+            other => other,
+        }
+    }
+
     /// Build a full LltsType::Struct from a struct name in struct_defs.
     pub(crate) fn full_struct_type(&self, name: &str) -> LltsType {
         if let Some(fields) = self.struct_defs.get(name) {
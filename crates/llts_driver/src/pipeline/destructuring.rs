@@ -0,0 +1,370 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, Stmt, expr::BinOp, types::LltsType};
+
+use super::context::LowerCtx;
+use super::utils::{coerce_to_type, expr_to_name, field_assign, into_option, ir_expr_type, property_key_name};
+use super::{
+    codegen_type_suffix, destructure_element, forget_result_narrowing, lower_coalesce, lower_expr, lower_expr_expecting,
+};
+
+// ---------------------------------------------------------------------------
+// Destructuring: declarations, parameters and assignment
+// ---------------------------------------------------------------------------
+//
+// A destructured value is held in a temporary, and each name in the pattern
+// is bound to an element or field of it, recursively for nested patterns:
+//
+//     const { pos: { x, y = 0 }, ...others } = e
+//     →  const __destructure_0 = e;
+//        const __destructure_1 = __destructure_0.pos;
+//        const x = __destructure_1.x;
+//        const y = __destructure_1.y ?? 0;
+//        const others: { <fields of e but pos> } = { ..: __destructure_0.. };
+//
+//     const [a, b = d, ...tail] = xs
+//     →  const __destructure_2 = xs;
+//        const a = __destructure_2[0];
+//        const b = 1 < __destructure_2.length ? __destructure_2[1] : d;
+//        const tail = [];  for (i = 2; i < __destructure_2.length; i++) tail.push(__destructure_2[i]);
+//
+// Destructuring assignment (`[a, b] = [b, a]`) evaluates the value into the
+// temporary first, so a swap reads both sides before writing either.
+
+/// A destructuring pattern, from a binding or an assignment target.
+enum Pattern<'p, 'a> {
+    /// A variable to declare or assign.
+    Name(String),
+    /// A field of a struct variable, in an assignment: `[o.x, o.y] = ...`.
+    Field { object: String, field: String },
+    /// `[first, , third, ...rest]`; holes are None.
+    Array { elements: Vec<Option<Pattern<'p, 'a>>>, rest: Option<Box<Pattern<'p, 'a>>> },
+    /// `{ key: pattern, ...rest }`, shorthand properties keyed by their name.
+    Object { properties: Vec<(String, Pattern<'p, 'a>)>, rest: Option<Box<Pattern<'p, 'a>>> },
+    /// `pattern = default`, for a missing element or null field.
+    Default(Box<Pattern<'p, 'a>>, &'p Expression<'a>),
+    /// An assignment target that cannot be destructured into.
+    Unsupported,
+}
+
+/// Whether names in a pattern are declared or assigned.
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Declare,
+    Assign,
+}
+
+fn binding_pattern<'p, 'a>(pattern: &'p BindingPattern<'a>, ctx: &LowerCtx) -> Pattern<'p, 'a> {
+    match pattern {
+        BindingPattern::BindingIdentifier(id) => Pattern::Name(ctx.binding_var_name(id)),
+        BindingPattern::AssignmentPattern(assign) => {
+            Pattern::Default(Box::new(binding_pattern(&assign.left, ctx)), &assign.right)
+        }
+        BindingPattern::ArrayPattern(array) => Pattern::Array {
+            elements: array.elements.iter().map(|el| el.as_ref().map(|el| binding_pattern(el, ctx))).collect(),
+            rest: array.rest.as_ref().map(|rest| Box::new(binding_pattern(&rest.argument, ctx))),
+        },
+        BindingPattern::ObjectPattern(object) => Pattern::Object {
+            properties: object
+                .properties
+                .iter()
+                .map(|prop| (property_key_name(&prop.key), binding_pattern(&prop.value, ctx)))
+                .collect(),
+            rest: object.rest.as_ref().map(|rest| Box::new(binding_pattern(&rest.argument, ctx))),
+        },
+    }
+}
+
+fn assignment_target<'p, 'a>(target: &'p AssignmentTarget<'a>, ctx: &LowerCtx) -> Pattern<'p, 'a> {
+    match target {
+        AssignmentTarget::AssignmentTargetIdentifier(id) => Pattern::Name(ctx.var_name(id)),
+        AssignmentTarget::StaticMemberExpression(member) => Pattern::Field {
+            object: expr_to_name(&member.object, ctx),
+            field: member.property.name.to_string(),
+        },
+        AssignmentTarget::ArrayAssignmentTarget(array) => Pattern::Array {
+            elements: array.elements.iter().map(|el| el.as_ref().map(|el| target_maybe_default(el, ctx))).collect(),
+            rest: array.rest.as_ref().map(|rest| Box::new(assignment_target(&rest.target, ctx))),
+        },
+        AssignmentTarget::ObjectAssignmentTarget(object) => Pattern::Object {
+            properties: object
+                .properties
+                .iter()
+                .map(|prop| match prop {
+                    AssignmentTargetProperty::AssignmentTargetPropertyIdentifier(prop) => {
+                        // `({ x } = o)`: the field keeps its source name, the
+                        // variable the one it resolves to
+                        let name = ctx.var_name(&prop.binding);
+                        let target = match &prop.init {
+                            Some(default) => Pattern::Default(Box::new(Pattern::Name(name)), default),
+                            None => Pattern::Name(name),
+                        };
+                        (prop.binding.name.to_string(), target)
+                    }
+                    AssignmentTargetProperty::AssignmentTargetPropertyProperty(prop) => {
+                        (property_key_name(&prop.name), target_maybe_default(&prop.binding, ctx))
+                    }
+                })
+                .collect(),
+            rest: object.rest.as_ref().map(|rest| Box::new(assignment_target(&rest.target, ctx))),
+        },
+        _ => Pattern::Unsupported,
+    }
+}
+
+fn target_maybe_default<'p, 'a>(target: &'p AssignmentTargetMaybeDefault<'a>, ctx: &LowerCtx) -> Pattern<'p, 'a> {
+    match target {
+        AssignmentTargetMaybeDefault::AssignmentTargetWithDefault(with_default) => {
+            Pattern::Default(Box::new(assignment_target(&with_default.binding, ctx)), &with_default.init)
+        }
+        other => other.as_assignment_target().map_or(Pattern::Unsupported, |target| assignment_target(target, ctx)),
+    }
+}
+
+/// Declare the names bound by `pattern` from `value`.
+pub(crate) fn lower_binding_pattern(pattern: &BindingPattern<'_>, value: Expr, ctx: &mut LowerCtx) -> Vec<Stmt> {
+    let mut out = Vec::new();
+    destructure(&binding_pattern(pattern, ctx), value, Mode::Declare, ctx, &mut out);
+    out
+}
+
+/// Lower `[a, b] = value` or `({ a, b } = value)`, or None if `target` is
+/// not a pattern.
+pub(crate) fn lower_destructuring_assign(
+    target: &AssignmentTarget<'_>,
+    value: &Expression<'_>,
+    ctx: &mut LowerCtx,
+) -> Option<Stmt> {
+    if !matches!(target, AssignmentTarget::ArrayAssignmentTarget(_) | AssignmentTarget::ObjectAssignmentTarget(_)) {
+        return None;
+    }
+    let value = lower_expr(value, ctx);
+    let mut out = Vec::new();
+    destructure(&assignment_target(target, ctx), value, Mode::Assign, ctx, &mut out);
+    Some(Stmt::Block(out))
+}
+
+fn destructure(pattern: &Pattern<'_, '_>, value: Expr, mode: Mode, ctx: &mut LowerCtx, out: &mut Vec<Stmt>) {
+    match pattern {
+        Pattern::Name(name) => bind_name(name, value, mode, ctx, out),
+        Pattern::Field { object, field } => assign_field(object, field, value, ctx, out),
+        Pattern::Default(inner, default) => {
+            // A value that cannot be null never takes the default.
+            let value = lower_coalesce(value, default, ctx);
+            destructure(inner, value, mode, ctx, out);
+        }
+        Pattern::Array { elements, rest } => {
+            let source = spill(value, ctx, out);
+            let source_type = ir_expr_type(&source);
+            for (i, element) in elements.iter().enumerate() {
+                let Some(element) = element else { continue };
+                let (elem, elem_type) = destructure_element(source.clone(), i);
+                match (element, &source_type) {
+                    // `[a = d] = xs` takes `d` when `xs` is too short.
+                    (Pattern::Default(inner, default), LltsType::Array(_)) => {
+                        let fallback = coerce_to_type(lower_expr_expecting(default, &elem_type, ctx), &elem_type);
+                        let fallback = match &elem_type {
+                            LltsType::Option(inner) => into_option(fallback, inner),
+                            _ => fallback,
+                        };
+                        let in_bounds = Expr::Binary {
+                            op: BinOp::Lt,
+                            lhs: Box::new(Expr::IntLit { value: i as i64, ty: LltsType::I64 }),
+                            rhs: Box::new(array_length(&source)),
+                            ty: LltsType::I64,
+                        };
+                        let value = Expr::Ternary {
+                            condition: Box::new(in_bounds),
+                            then_expr: Box::new(elem),
+                            else_expr: Box::new(fallback),
+                            ty: elem_type,
+                        };
+                        destructure(inner, value, mode, ctx, out);
+                    }
+                    _ => destructure(element, elem, mode, ctx, out),
+                }
+            }
+            if let Some(rest) = rest {
+                let tail = array_rest(&source, elements.len(), ctx, out);
+                destructure(rest, tail, mode, ctx, out);
+            }
+        }
+        Pattern::Object { properties, rest } => {
+            let source = spill(value, ctx, out);
+            let source_type = ir_expr_type(&source);
+            let LltsType::Struct { name: struct_name, fields } = &source_type else {
+                ctx.errors.push(format!("cannot destructure {} as an object", codegen_type_suffix(&source_type)));
+                return;
+            };
+            for (key, property) in properties {
+                let Some((field_index, field_type)) = ctx.lookup_field(struct_name, key) else {
+                    ctx.errors.push(format!("'{struct_name}' has no field '{key}' to destructure"));
+                    continue;
+                };
+                let field = Expr::FieldAccess {
+                    object: Box::new(source.clone()),
+                    object_type: source_type.clone(),
+                    field_index,
+                    field_type,
+                };
+                destructure(property, field, mode, ctx, out);
+            }
+            if let Some(rest) = rest {
+                let taken: Vec<&str> = properties.iter().map(|(key, _)| key.as_str()).collect();
+                let others = object_rest(&source, struct_name, fields, &taken, ctx);
+                destructure(rest, others, mode, ctx, out);
+            }
+        }
+        Pattern::Unsupported => ctx.errors.push("unsupported destructuring assignment target".to_string()),
+    }
+}
+
+fn bind_name(name: &str, value: Expr, mode: Mode, ctx: &mut LowerCtx, out: &mut Vec<Stmt>) {
+    if mode == Mode::Declare {
+        let ty = value_type(&value, ctx);
+        ctx.var_types.insert(name.to_string(), ty.clone());
+        ctx.closure_shapes.remove(name);
+        out.push(Stmt::VarDecl { name: name.to_string(), ty, init: Some(value) });
+        return;
+    }
+    let value = match ctx.var_types.get(name).cloned().unwrap_or(LltsType::F64) {
+        LltsType::Option(inner) => into_option(value, &inner),
+        ty => coerce_to_type(value, &ty),
+    };
+    forget_result_narrowing(name, ctx);
+    out.push(Stmt::Assign { target: name.to_string(), value });
+}
+
+fn assign_field(object: &str, field: &str, value: Expr, ctx: &mut LowerCtx, out: &mut Vec<Stmt>) {
+    let object_type = ctx.var_types.get(object).cloned();
+    let found = match &object_type {
+        Some(LltsType::Struct { name, .. }) => ctx.lookup_field(name, field),
+        _ => None,
+    };
+    let (Some(object_type), Some((field_index, field_type))) = (object_type, found) else {
+        ctx.errors.push(format!("cannot destructure into '{object}.{field}'"));
+        return;
+    };
+    let value = coerce_to_type(value, &field_type);
+    out.push(field_assign(object, &object_type, field_index, field_type, value));
+}
+
+/// The type of `value`, with struct types given their fields.
+fn value_type(value: &Expr, ctx: &LowerCtx) -> LltsType {
+    match ir_expr_type(value) {
+        LltsType::Struct { name, fields } if fields.is_empty() && !name.is_empty() => ctx.full_struct_type(&name),
+        other => other,
+    }
+}
+
+/// `value` as an expression that can be read repeatedly: a variable as is,
+/// anything else stored in a temporary first.
+fn spill(value: Expr, ctx: &mut LowerCtx, out: &mut Vec<Stmt>) -> Expr {
+    let ty = value_type(&value, ctx);
+    if let Expr::Var { name, .. } = value {
+        return Expr::Var { name, ty };
+    }
+    let name = fresh_temp(ctx);
+    ctx.var_types.insert(name.clone(), ty.clone());
+    out.push(Stmt::VarDecl { name: name.clone(), ty: ty.clone(), init: Some(value) });
+    Expr::Var { name, ty }
+}
+
+fn fresh_temp(ctx: &mut LowerCtx) -> String {
+    let name = format!("__destructure_{}", ctx.destructure_counter);
+    ctx.destructure_counter += 1;
+    name
+}
+
+fn array_length(array: &Expr) -> Expr {
+    Expr::FieldAccess {
+        object: Box::new(array.clone()),
+        object_type: ir_expr_type(array),
+        field_index: 1,
+        field_type: LltsType::I64,
+    }
+}
+
+/// `[..., ...rest] = source`: a new array of the elements of `source` from
+/// index `start` on.
+fn array_rest(source: &Expr, start: usize, ctx: &mut LowerCtx, out: &mut Vec<Stmt>) -> Expr {
+    let array_type = ir_expr_type(source);
+    let LltsType::Array(elem_type) = &array_type else {
+        ctx.errors.push(format!("cannot destructure the rest of {}", codegen_type_suffix(&array_type)));
+        return Expr::ArrayLit { elem_type: LltsType::F64, elements: Vec::new() };
+    };
+    let tail = fresh_temp(ctx);
+    let index = format!("{tail}$i");
+    let index_var = Expr::Var { name: index.clone(), ty: LltsType::I64 };
+    let tail_var = Expr::Var { name: tail.clone(), ty: array_type.clone() };
+    ctx.var_types.insert(tail.clone(), array_type.clone());
+    out.push(Stmt::VarDecl {
+        name: tail,
+        ty: array_type.clone(),
+        init: Some(Expr::ArrayLit { elem_type: (**elem_type).clone(), elements: Vec::new() }),
+    });
+    out.push(Stmt::For {
+        init: Some(Box::new(Stmt::VarDecl {
+            name: index.clone(),
+            ty: LltsType::I64,
+            init: Some(Expr::IntLit { value: start as i64, ty: LltsType::I64 }),
+        })),
+        condition: Some(Expr::Binary {
+            op: BinOp::Lt,
+            lhs: Box::new(index_var.clone()),
+            rhs: Box::new(array_length(source)),
+            ty: LltsType::I64,
+        }),
+        update: Some(Box::new(Stmt::Assign {
+            target: index,
+            value: Expr::Binary {
+                op: BinOp::Add,
+                lhs: Box::new(index_var.clone()),
+                rhs: Box::new(Expr::IntLit { value: 1, ty: LltsType::I64 }),
+                ty: LltsType::I64,
+            },
+        })),
+        body: vec![Stmt::Expr(Expr::MethodCall {
+            class_name: "__Array".to_string(),
+            method_name: "push".to_string(),
+            receiver: Box::new(tail_var.clone()),
+            args: vec![Expr::ArrayIndex {
+                array: Box::new(source.clone()),
+                index: Box::new(index_var),
+                elem_type: (**elem_type).clone(),
+            }],
+            ret_type: LltsType::Void,
+        })],
+    });
+    tail_var
+}
+
+/// `{ ..., ...rest } = source`: a struct of the fields of `source` not in
+/// `taken`, typed `__Rest${struct}${taken...}`.
+fn object_rest(
+    source: &Expr,
+    struct_name: &str,
+    fields: &[(String, LltsType)],
+    taken: &[&str],
+    ctx: &mut LowerCtx,
+) -> Expr {
+    let kept: Vec<(u32, &(String, LltsType))> = fields
+        .iter()
+        .enumerate()
+        .filter(|(_, (name, _))| !taken.contains(&name.as_str()))
+        .map(|(i, field)| (i as u32, field))
+        .collect();
+    let rest_fields: Vec<(String, LltsType)> = kept.iter().map(|(_, field)| (*field).clone()).collect();
+    let rest_name = format!("__Rest${struct_name}${}", taken.join("$"));
+    ctx.struct_defs.insert(rest_name.clone(), rest_fields.clone());
+    let values = kept
+        .into_iter()
+        .map(|(field_index, (_, field_type))| Expr::FieldAccess {
+            object: Box::new(source.clone()),
+            object_type: ir_expr_type(source),
+            field_index,
+            field_type: field_type.clone(),
+        })
+        .collect();
+    Expr::StructLit { struct_type: LltsType::Struct { name: rest_name, fields: rest_fields }, fields: values }
+}
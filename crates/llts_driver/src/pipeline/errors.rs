@@ -0,0 +1,618 @@
+use std::collections::{HashMap, HashSet};
+
+use oxc_ast::ast::*;
+
+use llts_codegen::{
+    Expr, FunctionDecl, Stmt,
+    expr::{BinOp, LogicalOp},
+    types::{LltsType, STRING_ERROR_TAG, error_type},
+};
+
+use super::context::LowerCtx;
+use super::utils::{field_assign, ir_expr_type};
+use super::visit::{expr_children, for_each_expr, for_each_expr_mut, for_each_stmt, for_each_stmt_mut, stmt_exprs};
+use super::{codegen_type_suffix, upcast_expr};
+
+// ---------------------------------------------------------------------------
+// Thrown values: `throw`, `catch (e)` and `instanceof`
+// ---------------------------------------------------------------------------
+//
+// Every throw carries the same error struct (`llts_codegen::types::error_type`):
+// a tag numbering the thrown value's type, a pointer to a refcounted copy of
+// the value, and the message an uncaught throw prints.
+//
+//     throw v;   →   const __thrown_N = v;
+//                    throw { tag: T, payload: box(__thrown_N), message: ... };
+//
+// The catch parameter is typed from the throws its try block can reach: the
+// single thrown type, or a union of them whose members `instanceof` narrows.
+// Rethrowing such a union throws the member it holds.
+//
+//     catch (e)  →   catch (__caught_N) {
+//                        const e = __caught_N.tag === T1 ? Union(0, unbox<T1>(__caught_N.payload))
+//                                : ...;
+//                        ...
+//                    }
+//
+// Once every file is lowered, `propagate_throws` makes the functions whose
+// throws can escape them return `Result<T, error struct>`, marked
+// transitively through calls, and checks the result after each call to one:
+//
+//     return v;   →   return Ok(v);
+//     throw ...;  →   return Err(error);            (outside a try block)
+//     f(x)        →   f(x)?                         (Err: thrown on)
+//
+// A function declaring `Result<T, E>` opts in instead: its throws, and those
+// of the functions it calls, return `Err` with the thrown value, which must
+// be an `E`. Its callers get the `Result` and never see a throw.
+//
+// A catch sees what its try block throws and what the functions it calls
+// throw, as far as they are lowered before it.
+
+/// The built-in `Error` class.
+const ERROR_CLASS: &str = "Error";
+
+/// Name prefix of the union a catch parameter holds when its try block can
+/// throw several types.
+const THROWN_UNION: &str = "__Thrown_";
+
+/// Register the built-in `Error` class: `message: string`, constructed with
+/// `new Error(message)`. User classes can extend it.
+pub(crate) fn register_builtin_error(ctx: &mut LowerCtx) {
+    let fields = vec![("message".to_string(), LltsType::String)];
+    ctx.struct_defs.insert(ERROR_CLASS.to_string(), fields);
+    ctx.class_methods.insert(ERROR_CLASS.to_string(), Vec::new());
+
+    let self_type = ctx.full_struct_type(ERROR_CLASS);
+    let ctor = format!("{ERROR_CLASS}_new");
+    ctx.fn_ret_types.insert(ctor.clone(), self_type.clone());
+    ctx.fn_param_types.insert(ctor.clone(), vec![LltsType::String]);
+    ctx.pending_functions.push(FunctionDecl {
+        name: ctor,
+        params: vec![("message".to_string(), LltsType::String)],
+        ret_type: self_type.clone(),
+        body: vec![
+            Stmt::VarDecl { name: "self".to_string(), ty: self_type.clone(), init: None },
+            field_assign(
+                "self",
+                &self_type,
+                0,
+                LltsType::String,
+                Expr::Var { name: "message".to_string(), ty: LltsType::String },
+            ),
+            Stmt::Return(Some(Expr::Var { name: "self".to_string(), ty: self_type })),
+        ],
+    });
+}
+
+/// Lower `throw value`: wrap the value in the error struct (see the module
+/// comment).
+pub(crate) fn lower_throw(value: Expr, id: u32, ctx: &mut LowerCtx) -> Vec<Stmt> {
+    throw_as(value, format!("__thrown_{id}"), ctx)
+}
+
+/// Throw `value`, held in variable `name` while it is wrapped.
+fn throw_as(value: Expr, name: String, ctx: &mut LowerCtx) -> Vec<Stmt> {
+    let ty = ir_expr_type(&value);
+    let thrown = Expr::Var { name: name.clone(), ty: ty.clone() };
+    let mut stmts = vec![Stmt::VarDecl { name: name.clone(), ty: ty.clone(), init: Some(value) }];
+    match &ty {
+        // Rethrowing a caught union throws the member it holds, so an outer
+        // catch sees the original type.
+        LltsType::Union { name: union_name, variants } if union_name.starts_with(THROWN_UNION) => {
+            let member = |index: usize, member_ty: &LltsType, ctx: &mut LowerCtx| {
+                let payload = Expr::FieldAccess {
+                    object: Box::new(thrown.clone()),
+                    object_type: ty.clone(),
+                    field_index: 1,
+                    field_type: member_ty.clone(),
+                };
+                throw_as(payload, format!("{name}_{index}"), ctx)
+            };
+            let last = variants.len() - 1;
+            let mut rest = member(last, &variants[last].1, ctx);
+            for (index, (_, member_ty)) in variants[..last].iter().enumerate().rev() {
+                rest = vec![Stmt::If {
+                    condition: Expr::Binary {
+                        op: BinOp::Eq,
+                        lhs: Box::new(Expr::FieldAccess {
+                            object: Box::new(thrown.clone()),
+                            object_type: ty.clone(),
+                            field_index: 0,
+                            field_type: LltsType::I32,
+                        }),
+                        rhs: Box::new(Expr::IntLit { value: index as i64, ty: LltsType::I32 }),
+                        ty: LltsType::I32,
+                    },
+                    then_body: member(index, member_ty, ctx),
+                    else_body: Some(rest),
+                }];
+            }
+            stmts.extend(rest);
+        }
+        _ => stmts.push(Stmt::Throw(Expr::StructLit {
+            struct_type: error_type(),
+            fields: vec![
+                Expr::IntLit { value: error_tag(&ty, ctx), ty: LltsType::I32 },
+                Expr::BoxValue { value: Box::new(thrown.clone()), ty: ty.clone() },
+                error_message(thrown, &ty, ctx),
+            ],
+        })),
+    }
+    stmts
+}
+
+/// The tag identifying thrown values of type `ty`, numbered on first use.
+fn error_tag(ty: &LltsType, ctx: &mut LowerCtx) -> i64 {
+    let key = match ty {
+        LltsType::String => return STRING_ERROR_TAG,
+        LltsType::Union { name, .. } => name.clone(),
+        other => codegen_type_suffix(other),
+    };
+    let next = STRING_ERROR_TAG + 1 + ctx.error_tags.len() as i64;
+    *ctx.error_tags.entry(key).or_insert(next)
+}
+
+/// What an uncaught throw of `thrown` prints: a string as is, `Name: message`
+/// for a class with a `message` field (like `Error`), otherwise the value as
+/// `print` shows it.
+fn error_message(thrown: Expr, ty: &LltsType, ctx: &LowerCtx) -> Expr {
+    match ty {
+        LltsType::String => thrown,
+        LltsType::Struct { name, .. } => match ctx.lookup_field(name, "message") {
+            Some((field_index, LltsType::String)) => Expr::StringConcat {
+                parts: vec![
+                    Expr::StringLit(format!("{name}: ")),
+                    Expr::FieldAccess {
+                        object: Box::new(thrown),
+                        object_type: ty.clone(),
+                        field_index,
+                        field_type: LltsType::String,
+                    },
+                ],
+            },
+            _ => Expr::StringConcat { parts: vec![thrown] },
+        },
+        _ => Expr::StringConcat { parts: vec![thrown] },
+    }
+}
+
+/// Bind the catch parameter `param` to the value of the error caught in
+/// `caught`, typed from the throws `try_body` can reach, and record its type
+/// and the tags it expects.
+pub(crate) fn bind_catch_param(param: &str, caught: &str, try_body: &[Stmt], id: usize, ctx: &mut LowerCtx) -> Stmt {
+    let error = Expr::Var { name: caught.to_string(), ty: error_type() };
+    let field = |index: u32, field_type: LltsType| Expr::FieldAccess {
+        object: Box::new(error.clone()),
+        object_type: error_type(),
+        field_index: index,
+        field_type,
+    };
+    let unbox = |ty: &LltsType| Expr::UnboxValue {
+        ptr: Box::new(field(1, LltsType::Ptr)),
+        ty: ty.clone(),
+    };
+
+    let thrown = thrown_types(try_body, ctx);
+    ctx.catch_tags.insert(caught.to_string(), thrown.iter().map(|(tag, _)| *tag).collect());
+    let (ty, value) = match thrown.as_slice() {
+        // Nothing can be caught; keep the message.
+        [] => (LltsType::String, field(2, LltsType::String)),
+        [(_, ty)] => (ty.clone(), unbox(ty)),
+        [.., (_, last_ty)] => {
+            let union_type = LltsType::Union {
+                name: format!("{THROWN_UNION}{id}"),
+                variants: thrown.iter().map(|(_, ty)| (codegen_type_suffix(ty), ty.clone())).collect(),
+            };
+            let member = |index: usize, ty: &LltsType| Expr::UnionLit {
+                tag: index as u32,
+                payload: Box::new(unbox(ty)),
+                union_type: union_type.clone(),
+            };
+            // The last member is whatever no earlier tag matched.
+            let last = member(thrown.len() - 1, last_ty);
+            let value = thrown[..thrown.len() - 1].iter().enumerate().rev().fold(last, |rest, (index, (tag, ty))| {
+                Expr::Ternary {
+                    condition: Box::new(Expr::Binary {
+                        op: BinOp::Eq,
+                        lhs: Box::new(field(0, LltsType::I32)),
+                        rhs: Box::new(Expr::IntLit { value: *tag, ty: LltsType::I32 }),
+                        ty: LltsType::I32,
+                    }),
+                    then_expr: Box::new(member(index, ty)),
+                    else_expr: Box::new(rest),
+                    ty: union_type.clone(),
+                }
+            });
+            (union_type, value)
+        }
+    };
+    ctx.var_types.insert(param.to_string(), ty.clone());
+    Stmt::VarDecl { name: param.to_string(), ty, init: Some(value) }
+}
+
+/// What statements can throw out of themselves, outside nested try blocks.
+#[derive(Default)]
+struct Uncaught {
+    /// (tag, type) of their `throw`s and of the `TypeError` string of a
+    /// failed `!`, in order of appearance.
+    values: Vec<(i64, LltsType)>,
+    /// The functions they call.
+    callees: Vec<String>,
+}
+
+/// Add (tag, type) to `thrown` unless the tag is already there.
+fn add_thrown(thrown: &mut Vec<(i64, LltsType)>, tag: i64, ty: &LltsType) {
+    if !thrown.iter().any(|(t, _)| *t == tag) {
+        thrown.push((tag, ty.clone()));
+    }
+}
+
+/// What `stmts` can throw out of themselves.
+fn uncaught(stmts: &[Stmt]) -> Uncaught {
+    fn walk_expr(expr: &Expr, out: &mut Uncaught) {
+        match expr {
+            Expr::OptionExpect { .. } => add_thrown(&mut out.values, STRING_ERROR_TAG, &LltsType::String),
+            Expr::Call { callee, .. } => out.callees.push(callee.clone()),
+            Expr::MethodCall { class_name, method_name, .. } => {
+                out.callees.push(format!("{class_name}_{method_name}"))
+            }
+            Expr::ConstructorCall { class_name, .. } => out.callees.push(format!("{class_name}_new")),
+            _ => {}
+        }
+        for child in expr_children(expr) {
+            walk_expr(child, out);
+        }
+    }
+    fn walk(stmts: &[Stmt], out: &mut Uncaught) {
+        for stmt in stmts {
+            for expr in stmt_exprs(stmt) {
+                walk_expr(expr, out);
+            }
+            match stmt {
+                Stmt::Throw(Expr::StructLit { fields, .. }) => {
+                    if let [Expr::IntLit { value: tag, .. }, Expr::BoxValue { ty, .. }, ..] = fields.as_slice() {
+                        add_thrown(&mut out.values, *tag, ty);
+                    }
+                }
+                Stmt::If { then_body, else_body, .. } => {
+                    walk(then_body, out);
+                    if let Some(els) = else_body {
+                        walk(els, out);
+                    }
+                }
+                Stmt::While { body, .. } | Stmt::ForOf { body, .. } | Stmt::Block(body) => walk(body, out),
+                Stmt::For { init, update, body, .. } => {
+                    walk(init.as_deref().map(std::slice::from_ref).unwrap_or_default(), out);
+                    walk(update.as_deref().map(std::slice::from_ref).unwrap_or_default(), out);
+                    walk(body, out);
+                }
+                Stmt::Switch { cases, .. } => {
+                    for (_, body) in cases {
+                        walk(body, out);
+                    }
+                }
+                // A nested try block's throws land in its own catch, if it
+                // has one.
+                Stmt::TryCatch { try_body, catch_body, finally_body, .. } => {
+                    walk(catch_body.as_ref().unwrap_or(try_body), out);
+                    if let Some(finally_body) = finally_body {
+                        walk(finally_body, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut out = Uncaught::default();
+    walk(stmts, &mut out);
+    out
+}
+
+/// The (tag, type) of each value `stmts` can throw out of themselves: their
+/// own throws, then those of the functions they call that are lowered
+/// already.
+fn thrown_types(stmts: &[Stmt], ctx: &LowerCtx) -> Vec<(i64, LltsType)> {
+    let Uncaught { mut values, callees } = uncaught(stmts);
+    for callee in &callees {
+        for (tag, ty) in ctx.fn_throws.get(callee).into_iter().flatten() {
+            add_thrown(&mut values, *tag, ty);
+        }
+    }
+    values
+}
+
+/// Record what lowered function `func` can throw, for the catch blocks of
+/// the functions lowered after it.
+pub(crate) fn record_fn_throws(func: &FunctionDecl, ctx: &mut LowerCtx) {
+    if let LltsType::Result { .. } = func.ret_type {
+        return;
+    }
+    let thrown = thrown_types(&func.body, ctx);
+    if !thrown.is_empty() {
+        ctx.fn_throws.insert(func.name.clone(), thrown);
+    }
+}
+
+/// Make every function whose throws can escape it return `Result<T, error
+/// struct>`, propagate the error after each call to one, and check that
+/// every catch parameter was typed knowing what its try block calls throw.
+/// Functions declaring a `Result` must only throw their error type.
+///
+/// `main` keeps its signature, as do functions used as values (closures,
+/// vtable slots), whose callers are unknown: their uncaught throws still end
+/// the program.
+pub(crate) fn propagate_throws(functions: &mut [FunctionDecl], ctx: &mut LowerCtx) {
+    let throwing = throwing_functions(functions);
+    for func in functions.iter() {
+        check_catches(func, &throwing, ctx);
+        check_declared_result(func, &throwing, ctx);
+    }
+
+    // Throwing function -> (its Ok type, its Result type).
+    let results: HashMap<String, (LltsType, LltsType)> = functions
+        .iter()
+        .filter(|f| throwing.contains_key(&f.name))
+        .map(|f| {
+            let result_type = LltsType::Result { ok: Box::new(f.ret_type.clone()), err: Box::new(error_type()) };
+            (f.name.clone(), (f.ret_type.clone(), result_type))
+        })
+        .collect();
+    for func in functions.iter_mut() {
+        for_each_expr_mut(&mut func.body, &mut |expr| propagate_call(expr, &results));
+        if let LltsType::Result { ok, .. } = &func.ret_type {
+            if **ok == LltsType::Void && !matches!(func.body.last(), Some(Stmt::Return(_))) {
+                let result_type = func.ret_type.clone();
+                func.body.push(Stmt::Return(Some(Expr::ResultOk { value: None, result_type })));
+            }
+            continue;
+        }
+        let Some((ok_type, result_type)) = results.get(&func.name) else { continue };
+        for_each_stmt_mut(&mut func.body, &mut |stmt| {
+            if let Stmt::Return(value) = stmt {
+                let value = value.take().map(Box::new);
+                *stmt = Stmt::Return(Some(Expr::ResultOk { value, result_type: result_type.clone() }));
+            }
+        });
+        if *ok_type == LltsType::Void && !matches!(func.body.last(), Some(Stmt::Return(_))) {
+            func.body.push(Stmt::Return(Some(Expr::ResultOk { value: None, result_type: result_type.clone() })));
+        }
+        func.ret_type = result_type.clone();
+    }
+}
+
+/// Each function whose throws can escape it, with every value it can throw:
+/// its own throws and those of the throwing functions it calls, to a fixed
+/// point. Excludes `main`, functions used as values and functions declaring
+/// a `Result`.
+fn throwing_functions(functions: &[FunctionDecl]) -> HashMap<String, Vec<(i64, LltsType)>> {
+    // Functions keeping their signature.
+    let mut kept: HashSet<String> = functions
+        .iter()
+        .filter(|f| matches!(f.ret_type, LltsType::Result { .. }))
+        .map(|f| f.name.clone())
+        .collect();
+    for func in functions.iter() {
+        for_each_expr(&func.body, &mut |expr| match expr {
+            Expr::Closure { function, .. } => {
+                kept.insert(function.clone());
+            }
+            Expr::DynCoerce { slots, .. } => kept.extend(slots.iter().cloned()),
+            _ => {}
+        });
+    }
+
+    let found: HashMap<&str, Uncaught> = functions.iter().map(|f| (f.name.as_str(), uncaught(&f.body))).collect();
+    let mut throwing: HashMap<String, Vec<(i64, LltsType)>> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for (&name, own) in &found {
+            if name == "main" || kept.contains(name) {
+                continue;
+            }
+            let mut thrown = own.values.clone();
+            for callee in &own.callees {
+                for (tag, ty) in throwing.get(callee).into_iter().flatten() {
+                    add_thrown(&mut thrown, *tag, ty);
+                }
+            }
+            // The sets only grow, so a longer one is a change.
+            if thrown.len() > throwing.get(name).map_or(0, Vec::len) {
+                throwing.insert(name.to_string(), thrown);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    throwing
+}
+
+/// Turn `expr`, if it calls a function in `results`, into a call returning
+/// its `Result` whose error is thrown on.
+fn propagate_call(expr: &mut Expr, results: &HashMap<String, (LltsType, LltsType)>) {
+    let callee = match expr {
+        Expr::Call { callee, .. } => callee.clone(),
+        Expr::MethodCall { class_name, method_name, .. } => format!("{class_name}_{method_name}"),
+        Expr::ConstructorCall { class_name, .. } => format!("{class_name}_new"),
+        _ => return,
+    };
+    let Some((ok_type, result_type)) = results.get(&callee) else { return };
+    let (Expr::Call { ret_type, .. } | Expr::MethodCall { ret_type, .. } | Expr::ConstructorCall { ret_type, .. }) = expr
+    else {
+        return;
+    };
+    // The walk reaches the call again inside the wrapper.
+    if ret_type == result_type {
+        return;
+    }
+    *ret_type = result_type.clone();
+    let call = std::mem::replace(expr, Expr::BoolLit(false));
+    *expr = Expr::ResultPropagate { value: Box::new(call), ok_type: ok_type.clone() };
+}
+
+/// Report the catch parameters of `func` typed before a function their try
+/// block calls was lowered, which would miss values it throws.
+fn check_catches(func: &FunctionDecl, throwing: &HashMap<String, Vec<(i64, LltsType)>>, ctx: &mut LowerCtx) {
+    let mut late = Vec::new();
+    for_each_stmt(&func.body, &mut |stmt| {
+        let Stmt::TryCatch { try_body, catch_param: Some(caught), .. } = stmt else { return };
+        let Some(expected) = ctx.catch_tags.get(caught) else { return };
+        for callee in uncaught(try_body).callees {
+            let missed = throwing.get(&callee).into_iter().flatten().any(|(tag, _)| !expected.contains(tag));
+            if missed && !late.contains(&callee) {
+                late.push(callee);
+            }
+        }
+    });
+    for callee in late {
+        ctx.errors.push(format!(
+            "a `catch` in `{}` cannot see what `{callee}` throws, as `{callee}` is declared after it; declare it first",
+            func.name
+        ));
+    }
+}
+
+/// Report the values `func`, declaring `Result<T, E>`, can throw that are
+/// not an `E`.
+fn check_declared_result(func: &FunctionDecl, throwing: &HashMap<String, Vec<(i64, LltsType)>>, ctx: &mut LowerCtx) {
+    let LltsType::Result { err, .. } = &func.ret_type else { return };
+    let Uncaught { mut values, callees } = uncaught(&func.body);
+    for callee in &callees {
+        for (tag, ty) in throwing.get(callee).into_iter().flatten() {
+            add_thrown(&mut values, *tag, ty);
+        }
+    }
+    for (_, ty) in values {
+        let is_err = match (&ty, &**err) {
+            (LltsType::Struct { name, .. }, LltsType::Struct { name: class, .. }) => is_class_or_subclass(name, class, ctx),
+            _ => ty == **err,
+        };
+        if !is_err {
+            ctx.errors.push(format!(
+                "`{}` returns `Result<_, {}>` but can throw {}",
+                func.name,
+                codegen_type_suffix(err),
+                codegen_type_suffix(&ty)
+            ));
+        }
+    }
+}
+
+/// Whether class `name` is `class` or extends it.
+fn is_class_or_subclass(name: &str, class: &str, ctx: &LowerCtx) -> bool {
+    let mut current = Some(name);
+    while let Some(n) = current {
+        if n == class {
+            return true;
+        }
+        current = ctx.class_parents.get(n).map(String::as_str);
+    }
+    false
+}
+
+/// The members of union `ty` that are instances of `class`, with their index.
+fn instance_members(ty: &LltsType, class: &str, ctx: &LowerCtx) -> Vec<(usize, LltsType)> {
+    let LltsType::Union { variants, .. } = ty else { return Vec::new() };
+    variants
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, member))| {
+            matches!(member, LltsType::Struct { name, .. } if is_class_or_subclass(name, class, ctx))
+        })
+        .map(|(index, (_, member))| (index, member.clone()))
+        .collect()
+}
+
+/// Lower `value instanceof class`: a tag check on a caught union, a constant
+/// on a class value.
+pub(crate) fn lower_instanceof(value: Expr, class: &str, ctx: &mut LowerCtx) -> Expr {
+    let ty = ir_expr_type(&value);
+    match &ty {
+        LltsType::Struct { name, .. } => Expr::BoolLit(is_class_or_subclass(name, class, ctx)),
+        LltsType::Union { .. } => {
+            let tag_is = |index: usize| Expr::Binary {
+                op: BinOp::Eq,
+                lhs: Box::new(Expr::FieldAccess {
+                    object: Box::new(value.clone()),
+                    object_type: ty.clone(),
+                    field_index: 0,
+                    field_type: LltsType::I32,
+                }),
+                rhs: Box::new(Expr::IntLit { value: index as i64, ty: LltsType::I32 }),
+                ty: LltsType::I32,
+            };
+            instance_members(&ty, class, ctx)
+                .into_iter()
+                .map(|(index, _)| tag_is(index))
+                .reduce(|lhs, rhs| Expr::Logical {
+                    op: LogicalOp::Or,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    ty: LltsType::Bool,
+                })
+                .unwrap_or(Expr::BoolLit(false))
+        }
+        _ => {
+            ctx.errors.push(format!(
+                "`instanceof` is only supported on caught errors and class values, got {}",
+                codegen_type_suffix(&ty)
+            ));
+            Expr::BoolLit(false)
+        }
+    }
+}
+
+/// For `if (e instanceof C)` on a variable holding a caught union, the
+/// variable's name, its type in the then-branch and the value it takes
+/// there: the matching member, or every matching member viewed as `C`.
+pub(crate) fn detect_instanceof_narrowing(test: &Expression<'_>, ctx: &LowerCtx) -> Option<(String, LltsType, Expr)> {
+    let Expression::BinaryExpression(bin) = test.without_parentheses() else { return None };
+    if bin.operator != BinaryOperator::Instanceof {
+        return None;
+    }
+    let (Expression::Identifier(var), Expression::Identifier(class)) = (&bin.left, &bin.right) else {
+        return None;
+    };
+    let (name, class) = (ctx.var_name(var), class.name.as_str());
+    let ty = ctx.var_types.get(&name)?.clone();
+    let members = instance_members(&ty, class, ctx);
+    let union_var = Expr::Var { name: name.clone(), ty: ty.clone() };
+    let payload = |member: &LltsType| Expr::FieldAccess {
+        object: Box::new(union_var.clone()),
+        object_type: ty.clone(),
+        field_index: 1,
+        field_type: member.clone(),
+    };
+    match members.as_slice() {
+        [] => None,
+        [(_, member)] => Some((name, member.clone(), payload(member))),
+        [.., (_, last)] => {
+            let class_type = ctx.full_struct_type(class);
+            let as_class = |member: &LltsType| match member {
+                LltsType::Struct { name, .. } if name == class => payload(member),
+                _ => upcast_expr(payload(member), class, ctx),
+            };
+            let value = members[..members.len() - 1].iter().rev().fold(as_class(last), |rest, (index, member)| {
+                Expr::Ternary {
+                    condition: Box::new(Expr::Binary {
+                        op: BinOp::Eq,
+                        lhs: Box::new(Expr::FieldAccess {
+                            object: Box::new(union_var.clone()),
+                            object_type: ty.clone(),
+                            field_index: 0,
+                            field_type: LltsType::I32,
+                        }),
+                        rhs: Box::new(Expr::IntLit { value: *index as i64, ty: LltsType::I32 }),
+                        ty: LltsType::I32,
+                    }),
+                    then_expr: Box::new(as_class(member)),
+                    else_expr: Box::new(rest),
+                    ty: class_type.clone(),
+                }
+            });
+            Some((name, class_type, value))
+        }
+    }
+}
@@ -5,15 +5,24 @@ use llts_codegen::{
     expr::BinOp,
     expr::LogicalOp,
     expr::UnaryOp,
-    types::LltsType,
+    types::{LltsType, TypeRegistry},
 };
 
 use super::context::LowerCtx;
 use super::utils::{
-    assignment_target_name, binding_name, coerce_to_type, expr_to_name, infer_ir_binary_type,
-    ir_expr_type, lower_binop, lower_unaryop, simple_target_name,
+    assignment_target_name, coerce_to_type, expr_to_name, infer_ir_binary_type, into_option, ir_expr_type,
+    lower_binop, lower_unaryop, simple_target_name,
+};
+use super::{
+    BoundParams, ENV_PARAM, bind_params, call_target, closure_env_type, codegen_type_suffix, coerce_struct_fields,
+    coerce_to_dyn, collect_captures, collection_method_call, collection_size, current_parent_class,
+    detect_result_narrowing, dyn_call, forget_result_narrowing, function_value, instantiate_generic_type, is_collection,
+    is_promise_builtin, is_result_builtin, lower_array_spread, lower_await, lower_chain, lower_destructuring_assign,
+    lower_inferred_generic_args, lower_instanceof, lower_narrowed, lower_new_collection, lower_promise_builtin,
+    lower_promise_combinator, lower_result_builtin, lower_result_expecting, lower_result_field, lower_stmts,
+    lower_ts_type_with_enums, lower_type_annotation, lower_yield, mangle_generic_name, method_target,
+    non_null_assertion, param_hints, record_param_shape, specialized_signature, spread_call_args, upcast_expr,
 };
-use super::{codegen_type_suffix, lower_stmts, lower_ts_type_with_enums, mangle_generic_name};
 
 pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
     match expr {
@@ -62,11 +71,36 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
             }
         }
         Expression::Identifier(id) => {
-            let name = id.name.to_string();
+            let name = ctx.var_name(id);
+            if !ctx.var_types.contains_key(&name) {
+                // A named function used as a value (`const f = add`, `apply(add)`)
+                if let Some(value) = function_value(&name, ctx) {
+                    return value;
+                }
+            }
             let ty = ctx.var_types.get(&name).cloned().unwrap_or(LltsType::F64);
             Expr::Var { name, ty }
         }
+        Expression::ThisExpression(_) => {
+            let ty = ctx.var_types.get("self").cloned().unwrap_or(LltsType::F64);
+            Expr::Var { name: "self".to_string(), ty }
+        }
+        Expression::Super(_) => {
+            // `super.field`: the parent part of `self`
+            let this = Expr::Var {
+                name: "self".to_string(),
+                ty: ctx.var_types.get("self").cloned().unwrap_or(LltsType::F64),
+            };
+            match current_parent_class(ctx) {
+                Some(parent) => upcast_expr(this, &parent, ctx),
+                None => this,
+            }
+        }
         Expression::BinaryExpression(bin) => {
+            if bin.operator == BinaryOperator::Instanceof {
+                let value = lower_expr(&bin.left, ctx);
+                return lower_instanceof(value, &expr_to_name(&bin.right, ctx), ctx);
+            }
             // Detect null comparison patterns: x !== null, x === null, null !== x, null === x
             let is_strict_eq = matches!(bin.operator, BinaryOperator::StrictEquality | BinaryOperator::Equality);
             let is_strict_ne = matches!(bin.operator, BinaryOperator::StrictInequality | BinaryOperator::Inequality);
@@ -126,168 +160,128 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
             Expr::Unary { op, operand: Box::new(operand_expr), ty }
         }
         Expression::CallExpression(call) => {
-            let mut args: Vec<Expr> = call.arguments.iter().map(|a| lower_argument(a, ctx)).collect();
-
-            match &call.callee {
-                Expression::StaticMemberExpression(member) => {
-                    let obj_name = expr_to_name(&member.object);
-                    let method = member.property.name.to_string();
-
-                    if obj_name == "console" && method == "log" {
-                        return Expr::Call {
-                            callee: "print".to_string(),
-                            args,
-                            ret_type: LltsType::Void,
-                        };
-                    }
+            // Generic function called without type arguments: they are
+            // inferred while its arguments are lowered.
+            if let Expression::Identifier(id) = &call.callee {
+                let callee = ctx.var_name(id);
+                if call.type_arguments.is_none()
+                    && ctx.generic_fn_indices.contains_key(&callee)
+                    && !ctx.var_types.contains_key(&callee)
+                {
+                    let (args, types) = lower_inferred_generic_args(&callee, &call.arguments, ctx);
+                    return generic_call(&callee, types, args, ctx);
+                }
+            }
 
-                    if obj_name == "Math" {
-                        return Expr::Call {
-                            callee: format!("Math_{method}"),
-                            args,
-                            ret_type: LltsType::F64,
-                        };
+            // Known parameter types give unannotated lambda arguments their signature.
+            let param_hint: Vec<LltsType> = match &call.callee {
+                Expression::Identifier(id) => {
+                    let callee = ctx.var_name(id);
+                    match ctx.var_types.get(&callee) {
+                        Some(LltsType::Function { params, .. }) => params.clone(),
+                        Some(_) => Vec::new(),
+                        None => param_hints(&callee, call.arguments.len(), ctx),
                     }
-
-                    // Dispatch array and string methods
-                    let receiver_type = ctx.var_types.get(&obj_name).cloned();
-                    let (class_name, ret_type) = match &receiver_type {
-                        Some(LltsType::Array(elem)) => {
-                            let ret = match method.as_str() {
-                                "push" => LltsType::Void,
-                                "pop" => *elem.clone(),
-                                "indexOf" => LltsType::I64,
-                                "includes" => LltsType::Bool,
-                                _ => LltsType::Void,
-                            };
-                            // Coerce push/indexOf/includes args to elem type
-                            if matches!(method.as_str(), "push" | "indexOf" | "includes") {
-                                for arg in args.iter_mut() {
-                                    // Patch StructLit args for struct arrays
-                                    if let Expr::StructLit { struct_type, fields } = arg {
-                                        if let LltsType::Struct { name: sname, .. } = elem.as_ref() {
-                                            if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                                                *struct_type = ctx.full_struct_type(sname);
-                                            }
-                                            if let LltsType::Struct { fields: type_fields, .. } = struct_type {
-                                                for (j, (_, field_ty)) in type_fields.iter().enumerate() {
-                                                    if j < fields.len() {
-                                                        let old = std::mem::replace(&mut fields[j], Expr::BoolLit(false));
-                                                        fields[j] = coerce_to_type(old, field_ty);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    let old = std::mem::replace(arg, Expr::BoolLit(false));
-                                    *arg = coerce_to_type(old, elem);
-                                }
-                            }
-                            ("__Array".to_string(), ret)
-                        }
-                        Some(LltsType::String) => {
-                            let ret = match method.as_str() {
-                                "charAt" | "slice" | "toUpperCase" | "toLowerCase"
-                                | "trim" | "substring" | "repeat" => LltsType::String,
-                                "charCodeAt" | "indexOf" | "lastIndexOf" => LltsType::I64,
-                                "includes" | "startsWith" | "endsWith" => LltsType::Bool,
-                                _ => LltsType::Void,
-                            };
-                            ("__String".to_string(), ret)
-                        }
-                        _ => {
-                            // Non-array/string method: coerce args via fn_param_types
-                            coerce_call_args(&mut args, &obj_name, ctx);
-                            (obj_name.clone(), LltsType::Void)
-                        },
+                }
+                _ => Vec::new(),
+            };
+            let mut args: Vec<Expr> = call
+                .arguments
+                .iter()
+                .enumerate()
+                .map(|(i, a)| match (a, param_hint.get(i)) {
+                    (Argument::SpreadElement(_), _) | (_, None) => lower_argument(a, ctx),
+                    (_, Some(pty)) => lower_expr_expecting(a.to_expression(), pty, ctx),
+                })
+                .collect();
+            // Optional, defaulted and rest parameters of the callee decide
+            // which parameter each argument fills.
+            let (target, receiver) = match &call.callee {
+                Expression::Identifier(id) => (call_target(&ctx.var_name(id), ctx), None),
+                Expression::Super(_) => {
+                    let parent = current_parent_class(ctx);
+                    (parent.and_then(|parent| call_target(&format!("{parent}_new"), ctx)), None)
+                }
+                Expression::StaticMemberExpression(member) => {
+                    let object = lower_expr(&member.object, ctx);
+                    let target = match ir_expr_type(&object) {
+                        LltsType::Struct { name, .. } => method_target(&format!("{name}_{}", member.property.name), ctx),
+                        _ => None,
                     };
+                    (target, Some(object))
+                }
+                _ => (None, None),
+            };
+            args = spread_call_args(target.as_ref(), &call.arguments, args, ctx);
 
-                    Expr::MethodCall {
-                        class_name,
-                        method_name: method,
-                        receiver: Box::new(lower_expr(&member.object, ctx)),
+            match &call.callee {
+                Expression::Super(_) => {
+                    // `super(...)` in a constructor; the class lowering turns the
+                    // call into the parent-field initialization of `self`.
+                    let parent = current_parent_class(ctx).unwrap_or_default();
+                    let callee = format!("{parent}_new");
+                    coerce_call_args(&mut args, &callee, ctx);
+                    Expr::Call {
+                        callee,
                         args,
-                        ret_type,
+                        ret_type: ctx.full_struct_type(&parent),
                     }
                 }
+                Expression::StaticMemberExpression(member) if matches!(member.object, Expression::Super(_)) => {
+                    // `super.method(...)`: call the parent's implementation on the
+                    // parent part of `self`.
+                    let parent = current_parent_class(ctx).unwrap_or_default();
+                    let callee = format!("{parent}_{}", member.property.name);
+                    let mut all_args = vec![receiver.unwrap_or_else(|| lower_expr(&member.object, ctx))];
+                    all_args.append(&mut args);
+                    coerce_call_args(&mut all_args, &callee, ctx);
+                    Expr::Call {
+                        ret_type: ctx.fn_ret_types.get(&callee).cloned().unwrap_or(LltsType::Void),
+                        callee,
+                        args: all_args,
+                    }
+                }
+                Expression::StaticMemberExpression(member) => {
+                    let object = receiver.unwrap_or_else(|| lower_expr(&member.object, ctx));
+                    lower_method_call(object, &expr_to_name(&member.object, ctx), &member.property.name, args, ctx)
+                }
                 Expression::Identifier(id) => {
-                    let callee = id.name.to_string();
-
-                    // Check for generic function call with explicit type arguments
-                    // e.g. identity<i32>(5), or with defaults: identity(5) when T has a default
-                    if ctx.generic_fn_indices.contains_key(&callee) {
-                        let concrete_types = if let Some(type_args) = &call.type_arguments {
-                            // Explicit type args provided
-                            let enum_names = ctx.enum_names();
-                            let mut types: Vec<LltsType> = type_args
-                                .params
-                                .iter()
-                                .map(|t| lower_ts_type_with_enums(t, &enum_names))
-                                .collect();
-                            // Fill in defaults for any missing trailing type args
-                            if let Some(param_info) = ctx.generic_fn_params.get(&callee) {
-                                while types.len() < param_info.len() {
-                                    if let Some(default_ty) = &param_info[types.len()].1 {
-                                        types.push(default_ty.clone());
-                                    } else {
-                                        break;
-                                    }
-                                }
-                            }
-                            Some(types)
-                        } else if let Some(param_info) = ctx.generic_fn_params.get(&callee) {
-                            // No type args — use defaults if ALL params have defaults
-                            let defaults: Vec<LltsType> = param_info
-                                .iter()
-                                .filter_map(|(_, default, _)| default.clone())
-                                .collect();
-                            if defaults.len() == param_info.len() {
-                                Some(defaults)
-                            } else {
-                                None // Not all params have defaults, can't use
-                            }
-                        } else {
-                            None
-                        };
-
-                        if let Some(concrete_types) = concrete_types {
-                            // Constraint checking: verify each concrete type is allowed
-                            if let Some(param_info) = ctx.generic_fn_params.get(&callee) {
-                                for (i, concrete) in concrete_types.iter().enumerate() {
-                                    if i < param_info.len() && !param_info[i].2.is_empty() {
-                                        let allowed = &param_info[i].2;
-                                        if !allowed.contains(concrete) {
-                                            eprintln!(
-                                                "warning: type {} does not satisfy constraint for parameter '{}' in {}",
-                                                codegen_type_suffix(concrete),
-                                                param_info[i].0,
-                                                callee,
-                                            );
-                                        }
-                                    }
+                    let callee = ctx.var_name(id);
+
+                    // Calls through a function-typed variable (closure or
+                    // callback parameter) go through the fat pointer.
+                    if let Some(fn_ty @ LltsType::Function { .. }) = ctx.var_types.get(&callee).cloned() {
+                        return indirect_call(Expr::Var { name: callee, ty: fn_ty }, args);
+                    }
+
+                    // Generic function call with explicit type arguments,
+                    // e.g. identity<i32>(5); missing trailing ones take their defaults.
+                    if let (true, Some(type_args)) = (ctx.generic_fn_indices.contains_key(&callee), &call.type_arguments) {
+                        let mut types: Vec<LltsType> = type_args
+                            .params
+                            .iter()
+                            .map(|t| lower_type_annotation(t, ctx))
+                            .collect();
+                        if let Some(param_info) = ctx.generic_fn_params.get(&callee) {
+                            while types.len() < param_info.len() {
+                                if let Some(default_ty) = &param_info[types.len()].1 {
+                                    types.push(default_ty.clone());
+                                } else {
+                                    break;
                                 }
                             }
+                        }
+                        return generic_call(&callee, types, args, ctx);
+                    }
 
-                            let mangled = mangle_generic_name(&callee, &concrete_types);
-
-                            // Queue monomorphization if not already done
-                            if !ctx.monomorphized.contains(&mangled) {
-                                ctx.monomorphized.insert(mangled.clone());
-                                ctx.pending_monomorphizations.push((
-                                    callee.clone(),
-                                    vec![],
-                                    concrete_types.clone(),
-                                    mangled.clone(),
-                                ));
-                            }
+                    // `sleep(ms)`, `readFile(path)`
+                    if is_promise_builtin(&callee, ctx) {
+                        return lower_promise_builtin(&callee, args, ctx);
+                    }
 
-                            let ret_type = ctx.fn_ret_types.get(&mangled).cloned().unwrap_or(LltsType::Void);
-                            return Expr::Call {
-                                callee: mangled,
-                                args,
-                                ret_type,
-                            };
-                        }
+                    // `Ok(x)`, `Err(e)`
+                    if is_result_builtin(&callee, ctx) {
+                        return lower_result_builtin(&callee, args, ctx);
                     }
 
                     let ret_type = ctx.fn_ret_types.get(&callee).cloned().unwrap_or(LltsType::Void);
@@ -298,29 +292,36 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
                         ret_type,
                     }
                 }
-                _ => Expr::Call {
-                    callee: "<unknown>".to_string(),
-                    args,
-                    ret_type: LltsType::Void,
-                },
+                other => {
+                    // Any other function-typed callee: `handlers[i](x)`, `make()(x)`
+                    let callee = lower_expr(other, ctx);
+                    if matches!(ir_expr_type(&callee), LltsType::Function { .. }) {
+                        return indirect_call(callee, args);
+                    }
+                    Expr::Call {
+                        callee: "<unknown>".to_string(),
+                        args,
+                        ret_type: LltsType::Void,
+                    }
+                }
             }
         }
         Expression::NewExpression(new_expr) => {
-            let class_name = expr_to_name(&new_expr.callee);
-            let args: Vec<Expr> = new_expr
-                .arguments
-                .iter()
-                .map(|a| lower_argument(a, ctx))
-                .collect();
-            Expr::ConstructorCall {
-                class_name: class_name.clone(),
-                args,
-                ret_type: ctx.full_struct_type(&class_name),
+            if let Some(collection) = lower_new_collection(new_expr, None, ctx) {
+                return collection;
             }
+            let mut class_name = expr_to_name(&new_expr.callee, ctx);
+            // `new Stack<i32>()` constructs the instance `Stack$i32`.
+            if let Some(type_args) = &new_expr.type_arguments {
+                let types: Vec<LltsType> = type_args.params.iter().map(|t| lower_type_annotation(t, ctx)).collect();
+                class_name = mangle_generic_name(&class_name, &types);
+                instantiate_generic_type(&class_name, ctx);
+            }
+            lower_new_expression(new_expr, class_name, ctx)
         }
         Expression::StaticMemberExpression(member) => {
             let field_name = member.property.name.to_string();
-            let obj_name = expr_to_name(&member.object);
+            let obj_name = expr_to_name(&member.object, ctx);
 
             // Check if this is an enum variant access (e.g. Color.Red)
             if let Some(value) = ctx.lookup_enum_variant(&obj_name, &field_name) {
@@ -348,98 +349,19 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
                 }
             }
 
-            let object = Box::new(lower_expr(&member.object, ctx));
-
-            // .length on arrays and strings
-            if field_name == "length" {
-                if let Some(obj_type) = ctx.var_types.get(&obj_name).cloned() {
-                    match &obj_type {
-                        LltsType::Array(_) => {
-                            // Array { ptr, len, cap } — len is at index 1
-                            return Expr::FieldAccess {
-                                object,
-                                object_type: obj_type.clone(),
-                                field_index: 1,
-                                field_type: LltsType::I64,
-                            };
-                        }
-                        LltsType::String => {
-                            // String { ptr, len } — len is at index 1
-                            return Expr::FieldAccess {
-                                object,
-                                object_type: obj_type.clone(),
-                                field_index: 1,
-                                field_type: LltsType::I64,
-                            };
-                        }
-                        _ => {}
-                    }
-                }
-            }
-
-            // Try to resolve the object's struct type for field access.
-            // First try var_types by name, then fall back to inferring from the IR.
-            let obj_type = ctx.var_types.get(&obj_name).cloned()
-                .unwrap_or_else(|| ir_expr_type(&object));
-
-            if let LltsType::Struct { name: struct_name, .. } = &obj_type {
-                if let Some((field_index, field_type)) = ctx.lookup_field(struct_name, &field_name) {
-                    return Expr::FieldAccess {
-                        object,
-                        object_type: obj_type.clone(),
-                        field_index,
-                        field_type,
-                    };
-                }
-            }
-
-            // Fallback: field_index 0 (best effort)
-            Expr::FieldAccess {
-                object,
-                object_type: LltsType::F64,
-                field_index: 0,
-                field_type: LltsType::F64,
-            }
+            let object = lower_expr(&member.object, ctx);
+            lower_member_access(object, &obj_name, &field_name, ctx)
         }
         Expression::ComputedMemberExpression(member) => {
             let arr_expr = lower_expr(&member.object, ctx);
             let idx_expr = lower_expr(&member.expression, ctx);
-            let elem_type = match ir_expr_type(&arr_expr) {
-                LltsType::Array(elem) => *elem,
-                _ => LltsType::F64,
-            };
-            Expr::ArrayIndex {
-                array: Box::new(arr_expr),
-                index: Box::new(idx_expr),
-                elem_type,
-            }
-        }
-        Expression::AssignmentExpression(assign) => {
-            let value = lower_expr(&assign.right, ctx);
-            let _target = assignment_target_name(&assign.left);
-            value
-        }
-        Expression::UpdateExpression(update) => {
-            let name = simple_target_name(&update.argument);
-            let ty = ctx.var_types.get(&name).cloned().unwrap_or(LltsType::F64);
-            let var = Expr::Var {
-                name: name.clone(),
-                ty: ty.clone(),
-            };
-            let one = coerce_to_type(Expr::FloatLit { value: 1.0, ty: LltsType::F64 }, &ty);
-            let op = if update.operator == UpdateOperator::Increment {
-                BinOp::Add
-            } else {
-                BinOp::Sub
-            };
-            Expr::Binary {
-                op,
-                lhs: Box::new(var),
-                rhs: Box::new(one),
-                ty,
-            }
+            lower_index_access(arr_expr, idx_expr)
         }
+        Expression::AssignmentExpression(assign) => lower_assignment(assign, ctx),
+        Expression::UpdateExpression(update) => lower_update(update, ctx),
         Expression::ParenthesizedExpression(paren) => lower_expr(&paren.expression, ctx),
+        Expression::YieldExpression(yield_expr) => lower_yield(yield_expr, ctx),
+        Expression::AwaitExpression(await_expr) => lower_await(await_expr, ctx),
         Expression::TSAsExpression(as_expr) => {
             let lowered = lower_expr(&as_expr.expression, ctx);
             let from = ir_expr_type(&lowered);
@@ -448,11 +370,13 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
             Expr::Cast { value: Box::new(lowered), from, to }
         }
         Expression::ArrayExpression(arr) => {
+            if let Some(spread) = lower_array_spread(arr, ctx) {
+                return spread;
+            }
             let elements: Vec<Expr> = arr
                 .elements
                 .iter()
                 .filter_map(|el| match el {
-                    ArrayExpressionElement::SpreadElement(_) => None,
                     ArrayExpressionElement::Elision(_) => None,
                     _ => Some(lower_array_element(el, ctx)),
                 })
@@ -463,7 +387,10 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
                     Expr::FloatLit { ty, .. } => ty.clone(),
                     Expr::StringLit(_) => LltsType::String,
                     Expr::BoolLit(_) => LltsType::Bool,
-                    _ => LltsType::F64,
+                    other => match ir_expr_type(other) {
+                        fn_ty @ LltsType::Function { .. } => fn_ty,
+                        _ => LltsType::F64,
+                    },
                 }
             } else {
                 LltsType::F64
@@ -491,9 +418,12 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
             }
         }
         Expression::ConditionalExpression(cond) => {
+            // `r.ok ? r.value : ...` narrows a Result in each branch.
+            let narrowing = detect_result_narrowing(&cond.test, ctx);
             let condition = lower_expr(&cond.test, ctx);
-            let then_expr = lower_expr(&cond.consequent, ctx);
-            let else_expr = lower_expr(&cond.alternate, ctx);
+            let narrowed = |then: bool| narrowing.as_ref().map(|(name, ok)| (name.as_str(), *ok == then));
+            let then_expr = lower_narrowed(&cond.consequent, narrowed(true), ctx);
+            let else_expr = lower_narrowed(&cond.alternate, narrowed(false), ctx);
             let ty = ir_expr_type(&then_expr);
             Expr::Ternary {
                 condition: Box::new(condition),
@@ -504,12 +434,12 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
         }
         Expression::LogicalExpression(log) => {
             let lhs = lower_expr(&log.left, ctx);
-            let rhs = lower_expr(&log.right, ctx);
             let op = match log.operator {
                 LogicalOperator::And => LogicalOp::And,
                 LogicalOperator::Or => LogicalOp::Or,
-                LogicalOperator::Coalesce => LogicalOp::Or, // ?? treated as || for now
+                LogicalOperator::Coalesce => return lower_coalesce(lhs, &log.right, ctx),
             };
+            let rhs = lower_expr(&log.right, ctx);
             Expr::Logical {
                 op,
                 lhs: Box::new(lhs),
@@ -522,208 +452,427 @@ pub(crate) fn lower_expr(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
             let lambda_name = format!("__lambda_{}", ctx.lambda_counter);
             ctx.lambda_counter += 1;
 
-            let enum_names = ctx.enum_names();
+            // Unannotated parameters and return type come from the context's
+            // expected function type, if any.
+            let (hint_params, hint_ret) = ctx.lambda_signature_hint.take().unwrap_or_default();
 
             // Save and set up parameter scope for lowering the body
             let saved_vars = ctx.var_types.clone();
 
-            let params: Vec<(String, LltsType)> = arrow
-                .params
-                .items
-                .iter()
-                .map(|p| {
-                    let pname = binding_name(&p.pattern);
-                    let pty = p
-                        .type_annotation
-                        .as_ref()
-                        .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                        .unwrap_or(LltsType::F64);
-                    let pty = match &pty {
-                        LltsType::Struct { name, fields } if fields.is_empty() => {
-                            ctx.full_struct_type(name)
-                        }
-                        other => other.clone(),
-                    };
-                    ctx.var_types.insert(pname.clone(), pty.clone());
-                    (pname, pty)
-                })
-                .collect();
+            let BoundParams { params, prologue, shape } = bind_params(&arrow.params, &hint_params, ctx);
 
-            let ret_type = arrow
+            let declared_ret = arrow
                 .return_type
                 .as_ref()
-                .map(|r| lower_ts_type_with_enums(&r.type_annotation, &enum_names))
-                .unwrap_or(LltsType::Void);
+                .map(|r| lower_type_annotation(&r.type_annotation, ctx))
+                .or(hint_ret);
+            let mut ret_type = declared_ret.clone().unwrap_or(LltsType::Void);
 
             // Track return type for struct inference inside the body
             ctx.var_types.insert("__fn_return_type__".to_string(), ret_type.clone());
 
             // Lower body: expression bodies have a single expression wrapped as a
             // return; block bodies are normal statement lists.
-            let body = if arrow.expression {
+            let mut body = prologue;
+            body.extend(if arrow.expression {
                 // Expression body: the single statement is an ExpressionStatement
                 // wrapping the return value.
                 let return_expr = if let Some(stmt) = arrow.body.statements.first() {
                     match stmt {
-                        Statement::ExpressionStatement(es) => lower_expr(&es.expression, ctx),
+                        Statement::ExpressionStatement(es) => match &declared_ret {
+                            Some(ret) => lower_expr_expecting(&es.expression, ret, ctx),
+                            None => lower_expr(&es.expression, ctx),
+                        },
                         _ => Expr::IntLit { value: 0, ty: LltsType::I32 },
                     }
                 } else {
                     Expr::IntLit { value: 0, ty: LltsType::I32 }
                 };
-                vec![Stmt::Return(Some(return_expr))]
+                // Without a declared return type the body expression decides it.
+                if declared_ret.is_none() {
+                    ret_type = ir_expr_type(&return_expr);
+                }
+                if ret_type == LltsType::Void {
+                    vec![Stmt::Expr(return_expr)]
+                } else {
+                    vec![Stmt::Return(Some(coerce_to_type(return_expr, &ret_type)))]
+                }
             } else {
                 lower_stmts(&arrow.body.statements, ctx)
-            };
+            });
 
             ctx.var_types = saved_vars;
 
+            // Free-variable analysis: enclosing locals referenced by the body
+            // are captured by reference through the env struct. Closures that
+            // escape are rewritten later by `box_escaping_captures`.
+            let captures = collect_captures(&body, &params, &ctx.var_types);
+            let env_type = closure_env_type(&lambda_name, &captures);
+            let mut full_body: Vec<Stmt> = captures
+                .iter()
+                .enumerate()
+                .map(|(i, (cname, cty))| Stmt::CaptureBind {
+                    name: cname.clone(),
+                    ty: cty.clone(),
+                    env_type: env_type.clone(),
+                    field_index: i as u32,
+                    by_ref: true,
+                })
+                .collect();
+            full_body.extend(body);
+
             // Register in fn_ret_types so call sites can resolve the return type
             ctx.fn_ret_types.insert(lambda_name.clone(), ret_type.clone());
+            record_param_shape(&lambda_name, shape, ctx);
+
+            let fn_type = LltsType::Function {
+                params: params.iter().map(|(_, t)| t.clone()).collect(),
+                ret: Box::new(ret_type.clone()),
+            };
+
+            // Every lambda takes the env pointer as a hidden first parameter
+            let mut all_params = vec![(ENV_PARAM.to_string(), LltsType::Ptr)];
+            all_params.extend(params);
 
             // Store the generated function for later emission
             ctx.pending_functions.push(FunctionDecl {
                 name: lambda_name.clone(),
-                params,
+                params: all_params,
                 ret_type,
-                body,
+                body: full_body,
             });
 
-            // Return a variable reference to the lambda function name
-            Expr::Var {
-                name: lambda_name,
-                ty: LltsType::F64, // placeholder; callers use fn_ret_types for call resolution
-            }
-        }
-        Expression::ChainExpression(chain) => {
-            // v1: treat optional chaining (?.) as regular access.
-            // Unwrap the ChainElement and lower it like a normal expression.
-            match &chain.expression {
-                ChainElement::CallExpression(call) => {
-                    // Reuse the CallExpression lowering logic
-                    let args: Vec<Expr> = call.arguments.iter().map(|a| lower_argument(a, ctx)).collect();
-                    match &call.callee {
-                        Expression::StaticMemberExpression(member) => {
-                            let obj_name = expr_to_name(&member.object);
-                            let method = member.property.name.to_string();
-
-                            if obj_name == "console" && method == "log" {
-                                return Expr::Call {
-                                    callee: "print".to_string(),
-                                    args,
-                                    ret_type: LltsType::Void,
-                                };
-                            }
+            Expr::Closure {
+                function: lambda_name,
+                captures: captures.into_iter().map(|(n, _)| n).collect(),
+                env_type,
+                ty: fn_type,
+                boxed: None,
+            }
+        }
+        Expression::ChainExpression(chain) => lower_chain(chain, ctx),
+        Expression::TSNonNullExpression(non_null) => {
+            let value = lower_expr(&non_null.expression, ctx);
+            non_null_assertion(value)
+        }
+        _ => {
+            Expr::IntLit {
+                value: 0,
+                ty: LltsType::I32,
+            }
+        }
+    }
+}
 
-                            if obj_name == "Math" {
-                                return Expr::Call {
-                                    callee: format!("Math_{method}"),
-                                    args,
-                                    ret_type: LltsType::F64,
-                                };
-                            }
+/// Lower an expression whose expected type is known from its context.
+///
+/// An arrow function expected to be a `LltsType::Function` takes its
+/// unannotated parameter and return types from it, and a class value
+/// expected to be an interface or base-class type is viewed through its
+/// vtable. `Ok(x)`, `Err(e)` and Result-shaped object literals expected to
+/// be a `Result` construct it; other expressions lower as usual.
+pub(crate) fn lower_expr_expecting(expr: &Expression<'_>, expected: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    if let Some(result) = lower_result_expecting(expr, expected, ctx) {
+        return result;
+    }
+    if let LltsType::Function { params, ret } = expected {
+        if matches!(expr.without_parentheses(), Expression::ArrowFunctionExpression(_)) {
+            ctx.lambda_signature_hint = Some((params.clone(), Some(*ret.clone())));
+        }
+    }
+    // `const m: Map<string, i32> = new Map()` takes the expected type arguments.
+    if let Expression::NewExpression(new_expr) = expr.without_parentheses() {
+        if let Some(collection) = lower_new_collection(new_expr, Some(expected), ctx) {
+            return collection;
+        }
+    }
+    // `const s: Stack<i32> = new Stack()` constructs the expected instance.
+    if let (Expression::NewExpression(new_expr), LltsType::Struct { name, .. }) = (expr.without_parentheses(), expected) {
+        let class_name = expr_to_name(&new_expr.callee, ctx);
+        if new_expr.type_arguments.is_none()
+            && ctx.type_instances.get(name).is_some_and(|(generic_name, _)| *generic_name == class_name)
+        {
+            return lower_new_expression(new_expr, name.clone(), ctx);
+        }
+    }
+    let lowered = lower_expr(expr, ctx);
+    coerce_to_dyn(lowered, expected, ctx)
+}
 
-                            // Dispatch array and string methods (chain expression)
-                            let receiver_type = ctx.var_types.get(&obj_name).cloned();
-                            let (class_name, ret_type) = match &receiver_type {
-                                Some(LltsType::Array(elem)) => {
-                                    let ret = match method.as_str() {
-                                        "push" => LltsType::Void,
-                                        "pop" => *elem.clone(),
-                                        "indexOf" => LltsType::I64,
-                                        "includes" => LltsType::Bool,
-                                        _ => LltsType::Void,
-                                    };
-                                    ("__Array".to_string(), ret)
-                                }
-                                Some(LltsType::String) => {
-                                    let ret = match method.as_str() {
-                                        "charAt" | "slice" | "toUpperCase" | "toLowerCase"
-                                        | "trim" | "substring" | "repeat" => LltsType::String,
-                                        "charCodeAt" | "indexOf" | "lastIndexOf" => LltsType::I64,
-                                        "includes" | "startsWith" | "endsWith" => LltsType::Bool,
-                                        _ => LltsType::Void,
-                                    };
-                                    ("__String".to_string(), ret)
-                                }
-                                _ => (obj_name.clone(), LltsType::Void),
-                            };
+/// Construct `class_name` (a class or generic class instance) from the
+/// arguments of a `new` expression.
+fn lower_new_expression(new_expr: &NewExpression<'_>, class_name: String, ctx: &mut LowerCtx) -> Expr {
+    let mut args: Vec<Expr> = new_expr
+        .arguments
+        .iter()
+        .map(|a| lower_argument(a, ctx))
+        .collect();
+    let target = call_target(&format!("{class_name}_new"), ctx);
+    args = spread_call_args(target.as_ref(), &new_expr.arguments, args, ctx);
+    coerce_call_args(&mut args, &format!("{class_name}_new"), ctx);
+    Expr::ConstructorCall {
+        ret_type: ctx.full_struct_type(&class_name),
+        class_name,
+        args,
+    }
+}
 
-                            Expr::MethodCall {
-                                class_name,
-                                method_name: method,
-                                receiver: Box::new(lower_expr(&member.object, ctx)),
-                                args,
-                                ret_type,
-                            }
-                        }
-                        Expression::Identifier(id) => {
-                            let callee = id.name.to_string();
-                            let ret_type = ctx.fn_ret_types.get(&callee).cloned().unwrap_or(LltsType::Void);
-                            Expr::Call {
-                                callee,
-                                args,
-                                ret_type,
+/// Call the specialization of generic function `callee` for `concrete_types`,
+/// queueing it for monomorphization on first use.
+fn generic_call(callee: &str, concrete_types: Vec<LltsType>, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    // Struct and class type arguments are used by value,
+    // so their members resolve statically in the body.
+    let concrete_types: Vec<LltsType> = concrete_types
+        .into_iter()
+        .map(|t| match t {
+            LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(&name),
+            other => other,
+        })
+        .collect();
+    let mangled = mangle_generic_name(callee, &concrete_types);
+    if let Some((param_types, ret)) = specialized_signature(callee, &concrete_types, ctx) {
+        ctx.fn_ret_types.insert(mangled.clone(), ret);
+        ctx.fn_param_types.insert(mangled.clone(), param_types);
+    }
+    coerce_call_args(&mut args, &mangled, ctx);
+
+    // Queue monomorphization if not already done
+    if !ctx.monomorphized.contains(&mangled) {
+        ctx.monomorphized.insert(mangled.clone());
+        ctx.pending_monomorphizations.push((
+            callee.to_string(),
+            vec![],
+            concrete_types,
+            mangled.clone(),
+        ));
+    }
+
+    let ret_type = ctx.fn_ret_types.get(&mangled).cloned().unwrap_or(LltsType::Void);
+    Expr::Call {
+        callee: mangled,
+        args,
+        ret_type,
+    }
+}
+
+/// Read `field_name` of the already lowered `object`. `obj_name` is the
+/// receiver's variable name, if it is one (see `expr_to_name`).
+pub(crate) fn lower_member_access(object: Expr, obj_name: &str, field_name: &str, ctx: &mut LowerCtx) -> Expr {
+    if field_name == "size" && is_collection(&ir_expr_type(&object)) {
+        return collection_size(object);
+    }
+
+    // Try to resolve the object's type for field access.
+    // First try var_types by name, then fall back to inferring from the IR.
+    let obj_type = ctx.var_types.get(obj_name).cloned()
+        .unwrap_or_else(|| ir_expr_type(&object));
+
+    // .length on arrays and strings
+    if field_name == "length" && matches!(obj_type, LltsType::Array(_) | LltsType::String) {
+        // Array { ptr, len, cap }, String { ptr, len } — len is at index 1
+        return Expr::FieldAccess {
+            object: Box::new(object),
+            object_type: obj_type,
+            field_index: 1,
+            field_type: LltsType::I64,
+        };
+    }
+
+    // `r.ok`, and `r.value` / `r.error` where `r.ok` narrowed them
+    if let LltsType::Result { .. } = &obj_type {
+        return lower_result_field(object, obj_name, field_name, &obj_type, ctx);
+    }
+
+    // Fields of interface and base-class values are read through
+    // their getter slot.
+    if let LltsType::Dyn { name: dyn_name } = &obj_type {
+        if let Some((index, slot)) = ctx.lookup_dyn_slot(dyn_name, field_name) {
+            if slot.params.is_none() {
+                return dyn_call(object, index, &slot, Vec::new(), ctx);
+            }
+        }
+    }
+
+    if let LltsType::Struct { name: struct_name, .. } = &obj_type {
+        if let Some((field_index, field_type)) = ctx.lookup_field(struct_name, field_name) {
+            return Expr::FieldAccess {
+                object: Box::new(object),
+                object_type: obj_type.clone(),
+                field_index,
+                field_type,
+            };
+        }
+    }
+
+    // Fallback: field_index 0 (best effort)
+    Expr::FieldAccess {
+        object: Box::new(object),
+        object_type: LltsType::F64,
+        field_index: 0,
+        field_type: LltsType::F64,
+    }
+}
+
+/// Call `method` on the already lowered `object`. `obj_name` is the
+/// receiver's variable name, if it is one (see `expr_to_name`).
+pub(crate) fn lower_method_call(object: Expr, obj_name: &str, method: &str, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    // Calling a function-typed struct field: `handler.callback(x)`
+    if let LltsType::Struct { name: struct_name, .. } = ir_expr_type(&object) {
+        if let Some((field_index, field_ty @ LltsType::Function { .. })) =
+            ctx.lookup_field(&struct_name, method)
+        {
+            let callee = Expr::FieldAccess {
+                object: Box::new(object),
+                object_type: ctx.full_struct_type(&struct_name),
+                field_index,
+                field_type: field_ty,
+            };
+            return indirect_call(callee, args);
+        }
+    }
+
+    // Interface and base-class receivers dispatch through the
+    // vtable; a field slot holding a function value is called
+    // through it.
+    if let LltsType::Dyn { name: dyn_name } = ir_expr_type(&object) {
+        if let Some((index, slot)) = ctx.lookup_dyn_slot(&dyn_name, method) {
+            if slot.params.is_some() {
+                return dyn_call(object, index, &slot, args, ctx);
+            }
+            return indirect_call(dyn_call(object, index, &slot, Vec::new(), ctx), args);
+        }
+    }
+
+    if is_collection(&ir_expr_type(&object)) {
+        return collection_method_call(object, method, args, ctx);
+    }
+
+    if obj_name == "console" && method == "log" {
+        return Expr::Call {
+            callee: "print".to_string(),
+            args,
+            ret_type: LltsType::Void,
+        };
+    }
+
+    if obj_name == "Math" {
+        return Expr::Call {
+            callee: format!("Math_{method}"),
+            args,
+            ret_type: LltsType::F64,
+        };
+    }
+
+    if obj_name == "Promise" && !ctx.var_types.contains_key("Promise") {
+        return lower_promise_combinator(method, args, ctx);
+    }
+
+    // Dispatch array and string methods
+    let receiver_type = ctx.var_types.get(obj_name).cloned().unwrap_or_else(|| ir_expr_type(&object));
+    let (class_name, ret_type) = match &receiver_type {
+        LltsType::Array(elem) => {
+            let ret = match method {
+                "push" => LltsType::Void,
+                "pop" => *elem.clone(),
+                "indexOf" => LltsType::I64,
+                "includes" => LltsType::Bool,
+                _ => LltsType::Void,
+            };
+            // Coerce push/indexOf/includes args to elem type
+            if matches!(method, "push" | "indexOf" | "includes") {
+                for arg in args.iter_mut() {
+                    // Patch StructLit args for struct arrays
+                    if let Expr::StructLit { struct_type, fields } = arg {
+                        if let LltsType::Struct { name: sname, .. } = elem.as_ref() {
+                            if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
+                                *struct_type = ctx.full_struct_type(sname);
                             }
-                        }
-                        _ => Expr::Call {
-                            callee: "<unknown>".to_string(),
-                            args,
-                            ret_type: LltsType::Void,
-                        },
-                    }
-                }
-                ChainElement::StaticMemberExpression(member) => {
-                    let field_name = member.property.name.to_string();
-                    let object = Box::new(lower_expr(&member.object, ctx));
-                    let obj_name = expr_to_name(&member.object);
-                    if let Some(obj_type) = ctx.var_types.get(&obj_name).cloned() {
-                        if let LltsType::Struct { name: struct_name, .. } = &obj_type {
-                            if let Some((field_index, field_type)) = ctx.lookup_field(struct_name, &field_name) {
-                                return Expr::FieldAccess {
-                                    object,
-                                    object_type: obj_type.clone(),
-                                    field_index,
-                                    field_type,
-                                };
+                            if let LltsType::Struct { fields: type_fields, .. } = struct_type {
+                                for (j, (_, field_ty)) in type_fields.iter().enumerate() {
+                                    if j < fields.len() {
+                                        let old = std::mem::replace(&mut fields[j], Expr::BoolLit(false));
+                                        fields[j] = coerce_to_type(old, field_ty);
+                                    }
+                                }
                             }
                         }
                     }
-                    Expr::FieldAccess {
-                        object,
-                        object_type: LltsType::F64,
-                        field_index: 0,
-                        field_type: LltsType::F64,
-                    }
-                }
-                ChainElement::ComputedMemberExpression(member) => {
-                    let arr_expr = lower_expr(&member.object, ctx);
-                    let idx_expr = lower_expr(&member.expression, ctx);
-                    let elem_type = match ir_expr_type(&arr_expr) {
-                        LltsType::Array(elem) => *elem,
-                        _ => LltsType::F64,
-                    };
-                    Expr::ArrayIndex {
-                        array: Box::new(arr_expr),
-                        index: Box::new(idx_expr),
-                        elem_type,
-                    }
-                }
-                _ => {
-                    // TSNonNullExpression, PrivateFieldExpression — fallback
-                    Expr::IntLit {
-                        value: 0,
-                        ty: LltsType::I32,
-                    }
+                    let old = std::mem::replace(arg, Expr::BoolLit(false));
+                    *arg = coerce_to_type(old, elem);
                 }
             }
+            ("__Array".to_string(), ret)
         }
-        _ => {
-            Expr::IntLit {
-                value: 0,
-                ty: LltsType::I32,
-            }
+        LltsType::String => {
+            let ret = match method {
+                "charAt" | "slice" | "toUpperCase" | "toLowerCase"
+                | "trim" | "substring" | "repeat" => LltsType::String,
+                "charCodeAt" | "indexOf" | "lastIndexOf" => LltsType::I64,
+                "includes" | "startsWith" | "endsWith" => LltsType::Bool,
+                _ => LltsType::Void,
+            };
+            ("__String".to_string(), ret)
         }
+        _ => match ir_expr_type(&object) {
+            // Class method: dispatch on the receiver's class
+            LltsType::Struct { name: struct_name, .. }
+                if ctx.fn_ret_types.contains_key(&format!("{struct_name}_{method}")) =>
+            {
+                let mangled = format!("{struct_name}_{method}");
+                if let Some(param_types) = ctx.fn_param_types.get(&mangled).cloned() {
+                    coerce_args_to(&mut args, &param_types[1..], ctx);
+                }
+                let ret = ctx.fn_ret_types.get(&mangled).cloned().unwrap_or(LltsType::Void);
+                (struct_name, ret)
+            }
+            _ => {
+                // Non-array/string method: coerce args via fn_param_types
+                coerce_call_args(&mut args, obj_name, ctx);
+                (obj_name.to_string(), LltsType::Void)
+            }
+        },
+    };
+
+    Expr::MethodCall {
+        class_name,
+        method_name: method.to_string(),
+        receiver: Box::new(object),
+        args,
+        ret_type,
+    }
+}
+
+/// Index the already lowered `array`.
+pub(crate) fn lower_index_access(array: Expr, index: Expr) -> Expr {
+    let elem_type = match ir_expr_type(&array) {
+        LltsType::Array(elem) => *elem,
+        _ => LltsType::F64,
+    };
+    Expr::ArrayIndex {
+        array: Box::new(array),
+        index: Box::new(index),
+        elem_type,
+    }
+}
+
+/// Call through a function value, coercing arguments to its parameter types.
+pub(crate) fn indirect_call(callee: Expr, mut args: Vec<Expr>) -> Expr {
+    let (params, ret) = match ir_expr_type(&callee) {
+        LltsType::Function { params, ret } => (params, *ret),
+        _ => (Vec::new(), LltsType::Void),
+    };
+    for (arg, pty) in args.iter_mut().zip(params.iter()) {
+        let old = std::mem::replace(arg, Expr::BoolLit(false));
+        *arg = match pty {
+            LltsType::Option(inner) => into_option(old, inner),
+            _ => coerce_to_type(old, pty),
+        };
+    }
+    Expr::IndirectCall {
+        callee: Box::new(callee),
+        args,
+        param_types: params,
+        ret_type: ret,
     }
 }
 
@@ -749,102 +898,281 @@ pub(crate) fn lower_array_element(el: &ArrayExpressionElement<'_>, ctx: &mut Low
 /// Patches StructLit args (duck typing) and coerces numeric literals.
 fn coerce_call_args(args: &mut Vec<Expr>, callee: &str, ctx: &mut LowerCtx) {
     if let Some(param_types) = ctx.fn_param_types.get(callee).cloned() {
-        for (i, param_ty) in param_types.iter().enumerate() {
-            if i >= args.len() { break; }
-            // Patch StructLit with empty name to match param struct type
-            if let Expr::StructLit { struct_type, fields } = &mut args[i] {
-                if let LltsType::Struct { name: param_name, .. } = param_ty {
-                    if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                        *struct_type = ctx.full_struct_type(param_name);
-                    }
-                    // Coerce struct fields to match declared field types
-                    if let LltsType::Struct { fields: type_fields, .. } = struct_type {
-                        for (j, (_, field_ty)) in type_fields.iter().enumerate() {
-                            if j < fields.len() {
-                                let old = std::mem::replace(&mut fields[j], Expr::BoolLit(false));
-                                fields[j] = coerce_to_type(old, field_ty);
-                            }
+        coerce_args_to(args, &param_types, ctx);
+    }
+}
+
+/// Coerce call arguments to the given parameter types.
+fn coerce_args_to(args: &mut [Expr], param_types: &[LltsType], ctx: &mut LowerCtx) {
+    for (i, param_ty) in param_types.iter().enumerate() {
+        if i >= args.len() { break; }
+        // Patch StructLit with empty name to match param struct type
+        if let Expr::StructLit { struct_type, fields } = &mut args[i] {
+            if let LltsType::Struct { name: param_name, .. } = param_ty {
+                if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
+                    *struct_type = ctx.full_struct_type(param_name);
+                }
+                // Coerce struct fields to match declared field types
+                if let LltsType::Struct { fields: type_fields, .. } = struct_type {
+                    for (j, (_, field_ty)) in type_fields.iter().enumerate() {
+                        if j < fields.len() {
+                            let old = std::mem::replace(&mut fields[j], Expr::BoolLit(false));
+                            fields[j] = coerce_to_type(old, field_ty);
                         }
                     }
                 }
             }
-            // Coerce numeric literals to match param type
-            let old = std::mem::replace(&mut args[i], Expr::BoolLit(false));
-            args[i] = coerce_to_type(old, param_ty);
         }
+        // Coerce numeric literals to match param type; view class values
+        // through interface/base-class params' vtables.
+        let old = std::mem::replace(&mut args[i], Expr::BoolLit(false));
+        let coerced = coerce_to_dyn(coerce_to_type(old, param_ty), param_ty, ctx);
+        // Values passed to nullable (e.g. optional) parameters are wrapped.
+        args[i] = match param_ty {
+            LltsType::Option(inner) => into_option(coerced, inner),
+            _ => coerced,
+        };
     }
 }
 
-/// Try to lower an expression as a Stmt::Assign (for assignment and update expressions).
+/// Lower `value ?? fallback`: the value unwrapped, or the fallback if it is
+/// null. The result is nullable only if the fallback is.
+pub(crate) fn lower_coalesce(value: Expr, fallback: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let LltsType::Option(inner) = ir_expr_type(&value) else {
+        // A value that cannot be null is never replaced.
+        return value;
+    };
+    let mut fallback = match lower_expr_expecting(fallback, &inner, ctx) {
+        Expr::OptionNone { .. } => Expr::OptionNone { inner_type: (*inner).clone() },
+        other => coerce_to_type(other, &inner),
+    };
+    if let (Expr::StructLit { struct_type, fields }, LltsType::Struct { .. }) = (&mut fallback, inner.as_ref()) {
+        if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
+            *struct_type = (*inner).clone();
+        }
+        coerce_struct_fields(fields, struct_type);
+    }
+    let ty = match ir_expr_type(&fallback) {
+        LltsType::Option(_) => LltsType::Option(inner),
+        _ => *inner,
+    };
+    Expr::Coalesce {
+        value: Box::new(value),
+        fallback: Box::new(fallback),
+        ty,
+    }
+}
+
+fn is_logical_assign(operator: AssignmentOperator) -> bool {
+    matches!(
+        operator,
+        AssignmentOperator::LogicalNullish | AssignmentOperator::LogicalOr | AssignmentOperator::LogicalAnd
+    )
+}
+
+/// Value stored by a logical assignment (`??=`, `||=`, `&&=`) to a target
+/// whose value is `current`: `x ??= v` stores `x ?? v`, `x ||= v` stores
+/// `x || v`. The right side is only evaluated when it decides the result.
+fn logical_assign_value(current: Expr, operator: AssignmentOperator, right: &Expression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let target_ty = ir_expr_type(&current);
+    if operator == AssignmentOperator::LogicalNullish {
+        let value = lower_coalesce(current, right, ctx);
+        return match target_ty {
+            LltsType::Option(inner) if !matches!(ir_expr_type(&value), LltsType::Option(_)) => {
+                Expr::OptionSome { value: Box::new(value), inner_type: *inner }
+            }
+            _ => value,
+        };
+    }
+
+    let (op, name) = match operator {
+        AssignmentOperator::LogicalOr => (LogicalOp::Or, "||="),
+        _ => (LogicalOp::And, "&&="),
+    };
+    if target_ty != LltsType::Bool {
+        ctx.errors.push(format!(
+            "`{name}` is only supported on booleans, got {}",
+            codegen_type_suffix(&target_ty)
+        ));
+    }
+    let rhs = lower_expr(right, ctx);
+    Expr::Logical {
+        op,
+        lhs: Box::new(current),
+        rhs: Box::new(rhs),
+        ty: LltsType::Bool,
+    }
+}
+
+/// The arithmetic operator of a compound assignment (`+=`, `<<=`, ...).
+fn compound_op(operator: AssignmentOperator) -> BinOp {
+    match operator {
+        AssignmentOperator::Addition => BinOp::Add,
+        AssignmentOperator::Subtraction => BinOp::Sub,
+        AssignmentOperator::Multiplication => BinOp::Mul,
+        AssignmentOperator::Division => BinOp::Div,
+        AssignmentOperator::Remainder => BinOp::Rem,
+        AssignmentOperator::ShiftLeft => BinOp::Shl,
+        AssignmentOperator::ShiftRight => BinOp::Shr,
+        AssignmentOperator::BitwiseAnd => BinOp::BitAnd,
+        AssignmentOperator::BitwiseOR => BinOp::BitOr,
+        AssignmentOperator::BitwiseXOR => BinOp::BitXor,
+        _ => BinOp::Add,
+    }
+}
+
+/// Lower an assignment target to a place (see `Expr::Assign`): a variable,
+/// a field of a place, or an element of an array. Reports an error and
+/// returns None for targets that cannot be stored to.
+fn lower_place(target: &SimpleAssignmentTarget<'_>, ctx: &mut LowerCtx) -> Option<Expr> {
+    match target {
+        SimpleAssignmentTarget::AssignmentTargetIdentifier(id) => {
+            let name = ctx.var_name(id);
+            let ty = ctx.var_types.get(&name).cloned().unwrap_or(LltsType::F64);
+            Some(Expr::Var { name, ty })
+        }
+        SimpleAssignmentTarget::StaticMemberExpression(member) => {
+            let object = lower_expr(&member.object, ctx);
+            let field = member.property.name.as_str();
+            let object_type = ir_expr_type(&object);
+            if !is_place(&object) {
+                // Struct values are copied, so the store would be lost.
+                ctx.errors.push(format!(
+                    "cannot assign to '{field}' of a temporary {}",
+                    codegen_type_suffix(&object_type)
+                ));
+                return None;
+            }
+            if let LltsType::Struct { name, .. } = &object_type {
+                if let Some((field_index, field_type)) = ctx.lookup_field(name, field) {
+                    return Some(Expr::FieldAccess {
+                        object: Box::new(object),
+                        object_type: ctx.full_struct_type(name),
+                        field_index,
+                        field_type,
+                    });
+                }
+            }
+            ctx.errors.push(format!(
+                "cannot assign to '{field}' of {}",
+                codegen_type_suffix(&object_type)
+            ));
+            None
+        }
+        SimpleAssignmentTarget::ComputedMemberExpression(member) => {
+            let array = lower_expr(&member.object, ctx);
+            match ir_expr_type(&array) {
+                LltsType::Array(_) => {
+                    let index = lower_expr(&member.expression, ctx);
+                    Some(lower_index_access(array, index))
+                }
+                other => {
+                    ctx.errors.push(format!(
+                        "cannot assign to an element of {}",
+                        codegen_type_suffix(&other)
+                    ));
+                    None
+                }
+            }
+        }
+        _ => {
+            ctx.errors.push("unsupported assignment target".to_string());
+            None
+        }
+    }
+}
+
+/// Whether `expr` names storage that `Expr::Assign` can store through: a
+/// variable, an array element, or a field of a place or of a `Ptr`.
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Var { .. } | Expr::ArrayIndex { .. } => true,
+        Expr::FieldAccess { object, .. } => ir_expr_type(object) == LltsType::Ptr || is_place(object),
+        _ => false,
+    }
+}
+
+/// `target = value`, `target op= value`: stores to the target's place and
+/// yields the stored value, so assignments can be used as expressions.
+fn lower_assignment(assign: &AssignmentExpression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let Some(target) = assign.left.as_simple_assignment_target() else {
+        ctx.errors.push("a destructuring assignment cannot be used as a value".to_string());
+        return lower_expr(&assign.right, ctx);
+    };
+    let Some(place) = lower_place(target, ctx) else {
+        return lower_expr(&assign.right, ctx);
+    };
+    let ty = ir_expr_type(&place);
+    let mut current = None;
+    let (op, value) = if assign.operator == AssignmentOperator::Assign {
+        let value = lower_expr_expecting(&assign.right, &ty, ctx);
+        (None, value)
+    } else if is_logical_assign(assign.operator) {
+        // The place's current value decides whether to store; it is read
+        // through the same address, so `items[next()].f ??= v` calls `next` once.
+        let name = format!("__current_{}", assign.span.start);
+        let read = Expr::Var { name: name.clone(), ty: ty.clone() };
+        current = Some(name);
+        (None, logical_assign_value(read, assign.operator, &assign.right, ctx))
+    } else {
+        let value = lower_expr(&assign.right, ctx);
+        (Some(compound_op(assign.operator)), value)
+    };
+    let value = match &ty {
+        LltsType::Option(inner) if op.is_none() => into_option(value, inner),
+        _ => coerce_to_type(value, &ty),
+    };
+    if let Expr::Var { name, .. } = &place {
+        // A new Result value is not narrowed by earlier `r.ok` checks.
+        forget_result_narrowing(name, ctx);
+    }
+    Expr::Assign { target: Box::new(place), op, current, value: Box::new(value), ty }
+}
+
+/// `target++`, `--target`: yields the old value for postfix and the new
+/// one for prefix operators.
+fn lower_update(update: &UpdateExpression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let Some(place) = lower_place(&update.argument, ctx) else {
+        return Expr::FloatLit { value: 0.0, ty: LltsType::F64 };
+    };
+    let ty = ir_expr_type(&place);
+    if !TypeRegistry::is_numeric(&ty) {
+        ctx.errors.push(format!("cannot increment or decrement {}", codegen_type_suffix(&ty)));
+    }
+    let op = if update.operator == UpdateOperator::Increment {
+        BinOp::Add
+    } else {
+        BinOp::Sub
+    };
+    Expr::Update { target: Box::new(place), op, prefix: update.prefix, ty }
+}
+
+/// Try to lower an assignment or update expression as a statement: a
+/// Stmt::Assign to a variable, or a store through a place (see `lower_place`).
 /// Returns None for non-assignment expressions.
 pub(crate) fn try_lower_as_assign(expr: &Expression<'_>, ctx: &mut LowerCtx) -> Option<Stmt> {
-    match expr {
+    // `({ a, b } = o)` needs its parentheses to parse as an assignment.
+    match expr.without_parentheses() {
         Expression::AssignmentExpression(assign) => {
-            // Check if the target is a field access (e.g. obj.field = value)
-            if let AssignmentTarget::StaticMemberExpression(member) = &assign.left {
-                let obj_name = expr_to_name(&member.object);
-                let field_name = member.property.name.to_string();
-
-                if let Some(obj_type) = ctx.var_types.get(&obj_name).cloned() {
-                    if let LltsType::Struct { name: struct_name, .. } = &obj_type {
-                        if let Some((field_index, field_type)) = ctx.lookup_field(struct_name, &field_name) {
-                            let value = if assign.operator == AssignmentOperator::Assign {
-                                let v = lower_expr(&assign.right, ctx);
-                                coerce_to_type(v, &field_type)
-                            } else {
-                                let op = match assign.operator {
-                                    AssignmentOperator::Addition => BinOp::Add,
-                                    AssignmentOperator::Subtraction => BinOp::Sub,
-                                    AssignmentOperator::Multiplication => BinOp::Mul,
-                                    AssignmentOperator::Division => BinOp::Div,
-                                    AssignmentOperator::Remainder => BinOp::Rem,
-                                    AssignmentOperator::ShiftLeft => BinOp::Shl,
-                                    AssignmentOperator::ShiftRight => BinOp::Shr,
-                                    AssignmentOperator::BitwiseAnd => BinOp::BitAnd,
-                                    AssignmentOperator::BitwiseOR => BinOp::BitOr,
-                                    AssignmentOperator::BitwiseXOR => BinOp::BitXor,
-                                    _ => BinOp::Add,
-                                };
-                                let lhs = Expr::FieldAccess {
-                                    object: Box::new(lower_expr(&member.object, ctx)),
-                                    object_type: obj_type.clone(),
-                                    field_index,
-                                    field_type: field_type.clone(),
-                                };
-                                let rhs = lower_expr(&assign.right, ctx);
-                                let rhs = coerce_to_type(rhs, &field_type);
-                                Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty: field_type.clone() }
-                            };
-                            return Some(Stmt::FieldAssign {
-                                object_name: obj_name,
-                                object_type: obj_type,
-                                field_index,
-                                value,
-                            });
-                        }
-                    }
-                }
+            // `[a, b] = [b, a]`, `({ x, y } = point)`
+            if let Some(stmt) = lower_destructuring_assign(&assign.left, &assign.right, ctx) {
+                return Some(stmt);
+            }
+            // `this.pos.x = 0`, `grid[r][c] = v`: stored through a place
+            if !matches!(assign.left, AssignmentTarget::AssignmentTargetIdentifier(_)) {
+                return Some(Stmt::Expr(lower_assignment(assign, ctx)));
             }
 
-            let target = assignment_target_name(&assign.left);
+            let target = assignment_target_name(&assign.left, ctx);
             let target_ty = ctx.var_types.get(&target).cloned().unwrap_or(LltsType::F64);
             let value = if assign.operator == AssignmentOperator::Assign {
-                let v = lower_expr(&assign.right, ctx);
+                let v = lower_expr_expecting(&assign.right, &target_ty, ctx);
                 coerce_to_type(v, &target_ty)
+            } else if is_logical_assign(assign.operator) {
+                let current = Expr::Var { name: target.clone(), ty: target_ty.clone() };
+                logical_assign_value(current, assign.operator, &assign.right, ctx)
             } else {
-                let op = match assign.operator {
-                    AssignmentOperator::Addition => BinOp::Add,
-                    AssignmentOperator::Subtraction => BinOp::Sub,
-                    AssignmentOperator::Multiplication => BinOp::Mul,
-                    AssignmentOperator::Division => BinOp::Div,
-                    AssignmentOperator::Remainder => BinOp::Rem,
-                    AssignmentOperator::ShiftLeft => BinOp::Shl,
-                    AssignmentOperator::ShiftRight => BinOp::Shr,
-                    AssignmentOperator::BitwiseAnd => BinOp::BitAnd,
-                    AssignmentOperator::BitwiseOR => BinOp::BitOr,
-                    AssignmentOperator::BitwiseXOR => BinOp::BitXor,
-                    _ => BinOp::Add,
-                };
+                let op = compound_op(assign.operator);
                 let lhs = Expr::Var { name: target.clone(), ty: target_ty.clone() };
                 let rhs = lower_expr(&assign.right, ctx);
                 let rhs = coerce_to_type(rhs, &target_ty);
@@ -862,10 +1190,16 @@ pub(crate) fn try_lower_as_assign(expr: &Expression<'_>, ctx: &mut LowerCtx) ->
             } else {
                 value
             };
+            // A new Result value is not narrowed by earlier `r.ok` checks.
+            forget_result_narrowing(&target, ctx);
             Some(Stmt::Assign { target, value })
         }
         Expression::UpdateExpression(update) => {
-            let name = simple_target_name(&update.argument);
+            // `arr[i]++`, `this.count--`
+            if !matches!(update.argument, SimpleAssignmentTarget::AssignmentTargetIdentifier(_)) {
+                return Some(Stmt::Expr(lower_update(update, ctx)));
+            }
+            let name = simple_target_name(&update.argument, ctx);
             let ty = ctx.var_types.get(&name).cloned().unwrap_or(LltsType::F64);
             let var = Expr::Var { name: name.clone(), ty: ty.clone() };
             let one = match &ty {
@@ -0,0 +1,886 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{
+    Expr, FunctionDecl, Stmt,
+    expr::{BinOp, UnaryOp},
+    types::{LltsType, error_type},
+};
+
+use super::closures::ENV_PARAM;
+use super::context::{DynSlot, LowerCtx};
+use super::utils::{coerce_to_type, ir_expr_type};
+use super::visit::{expr_children, expr_children_mut, for_each_expr, for_each_expr_mut, for_each_stmt_mut};
+use super::{
+    AWAIT_MARKER, coerce_struct_fields, dyn_call, instantiate_type_refs, is_concrete_type, lower_expr_expecting,
+    lower_stmts, mangle_generic_name, parse_type_suffixes, poll_call,
+};
+
+// ---------------------------------------------------------------------------
+// Generators: `function*` lowered to a state machine
+// ---------------------------------------------------------------------------
+//
+// `function* f(params): Generator<T>` becomes a factory `f` returning a
+// `Generator$T` value: a `Dyn` with a single `next(): T | null` slot. Its data
+// cell is the state struct `__gen_f { __gen_state: i32, params..., locals... }`
+// and its vtable holds `__gen_f_next`, which resumes the body:
+//
+//     while (true) {
+//         if (__gen_state == 0) { ...; __gen_state = 1; return Some(x); }
+//         if (__gen_state == 1) { ...; __gen_state = 2; continue; }
+//         ...
+//         return None;
+//     }
+//
+// The body is lowered as usual, `yield e;` as a marker call, then split at
+// each yield. Statements holding a yield (blocks, `if`, loops, `try`) are
+// dissolved into states; the others run verbatim inside their state. Locals
+// declared at dissolved levels live in the state struct, so they survive
+// across `next()` calls. State -1 is done.
+//
+// `async` functions go through the same transform (see `async_fns`): their
+// `poll()` suspends at each `await` until the awaited promise is ready.
+
+/// Callee of the marker call `yield e;` lowers to before the state machine
+/// transform.
+const YIELD_MARKER: &str = "__yield";
+
+/// `var_types` key holding the element type of the generator being lowered.
+const YIELD_TYPE: &str = "__gen_yield_type__";
+
+/// State struct field holding the current state.
+pub(crate) const STATE_FIELD: &str = "__gen_state";
+
+/// Built-in generic types lowered to a `Dyn` with a single slot returning
+/// `T | null`, with the slot's name: generators, and the promises of async
+/// functions.
+const RESUMABLE_TYPES: [(&str, &str); 2] = [("Generator", "next"), ("Promise", "poll")];
+
+/// The name (`Generator`, `Promise`) and type argument of the resumable type
+/// named `name` (`Generator$i32`, the lowered form of `Generator<i32>`).
+/// Placeholders of type parameters come back as bare structs
+/// (`Generator$T` -> `T`).
+pub(crate) fn resumable_type_arg(name: &str, ctx: &LowerCtx) -> Option<(&'static str, LltsType)> {
+    let (kind, suffix) = RESUMABLE_TYPES
+        .iter()
+        .find_map(|(kind, _)| Some((*kind, name.strip_prefix(kind)?.strip_prefix('$')?)))?;
+    let [arg]: [LltsType; 1] = parse_type_suffixes(suffix, ctx)?.try_into().ok()?;
+    Some((kind, arg))
+}
+
+/// The `kind<arg>` type (`Generator<i32>`), registered as a dynamically
+/// dispatched type with its single slot (`next(): i32 | null`) on first use.
+pub(crate) fn resumable_type(kind: &str, arg: &LltsType, ctx: &mut LowerCtx) -> LltsType {
+    let name = mangle_generic_name(kind, std::slice::from_ref(arg));
+    let slot = RESUMABLE_TYPES.iter().find(|(k, _)| *k == kind).map_or("next", |(_, slot)| *slot);
+    ctx.dyn_types.entry(name.clone()).or_insert_with(|| {
+        vec![DynSlot {
+            name: slot.to_string(),
+            params: Some(Vec::new()),
+            ret: LltsType::Option(Box::new(slot_payload(arg))),
+        }]
+    });
+    LltsType::Dyn { name }
+}
+
+/// What the slot of a resumable type with argument `arg` carries: `arg`,
+/// with `void` (a `Promise<void>`) carried as `bool`.
+pub(crate) fn slot_payload(arg: &LltsType) -> LltsType {
+    match arg {
+        LltsType::Void => LltsType::Bool,
+        other => other.clone(),
+    }
+}
+
+/// Instantiate the resumable type named `name` if it is one (see
+/// [`resumable_type_arg`]) and its type argument is concrete. Returns
+/// whether `name` is a resumable type.
+pub(crate) fn instantiate_resumable_type(name: &str, ctx: &mut LowerCtx) -> bool {
+    let Some((kind, arg)) = resumable_type_arg(name, ctx) else { return false };
+    if is_concrete_type(&arg, ctx) {
+        instantiate_type_refs(&arg, ctx);
+        let arg = ctx.resolve_annotation(arg);
+        resumable_type(kind, &arg, ctx);
+    }
+    true
+}
+
+/// The element type of values of `ty`, if it is a generator type.
+pub(crate) fn generator_elem(ty: &LltsType, ctx: &LowerCtx) -> Option<LltsType> {
+    let LltsType::Dyn { name } = ty else { return None };
+    if !name.starts_with("Generator$") {
+        return None;
+    }
+    match ctx.lookup_dyn_slot(name, "next")?.1.ret {
+        LltsType::Option(elem) => Some(*elem),
+        _ => None,
+    }
+}
+
+/// Lower `yield e` to the marker call the state machine transform splits
+/// the body at.
+pub(crate) fn lower_yield(yield_expr: &YieldExpression<'_>, ctx: &mut LowerCtx) -> Expr {
+    let Some(elem) = ctx.var_types.get(YIELD_TYPE).cloned() else {
+        ctx.errors.push("`yield` outside a generator function".to_string());
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    };
+    let Some(argument) = &yield_expr.argument else {
+        ctx.errors.push("`yield` needs a value in LLTS; a generator only yields its element type".to_string());
+        return Expr::IntLit { value: 0, ty: LltsType::I32 };
+    };
+    let mut value = coerce_to_type(lower_expr_expecting(argument, &elem, ctx), &elem);
+    if let Expr::StructLit { struct_type, fields } = &mut value {
+        if matches!(struct_type, LltsType::Struct { name, .. } if name.is_empty()) {
+            *struct_type = elem.clone();
+        }
+        coerce_struct_fields(fields, struct_type);
+    }
+    Expr::Call {
+        callee: YIELD_MARKER.to_string(),
+        args: vec![value],
+        ret_type: LltsType::Void,
+    }
+}
+
+/// Lower generator function `name` (see the module comment): returns the
+/// factory and queues its `next` function. `ret_type` must be a generator type,
+/// and `prologue` (see `bind_params`) runs before the body.
+pub(crate) fn lower_generator(
+    func: &Function<'_>,
+    name: String,
+    params: Vec<(String, LltsType)>,
+    prologue: Vec<Stmt>,
+    ret_type: LltsType,
+    ctx: &mut LowerCtx,
+) -> Option<FunctionDecl> {
+    let Some(elem) = generator_elem(&ret_type, ctx) else {
+        ctx.errors.push(format!("generator function '{name}' must declare its return type as Generator<T>"));
+        return None;
+    };
+    // `return;` ends the generator; yields carry the values.
+    ctx.var_types.insert("__fn_return_type__".to_string(), LltsType::Void);
+    ctx.var_types.insert(YIELD_TYPE.to_string(), elem.clone());
+    let mut body = prologue;
+    if let Some(b) = &func.body {
+        body.extend(lower_stmts(&b.statements, ctx));
+    }
+    Some(build_state_machine(MachineKind::Generator, name, params, ret_type, elem, body, ctx))
+}
+
+/// What a state machine resumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MachineKind {
+    /// A generator: `next()` returns each yielded value, then null.
+    Generator,
+    /// An async function: `poll()` returns null until the result is ready.
+    Async {
+        /// Whether it is a `Promise<void>`, finished with `true`.
+        void: bool,
+    },
+}
+
+/// Build the state machine of resumable function `name` from its lowered
+/// `body`: returns the factory and queues the `next`/`poll` function.
+/// `ret_type` is the `Generator<T>`/`Promise<T>` type, whose slot returns
+/// `payload | null`.
+pub(crate) fn build_state_machine(
+    kind: MachineKind,
+    name: String,
+    params: Vec<(String, LltsType)>,
+    ret_type: LltsType,
+    payload: LltsType,
+    body: Vec<Stmt>,
+    ctx: &mut LowerCtx,
+) -> FunctionDecl {
+    let mut fields = vec![(STATE_FIELD.to_string(), LltsType::I32)];
+    fields.extend(params.iter().cloned());
+    let mut machine = StateMachine::new(kind, &name, payload.clone(), fields);
+    machine.block(body);
+    let (states, fields, errors) = machine.finish();
+    ctx.errors.extend(errors);
+
+    let (state_struct, slot) = match kind {
+        MachineKind::Generator => (format!("__gen_{name}"), "next"),
+        MachineKind::Async { .. } => (format!("__async_{name}"), "poll"),
+    };
+    let state_type = LltsType::Struct { name: state_struct.clone(), fields: fields.clone() };
+    let opt_type = LltsType::Option(Box::new(payload.clone()));
+
+    // next()/poll(): bind every field of the data cell, then dispatch on the state.
+    let mut next_body = bind_state_fields(&state_type);
+    let mut dispatch: Vec<Stmt> = states
+        .into_iter()
+        .enumerate()
+        .map(|(k, block)| Stmt::If {
+            condition: Expr::Binary {
+                op: BinOp::Eq,
+                lhs: Box::new(state_var()),
+                rhs: Box::new(state_lit(k as i64)),
+                ty: LltsType::I32,
+            },
+            // Locals of verbatim statements stay scoped to their state.
+            then_body: vec![Stmt::Block(block)],
+            else_body: None,
+        })
+        .collect();
+    dispatch.push(Stmt::Return(Some(Expr::OptionNone { inner_type: payload })));
+    next_body.push(Stmt::While { condition: Expr::BoolLit(true), body: dispatch });
+    let next_name = format!("{state_struct}_{slot}");
+    ctx.pending_functions.push(FunctionDecl {
+        name: next_name.clone(),
+        params: vec![(ENV_PARAM.to_string(), LltsType::Ptr)],
+        ret_type: opt_type,
+        body: next_body,
+    });
+
+    // The factory starts in state 0 with the arguments stored; locals are
+    // written before they are read.
+    let mut initial = vec![state_lit(0)];
+    initial.extend(params.iter().map(|(p, ty)| Expr::Var { name: p.clone(), ty: ty.clone() }));
+    let LltsType::Dyn { name: dyn_name } = &ret_type else { unreachable!("resumable types are Dyn") };
+    let resumable = Expr::DynCoerce {
+        value: Box::new(Expr::StructLit { struct_type: state_type, fields: initial }),
+        vtable: format!("__vtable_{state_struct}_as_{dyn_name}"),
+        slots: vec![next_name],
+        ty: ret_type.clone(),
+    };
+    FunctionDecl {
+        name,
+        params,
+        ret_type,
+        body: vec![Stmt::Return(Some(resumable))],
+    }
+}
+
+/// Lower `for (const x of gen)` over a generator: call `next()` until it
+/// returns null. `body` already holds any destructuring of `elem_name`.
+pub(crate) fn generator_for_of(
+    generator: Expr,
+    elem_name: String,
+    elem_type: LltsType,
+    mut body: Vec<Stmt>,
+    id: u32,
+    ctx: &mut LowerCtx,
+) -> Stmt {
+    let gen_type = ir_expr_type(&generator);
+    let LltsType::Dyn { name: dyn_name } = &gen_type else { unreachable!("generator_for_of on {gen_type:?}") };
+    let (index, slot) = ctx.lookup_dyn_slot(dyn_name, "next").expect("generator types have a next slot");
+    let gen_name = format!("__forof_gen_{id}");
+    let next_name = format!("__forof_next_{id}");
+    let opt_type = LltsType::Option(Box::new(elem_type.clone()));
+    let next_var = Expr::Var { name: next_name.clone(), ty: opt_type.clone() };
+    let gen_var = Expr::Var { name: gen_name.clone(), ty: gen_type.clone() };
+
+    let mut loop_body = vec![
+        Stmt::Assign { target: next_name.clone(), value: dyn_call(gen_var, index, &slot, Vec::new(), ctx) },
+        Stmt::If {
+            condition: Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(Expr::OptionIsSome {
+                    value: Box::new(next_var.clone()),
+                    inner_type: elem_type.clone(),
+                }),
+                ty: LltsType::Bool,
+            },
+            then_body: vec![Stmt::Break],
+            else_body: None,
+        },
+        Stmt::Assign {
+            target: elem_name.clone(),
+            value: Expr::OptionUnwrap { value: Box::new(next_var), inner_type: elem_type.clone() },
+        },
+    ];
+    loop_body.append(&mut body);
+    Stmt::Block(vec![
+        Stmt::VarDecl { name: gen_name, ty: gen_type, init: Some(generator) },
+        Stmt::VarDecl { name: next_name, ty: opt_type, init: None },
+        Stmt::VarDecl { name: elem_name, ty: elem_type, init: None },
+        Stmt::While { condition: Expr::BoolLit(true), body: loop_body },
+    ])
+}
+
+/// Bind each field of state struct `state_type`, the data cell `__env`
+/// points to, as a variable writing through to the cell.
+pub(crate) fn bind_state_fields(state_type: &LltsType) -> Vec<Stmt> {
+    let LltsType::Struct { fields, .. } = state_type else { return Vec::new() };
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, (field, ty))| Stmt::CaptureBind {
+            name: field.clone(),
+            ty: ty.clone(),
+            env_type: state_type.clone(),
+            field_index: i as u32,
+            by_ref: false,
+        })
+        .collect()
+}
+
+fn state_var() -> Expr {
+    Expr::Var { name: STATE_FIELD.to_string(), ty: LltsType::I32 }
+}
+
+fn state_lit(state: i64) -> Expr {
+    Expr::IntLit { value: state, ty: LltsType::I32 }
+}
+
+/// Whether `expr` is a `yield` or `await` marker call.
+fn is_suspend(expr: &Expr) -> bool {
+    matches!(expr, Expr::Call { callee, .. } if callee == YIELD_MARKER || callee == AWAIT_MARKER)
+}
+
+/// Whether `stmt` holds a `yield` or `await`, at any depth.
+fn contains_suspend(stmt: &Stmt) -> bool {
+    let mut found = false;
+    for_each_expr(std::slice::from_ref(stmt), &mut |e| found |= is_suspend(e));
+    found
+}
+
+/// Whether `stmts` end by returning or jumping to another state.
+fn ends_in_exit(stmts: &[Stmt]) -> bool {
+    match stmts.last() {
+        Some(Stmt::Return(_) | Stmt::Continue) => true,
+        Some(Stmt::Block(body)) => ends_in_exit(body),
+        Some(Stmt::If { then_body, else_body: Some(else_body), .. }) => {
+            ends_in_exit(then_body) && ends_in_exit(else_body)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` holds a `yield` or `await`.
+fn expr_contains_suspend(expr: &Expr) -> bool {
+    is_suspend(expr) || expr_children(expr).into_iter().any(expr_contains_suspend)
+}
+
+/// Replace the first `await` that `expr` evaluates unconditionally (not in
+/// the right of `&&`/`||`/`??`, a branch of `?:` or after a `?.`) with a read
+/// of variable `temp`, returning the `await` marker. Inner awaits come first,
+/// as they run first.
+fn take_await(expr: &mut Expr, temp: &str) -> Option<Expr> {
+    let children = match expr {
+        Expr::Logical { lhs, .. } => vec![lhs.as_mut()],
+        Expr::Ternary { condition, .. } => vec![condition.as_mut()],
+        Expr::OptionAndThen { value, .. } | Expr::Coalesce { value, .. } => vec![value.as_mut()],
+        _ => expr_children_mut(expr),
+    };
+    for child in children {
+        if let Some(marker) = take_await(child, temp) {
+            return Some(marker);
+        }
+    }
+    if !matches!(expr, Expr::Call { callee, .. } if callee == AWAIT_MARKER) {
+        return None;
+    }
+    let ty = ir_expr_type(expr);
+    Some(std::mem::replace(expr, Expr::Var { name: temp.to_string(), ty }))
+}
+
+/// Rename variable `from` to `to` in `stmts`: declarations, reads, writes
+/// and closure captures.
+fn rename_var(stmts: &mut [Stmt], from: &str, to: &str) {
+    for_each_stmt_mut(stmts, &mut |stmt| match stmt {
+        Stmt::VarDecl { name, .. } | Stmt::Assign { target: name, .. } if *name == from =>
+        {
+            *name = to.to_string();
+        }
+        Stmt::ForOf { elem_name, .. } if *elem_name == from => *elem_name = to.to_string(),
+        _ => {}
+    });
+    for_each_expr_mut(stmts, &mut |expr| match expr {
+        Expr::Var { name, .. } if *name == from => *name = to.to_string(),
+        Expr::Closure { captures, .. } => {
+            for capture in captures.iter_mut().filter(|c| c.as_str() == from) {
+                *capture = to.to_string();
+            }
+        }
+        _ => {}
+    });
+}
+
+/// Splits a lowered generator or async function body into the blocks of
+/// its states.
+struct StateMachine {
+    kind: MachineKind,
+    /// Function name, for error messages.
+    name: String,
+    /// What `next()`/`poll()` returns values of (wrapped in an option).
+    payload: LltsType,
+    /// Statements of each state; every block ends by returning or by
+    /// jumping (`__gen_state = k; continue;`).
+    states: Vec<Vec<Stmt>>,
+    /// State statements are currently appended to.
+    current: usize,
+    /// (break state, continue state) of each enclosing dissolved loop.
+    loops: Vec<(usize, usize)>,
+    /// (error field, catch state) of each enclosing dissolved `try`.
+    handlers: Vec<(String, usize)>,
+    /// The innermost dissolved `try` each state was created in, if any.
+    state_handlers: Vec<Option<(String, usize)>>,
+    /// State struct fields: the state, the parameters, then hoisted locals.
+    fields: Vec<(String, LltsType)>,
+    /// Counter for hidden `for...of` and `await` locals.
+    temps: usize,
+    errors: Vec<String>,
+}
+
+impl StateMachine {
+    fn new(kind: MachineKind, name: &str, payload: LltsType, fields: Vec<(String, LltsType)>) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            payload,
+            states: vec![Vec::new()],
+            current: 0,
+            loops: Vec::new(),
+            handlers: Vec::new(),
+            state_handlers: vec![None],
+            fields,
+            temps: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// The state blocks, state struct fields and errors found.
+    fn finish(mut self) -> (Vec<Vec<Stmt>>, Vec<(String, LltsType)>, Vec<String>) {
+        // Falling off the end of a state (the end of the body) finishes the
+        // generator or async function.
+        for k in 0..self.states.len() {
+            if !ends_in_exit(&self.states[k]) {
+                let done = self.done();
+                self.states[k].extend(done);
+            }
+            // A throw in a state of a dissolved `try` stores the error and
+            // moves to its catch state.
+            if let Some((field, catch_state)) = self.state_handlers[k].clone() {
+                let thrown = format!("{field}$thrown");
+                let mut catch_body = vec![Stmt::Assign {
+                    target: field,
+                    value: Expr::Var { name: thrown.clone(), ty: error_type() },
+                }];
+                catch_body.extend(Self::jump(catch_state));
+                self.states[k] = vec![Stmt::TryCatch {
+                    try_body: std::mem::take(&mut self.states[k]),
+                    catch_param: Some(thrown),
+                    catch_body: Some(catch_body),
+                    finally_body: None,
+                }];
+            }
+        }
+        (self.states, self.fields, self.errors)
+    }
+
+    fn new_state(&mut self) -> usize {
+        self.states.push(Vec::new());
+        self.state_handlers.push(self.handlers.last().cloned());
+        self.states.len() - 1
+    }
+
+    /// Append `stmt` to the current state. Code after a jump or return is
+    /// unreachable and goes to a fresh state.
+    fn emit(&mut self, stmt: Stmt) {
+        if ends_in_exit(&self.states[self.current]) {
+            self.current = self.new_state();
+        }
+        self.states[self.current].push(stmt);
+    }
+
+    /// Statements moving to state `target`.
+    fn jump(target: usize) -> Vec<Stmt> {
+        vec![
+            Stmt::Assign { target: STATE_FIELD.to_string(), value: state_lit(target as i64) },
+            Stmt::Continue,
+        ]
+    }
+
+    /// Statements finishing the machine without a value: a generator is
+    /// exhausted, a `Promise<void>` resolved.
+    fn done(&self) -> Vec<Stmt> {
+        self.finish_with(None)
+    }
+
+    /// Statements finishing the machine; an async function resolves to
+    /// `value`.
+    fn finish_with(&self, value: Option<Expr>) -> Vec<Stmt> {
+        let value = match (self.kind, value) {
+            (MachineKind::Async { void: true }, _) => Some(Expr::BoolLit(true)),
+            (MachineKind::Async { .. }, value) => value,
+            (MachineKind::Generator, _) => None,
+        };
+        let result = match value {
+            Some(value) => Expr::OptionSome { value: Box::new(value), inner_type: self.payload.clone() },
+            None => Expr::OptionNone { inner_type: self.payload.clone() },
+        };
+        vec![
+            Stmt::Assign { target: STATE_FIELD.to_string(), value: state_lit(-1) },
+            Stmt::Return(Some(result)),
+        ]
+    }
+
+    /// The suspending keyword and the kind of function, for error messages.
+    fn keyword(&self) -> (&'static str, &'static str) {
+        match self.kind {
+            MachineKind::Generator => ("yield", "generator"),
+            MachineKind::Async { .. } => ("await", "async function"),
+        }
+    }
+
+    /// End the current state by moving to `target`.
+    fn goto(&mut self, target: usize) {
+        for stmt in Self::jump(target) {
+            self.emit(stmt);
+        }
+    }
+
+    fn block(&mut self, mut stmts: Vec<Stmt>) {
+        for i in 0..stmts.len() {
+            let stmt = std::mem::replace(&mut stmts[i], Stmt::Break);
+            if let Stmt::VarDecl { name, ty, init } = stmt {
+                let field = self.hoist(&name, &ty);
+                if field != name {
+                    rename_var(&mut stmts[i + 1..], &name, &field);
+                }
+                match init {
+                    Some(value) if self.kind == MachineKind::Generator && expr_contains_suspend(&value) => {
+                        self.yield_in_expression();
+                    }
+                    Some(value) => self.stmt(Stmt::Assign { target: field, value }),
+                    None => {}
+                }
+                continue;
+            }
+            self.stmt(stmt);
+        }
+    }
+
+    /// Store local `name` in the state struct, returning its field name. A
+    /// redeclaration with another type (a shadowing binding, or a
+    /// narrowed `x !== null` check) gets a field of its own.
+    fn hoist(&mut self, name: &str, ty: &LltsType) -> String {
+        match self.fields.iter().find(|(field, _)| field == name) {
+            None => {
+                self.fields.push((name.to_string(), ty.clone()));
+                name.to_string()
+            }
+            Some((_, existing)) if existing == ty => name.to_string(),
+            Some(_) => {
+                let mut n = 1;
+                while self.fields.iter().any(|(field, _)| *field == format!("{name}${n}")) {
+                    n += 1;
+                }
+                let field = format!("{name}${n}");
+                self.fields.push((field.clone(), ty.clone()));
+                field
+            }
+        }
+    }
+
+    fn stmt(&mut self, mut stmt: Stmt) {
+        if !contains_suspend(&stmt) {
+            self.rewrite_exits(&mut stmt, 0, 0);
+            self.emit(stmt);
+            return;
+        }
+        if matches!(self.kind, MachineKind::Async { .. }) && self.hoist_await(&mut stmt) {
+            self.stmt(stmt);
+            return;
+        }
+        match stmt {
+            Stmt::Expr(Expr::Call { callee, mut args, .. }) if callee == YIELD_MARKER && !expr_contains_suspend(&args[0]) => {
+                let value = args.remove(0);
+                let resume = self.new_state();
+                self.emit(Stmt::Assign { target: STATE_FIELD.to_string(), value: state_lit(resume as i64) });
+                self.emit(Stmt::Return(Some(Expr::OptionSome { value: Box::new(value), inner_type: self.payload.clone() })));
+                self.current = resume;
+            }
+            Stmt::Block(body) => self.block(body),
+            Stmt::If { condition, then_body, else_body } if !expr_contains_suspend(&condition) => {
+                let then_state = self.new_state();
+                let else_state = else_body.as_ref().map(|_| self.new_state());
+                let after = self.new_state();
+                self.emit(Stmt::If {
+                    condition,
+                    then_body: Self::jump(then_state),
+                    else_body: Some(Self::jump(else_state.unwrap_or(after))),
+                });
+                self.current = then_state;
+                self.block(then_body);
+                self.goto(after);
+                if let (Some(else_state), Some(else_body)) = (else_state, else_body) {
+                    self.current = else_state;
+                    self.block(else_body);
+                    self.goto(after);
+                }
+                self.current = after;
+            }
+            Stmt::While { condition, body } if !expr_contains_suspend(&condition) => {
+                self.dissolve_loop(Some(condition), body, None);
+            }
+            Stmt::For { init, condition, update, body }
+                if !condition.as_ref().is_some_and(expr_contains_suspend)
+                    && !update.as_deref().is_some_and(contains_suspend) =>
+            {
+                // The init runs once, as a dissolved-level declaration.
+                if let Some(init) = init {
+                    self.block(vec![*init]);
+                }
+                self.dissolve_loop(condition, body, update.map(|u| *u));
+            }
+            Stmt::ForOf { elem_name, elem_type, iterable, mut body } if !expr_contains_suspend(&iterable) => {
+                // Walk the array by index, with the array and index in the state struct.
+                let n = self.temps;
+                self.temps += 1;
+                let iter_name = format!("__gen_iter_{n}");
+                let idx_name = format!("__gen_idx_{n}");
+                let iter_type = ir_expr_type(&iterable);
+                let iter = Expr::Var { name: iter_name.clone(), ty: iter_type.clone() };
+                let idx = Expr::Var { name: idx_name.clone(), ty: LltsType::I64 };
+                body.insert(0, Stmt::VarDecl {
+                    name: elem_name,
+                    ty: elem_type.clone(),
+                    init: Some(Expr::ArrayIndex {
+                        array: Box::new(iter.clone()),
+                        index: Box::new(idx.clone()),
+                        elem_type,
+                    }),
+                });
+                self.block(vec![
+                    Stmt::VarDecl { name: iter_name, ty: iter_type.clone(), init: Some(iterable) },
+                    Stmt::For {
+                        init: Some(Box::new(Stmt::VarDecl {
+                            name: idx_name.clone(),
+                            ty: LltsType::I64,
+                            init: Some(Expr::IntLit { value: 0, ty: LltsType::I64 }),
+                        })),
+                        condition: Some(Expr::Binary {
+                            op: BinOp::Lt,
+                            lhs: Box::new(idx.clone()),
+                            rhs: Box::new(Expr::FieldAccess {
+                                object: Box::new(iter),
+                                object_type: iter_type,
+                                field_index: 1,
+                                field_type: LltsType::I64,
+                            }),
+                            ty: LltsType::I64,
+                        }),
+                        update: Some(Box::new(Stmt::Assign {
+                            target: idx_name,
+                            value: Expr::Binary {
+                                op: BinOp::Add,
+                                lhs: Box::new(idx),
+                                rhs: Box::new(Expr::IntLit { value: 1, ty: LltsType::I64 }),
+                                ty: LltsType::I64,
+                            },
+                        })),
+                        body,
+                    },
+                ]);
+            }
+            Stmt::TryCatch { try_body, catch_param: Some(caught), catch_body: Some(catch_body), finally_body: None } => {
+                self.dissolve_try(try_body, caught, catch_body);
+            }
+            Stmt::Switch { .. } | Stmt::TryCatch { .. } => {
+                let (keyword, what) = self.keyword();
+                self.errors.push(format!(
+                    "`{keyword}` inside switch or a try with `finally` is not supported (in {what} '{}')",
+                    self.name
+                ));
+            }
+            _ if self.kind == MachineKind::Generator => self.yield_in_expression(),
+            Stmt::While { .. } | Stmt::For { .. } => self.errors.push(format!(
+                "`await` in a loop condition or update is not supported (in async function '{}'); \
+                 await into a variable inside the loop instead",
+                self.name
+            )),
+            // Left in place by `take_await`: it would run conditionally.
+            _ => self.errors.push(format!(
+                "`await` in the right operand of `&&`/`||`/`??`, a branch of `?:` or after `?.` is not supported \
+                 (in async function '{}'); await into a variable first",
+                self.name
+            )),
+        }
+    }
+
+    fn yield_in_expression(&mut self) {
+        self.errors.push(format!(
+            "`yield` must be a statement of its own (`yield value;`) in generator '{}'",
+            self.name
+        ));
+    }
+
+    /// Suspend at the first `await` the own expression of `stmt` evaluates
+    /// (see [`take_await`]; awaits in nested statements are left for their
+    /// statement), leaving `stmt` to read the awaited value from a field.
+    /// Returns false if there is none.
+    fn hoist_await(&mut self, stmt: &mut Stmt) -> bool {
+        let expr = match stmt {
+            Stmt::Expr(e)
+            | Stmt::Assign { value: e, .. }
+            | Stmt::Return(Some(e))
+            | Stmt::Throw(e)
+            | Stmt::If { condition: e, .. }
+            | Stmt::Switch { discriminant: e, .. }
+            | Stmt::ForOf { iterable: e, .. } => e,
+            _ => return false,
+        };
+        let n = self.temps;
+        let value_name = format!("__await_value_{n}");
+        let Some(Expr::Call { mut args, ret_type: value_type, .. }) = take_await(expr, &value_name) else {
+            return false;
+        };
+        self.temps += 1;
+        let promise = args.remove(0);
+        if value_type == LltsType::Void {
+            // `await p;` on a Promise<void> leaves nothing to read.
+            if matches!(stmt, Stmt::Expr(Expr::Var { name, .. }) if *name == value_name) {
+                *stmt = Stmt::Block(Vec::new());
+            } else {
+                self.errors.push(format!(
+                    "the result of awaiting a Promise<void> cannot be used (in async function '{}')",
+                    self.name
+                ));
+            }
+            self.await_promise(promise, &value_type, None, n);
+        } else {
+            let field = self.hoist(&value_name, &value_type);
+            self.await_promise(promise, &value_type, Some(field), n);
+        }
+        true
+    }
+
+    /// Suspend until `promise` (a `Promise<value_type>`) is ready, then store
+    /// its value in `target`: the promise is polled on entry to a state of
+    /// its own, and again on each `poll()` while it is pending.
+    fn await_promise(&mut self, promise: Expr, value_type: &LltsType, target: Option<String>, n: usize) {
+        let payload = slot_payload(value_type);
+        let opt_type = LltsType::Option(Box::new(payload.clone()));
+        let promise_type = ir_expr_type(&promise);
+        let promise_field = self.hoist(&format!("__await_{n}"), &promise_type);
+        let result_field = self.hoist(&format!("__await_result_{n}"), &opt_type);
+        let result = Expr::Var { name: result_field.clone(), ty: opt_type };
+        self.emit(Stmt::Assign { target: promise_field.clone(), value: promise });
+        let resume = self.new_state();
+        self.goto(resume);
+        self.current = resume;
+        self.emit(Stmt::Assign {
+            target: result_field,
+            value: poll_call(Expr::Var { name: promise_field, ty: promise_type }, &payload),
+        });
+        self.emit(Stmt::If {
+            condition: Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(Expr::OptionIsSome { value: Box::new(result.clone()), inner_type: payload.clone() }),
+                ty: LltsType::Bool,
+            },
+            then_body: vec![Stmt::Return(Some(Expr::OptionNone { inner_type: self.payload.clone() }))],
+            else_body: None,
+        });
+        if let Some(target) = target {
+            self.emit(Stmt::Assign {
+                target,
+                value: Expr::OptionUnwrap { value: Box::new(result), inner_type: payload },
+            });
+        }
+    }
+
+    /// Dissolve a loop: a head state testing `condition`, the body, then the
+    /// `update` (in a state of its own, the `continue` target, when present).
+    fn dissolve_loop(&mut self, condition: Option<Expr>, body: Vec<Stmt>, update: Option<Stmt>) {
+        let head = self.new_state();
+        let cont = if update.is_some() { self.new_state() } else { head };
+        let after = self.new_state();
+        self.goto(head);
+        self.current = head;
+        if let Some(condition) = condition {
+            self.emit(Stmt::If { condition, then_body: Vec::new(), else_body: Some(Self::jump(after)) });
+        }
+        self.loops.push((after, cont));
+        self.block(body);
+        self.loops.pop();
+        self.goto(cont);
+        if let Some(update) = update {
+            self.current = cont;
+            self.emit(update);
+            self.goto(head);
+        }
+        self.current = after;
+    }
+
+    /// Dissolve a `try`/`catch` holding a suspension. The states of the try
+    /// body each run inside a `try` of their own (see `finish`), whose catch
+    /// stores the error in the field of `caught`, the error the catch body
+    /// reads, and moves to the catch state.
+    fn dissolve_try(&mut self, try_body: Vec<Stmt>, caught: String, mut catch_body: Vec<Stmt>) {
+        let field = self.hoist(&caught, &error_type());
+        if field != caught {
+            rename_var(&mut catch_body, &caught, &field);
+        }
+        let catch_state = self.new_state();
+        let after = self.new_state();
+        self.handlers.push((field, catch_state));
+        let try_state = self.new_state();
+        self.goto(try_state);
+        self.current = try_state;
+        self.block(try_body);
+        self.goto(after);
+        self.handlers.pop();
+        self.current = catch_state;
+        self.block(catch_body);
+        self.goto(after);
+        self.current = after;
+    }
+
+    /// Rewrite the exits of a statement that runs verbatim: `return` finishes
+    /// the generator or resolves the async function, and `break`/`continue`
+    /// aimed at a dissolved loop (not caught by a loop, or for `break` a
+    /// switch, inside the statement) jump to its states.
+    fn rewrite_exits(&mut self, stmt: &mut Stmt, loop_depth: usize, switch_depth: usize) {
+        match stmt {
+            Stmt::Return(value) => {
+                if value.is_some() && self.kind == MachineKind::Generator {
+                    self.errors.push(format!(
+                        "generator '{}' cannot return a value; yield it instead",
+                        self.name
+                    ));
+                }
+                *stmt = Stmt::Block(self.finish_with(value.take()));
+            }
+            Stmt::Break if loop_depth == 0 && switch_depth == 0 => {
+                if let Some(&(after, _)) = self.loops.last() {
+                    *stmt = Stmt::Block(Self::jump(after));
+                }
+            }
+            Stmt::Continue if loop_depth == 0 => {
+                if let Some(&(_, cont)) = self.loops.last() {
+                    *stmt = Stmt::Block(Self::jump(cont));
+                }
+            }
+            Stmt::If { then_body, else_body, .. } => {
+                for s in then_body.iter_mut().chain(else_body.iter_mut().flatten()) {
+                    self.rewrite_exits(s, loop_depth, switch_depth);
+                }
+            }
+            Stmt::Block(body) => {
+                for s in body {
+                    self.rewrite_exits(s, loop_depth, switch_depth);
+                }
+            }
+            Stmt::TryCatch { try_body, catch_body, finally_body, .. } => {
+                let rest = catch_body.iter_mut().chain(finally_body.iter_mut()).flatten();
+                for s in try_body.iter_mut().chain(rest) {
+                    self.rewrite_exits(s, loop_depth, switch_depth);
+                }
+            }
+            Stmt::While { body, .. } | Stmt::For { body, .. } | Stmt::ForOf { body, .. } => {
+                for s in body {
+                    self.rewrite_exits(s, loop_depth + 1, switch_depth);
+                }
+            }
+            Stmt::Switch { cases, .. } => {
+                for s in cases.iter_mut().flat_map(|(_, body)| body.iter_mut()) {
+                    self.rewrite_exits(s, loop_depth, switch_depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
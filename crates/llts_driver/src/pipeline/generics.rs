@@ -3,13 +3,18 @@ use std::collections::{HashMap, HashSet};
 use oxc_ast::ast::*;
 
 use llts_codegen::{
-    FunctionDecl,
+    Expr, FunctionDecl, StructDecl,
+    expr::UnaryOp,
     types::LltsType,
 };
 
 use super::context::LowerCtx;
-use super::utils::{binding_name, ts_type_name_string};
-use super::{lower_stmts, lower_ts_type_with_enums};
+use super::utils::ir_expr_type;
+use super::{
+    BoundParams, bind_params, instantiate_resumable_type, lower_argument, lower_async_function, lower_class_methods_as,
+    lower_generator, lower_stmts, lower_ts_type_with_enums, record_param_shape, resumable_type, resumable_type_arg,
+    shape_of,
+};
 
 /// Lower a generic function with concrete type substitutions, producing a specialized FunctionDecl.
 pub(crate) fn lower_generic_function(
@@ -18,39 +23,35 @@ pub(crate) fn lower_generic_function(
     mangled_name: &str,
     generics: &HashMap<String, LltsType>,
 ) -> Option<FunctionDecl> {
-    let enum_names = ctx.enum_names();
     let saved_vars = ctx.var_types.clone();
-    let params: Vec<(String, LltsType)> = func
-        .params
-        .items
-        .iter()
-        .map(|p| {
-            let pname = binding_name(&p.pattern);
-            let pty = p
-                .type_annotation
-                .as_ref()
-                .map(|ann| lower_ts_type_with_generics(&ann.type_annotation, &enum_names, generics))
-                .unwrap_or(LltsType::F64);
-            let pty = match &pty {
-                LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(name),
-                other => other.clone(),
-            };
-            ctx.var_types.insert(pname.clone(), pty.clone());
-            (pname, pty)
-        })
-        .collect();
+    // Local annotations naming a type parameter resolve to its concrete type.
+    let saved_type_params = std::mem::replace(&mut ctx.type_params_in_scope, generics.clone());
+    if shape_of(&func.params).is_some() {
+        ctx.errors.push(format!("generic function '{mangled_name}' cannot have optional, default or rest parameters"));
+    }
+    let BoundParams { params, prologue, .. } = bind_params(&func.params, &[], ctx);
     let ret_type = func
         .return_type
         .as_ref()
-        .map(|r| lower_ts_type_with_generics(&r.type_annotation, &enum_names, generics))
+        .map(|r| lower_type_annotation(&r.type_annotation, ctx))
         .unwrap_or(LltsType::Void);
+    if func.generator || func.r#async {
+        let decl = if func.generator {
+            lower_generator(func, mangled_name.to_string(), params, prologue, ret_type, ctx)
+        } else {
+            lower_async_function(func, mangled_name.to_string(), params, prologue, ret_type, ctx)
+        };
+        ctx.var_types = saved_vars;
+        ctx.type_params_in_scope = saved_type_params;
+        return decl;
+    }
     ctx.var_types.insert("__fn_return_type__".to_string(), ret_type.clone());
-    let body = func
-        .body
-        .as_ref()
-        .map(|b| lower_stmts(&b.statements, ctx))
-        .unwrap_or_default();
+    let mut body = prologue;
+    if let Some(b) = &func.body {
+        body.extend(lower_stmts(&b.statements, ctx));
+    }
     ctx.var_types = saved_vars;
+    ctx.type_params_in_scope = saved_type_params;
     Some(FunctionDecl {
         name: mangled_name.to_string(),
         params,
@@ -59,83 +60,610 @@ pub(crate) fn lower_generic_function(
     })
 }
 
-/// Lower a TS type annotation with generic type parameter substitution.
-pub(crate) fn lower_ts_type_with_generics(
-    ts_type: &TSType<'_>,
-    enum_names: &HashSet<String>,
-    generics: &HashMap<String, LltsType>,
-) -> LltsType {
-    match ts_type {
-        TSType::TSTypeReference(ref_type) => {
-            let name = ts_type_name_string(&ref_type.type_name);
-            if let Some(concrete) = generics.get(&name) {
+/// Record the signature of a generic function (second pass) with its type
+/// parameters as placeholders, so call sites know the specialized types
+/// before the specialization is lowered.
+pub(crate) fn register_generic_signature(func: &Function<'_>, ctx: &mut LowerCtx, enum_names: &HashSet<String>) {
+    let Some(id) = &func.id else { return };
+    let params: Vec<LltsType> = func
+        .params
+        .items
+        .iter()
+        .map(|p| {
+            p.type_annotation
+                .as_ref()
+                .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, enum_names))
+                .unwrap_or(LltsType::F64)
+        })
+        .collect();
+    let ret = func
+        .return_type
+        .as_ref()
+        .map(|r| lower_ts_type_with_enums(&r.type_annotation, enum_names))
+        .unwrap_or(LltsType::Void);
+    ctx.generic_fn_sigs.insert(id.name.to_string(), (params, ret));
+}
+
+/// Replace type parameter placeholders in `ty` with their concrete types.
+/// Generic type instances are re-instantiated with the substituted
+/// arguments (`Pair$A$string` -> `Pair$i32$string`); remaining bare struct
+/// names are resolved (see [`LowerCtx::resolve_named_type`]).
+pub(crate) fn substitute_type_params(ty: &LltsType, generics: &HashMap<String, LltsType>, ctx: &mut LowerCtx) -> LltsType {
+    match ty {
+        LltsType::Struct { name, fields } if fields.is_empty() => {
+            if let Some(concrete) = generics.get(name) {
                 return concrete.clone();
             }
-            match name.as_str() {
-                "Array" => {
-                    let elem = ref_type
-                        .type_arguments
-                        .as_ref()
-                        .and_then(|args| args.params.first())
-                        .map(|t| lower_ts_type_with_generics(t, enum_names, generics))
-                        .unwrap_or(LltsType::F64);
-                    return LltsType::Array(Box::new(elem));
+            if let Some((kind, arg)) = resumable_type_arg(name, ctx) {
+                let arg = substitute_type_params(&arg, generics, ctx);
+                return resumable_type(kind, &arg, ctx);
+            }
+            if let Some((generic_name, args)) = parse_generic_instance(name, ctx) {
+                let args: Vec<LltsType> = args.iter().map(|a| substitute_type_params(a, generics, ctx)).collect();
+                let mangled = mangle_generic_name(&generic_name, &args);
+                instantiate_generic_type(&mangled, ctx);
+                return ctx.full_struct_type(&mangled);
+            }
+            ctx.resolve_named_type(name)
+        }
+        LltsType::Array(elem) => LltsType::Array(Box::new(substitute_type_params(elem, generics, ctx))),
+        LltsType::Option(inner) => LltsType::Option(Box::new(substitute_type_params(inner, generics, ctx))),
+        LltsType::Set(elem) => LltsType::Set(Box::new(substitute_type_params(elem, generics, ctx))),
+        LltsType::Result { ok, err } => LltsType::Result {
+            ok: Box::new(substitute_type_params(ok, generics, ctx)),
+            err: Box::new(substitute_type_params(err, generics, ctx)),
+        },
+        LltsType::Map { key, value } => LltsType::Map {
+            key: Box::new(substitute_type_params(key, generics, ctx)),
+            value: Box::new(substitute_type_params(value, generics, ctx)),
+        },
+        LltsType::Function { params, ret } => LltsType::Function {
+            params: params.iter().map(|p| substitute_type_params(p, generics, ctx)).collect(),
+            ret: Box::new(substitute_type_params(ret, generics, ctx)),
+        },
+        other => other.clone(),
+    }
+}
+
+/// The (param types, return type) of `generic_name` specialized to `concrete_types`.
+pub(crate) fn specialized_signature(
+    generic_name: &str,
+    concrete_types: &[LltsType],
+    ctx: &mut LowerCtx,
+) -> Option<(Vec<LltsType>, LltsType)> {
+    let (params, ret) = ctx.generic_fn_sigs.get(generic_name)?.clone();
+    let generics: HashMap<String, LltsType> = ctx
+        .generic_fn_params
+        .get(generic_name)?
+        .iter()
+        .map(|(name, _, _)| name.clone())
+        .zip(concrete_types.iter().cloned())
+        .collect();
+    Some((
+        params.iter().map(|p| substitute_type_params(p, &generics, ctx)).collect(),
+        substitute_type_params(&ret, &generics, ctx),
+    ))
+}
+
+/// Check the concrete type arguments of one instantiation against their
+/// parameters' constraints. Returns false if the instantiation cannot be
+/// specialized.
+///
+/// A constraint naming an interface (`T extends HasLength`,
+/// `T extends Comparable<T>`) is satisfied by a struct or class that provides
+/// all of its fields and methods, and is an error otherwise. Other
+/// constraints list the allowed types; other types only get a warning.
+pub(crate) fn check_generic_constraints(generic_name: &str, concrete_types: &[LltsType], ctx: &mut LowerCtx) -> bool {
+    let Some(param_info) = ctx.generic_fn_params.get(generic_name) else { return true };
+    let mut satisfied = true;
+    for ((param, _, allowed), concrete) in param_info.iter().zip(concrete_types) {
+        match allowed.as_slice() {
+            [] => {}
+            [LltsType::Struct { name: iface, .. }]
+                if ctx.struct_defs.contains_key(iface) || ctx.generic_type_defs.contains_key(iface) =>
+            {
+                let missing = missing_interface_members(concrete, iface, ctx);
+                if !missing.is_empty() {
+                    ctx.errors.push(format!(
+                        "type {} does not satisfy constraint {iface} for parameter '{param}' in {generic_name} (missing {})",
+                        codegen_type_suffix(concrete),
+                        missing.join(", "),
+                    ));
+                    satisfied = false;
                 }
-                "Option" => {
-                    let inner = ref_type
-                        .type_arguments
-                        .as_ref()
-                        .and_then(|args| args.params.first())
-                        .map(|t| lower_ts_type_with_generics(t, enum_names, generics))
-                        .unwrap_or(LltsType::F64);
-                    return LltsType::Option(Box::new(inner));
+            }
+            _ if !allowed.contains(concrete) => {
+                eprintln!(
+                    "warning: type {} does not satisfy constraint for parameter '{param}' in {generic_name}",
+                    codegen_type_suffix(concrete),
+                );
+            }
+            _ => {}
+        }
+    }
+    satisfied
+}
+
+/// Members of interface `iface` that `ty` does not provide: fields by name
+/// and type, methods (`{Class}_{method}`) by name and arity.
+fn missing_interface_members(ty: &LltsType, iface: &str, ctx: &LowerCtx) -> Vec<String> {
+    let class = match ty {
+        LltsType::Struct { name, .. } => name.as_str(),
+        _ => "",
+    };
+    let fields = match ctx.generic_type_defs.get(iface) {
+        Some((_, fields)) => Some(fields),
+        None => ctx.struct_defs.get(iface),
+    };
+    let mut missing = Vec::new();
+    for (field, field_ty) in fields.into_iter().flatten() {
+        // Fields typed by one of the interface's own type parameters match anything.
+        let is_type_param = matches!(
+            field_ty,
+            LltsType::Struct { name, fields } if fields.is_empty() && !ctx.struct_defs.contains_key(name)
+        );
+        match ctx.lookup_field(class, field) {
+            Some((_, actual)) if is_type_param || codegen_type_suffix(&actual) == codegen_type_suffix(field_ty) => {}
+            _ => missing.push(field.clone()),
+        }
+    }
+    for slot in ctx.dyn_types.get(iface).into_iter().flatten() {
+        let Some(params) = &slot.params else { continue };
+        match ctx.fn_param_types.get(&format!("{class}_{}", slot.name)) {
+            Some(actual) if actual.len() == params.len() + 1 => {}
+            _ => missing.push(format!("{}()", slot.name)),
+        }
+    }
+    missing
+}
+
+// ---------------------------------------------------------------------------
+// Generic interfaces, type aliases and classes
+// ---------------------------------------------------------------------------
+
+/// Register a struct declared in the first pass, or, when it declares type
+/// parameters, the generic definition its instances are stamped out from.
+pub(crate) fn register_type_decl(
+    decl: StructDecl,
+    type_params: Option<&TSTypeParameterDeclaration<'_>>,
+    ctx: &mut LowerCtx,
+    structs: &mut Vec<StructDecl>,
+) {
+    let params: Vec<String> = type_params
+        .map(|tp| tp.params.iter().map(|p| p.name.name.to_string()).collect())
+        .unwrap_or_default();
+    if params.is_empty() {
+        ctx.struct_defs.insert(decl.name.clone(), decl.fields.clone());
+        structs.push(decl);
+    } else {
+        ctx.generic_type_defs.insert(decl.name, (params, decl.fields));
+    }
+}
+
+/// Lower a type annotation in the current context: type parameters in scope
+/// become their concrete types, generic type references (`Pair<i32, string>`)
+/// are instantiated, and struct names are resolved (see
+/// [`LowerCtx::resolve_annotation`]).
+pub(crate) fn lower_type_annotation(ts_type: &TSType<'_>, ctx: &mut LowerCtx) -> LltsType {
+    let ty = lower_ts_type_with_enums(ts_type, &ctx.enum_names());
+    let ty = if ctx.type_params_in_scope.is_empty() {
+        ty
+    } else {
+        let generics = ctx.type_params_in_scope.clone();
+        substitute_type_params(&ty, &generics, ctx)
+    };
+    instantiate_type_refs(&ty, ctx);
+    ctx.resolve_annotation(ty)
+}
+
+/// Instantiate the generic types `ty` refers to by name. Names that still
+/// hold type parameter placeholders (`Pair$T$string`) are left alone.
+pub(crate) fn instantiate_type_refs(ty: &LltsType, ctx: &mut LowerCtx) {
+    match ty {
+        LltsType::Struct { name, .. } => instantiate_generic_type(name, ctx),
+        LltsType::Array(inner) | LltsType::Option(inner) | LltsType::Set(inner) => instantiate_type_refs(inner, ctx),
+        LltsType::Result { ok, err } | LltsType::Map { key: ok, value: err } => {
+            instantiate_type_refs(ok, ctx);
+            instantiate_type_refs(err, ctx);
+        }
+        LltsType::Function { params, ret } => {
+            for p in params {
+                instantiate_type_refs(p, ctx);
+            }
+            instantiate_type_refs(ret, ctx);
+        }
+        LltsType::Union { variants, .. } => {
+            for (_, v) in variants {
+                instantiate_type_refs(v, ctx);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Stamp out the generic type instance `mangled` (`Stack$i32`) on first use:
+/// its fields are the generic fields with the type arguments substituted,
+/// and a class instance gets `{mangled}_new` / `{mangled}_{method}`
+/// signatures. The struct declaration and methods are emitted at the end of
+/// lowering (see `pending_type_instances`).
+pub(crate) fn instantiate_generic_type(mangled: &str, ctx: &mut LowerCtx) {
+    if ctx.type_instances.contains_key(mangled)
+        || ctx.struct_defs.contains_key(mangled)
+        || instantiate_resumable_type(mangled, ctx)
+    {
+        return;
+    }
+    let Some((generic_name, args)) = parse_generic_instance(mangled, ctx) else { return };
+    if !args.iter().all(|arg| is_concrete_type(arg, ctx)) {
+        return;
+    }
+    // Registered up front so self-referential fields (`next: Node<T> | null`) terminate.
+    ctx.type_instances.insert(mangled.to_string(), (generic_name.clone(), Vec::new()));
+    let no_params = HashMap::new();
+    let args: Vec<LltsType> = args.iter().map(|arg| substitute_type_params(arg, &no_params, ctx)).collect();
+    let generics = type_param_map(&generic_name, &args, ctx);
+    let generic_fields = ctx.generic_type_defs[&generic_name].1.clone();
+    let fields = generic_fields
+        .iter()
+        .map(|(name, ty)| (name.clone(), substitute_type_params(ty, &generics, ctx)))
+        .collect();
+    ctx.struct_defs.insert(mangled.to_string(), fields);
+    ctx.type_instances.insert(mangled.to_string(), (generic_name, args));
+    ctx.pending_type_instances.push(mangled.to_string());
+    register_instance_signatures(mangled, ctx);
+}
+
+/// Register the constructor and method signatures of a generic class
+/// instance (`Stack$i32_new`, `Stack$i32_push`) from the placeholder
+/// signatures of the generic class. Does nothing until those are registered
+/// in the second pass, which calls it again for earlier instances.
+pub(crate) fn register_instance_signatures(mangled: &str, ctx: &mut LowerCtx) {
+    let Some((generic_name, args)) = ctx.type_instances.get(mangled).cloned() else { return };
+    let Some(methods) = ctx.class_methods.get(&generic_name).cloned() else { return };
+    let generics = type_param_map(&generic_name, &args, ctx);
+    let self_type = ctx.full_struct_type(mangled);
+    for method in &methods {
+        let placeholder = format!("{generic_name}_{method}");
+        let specialized = format!("{mangled}_{method}");
+        if let Some(params) = ctx.fn_param_types.get(&placeholder).cloned() {
+            let mut specialized_params = vec![self_type.clone()];
+            for p in params.iter().skip(1) {
+                specialized_params.push(substitute_type_params(p, &generics, ctx));
+            }
+            ctx.fn_param_types.insert(specialized.clone(), specialized_params);
+        }
+        if let Some(ret) = ctx.fn_ret_types.get(&placeholder).cloned() {
+            let ret = substitute_type_params(&ret, &generics, ctx);
+            ctx.fn_ret_types.insert(specialized.clone(), ret);
+        }
+        let shape = ctx.param_shapes.get(&placeholder).cloned();
+        record_param_shape(&specialized, shape, ctx);
+    }
+    if let Some(params) = ctx.fn_param_types.get(&format!("{generic_name}_new")).cloned() {
+        let params = params.iter().map(|p| substitute_type_params(p, &generics, ctx)).collect();
+        ctx.fn_param_types.insert(format!("{mangled}_new"), params);
+    }
+    let shape = ctx.param_shapes.get(&format!("{generic_name}_new")).cloned();
+    record_param_shape(&format!("{mangled}_new"), shape, ctx);
+    ctx.fn_ret_types.insert(format!("{mangled}_new"), self_type);
+    ctx.class_methods.insert(mangled.to_string(), methods);
+}
+
+/// Lower the constructor and methods of a generic class instance with its
+/// type arguments in scope.
+pub(crate) fn lower_class_instance(class: &Class<'_>, mangled: &str, ctx: &mut LowerCtx) -> Vec<FunctionDecl> {
+    let Some((generic_name, args)) = ctx.type_instances.get(mangled).cloned() else { return Vec::new() };
+    let generics = type_param_map(&generic_name, &args, ctx);
+    let saved_type_params = std::mem::replace(&mut ctx.type_params_in_scope, generics);
+    let methods = lower_class_methods_as(class, mangled, ctx);
+    ctx.type_params_in_scope = saved_type_params;
+    methods
+}
+
+/// Type parameter name -> argument for one instance of a generic type.
+fn type_param_map(generic_name: &str, args: &[LltsType], ctx: &LowerCtx) -> HashMap<String, LltsType> {
+    ctx.generic_type_defs
+        .get(generic_name)
+        .map(|(params, _)| params.iter().cloned().zip(args.iter().cloned()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether `ty` refers only to declared types, i.e. holds no type parameter
+/// placeholders.
+pub(crate) fn is_concrete_type(ty: &LltsType, ctx: &LowerCtx) -> bool {
+    match ty {
+        LltsType::Struct { name, fields } if fields.is_empty() => match parse_generic_instance(name, ctx) {
+            Some((_, args)) => args.iter().all(|arg| is_concrete_type(arg, ctx)),
+            None => match resumable_type_arg(name, ctx) {
+                Some((_, arg)) => is_concrete_type(&arg, ctx),
+                None => {
+                    ctx.struct_defs.contains_key(name)
+                        || ctx.class_methods.contains_key(name)
+                        || ctx.dyn_types.contains_key(name)
+                        || ctx.discriminated_unions.contains_key(name)
                 }
-                "Result" => {
-                    let mut args_iter = ref_type
-                        .type_arguments
-                        .as_ref()
-                        .map(|a| a.params.iter().map(|t| lower_ts_type_with_generics(t, enum_names, generics)))
-                        .into_iter()
-                        .flatten();
-                    let ok = args_iter.next().unwrap_or(LltsType::Void);
-                    let err = args_iter.next().unwrap_or(LltsType::Void);
-                    return LltsType::Result { ok: Box::new(ok), err: Box::new(err) };
+            },
+        },
+        LltsType::Array(inner) | LltsType::Option(inner) | LltsType::Set(inner) => is_concrete_type(inner, ctx),
+        LltsType::Map { key, value } => is_concrete_type(key, ctx) && is_concrete_type(value, ctx),
+        _ => true,
+    }
+}
+
+/// Split a generic type instance name (`Stack$Pair$i32$string`) back into
+/// the generic name and its type arguments. Nested instances are regrouped
+/// by the arity of their generic type. `None` for any other name.
+fn parse_generic_instance(name: &str, ctx: &LowerCtx) -> Option<(String, Vec<LltsType>)> {
+    let mut parts = name.split('$');
+    let generic_name = parts.next()?;
+    let (params, _) = ctx.generic_type_defs.get(generic_name)?;
+    let parts: Vec<&str> = parts.collect();
+    let mut pos = 0;
+    let args = (0..params.len())
+        .map(|_| parse_type_suffix(&parts, &mut pos, ctx))
+        .collect::<Option<Vec<_>>>()?;
+    (pos == parts.len()).then(|| (generic_name.to_string(), args))
+}
+
+/// Parse the `$`-separated types written by [`codegen_type_suffix`] in
+/// `suffix` (`i32$Pair$i32$string` -> `i32`, `Pair$i32$string`).
+pub(crate) fn parse_type_suffixes(suffix: &str, ctx: &LowerCtx) -> Option<Vec<LltsType>> {
+    let parts: Vec<&str> = suffix.split('$').collect();
+    let mut pos = 0;
+    let mut types = Vec::new();
+    while pos < parts.len() {
+        types.push(parse_type_suffix(&parts, &mut pos, ctx)?);
+    }
+    Some(types)
+}
+
+/// Parse one type written by [`codegen_type_suffix`] from `parts[*pos..]`.
+fn parse_type_suffix(parts: &[&str], pos: &mut usize, ctx: &LowerCtx) -> Option<LltsType> {
+    let head = *parts.get(*pos)?;
+    *pos += 1;
+    type_from_suffix(head, parts, pos, ctx)
+}
+
+fn type_from_suffix(head: &str, parts: &[&str], pos: &mut usize, ctx: &LowerCtx) -> Option<LltsType> {
+    if let Some(elem) = head.strip_prefix("arr_") {
+        return Some(LltsType::Array(Box::new(type_from_suffix(elem, parts, pos, ctx)?)));
+    }
+    if let Some(inner) = head.strip_prefix("opt_") {
+        return Some(LltsType::Option(Box::new(type_from_suffix(inner, parts, pos, ctx)?)));
+    }
+    if let Some(elem) = head.strip_prefix("set_") {
+        return Some(LltsType::Set(Box::new(type_from_suffix(elem, parts, pos, ctx)?)));
+    }
+    // `map_K$V`: the key shares the head part, the value follows.
+    if let Some(key) = head.strip_prefix("map_") {
+        let key = type_from_suffix(key, parts, pos, ctx)?;
+        let value = parse_type_suffix(parts, pos, ctx)?;
+        return Some(LltsType::Map { key: Box::new(key), value: Box::new(value) });
+    }
+    let ty = match head {
+        "i8" => LltsType::I8,
+        "i16" => LltsType::I16,
+        "i32" => LltsType::I32,
+        "i64" => LltsType::I64,
+        "u8" => LltsType::U8,
+        "u16" => LltsType::U16,
+        "u32" => LltsType::U32,
+        "u64" => LltsType::U64,
+        "f32" => LltsType::F32,
+        "f64" => LltsType::F64,
+        "bool" => LltsType::Bool,
+        "string" => LltsType::String,
+        "void" => LltsType::Void,
+        name => {
+            let mut mangled = name.to_string();
+            let arity = match name {
+                "Generator" | "Promise" => 1,
+                _ => ctx.generic_type_defs.get(name).map_or(0, |(params, _)| params.len()),
+            };
+            for _ in 0..arity {
+                let start = *pos;
+                parse_type_suffix(parts, pos, ctx)?;
+                for part in &parts[start..*pos] {
+                    mangled.push('$');
+                    mangled.push_str(part);
                 }
-                _ => {}
             }
-            lower_ts_type_with_enums(ts_type, enum_names)
-        }
-        TSType::TSArrayType(arr) => {
-            LltsType::Array(Box::new(lower_ts_type_with_generics(&arr.element_type, enum_names, generics)))
-        }
-        TSType::TSUnionType(union) => {
-            let mut types: Vec<LltsType> = Vec::new();
-            let mut has_null = false;
-            for ty in &union.types {
-                match ty {
-                    TSType::TSNullKeyword(_) | TSType::TSUndefinedKeyword(_) => has_null = true,
-                    _ => types.push(lower_ts_type_with_generics(ty, enum_names, generics)),
+            LltsType::Struct { name: mangled, fields: vec![] }
+        }
+    };
+    Some(ty)
+}
+
+// ---------------------------------------------------------------------------
+// Type argument inference
+// ---------------------------------------------------------------------------
+
+/// Lower the arguments of a call to generic function `callee` written
+/// without type arguments, inferring them from the argument types.
+///
+/// Non-function arguments are lowered first; function arguments (`(x) => ...`)
+/// then get the parameter types inferred so far as their signature, and their
+/// return types bind the remaining parameters (`U` in
+/// `map<T, U>(xs: T[], f: (x: T) => U)`). Bare numeric literals only bind a
+/// parameter nothing else binds, so `max(n, 0)` with `n: i32` picks `i32`.
+/// Unbound parameters take their `<T = ...>` default; conflicting or missing
+/// bindings are recorded in `ctx.errors`.
+pub(crate) fn lower_inferred_generic_args(
+    callee: &str,
+    arguments: &[Argument<'_>],
+    ctx: &mut LowerCtx,
+) -> (Vec<Expr>, Vec<LltsType>) {
+    let type_params = ctx.generic_fn_params.get(callee).cloned().unwrap_or_default();
+    let names: Vec<String> = type_params.iter().map(|(name, _, _)| name.clone()).collect();
+    let Some((param_types, _)) = ctx.generic_fn_sigs.get(callee).cloned() else {
+        ctx.errors.push(format!("no signature recorded for generic function '{callee}'"));
+        return (arguments.iter().map(|a| lower_argument(a, ctx)).collect(), Vec::new());
+    };
+
+    let mut strong: HashMap<String, Vec<LltsType>> = HashMap::new();
+    let mut weak: HashMap<String, Vec<LltsType>> = HashMap::new();
+    let mut args: Vec<Option<Expr>> = Vec::with_capacity(arguments.len());
+    for (i, arg) in arguments.iter().enumerate() {
+        if matches!(arg, Argument::ArrowFunctionExpression(_) | Argument::FunctionExpression(_)) {
+            args.push(None);
+            continue;
+        }
+        let expr = lower_argument(arg, ctx);
+        // `null` and `[]` say nothing about their type.
+        let untyped = match &expr {
+            Expr::OptionNone { .. } => true,
+            Expr::ArrayLit { elements, .. } => elements.is_empty(),
+            _ => false,
+        };
+        if let (Some(param), false) = (param_types.get(i), untyped) {
+            let out = if is_literal_arg(&expr) { &mut weak } else { &mut strong };
+            collect_type_arg_candidates(param, &ir_expr_type(&expr), &names, ctx, out);
+        }
+        args.push(Some(expr));
+    }
+
+    for (i, arg) in arguments.iter().enumerate() {
+        if args[i].is_some() {
+            continue;
+        }
+        if let Some(LltsType::Function { params, ret }) = param_types.get(i) {
+            let bound = current_bindings(&names, &strong, &weak);
+            let hint_params = params
+                .iter()
+                .map(|p| {
+                    let ty = substitute_type_params(p, &bound, ctx);
+                    if is_concrete_type(&ty, ctx) { ty } else { LltsType::F64 }
+                })
+                .collect();
+            let ret = substitute_type_params(ret, &bound, ctx);
+            let hint_ret = is_concrete_type(&ret, ctx).then_some(ret);
+            ctx.lambda_signature_hint = Some((hint_params, hint_ret));
+        }
+        let expr = lower_argument(arg, ctx);
+        ctx.lambda_signature_hint = None;
+        if let Some(param) = param_types.get(i) {
+            collect_type_arg_candidates(param, &ir_expr_type(&expr), &names, ctx, &mut strong);
+        }
+        args[i] = Some(expr);
+    }
+
+    let mut types = Vec::with_capacity(type_params.len());
+    for (name, default, _) in &type_params {
+        let candidates = strong.get(name).or_else(|| weak.get(name));
+        let ty = match candidates.and_then(|c| c.first()) {
+            Some(first) => {
+                let conflict = strong
+                    .get(name)
+                    .into_iter()
+                    .flatten()
+                    .find(|c| codegen_type_suffix(c) != codegen_type_suffix(first));
+                if let Some(other) = conflict {
+                    ctx.errors.push(format!(
+                        "ambiguous type argument for '{name}' in call to {callee}: inferred both {} and {}; pass it explicitly, e.g. {callee}<{}>(...)",
+                        codegen_type_suffix(first),
+                        codegen_type_suffix(other),
+                        codegen_type_suffix(first),
+                    ));
                 }
+                first.clone()
             }
-            if has_null && types.len() == 1 {
-                LltsType::Option(Box::new(types.remove(0)))
-            } else if types.len() == 1 {
-                types.remove(0)
-            } else {
-                LltsType::Union {
-                    name: String::new(),
-                    variants: types.into_iter().enumerate().map(|(i, ty)| (format!("v{i}"), ty)).collect(),
+            None => match default {
+                Some(default) => default.clone(),
+                None => {
+                    ctx.errors.push(format!(
+                        "cannot infer type argument for '{name}' in call to {callee}; pass it explicitly"
+                    ));
+                    LltsType::F64
+                }
+            },
+        };
+        types.push(ty);
+    }
+
+    (args.into_iter().flatten().collect(), types)
+}
+
+/// Match a parameter type from a generic signature against the type of the
+/// argument passed for it, recording a candidate for each type parameter
+/// it determines.
+fn collect_type_arg_candidates(
+    param: &LltsType,
+    actual: &LltsType,
+    names: &[String],
+    ctx: &LowerCtx,
+    out: &mut HashMap<String, Vec<LltsType>>,
+) {
+    match (param, actual) {
+        (LltsType::Struct { name, fields }, _) if fields.is_empty() && names.contains(name) => {
+            out.entry(name.clone()).or_default().push(actual.clone());
+        }
+        (LltsType::Struct { name, fields }, LltsType::Struct { name: actual_name, .. }) if fields.is_empty() => {
+            // `Box<T>` against `Box<i32>`
+            if let (Some((generic, params)), Some((actual_generic, args))) =
+                (parse_generic_instance(name, ctx), parse_generic_instance(actual_name, ctx))
+            {
+                if generic == actual_generic {
+                    for (p, a) in params.iter().zip(&args) {
+                        collect_type_arg_candidates(p, a, names, ctx, out);
+                    }
                 }
             }
         }
-        TSType::TSParenthesizedType(paren) => {
-            lower_ts_type_with_generics(&paren.type_annotation, enum_names, generics)
+        (LltsType::Array(p), LltsType::Array(a))
+        | (LltsType::Option(p), LltsType::Option(a))
+        | (LltsType::Set(p), LltsType::Set(a)) => {
+            collect_type_arg_candidates(p, a, names, ctx, out);
+        }
+        (LltsType::Map { key: p_key, value: p_value }, LltsType::Map { key, value }) => {
+            collect_type_arg_candidates(p_key, key, names, ctx, out);
+            collect_type_arg_candidates(p_value, value, names, ctx, out);
+        }
+        // A plain value passed for `T | null`
+        (LltsType::Option(p), a) => collect_type_arg_candidates(p, a, names, ctx, out),
+        (LltsType::Result { ok: p_ok, err: p_err }, LltsType::Result { ok, err }) => {
+            collect_type_arg_candidates(p_ok, ok, names, ctx, out);
+            collect_type_arg_candidates(p_err, err, names, ctx, out);
+        }
+        (
+            LltsType::Function { params: p_params, ret: p_ret },
+            LltsType::Function { params: a_params, ret: a_ret },
+        ) => {
+            for (p, a) in p_params.iter().zip(a_params) {
+                collect_type_arg_candidates(p, a, names, ctx, out);
+            }
+            collect_type_arg_candidates(p_ret, a_ret, names, ctx, out);
         }
-        _ => lower_ts_type_with_enums(ts_type, enum_names),
+        _ => {}
+    }
+}
+
+/// The type parameters bound so far, preferring non-literal candidates.
+fn current_bindings(
+    names: &[String],
+    strong: &HashMap<String, Vec<LltsType>>,
+    weak: &HashMap<String, Vec<LltsType>>,
+) -> HashMap<String, LltsType> {
+    names
+        .iter()
+        .filter_map(|name| {
+            let ty = strong.get(name).or_else(|| weak.get(name))?.first()?;
+            Some((name.clone(), ty.clone()))
+        })
+        .collect()
+}
+
+/// Whether an argument is a bare numeric literal (`3`, `-1.5`, `[1, 2]`)
+/// whose type only follows the language default.
+fn is_literal_arg(expr: &Expr) -> bool {
+    match expr {
+        Expr::IntLit { .. } | Expr::FloatLit { .. } => true,
+        Expr::Unary { op: UnaryOp::Neg, operand, .. } => is_literal_arg(operand),
+        Expr::ArrayLit { elements, .. } => !elements.is_empty() && elements.iter().all(is_literal_arg),
+        _ => false,
     }
 }
 
-/// Mangle a generic function name with concrete type arguments.
+/// Mangle a generic function or type name with concrete type arguments.
 pub(crate) fn mangle_generic_name(name: &str, type_args: &[LltsType]) -> String {
     let mut mangled = name.to_string();
     for arg in type_args {
@@ -163,6 +691,9 @@ pub(crate) fn codegen_type_suffix(ty: &LltsType) -> String {
         LltsType::Struct { name, .. } => name.clone(),
         LltsType::Array(elem) => format!("arr_{}", codegen_type_suffix(elem)),
         LltsType::Option(inner) => format!("opt_{}", codegen_type_suffix(inner)),
+        LltsType::Map { key, value } => format!("map_{}${}", codegen_type_suffix(key), codegen_type_suffix(value)),
+        LltsType::Set(elem) => format!("set_{}", codegen_type_suffix(elem)),
+        LltsType::Dyn { name } => name.clone(),
         _ => "unknown".to_string(),
     }
 }
@@ -0,0 +1,311 @@
+use llts_codegen::{
+    Expr, FunctionDecl, Stmt,
+    expr::{BinOp, UnaryOp},
+    types::LltsType,
+};
+
+use super::context::LowerCtx;
+use super::utils::{field_assign, ir_expr_type};
+use super::visit::for_each_stmt_mut;
+
+// ---------------------------------------------------------------------------
+// Iterator protocol: `for...of` over iterators and iterables
+// ---------------------------------------------------------------------------
+//
+// A class with a `next(): T | null` method is an iterator; a class with an
+// `iter()` method (the `[Symbol.iterator]()` of JS) returning something
+// `for...of` accepts is an iterable. `for (const x of it)` calls `next()`
+// until it returns null:
+//
+//     let __forof_iter_N = it;             // it.iter() for an iterable
+//     while (true) {
+//         __forof_step_N = C_next$step(__forof_iter_N);
+//         __forof_iter_N = __forof_step_N.iter;
+//         if (__forof_step_N.next === null) break;
+//         x = __forof_step_N.next!;
+//         ...
+//     }
+//
+// Class values are copied into calls, so `C_next` itself would advance a
+// copy of the iterator. The loop calls `C_next$step` instead, lowered with
+// the class: the body of `C_next` with each `return v` turned into
+// `return { iter: self, next: v }`, handing back the advanced iterator with
+// the value. Every call is static.
+//
+// Strings iterate by code point through the same protocol, with the
+// built-in iterator `__StringIter { s, pos }` whose `next()` is generated
+// here.
+
+/// The built-in iterator over the code points of a string.
+const STRING_ITER: &str = "__StringIter";
+
+/// Suffix of the step variant of a `next` method.
+const STEP_SUFFIX: &str = "$step";
+
+/// The class name and element type of `ty`, if it is an iterator: a class
+/// with a `next(): T | null` method.
+pub(crate) fn iterator_class(ty: &LltsType, ctx: &LowerCtx) -> Option<(String, LltsType)> {
+    let LltsType::Struct { name, .. } = ty else { return None };
+    let next = format!("{name}_next");
+    if ctx.fn_param_types.get(&next).map(Vec::len) != Some(1) {
+        return None;
+    }
+    match ctx.fn_ret_types.get(&next)? {
+        LltsType::Option(elem) => {
+            let elem = match elem.as_ref() {
+                LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(name),
+                other => other.clone(),
+            };
+            Some((name.clone(), elem))
+        }
+        _ => None,
+    }
+}
+
+/// What `for...of` over `iterable` walks: `iterable.iter()` for an iterable
+/// class that is not itself an iterator, a code point iterator for a
+/// string, otherwise `iterable` itself.
+pub(crate) fn iterable_source(iterable: Expr, ctx: &mut LowerCtx) -> Expr {
+    let ty = ir_expr_type(&iterable);
+    match &ty {
+        LltsType::String => string_iterator(iterable, ctx),
+        LltsType::Struct { name, .. } if iterator_class(&ty, ctx).is_none() => {
+            let iter = format!("{name}_iter");
+            if ctx.fn_param_types.get(&iter).map(Vec::len) != Some(1) {
+                return iterable;
+            }
+            let ret_type = ctx.fn_ret_types.get(&iter).cloned().unwrap_or(LltsType::Void);
+            let source = Expr::MethodCall {
+                class_name: name.clone(),
+                method_name: "iter".to_string(),
+                receiver: Box::new(iterable),
+                args: Vec::new(),
+                ret_type,
+            };
+            match ir_expr_type(&source) {
+                LltsType::String => string_iterator(source, ctx),
+                _ => source,
+            }
+        }
+        _ => iterable,
+    }
+}
+
+/// Lower `for (const elem_name of iterator)` over an instance of iterator
+/// class `class` (see the module comment). `body` already holds any
+/// destructuring of `elem_name`.
+pub(crate) fn iterator_for_of(
+    iterator: Expr,
+    class: &str,
+    elem_name: String,
+    elem_type: LltsType,
+    mut body: Vec<Stmt>,
+    id: u32,
+) -> Stmt {
+    let iter_type = ir_expr_type(&iterator);
+    let next = format!("{class}_next");
+    let opt_type = LltsType::Option(Box::new(elem_type.clone()));
+    let step_type = step_type(class, &iter_type, &opt_type);
+
+    let iter_name = format!("__forof_iter_{id}");
+    let step_name = format!("__forof_step_{id}");
+    let step_var = Expr::Var { name: step_name.clone(), ty: step_type.clone() };
+    let step_field = |index: u32, field_type: &LltsType| Expr::FieldAccess {
+        object: Box::new(step_var.clone()),
+        object_type: step_type.clone(),
+        field_index: index,
+        field_type: field_type.clone(),
+    };
+    let next_value = step_field(1, &opt_type);
+
+    let mut loop_body = vec![
+        Stmt::Assign {
+            target: step_name.clone(),
+            value: Expr::Call {
+                callee: format!("{next}{STEP_SUFFIX}"),
+                args: vec![Expr::Var { name: iter_name.clone(), ty: iter_type.clone() }],
+                ret_type: step_type.clone(),
+            },
+        },
+        Stmt::Assign { target: iter_name.clone(), value: step_field(0, &iter_type) },
+        Stmt::If {
+            condition: Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(Expr::OptionIsSome {
+                    value: Box::new(next_value.clone()),
+                    inner_type: elem_type.clone(),
+                }),
+                ty: LltsType::Bool,
+            },
+            then_body: vec![Stmt::Break],
+            else_body: None,
+        },
+        Stmt::Assign {
+            target: elem_name.clone(),
+            value: Expr::OptionUnwrap { value: Box::new(next_value), inner_type: elem_type.clone() },
+        },
+    ];
+    loop_body.append(&mut body);
+    Stmt::Block(vec![
+        Stmt::VarDecl { name: iter_name, ty: iter_type, init: Some(iterator) },
+        Stmt::VarDecl { name: step_name, ty: step_type, init: None },
+        Stmt::VarDecl { name: elem_name, ty: elem_type, init: None },
+        Stmt::While { condition: Expr::BoolLit(true), body: loop_body },
+    ])
+}
+
+/// The `$step` variant of `method` if it is the `next` method of iterator
+/// class `class` (see the module comment).
+pub(crate) fn iterator_step(method: &FunctionDecl, class: &str) -> Option<FunctionDecl> {
+    let is_next = method.name.strip_prefix(class) == Some("_next")
+        && method.params.len() == 1
+        && matches!(method.ret_type, LltsType::Option(_));
+    is_next.then(|| step_function(method, class))
+}
+
+/// `C_next$step`: `next` returning the advanced iterator with each value.
+fn step_function(next: &FunctionDecl, class: &str) -> FunctionDecl {
+    let (self_name, self_type) = next.params[0].clone();
+    let step_type = step_type(class, &self_type, &next.ret_type);
+    let mut body = next.body.clone();
+    for_each_stmt_mut(&mut body, &mut |stmt| {
+        if let Stmt::Return(Some(value)) = stmt {
+            let value_expr = std::mem::replace(value, Expr::BoolLit(false));
+            *value = Expr::StructLit {
+                struct_type: step_type.clone(),
+                fields: vec![Expr::Var { name: self_name.clone(), ty: self_type.clone() }, value_expr],
+            };
+        }
+    });
+    FunctionDecl {
+        name: format!("{}{STEP_SUFFIX}", next.name),
+        params: next.params.clone(),
+        ret_type: step_type,
+        body,
+    }
+}
+
+/// What `C_next$step` returns: `{ iter: C, next: T | null }`.
+fn step_type(class: &str, iter_type: &LltsType, opt_type: &LltsType) -> LltsType {
+    LltsType::Struct {
+        name: format!("__step_{class}"),
+        fields: vec![("iter".to_string(), iter_type.clone()), ("next".to_string(), opt_type.clone())],
+    }
+}
+
+/// A `__StringIter` over the code points of `string`, declaring its `next`
+/// method on first use.
+fn string_iterator(string: Expr, ctx: &mut LowerCtx) -> Expr {
+    let iter_type = string_iter_type();
+    let next = format!("{STRING_ITER}_next");
+    if !ctx.fn_ret_types.contains_key(&next) {
+        let opt_type = LltsType::Option(Box::new(LltsType::String));
+        ctx.fn_ret_types.insert(next.clone(), opt_type.clone());
+        ctx.fn_param_types.insert(next.clone(), vec![iter_type.clone()]);
+        let next = FunctionDecl {
+            name: next,
+            params: vec![("self".to_string(), iter_type.clone())],
+            ret_type: opt_type,
+            body: string_next_body(&iter_type),
+        };
+        ctx.pending_functions.push(step_function(&next, STRING_ITER));
+        ctx.pending_functions.push(next);
+    }
+    Expr::StructLit {
+        struct_type: iter_type,
+        fields: vec![string, Expr::IntLit { value: 0, ty: LltsType::I64 }],
+    }
+}
+
+fn string_iter_type() -> LltsType {
+    LltsType::Struct {
+        name: STRING_ITER.to_string(),
+        fields: vec![("s".to_string(), LltsType::String), ("pos".to_string(), LltsType::I64)],
+    }
+}
+
+/// `__StringIter.next()`: the UTF-8 sequence at `pos`, its length taken from
+/// the lead byte.
+///
+///     if (this.pos >= this.s.length) return null;
+///     const lead = this.s.charCodeAt(this.pos);
+///     let len = 1;
+///     if (lead >= 0xF0) len = 4; else if (lead >= 0xE0) len = 3; else if (lead >= 0xC0) len = 2;
+///     const ch = this.s.slice(this.pos, this.pos + len);
+///     this.pos += len;
+///     return ch;
+fn string_next_body(iter_type: &LltsType) -> Vec<Stmt> {
+    let this = Expr::Var { name: "self".to_string(), ty: iter_type.clone() };
+    let field = |index: u32, ty: LltsType| Expr::FieldAccess {
+        object: Box::new(this.clone()),
+        object_type: iter_type.clone(),
+        field_index: index,
+        field_type: ty,
+    };
+    let s = field(0, LltsType::String);
+    let pos = field(1, LltsType::I64);
+    let len = Expr::Var { name: "len".to_string(), ty: LltsType::I64 };
+    let int = |value: i64| Expr::IntLit { value, ty: LltsType::I64 };
+    let binary = |op: BinOp, lhs: Expr, rhs: Expr| Expr::Binary {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+        ty: LltsType::I64,
+    };
+    let string_method = |method: &str, args: Vec<Expr>, ret_type: LltsType| Expr::MethodCall {
+        class_name: "__String".to_string(),
+        method_name: method.to_string(),
+        receiver: Box::new(s.clone()),
+        args,
+        ret_type,
+    };
+    let lead = Expr::Var { name: "lead".to_string(), ty: LltsType::I64 };
+    // `if (lead >= 0xF0) len = 4; else if ...`, innermost last
+    let len_by_lead = [(0xF0, 4), (0xE0, 3), (0xC0, 2)].into_iter().rev().fold(None, |rest, (min, bytes)| {
+        Some(vec![Stmt::If {
+            condition: binary(BinOp::Ge, lead.clone(), int(min)),
+            then_body: vec![Stmt::Assign { target: "len".to_string(), value: int(bytes) }],
+            else_body: rest,
+        }])
+    });
+    let mut body = vec![
+        Stmt::If {
+            condition: binary(
+                BinOp::Ge,
+                pos.clone(),
+                Expr::FieldAccess {
+                    object: Box::new(s.clone()),
+                    object_type: LltsType::String,
+                    field_index: 1,
+                    field_type: LltsType::I64,
+                },
+            ),
+            then_body: vec![Stmt::Return(Some(Expr::OptionNone { inner_type: LltsType::String }))],
+            else_body: None,
+        },
+        Stmt::VarDecl {
+            name: "lead".to_string(),
+            ty: LltsType::I64,
+            init: Some(string_method("charCodeAt", vec![pos.clone()], LltsType::I64)),
+        },
+        Stmt::VarDecl { name: "len".to_string(), ty: LltsType::I64, init: Some(int(1)) },
+    ];
+    body.extend(len_by_lead.unwrap_or_default());
+    body.extend([
+        Stmt::VarDecl {
+            name: "ch".to_string(),
+            ty: LltsType::String,
+            init: Some(string_method(
+                "slice",
+                vec![pos.clone(), binary(BinOp::Add, pos.clone(), len.clone())],
+                LltsType::String,
+            )),
+        },
+        field_assign("self", iter_type, 1, LltsType::I64, binary(BinOp::Add, pos, len)),
+        Stmt::Return(Some(Expr::OptionSome {
+            value: Box::new(Expr::Var { name: "ch".to_string(), ty: LltsType::String }),
+            inner_type: LltsType::String,
+        })),
+    ]);
+    body
+}
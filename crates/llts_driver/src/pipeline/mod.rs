@@ -3,38 +3,70 @@ use std::collections::{HashMap, HashSet};
 use oxc_ast::ast::*;
 
 use llts_codegen::{
-    EnumDecl, Expr, FunctionDecl, ProgramIR, Stmt, StructDecl,
+    EnumDecl, FunctionDecl, ProgramIR, Stmt, StructDecl,
     types::LltsType,
 };
 
+mod async_fns;
+mod chains;
+mod closures;
+mod collections;
 mod compile;
 mod context;
+mod destructuring;
+mod errors;
 mod exprs;
+mod generators;
 mod generics;
+mod iterators;
+mod params;
+mod results;
+mod spread;
 mod stmts;
 mod types;
 mod unions;
 mod utils;
+mod visit;
+mod vtables;
 pub use compile::{compile_file, CompileError, CompileOptions};
+pub(crate) use async_fns::*;
+pub(crate) use chains::*;
+pub(crate) use closures::*;
+pub(crate) use collections::*;
 pub(crate) use context::*;
+pub(crate) use destructuring::*;
+pub(crate) use errors::*;
 pub(crate) use exprs::*;
+pub(crate) use generators::*;
 pub(crate) use generics::*;
+pub(crate) use iterators::*;
+pub(crate) use params::*;
+pub(crate) use results::*;
+pub(crate) use spread::*;
 pub(crate) use stmts::*;
 pub(crate) use types::*;
 pub(crate) use unions::*;
 pub(crate) use utils::*;
+pub(crate) use vtables::*;
 
 // ---------------------------------------------------------------------------
 // AST → ProgramIR lowering
 // ---------------------------------------------------------------------------
 
-/// Lower a program with a shared LowerCtx (for multi-file compilation).
+/// Lower `modules[module_idx]` with a shared LowerCtx (for multi-file
+/// compilation). `modules` holds every file of the compilation, so generic
+/// functions and classes declared in one file can be specialized from another.
 /// If `is_entry` is false, `main` functions are excluded from the output.
 pub(crate) fn lower_program_with_ctx(
-    program: &Program<'_>,
+    modules: &[Program<'_>],
+    module_idx: usize,
     ctx: &mut LowerCtx,
     is_entry: bool,
 ) -> ProgramIR {
+    let program = &modules[module_idx];
+    // A subclass is laid out after its parent, wherever it is declared
+    let body = llts_analysis::declaration_order(program);
+    ctx.current_module = module_idx;
     let mut structs = Vec::new();
     let mut enums = Vec::new();
     let mut functions = Vec::new();
@@ -56,14 +88,12 @@ pub(crate) fn lower_program_with_ctx(
                             lit_value,
                         );
                     }
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                    register_type_decl(s, iface.type_parameters.as_deref(), ctx, structs);
                 }
             }
             Declaration::ClassDeclaration(class) => {
-                if let Some(s) = lower_class_struct(class) {
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                if let Some(s) = lower_class_struct(class, ctx) {
+                    register_type_decl(s, class.type_parameters.as_deref(), ctx, structs);
                 }
             }
             Declaration::TSEnumDeclaration(d) => {
@@ -73,8 +103,7 @@ pub(crate) fn lower_program_with_ctx(
             }
             Declaration::TSTypeAliasDeclaration(alias) => {
                 if let Some(s) = lower_type_alias(alias) {
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                    register_type_decl(s, alias.type_parameters.as_deref(), ctx, structs);
                 }
                 detect_discriminated_union(alias, ctx, structs, enums);
             }
@@ -84,41 +113,22 @@ pub(crate) fn lower_program_with_ctx(
 
     // Helper: collect function return type and parameter types from a Declaration node
     fn collect_fn_sig(decl: &Declaration<'_>, ctx: &mut LowerCtx, enum_names: &HashSet<String>) {
+        if let Declaration::ClassDeclaration(class) = decl {
+            register_class_signatures(class, ctx);
+        }
         if let Declaration::FunctionDeclaration(func) = decl {
+            if func.id.as_ref().is_some_and(|id| ctx.generic_fn_indices.contains_key(id.name.as_str())) {
+                register_generic_signature(func, ctx, enum_names);
+                return;
+            }
             if let Some(id) = &func.id {
-                let ret_type = func
-                    .return_type
-                    .as_ref()
-                    .map(|r| lower_ts_type_with_enums(&r.type_annotation, enum_names))
-                    .unwrap_or(LltsType::Void);
-                let ret_type = match &ret_type {
-                    LltsType::Struct { name, fields } if fields.is_empty() => {
-                        if let Some(du) = ctx.discriminated_unions.get(name) {
-                            du.union_type.clone()
-                        } else {
-                            ctx.full_struct_type(name)
-                        }
-                    }
-                    other => other.clone(),
-                };
-                ctx.fn_ret_types.insert(id.name.to_string(), ret_type);
-                // Collect parameter types
-                let param_types: Vec<LltsType> = func.params.items.iter().map(|p| {
-                    let pty = p.type_annotation.as_ref()
-                        .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, enum_names))
-                        .unwrap_or(LltsType::F64);
-                    match &pty {
-                        LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(name),
-                        other => other.clone(),
-                    }
-                }).collect();
-                ctx.fn_param_types.insert(id.name.to_string(), param_types);
+                register_fn_signature(&id.name, func, ctx);
             }
         }
     }
 
     // First pass: collect all struct/interface/enum definitions + register generic functions
-    for (stmt_idx, stmt) in program.body.iter().enumerate() {
+    for &(stmt_idx, stmt) in &body {
         match stmt {
             // Register generic function definitions (have TSTypeParameterDeclaration)
             Statement::FunctionDeclaration(func) => {
@@ -126,7 +136,7 @@ pub(crate) fn lower_program_with_ctx(
                     if !type_params.params.is_empty() {
                         if let Some(id) = &func.id {
                             let name = id.name.to_string();
-                            ctx.generic_fn_indices.insert(name.clone(), stmt_idx);
+                            ctx.generic_fn_indices.insert(name.clone(), (module_idx, stmt_idx));
                             ctx.generic_fn_params.insert(name, extract_generic_param_info(type_params, &ctx.type_alias_members));
                         }
                     }
@@ -138,7 +148,7 @@ pub(crate) fn lower_program_with_ctx(
                         if !type_params.params.is_empty() {
                             if let Some(id) = &func.id {
                                 let name = id.name.to_string();
-                                ctx.generic_fn_indices.insert(name.clone(), stmt_idx);
+                                ctx.generic_fn_indices.insert(name.clone(), (module_idx, stmt_idx));
                                 ctx.generic_fn_params.insert(name, extract_generic_param_info(type_params, &ctx.type_alias_members));
                             }
                         }
@@ -158,14 +168,12 @@ pub(crate) fn lower_program_with_ctx(
                             lit_value,
                         );
                     }
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                    register_type_decl(s, iface.type_parameters.as_deref(), ctx, &mut structs);
                 }
             }
             Statement::ClassDeclaration(class) => {
-                if let Some(s) = lower_class_struct(class) {
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                if let Some(s) = lower_class_struct(class, ctx) {
+                    register_type_decl(s, class.type_parameters.as_deref(), ctx, &mut structs);
                 }
             }
             Statement::TSEnumDeclaration(decl) => {
@@ -175,12 +183,11 @@ pub(crate) fn lower_program_with_ctx(
             }
             Statement::TSTypeAliasDeclaration(alias) => {
                 if let Some(s) = lower_type_alias(alias) {
-                    ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                    structs.push(s);
+                    register_type_decl(s, alias.type_parameters.as_deref(), ctx, &mut structs);
                 }
                 // Store non-struct type aliases (unions, primitives, etc.)
                 let alias_name = alias.id.name.to_string();
-                if !ctx.struct_defs.contains_key(&alias_name) {
+                if !ctx.struct_defs.contains_key(&alias_name) && !ctx.generic_type_defs.contains_key(&alias_name) {
                     let resolved = lower_ts_type(&alias.type_annotation);
                     ctx.type_aliases.insert(alias_name.clone(), resolved);
                     // For union type aliases, also store the individual members (pre-widening)
@@ -200,9 +207,8 @@ pub(crate) fn lower_program_with_ctx(
             }
             Statement::ExportDefaultDeclaration(export) => {
                 if let ExportDefaultDeclarationKind::ClassDeclaration(class) = &export.declaration {
-                    if let Some(s) = lower_class_struct(class) {
-                        ctx.struct_defs.insert(s.name.clone(), s.fields.clone());
-                        structs.push(s);
+                    if let Some(s) = lower_class_struct(class, ctx) {
+                        register_type_decl(s, class.type_parameters.as_deref(), ctx, &mut structs);
                     }
                 }
             }
@@ -210,91 +216,60 @@ pub(crate) fn lower_program_with_ctx(
         }
     }
 
+    // Interfaces with methods and extended classes are dispatched through
+    // vtables; annotations naming them resolve to `Dyn` from here on.
+    register_dyn_types(program, ctx);
+
+    // Generic types named in struct fields (`pair: Pair<i32, string>`).
+    let field_types: Vec<LltsType> = ctx.struct_defs.values().flatten().map(|(_, ty)| ty.clone()).collect();
+    for ty in &field_types {
+        instantiate_type_refs(ty, ctx);
+    }
+
     // Second pass: collect function return types (enums are now registered)
-    // Skip generic functions — their return types depend on type parameters.
+    // Generic functions record placeholder signatures, specialized per call site.
     let enum_names = ctx.enum_names();
-    for stmt in &program.body {
+    for &(_, stmt) in &body {
         match stmt {
             Statement::FunctionDeclaration(func) => {
                 if let Some(id) = &func.id {
-                    // Skip generic functions
+                    // Generic functions only record their placeholder signature
                     if ctx.generic_fn_indices.contains_key(id.name.as_str()) {
+                        register_generic_signature(func, ctx, &enum_names);
                         continue;
                     }
-                    let ret_type = func
-                        .return_type
-                        .as_ref()
-                        .map(|r| lower_ts_type_with_enums(&r.type_annotation, &enum_names))
-                        .unwrap_or(LltsType::Void);
-                    let ret_type = match &ret_type {
-                        LltsType::Struct { name, fields } if fields.is_empty() => {
-                            if let Some(du) = ctx.discriminated_unions.get(name) {
-                                du.union_type.clone()
-                            } else {
-                                ctx.full_struct_type(name)
-                            }
-                        }
-                        other => other.clone(),
-                    };
-                    ctx.fn_ret_types.insert(id.name.to_string(), ret_type);
-                    // Collect parameter types
-                    let param_types: Vec<LltsType> = func.params.items.iter().map(|p| {
-                        let pty = p.type_annotation.as_ref()
-                            .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                            .unwrap_or(LltsType::F64);
-                        match &pty {
-                            LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(name),
-                            other => other.clone(),
-                        }
-                    }).collect();
-                    ctx.fn_param_types.insert(id.name.to_string(), param_types);
+                    register_fn_signature(&id.name, func, ctx);
                 }
             }
+            Statement::ClassDeclaration(class) => register_class_signatures(class, ctx),
             Statement::ExportNamedDeclaration(export) => {
                 if let Some(decl) = &export.declaration {
                     collect_fn_sig(decl, ctx, &enum_names);
                 }
             }
             Statement::ExportDefaultDeclaration(export) => {
+                if let ExportDefaultDeclarationKind::ClassDeclaration(class) = &export.declaration {
+                    register_class_signatures(class, ctx);
+                }
                 if let ExportDefaultDeclarationKind::FunctionDeclaration(func) = &export.declaration {
                     if let Some(id) = &func.id {
-                        let ret_type = func
-                            .return_type
-                            .as_ref()
-                            .map(|r| lower_ts_type_with_enums(&r.type_annotation, &enum_names))
-                            .unwrap_or(LltsType::Void);
-                        let ret_type = match &ret_type {
-                            LltsType::Struct { name, fields } if fields.is_empty() => {
-                                if let Some(du) = ctx.discriminated_unions.get(name) {
-                                    du.union_type.clone()
-                                } else {
-                                    ctx.full_struct_type(name)
-                                }
-                            }
-                            other => other.clone(),
-                        };
-                        ctx.fn_ret_types.insert(id.name.to_string(), ret_type);
-                        // Collect parameter types
-                        let param_types: Vec<LltsType> = func.params.items.iter().map(|p| {
-                            let pty = p.type_annotation.as_ref()
-                                .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                                .unwrap_or(LltsType::F64);
-                            match &pty {
-                                LltsType::Struct { name, fields } if fields.is_empty() => ctx.full_struct_type(name),
-                                other => other.clone(),
-                            }
-                        }).collect();
-                        ctx.fn_param_types.insert(id.name.to_string(), param_types);
+                        register_fn_signature(&id.name, func, ctx);
                     }
                 }
             }
             _ => {}
         }
     }
+    layout_base_class_slots(ctx);
+    // Generic class instances created before their class's signatures were known.
+    let instances: Vec<String> = ctx.type_instances.keys().cloned().collect();
+    for mangled in &instances {
+        register_instance_signatures(mangled, ctx);
+    }
 
     // Third pass: lower functions and class methods with full context
     // Skip generic functions — they are monomorphized on-demand at call sites.
-    for stmt in &program.body {
+    for &(_, stmt) in &body {
         match stmt {
             Statement::FunctionDeclaration(func) => {
                 // Skip generic function definitions
@@ -314,6 +289,8 @@ pub(crate) fn lower_program_with_ctx(
                     functions.push(f);
                 }
             }
+            // Generic classes are lowered per instance, after this pass.
+            Statement::ClassDeclaration(class) if class.type_parameters.is_some() => {}
             Statement::ClassDeclaration(class) => {
                 functions.extend(lower_class_methods(class, ctx));
             }
@@ -338,12 +315,13 @@ pub(crate) fn lower_program_with_ctx(
                                 functions.push(f);
                             }
                         }
+                        Declaration::ClassDeclaration(class) if class.type_parameters.is_some() => {}
                         Declaration::ClassDeclaration(class) => {
                             functions.extend(lower_class_methods(class, ctx));
                         }
                         Declaration::VariableDeclaration(var_decl) => {
                             for declarator in &var_decl.declarations {
-                                let name = binding_name(&declarator.id);
+                                let name = binding_name(&declarator.id, ctx);
                                 let ty = declarator
                                     .type_annotation
                                     .as_ref()
@@ -351,11 +329,18 @@ pub(crate) fn lower_program_with_ctx(
                                     .or_else(|| declarator.init.as_ref().map(|e| infer_expr_type(e)))
                                     .unwrap_or(LltsType::F64);
                                 ctx.var_types.insert(name.clone(), ty.clone());
-                                let init = declarator.init.as_ref().map(|e| lower_expr(e, ctx));
-                                // Skip arrow function initializers (handled as pending functions)
-                                if let Some(Expr::Var { name: ref ln, .. }) = init {
-                                    if ln.starts_with("__lambda_") { continue; }
+                                let init = declarator.init.as_ref().map(|e| lower_expr_expecting(e, &ty, ctx));
+                                // Capture-free arrow initializers become named functions
+                                if try_name_lambda(&init, &name, ctx) {
+                                    continue;
                                 }
+                                let ty = match init.as_ref().map(ir_expr_type) {
+                                    Some(init_ty) if declarator.type_annotation.is_none() && takes_init_type(&init_ty) => {
+                                        ctx.var_types.insert(name.clone(), init_ty.clone());
+                                        init_ty
+                                    }
+                                    _ => ty,
+                                };
                                 top_level_vars.push(Stmt::VarDecl { name, ty, init });
                             }
                         }
@@ -370,6 +355,7 @@ pub(crate) fn lower_program_with_ctx(
                             functions.push(f);
                         }
                     }
+                    ExportDefaultDeclarationKind::ClassDeclaration(class) if class.type_parameters.is_some() => {}
                     ExportDefaultDeclarationKind::ClassDeclaration(class) => {
                         functions.extend(lower_class_methods(class, ctx));
                     }
@@ -396,38 +382,69 @@ pub(crate) fn lower_program_with_ctx(
     // Append any lambda functions generated from arrow expressions
     functions.extend(ctx.pending_functions.drain(..));
 
-    // Monomorphization pass: stamp out specialized copies of generic functions.
-    let pending = std::mem::take(&mut ctx.pending_monomorphizations);
-    for (generic_name, _type_param_names, concrete_types, mangled_name) in pending {
-        if let Some(&stmt_idx) = ctx.generic_fn_indices.get(&generic_name) {
-            let func_ast = match &program.body[stmt_idx] {
-                Statement::FunctionDeclaration(func) => Some(func.as_ref()),
-                Statement::ExportNamedDeclaration(export) => {
-                    if let Some(Declaration::FunctionDeclaration(func)) = &export.declaration {
-                        Some(func.as_ref())
-                    } else {
-                        None
+    // Monomorphization pass: stamp out specialized copies of generic functions
+    // and the struct declarations and methods of generic type instances.
+    // Specializations may request further ones, so repeat until none are left.
+    loop {
+        let pending = std::mem::take(&mut ctx.pending_monomorphizations);
+        let instances = std::mem::take(&mut ctx.pending_type_instances);
+        if pending.is_empty() && instances.is_empty() {
+            break;
+        }
+        for (generic_name, _type_param_names, concrete_types, mangled_name) in pending {
+            if !check_generic_constraints(&generic_name, &concrete_types, ctx) {
+                continue;
+            }
+            if let Some(&(decl_module, stmt_idx)) = ctx.generic_fn_indices.get(&generic_name) {
+                let func_ast = match &modules[decl_module].body[stmt_idx] {
+                    Statement::FunctionDeclaration(func) => Some(func.as_ref()),
+                    Statement::ExportNamedDeclaration(export) => {
+                        if let Some(Declaration::FunctionDeclaration(func)) = &export.declaration {
+                            Some(func.as_ref())
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(func) = func_ast {
+                    let type_param_names: Vec<String> = func
+                        .type_parameters
+                        .as_ref()
+                        .map(|tp| tp.params.iter().map(|p| p.name.name.to_string()).collect())
+                        .unwrap_or_default();
+                    let generics: HashMap<String, LltsType> = type_param_names
+                        .iter()
+                        .zip(concrete_types.iter())
+                        .map(|(name, ty)| (name.clone(), ty.clone()))
+                        .collect();
+                    // The generic's identifiers resolve against its own module
+                    ctx.current_module = decl_module;
+                    let specialized = lower_generic_function(func, ctx, &mangled_name, &generics);
+                    ctx.current_module = module_idx;
+                    if let Some(specialized) = specialized {
+                        ctx.fn_ret_types.insert(mangled_name.clone(), specialized.ret_type.clone());
+                        record_fn_throws(&specialized, ctx);
+                        functions.push(specialized);
                     }
                 }
-                _ => None,
-            };
-            if let Some(func) = func_ast {
-                let type_param_names: Vec<String> = func
-                    .type_parameters
-                    .as_ref()
-                    .map(|tp| tp.params.iter().map(|p| p.name.name.to_string()).collect())
-                    .unwrap_or_default();
-                let generics: HashMap<String, LltsType> = type_param_names
-                    .iter()
-                    .zip(concrete_types.iter())
-                    .map(|(name, ty)| (name.clone(), ty.clone()))
-                    .collect();
-                if let Some(specialized) = lower_generic_function(func, ctx, &mangled_name, &generics) {
-                    ctx.fn_ret_types.insert(mangled_name.clone(), specialized.ret_type.clone());
-                    functions.push(specialized);
-                }
             }
         }
+        for mangled in instances {
+            let fields = ctx.struct_defs.get(&mangled).cloned().unwrap_or_default();
+            structs.push(StructDecl { name: mangled.clone(), fields });
+            let generic_name = ctx.type_instances[&mangled].0.clone();
+            let decl = modules
+                .iter()
+                .enumerate()
+                .find_map(|(decl_module, module)| Some((decl_module, generic_class_decl(module, &generic_name)?)));
+            if let Some((decl_module, class)) = decl {
+                ctx.current_module = decl_module;
+                functions.extend(lower_class_instance(class, &mangled, ctx));
+                ctx.current_module = module_idx;
+            }
+        }
+        functions.extend(ctx.pending_functions.drain(..));
     }
 
     // Prepend top-level variable declarations to main's body.
@@ -438,6 +455,8 @@ pub(crate) fn lower_program_with_ctx(
         }
     }
 
+    box_escaping_captures(&mut functions);
+
     ProgramIR {
         structs,
         enums,
@@ -445,14 +464,46 @@ pub(crate) fn lower_program_with_ctx(
     }
 }
 
+/// The declaration of generic class `name` in `program`, if it is declared there.
+fn generic_class_decl<'p, 'a>(program: &'p Program<'a>, name: &str) -> Option<&'p Class<'a>> {
+    program.body.iter().find_map(|stmt| {
+        let class = match stmt {
+            Statement::ClassDeclaration(class) => class,
+            Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                Some(Declaration::ClassDeclaration(class)) => class,
+                _ => return None,
+            },
+            Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                ExportDefaultDeclarationKind::ClassDeclaration(class) => class,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let is_match = class.type_parameters.is_some() && class.id.as_ref().is_some_and(|id| id.name.as_str() == name);
+        is_match.then_some(&**class)
+    })
+}
+
+/// Record the return and parameter types of function `name`, and the
+/// shape of its parameters if it has optional, defaulted or rest ones.
+fn register_fn_signature(name: &str, func: &Function<'_>, ctx: &mut LowerCtx) {
+    let ret_type = func
+        .return_type
+        .as_ref()
+        .map(|r| lower_type_annotation(&r.type_annotation, ctx))
+        .unwrap_or(LltsType::Void);
+    ctx.fn_ret_types.insert(name.to_string(), ret_type);
+    let (param_types, shape) = signature_params(&func.params, ctx);
+    ctx.fn_param_types.insert(name.to_string(), param_types);
+    record_param_shape(name, shape, ctx);
+}
+
 fn lower_function(func: &Function<'_>, ctx: &mut LowerCtx) -> Option<FunctionDecl> {
     let name = func
         .id
         .as_ref()
         .map(|id| id.name.to_string())?;
 
-    let enum_names = ctx.enum_names();
-
     // Save state and restore after function (each function gets its own scope)
     let saved_vars = ctx.var_types.clone();
 
@@ -460,63 +511,40 @@ fn lower_function(func: &Function<'_>, ctx: &mut LowerCtx) -> Option<FunctionDec
     let fn_ret = ctx.fn_ret_types.get(&name).cloned().unwrap_or(LltsType::Void);
     ctx.var_types.insert("__fn_return_type__".to_string(), fn_ret);
 
-    let params: Vec<(String, LltsType)> = func
-        .params
-        .items
-        .iter()
-        .map(|p| {
-            let pname = binding_name(&p.pattern);
-            let pty = p
-                .type_annotation
-                .as_ref()
-                .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                .unwrap_or(LltsType::F64);
-            // Resolve struct types to include field info, or discriminated union types.
-            let pty = match &pty {
-                LltsType::Struct { name, fields } if fields.is_empty() => {
-                    if let Some(du) = ctx.discriminated_unions.get(name) {
-                        du.union_type.clone()
-                    } else {
-                        ctx.full_struct_type(name)
-                    }
-                }
-                other => other.clone(),
-            };
-            ctx.var_types.insert(pname.clone(), pty.clone());
-            (pname, pty)
-        })
-        .collect();
+    let BoundParams { params, prologue, .. } = bind_params(&func.params, &[], ctx);
 
     let ret_type = func
         .return_type
         .as_ref()
-        .map(|r| lower_ts_type_with_enums(&r.type_annotation, &enum_names))
+        .map(|r| lower_type_annotation(&r.type_annotation, ctx))
         .unwrap_or(LltsType::Void);
-    let ret_type = match &ret_type {
-        LltsType::Struct { name, fields } if fields.is_empty() => {
-            if let Some(du) = ctx.discriminated_unions.get(name) {
-                du.union_type.clone()
-            } else {
-                ctx.full_struct_type(name)
-            }
-        }
-        other => other.clone(),
-    };
 
-    let body = func
-        .body
-        .as_ref()
-        .map(|b| lower_stmts(&b.statements, ctx))
-        .unwrap_or_default();
+    if func.generator {
+        let decl = lower_generator(func, name, params, prologue, ret_type, ctx);
+        ctx.var_types = saved_vars;
+        return decl;
+    }
+    if func.r#async {
+        let decl = lower_async_function(func, name, params, prologue, ret_type, ctx);
+        ctx.var_types = saved_vars;
+        return decl;
+    }
+
+    let mut body = prologue;
+    if let Some(b) = &func.body {
+        body.extend(lower_stmts(&b.statements, ctx));
+    }
 
     ctx.var_types = saved_vars;
 
-    Some(FunctionDecl {
+    let decl = FunctionDecl {
         name,
         params,
         ret_type,
         body,
-    })
+    };
+    record_fn_throws(&decl, ctx);
+    Some(decl)
 }
 
 
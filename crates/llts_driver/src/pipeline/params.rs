@@ -0,0 +1,188 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, Stmt, types::LltsType};
+
+use super::context::{LowerCtx, ParamShape};
+use super::utils::{binding_name, coerce_to_type, infer_expr_type, into_option};
+use super::{lower_binding_pattern, lower_coalesce, lower_type_annotation, record_tuple_length, rest_param};
+
+// ---------------------------------------------------------------------------
+// Parameters: optional, default and rest
+// ---------------------------------------------------------------------------
+//
+// Optional and defaulted parameters are nullable; a call that leaves them
+// out passes `null`, and a default is applied at function entry:
+//
+//     function f(a: T, b: U = d, c?: V, ...r: W[]) { body }
+//     →  function f(a: T, b$arg: U | null, c: V | null, r: W[]) {
+//            const b: U = b$arg ?? d;
+//            body
+//        }
+//
+//     f(x)          →   f(x, null, null, [])
+//     f(x, y, z)    →   f(x, y, z, [])
+//
+// The default is evaluated in the callee, after the parameters before it are
+// bound, so it can refer to them. A destructured parameter is received as
+// `__param{i}` and destructured in the same place (see `destructuring`).
+// Rest parameters are packed by the call (see `spread`).
+
+/// The parameters of a function as its body sees them.
+pub(crate) struct BoundParams {
+    /// IR parameters: a defaulted parameter is received as `{name}$arg`.
+    pub(crate) params: Vec<(String, LltsType)>,
+    /// Statements binding defaulted parameters to their value, to run first.
+    pub(crate) prologue: Vec<Stmt>,
+    /// The parameters' shape, if calls may leave some out or pass more.
+    pub(crate) shape: Option<ParamShape>,
+}
+
+/// Whether a call may leave out `param`: it is optional or has a default.
+fn is_omittable(param: &FormalParameter<'_>) -> bool {
+    param.optional || param.initializer.is_some()
+}
+
+fn nullable(ty: LltsType) -> LltsType {
+    match ty {
+        LltsType::Option(_) => ty,
+        other => LltsType::Option(Box::new(other)),
+    }
+}
+
+/// The type `param` has in the function body: its annotation, else `hint`
+/// (from an expected function type) or the type of its default value.
+fn param_value_type(param: &FormalParameter<'_>, hint: Option<&LltsType>, ctx: &mut LowerCtx) -> LltsType {
+    if let Some(ann) = &param.type_annotation {
+        return lower_type_annotation(&ann.type_annotation, ctx);
+    }
+    match (hint, &param.initializer) {
+        (Some(LltsType::Option(inner)), _) if is_omittable(param) => ctx.resolve_annotation((**inner).clone()),
+        (Some(hint), _) => ctx.resolve_annotation(hint.clone()),
+        (None, Some(default)) => ctx.resolve_annotation(infer_expr_type(default)),
+        (None, None) => LltsType::F64,
+    }
+}
+
+/// The shape of `params`, or None if each parameter takes one argument.
+pub(crate) fn shape_of(params: &FormalParameters<'_>) -> Option<ParamShape> {
+    let required = params.items.iter().position(is_omittable).unwrap_or(params.items.len());
+    let rest = params.rest.is_some();
+    (required < params.items.len() || rest).then_some(ParamShape { required, rest })
+}
+
+/// The parameter types of `params` as callers see them (optional and
+/// defaulted ones nullable, a rest parameter last as its array type), and
+/// their shape.
+pub(crate) fn signature_params(params: &FormalParameters<'_>, ctx: &mut LowerCtx) -> (Vec<LltsType>, Option<ParamShape>) {
+    let mut types: Vec<LltsType> = params
+        .items
+        .iter()
+        .map(|p| {
+            let ty = param_value_type(p, None, ctx);
+            if is_omittable(p) { nullable(ty) } else { ty }
+        })
+        .collect();
+    if let Some((_, rest_type)) = rest_param(params, ctx) {
+        types.push(rest_type);
+    }
+    (types, shape_of(params))
+}
+
+/// Bring `params` into scope for lowering the function body. `hints` are
+/// the parameter types of an expected function type, for unannotated
+/// arrow function parameters.
+pub(crate) fn bind_params(params: &FormalParameters<'_>, hints: &[LltsType], ctx: &mut LowerCtx) -> BoundParams {
+    let mut bound = Vec::new();
+    let mut prologue = Vec::new();
+    for (i, param) in params.items.iter().enumerate() {
+        // A destructured parameter is received whole, as `__param{i}`.
+        let destructured = !matches!(param.pattern, BindingPattern::BindingIdentifier(_));
+        let name = if destructured { format!("__param{i}") } else { binding_name(&param.pattern, ctx) };
+        let value_type = param_value_type(param, hints.get(i), ctx);
+        record_tuple_length(&name, param.type_annotation.as_deref(), ctx);
+        ctx.closure_shapes.remove(&name);
+        let value_type = match &param.initializer {
+            Some(default) => {
+                let arg_name = format!("{name}$arg");
+                let arg_type = nullable(value_type.clone());
+                ctx.var_types.insert(arg_name.clone(), arg_type.clone());
+                let value = lower_coalesce(Expr::Var { name: arg_name.clone(), ty: arg_type.clone() }, default, ctx);
+                let value = match &value_type {
+                    LltsType::Option(inner) => into_option(value, inner),
+                    _ => coerce_to_type(value, &value_type),
+                };
+                ctx.var_types.insert(name.clone(), value_type.clone());
+                prologue.push(Stmt::VarDecl { name: name.clone(), ty: value_type.clone(), init: Some(value) });
+                bound.push((arg_name, arg_type));
+                value_type
+            }
+            None => {
+                let ty = if param.optional { nullable(value_type) } else { value_type };
+                ctx.var_types.insert(name.clone(), ty.clone());
+                bound.push((name.clone(), ty.clone()));
+                ty
+            }
+        };
+        if destructured {
+            let value = Expr::Var { name, ty: value_type };
+            prologue.extend(lower_binding_pattern(&param.pattern, value, ctx));
+        }
+    }
+    // `...rest: T[]` receives the trailing arguments as an array.
+    if let Some((rest_name, rest_type)) = rest_param(params, ctx) {
+        ctx.var_types.insert(rest_name.clone(), rest_type.clone());
+        bound.push((rest_name, rest_type));
+    }
+    BoundParams { params: bound, prologue, shape: shape_of(params) }
+}
+
+/// The shape of the function, closure variable or constructor `callee`
+/// and the parameter types its arguments fill, if it has a shape.
+pub(crate) fn call_target(callee: &str, ctx: &LowerCtx) -> Option<(ParamShape, Vec<LltsType>)> {
+    if let Some(LltsType::Function { params, .. }) = ctx.var_types.get(callee) {
+        return Some((ctx.closure_shapes.get(callee)?.clone(), params.clone()));
+    }
+    if ctx.var_types.contains_key(callee) {
+        return None;
+    }
+    Some((ctx.param_shapes.get(callee)?.clone(), ctx.fn_param_types.get(callee)?.clone()))
+}
+
+/// Like `call_target`, for method `mangled` (`{Class}_{method}`), whose
+/// receiver is not an argument.
+pub(crate) fn method_target(mangled: &str, ctx: &LowerCtx) -> Option<(ParamShape, Vec<LltsType>)> {
+    let shape = ctx.param_shapes.get(mangled)?.clone();
+    let params = ctx.fn_param_types.get(mangled)?.get(1..)?.to_vec();
+    Some((shape, params))
+}
+
+/// Pass `null` for the optional and defaulted parameters left out of a
+/// call whose fixed (non-rest) arguments are `args`.
+pub(crate) fn fill_omitted_args(shape: &ParamShape, params: &[LltsType], args: &mut Vec<Expr>, ctx: &mut LowerCtx) {
+    let fixed = params.len() - usize::from(shape.rest);
+    if args.len() < shape.required {
+        ctx.errors.push(format!("missing arguments: expected at least {}, got {}", shape.required, args.len()));
+        return;
+    }
+    if args.len() > fixed && !shape.rest {
+        ctx.errors.push(format!("too many arguments: expected at most {fixed}, got {}", args.len()));
+        return;
+    }
+    for param in params.iter().take(fixed).skip(args.len()) {
+        match param {
+            LltsType::Option(inner) => args.push(Expr::OptionNone { inner_type: (**inner).clone() }),
+            _ => {
+                ctx.errors.push(format!("missing arguments: expected {fixed}, got {}", args.len()));
+                return;
+            }
+        }
+    }
+}
+
+/// Record the shape of function `name`, replacing any earlier one.
+pub(crate) fn record_param_shape(name: &str, shape: Option<ParamShape>, ctx: &mut LowerCtx) {
+    match shape {
+        Some(shape) => ctx.param_shapes.insert(name.to_string(), shape),
+        None => ctx.param_shapes.remove(name),
+    };
+}
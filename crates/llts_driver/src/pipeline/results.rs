@@ -0,0 +1,217 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{
+    Expr,
+    expr::BinOp,
+    types::{LltsType, RESULT_ERR_TAG, RESULT_OK_TAG},
+};
+
+use super::context::{LowerCtx, source_name};
+use super::utils::{coerce_to_type, ir_expr_type, property_key_name};
+use super::{lower_expr, lower_expr_expecting};
+
+// ---------------------------------------------------------------------------
+// Result values: `Ok`, `Err` and `r.ok` narrowing
+// ---------------------------------------------------------------------------
+//
+// `Result<T, E>` is the prelude's `{ ok: true; value: T } | { ok: false;
+// error: E }`, lowered to the tagged `LltsType::Result` that functions
+// declaring it return:
+//
+//     Ok(x), { ok: true, value: x }       →   ResultOk(x)
+//     Err(e), { ok: false, error: e }     →   UnionLit(RESULT_ERR_TAG, e)
+//     r.ok                                →   r.tag == RESULT_OK_TAG
+//
+// `r.value` and `r.error` read the payload where `r.ok` has narrowed `r` to
+// that side: the branches of `if (r.ok)` and `r.ok ? ... : ...`, and the
+// code after an `if (!r.ok)` that returns, throws, breaks or continues. The
+// narrowed side is kept in `var_types` as `r.value` or `r.error`, so it ends
+// with the branch like other narrowing.
+
+/// Whether a call of `callee` is one of the Result constructors (`Ok`,
+/// `Err`) rather than a declared function or variable.
+pub(crate) fn is_result_builtin(callee: &str, ctx: &LowerCtx) -> bool {
+    matches!(callee, "Ok" | "Err")
+        && !ctx.fn_ret_types.contains_key(callee)
+        && !ctx.var_types.contains_key(callee)
+}
+
+/// Lower `Ok(x)` or `Err(e)` with no expected type. The side it does not
+/// construct is `void` until `retarget_result` gives it the Result type its
+/// context expects.
+pub(crate) fn lower_result_builtin(callee: &str, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    let is_ok = callee == "Ok";
+    if args.len() > 1 || (!is_ok && args.is_empty()) {
+        ctx.errors.push(format!("{callee}() takes 1 argument, got {}", args.len()));
+        args.resize(1, Expr::IntLit { value: 0, ty: LltsType::I32 });
+    }
+    let payload = args.pop();
+    let side = payload.as_ref().map(ir_expr_type).unwrap_or(LltsType::Void);
+    let result_type = if is_ok {
+        LltsType::Result { ok: Box::new(side), err: Box::new(LltsType::Void) }
+    } else {
+        LltsType::Result { ok: Box::new(LltsType::Void), err: Box::new(side) }
+    };
+    result_value(is_ok, payload, result_type)
+}
+
+/// Lower `expr` to a value of Result type `expected` if it constructs one:
+/// `Ok(x)`, `Err(e)`, `{ ok: true, value: x }` or `{ ok: false, error: e }`.
+/// The payload is lowered expecting its side of the Result.
+pub(crate) fn lower_result_expecting(expr: &Expression<'_>, expected: &LltsType, ctx: &mut LowerCtx) -> Option<Expr> {
+    let LltsType::Result { ok, err } = expected else { return None };
+    let (is_ok, payload) = match expr.without_parentheses() {
+        Expression::CallExpression(call) => {
+            let Expression::Identifier(id) = &call.callee else { return None };
+            if !is_result_builtin(&ctx.var_name(id), ctx)
+                || call.arguments.len() > 1
+                || matches!(call.arguments.first(), Some(Argument::SpreadElement(_)))
+            {
+                return None;
+            }
+            let payload = call.arguments.first().map(Argument::to_expression);
+            if id.name.as_str() == "Err" && payload.is_none() {
+                return None;
+            }
+            (id.name.as_str() == "Ok", payload)
+        }
+        Expression::ObjectExpression(obj) => result_object(obj)?,
+        _ => return None,
+    };
+    let side = if is_ok { ok } else { err };
+    let payload = payload.map(|p| coerce_to_type(lower_expr_expecting(p, side, ctx), side));
+    Some(result_value(is_ok, payload, expected.clone()))
+}
+
+/// The side and payload of an object literal shaped like a Result:
+/// `{ ok: true, value: x }`, `{ ok: false, error: e }`, or `{ ok: true }`
+/// for a `void` value.
+fn result_object<'b, 'a>(obj: &'b ObjectExpression<'a>) -> Option<(bool, Option<&'b Expression<'a>>)> {
+    let mut is_ok = None;
+    let mut payload = None;
+    for prop in &obj.properties {
+        let ObjectPropertyKind::ObjectProperty(p) = prop else { return None };
+        match (property_key_name(&p.key).as_str(), &p.value) {
+            ("ok", Expression::BooleanLiteral(b)) => is_ok = Some(b.value),
+            ("value" | "error", value) if payload.is_none() => payload = Some((property_key_name(&p.key), value)),
+            _ => return None,
+        }
+    }
+    match (is_ok?, payload) {
+        (true, None) => Some((true, None)),
+        (true, Some((field, value))) if field == "value" => Some((true, Some(value))),
+        (false, Some((field, value))) if field == "error" => Some((false, Some(value))),
+        _ => None,
+    }
+}
+
+fn result_value(is_ok: bool, payload: Option<Expr>, result_type: LltsType) -> Expr {
+    match (is_ok, payload) {
+        (true, value) => Expr::ResultOk { value: value.map(Box::new), result_type },
+        (false, payload) => Expr::UnionLit {
+            tag: RESULT_ERR_TAG,
+            payload: Box::new(payload.unwrap_or(Expr::IntLit { value: 0, ty: LltsType::I32 })),
+            union_type: result_type,
+        },
+    }
+}
+
+/// Give a constructed Result (e.g. `Ok(1)` lowered without an expected type)
+/// the Result type `expected`, coercing its payload to that side.
+pub(crate) fn retarget_result(expr: Expr, expected: &LltsType) -> Expr {
+    let LltsType::Result { ok, err } = expected else { return expr };
+    match expr {
+        Expr::ResultOk { value, .. } => Expr::ResultOk {
+            value: value.map(|v| Box::new(coerce_to_type(*v, ok))),
+            result_type: expected.clone(),
+        },
+        Expr::UnionLit { tag, payload, union_type: LltsType::Result { .. } } if tag == RESULT_ERR_TAG => Expr::UnionLit {
+            tag,
+            payload: Box::new(coerce_to_type(*payload, err)),
+            union_type: expected.clone(),
+        },
+        other => other,
+    }
+}
+
+/// Lower `object.field` on a value of type `result_type`: `ok` tests the
+/// tag, `value` and `error` read the payload where narrowing allows it.
+pub(crate) fn lower_result_field(object: Expr, obj_name: &str, field_name: &str, result_type: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    let tag = Expr::FieldAccess {
+        object: Box::new(object.clone()),
+        object_type: result_type.clone(),
+        field_index: 0,
+        field_type: LltsType::I32,
+    };
+    if field_name == "ok" {
+        return Expr::Binary {
+            op: BinOp::Eq,
+            lhs: Box::new(tag),
+            rhs: Box::new(Expr::IntLit { value: RESULT_OK_TAG as i64, ty: LltsType::I32 }),
+            ty: LltsType::I32,
+        };
+    }
+    let side = match field_name {
+        "value" => "true",
+        "error" => "false",
+        _ => {
+            ctx.errors.push(format!("Result has no field '{field_name}'; it has ok, value and error"));
+            return tag;
+        }
+    };
+    let field_type = match ctx.var_types.get(&format!("{obj_name}.{field_name}")) {
+        Some(ty) => ty.clone(),
+        None => {
+            let shown = if obj_name == "<expr>" { "result" } else { source_name(obj_name) };
+            ctx.errors.push(format!("`{shown}.{field_name}` can only be read where `{shown}.ok` is known to be {side}"));
+            LltsType::I32
+        }
+    };
+    Expr::FieldAccess {
+        object: Box::new(object),
+        object_type: result_type.clone(),
+        field_index: 1,
+        field_type,
+    }
+}
+
+/// Detect an if/conditional test narrowing a Result variable: `r.ok` (the
+/// consequent has `r.value`) or `!r.ok` (it has `r.error`). Returns the
+/// variable and whether the consequent sees the Ok side.
+pub(crate) fn detect_result_narrowing(test: &Expression<'_>, ctx: &LowerCtx) -> Option<(String, bool)> {
+    match test.without_parentheses() {
+        Expression::UnaryExpression(unary) if unary.operator == UnaryOperator::LogicalNot => {
+            detect_result_narrowing(&unary.argument, ctx).map(|(name, ok)| (name, !ok))
+        }
+        Expression::StaticMemberExpression(member) if member.property.name == "ok" => {
+            let Expression::Identifier(id) = member.object.without_parentheses() else { return None };
+            let name = ctx.var_name(id);
+            matches!(ctx.var_types.get(&name), Some(LltsType::Result { .. })).then_some((name, true))
+        }
+        _ => None,
+    }
+}
+
+/// Narrow Result variable `name` to its Ok side (`name.value` readable) or
+/// its Err side (`name.error` readable).
+pub(crate) fn narrow_result(name: &str, ok: bool, ctx: &mut LowerCtx) {
+    let Some(LltsType::Result { ok: ok_type, err }) = ctx.var_types.get(name).cloned() else { return };
+    let (field, ty) = if ok { ("value", *ok_type) } else { ("error", *err) };
+    ctx.var_types.insert(format!("{name}.{field}"), ty);
+}
+
+/// Drop the narrowing of `name` when it is assigned a new value.
+pub(crate) fn forget_result_narrowing(name: &str, ctx: &mut LowerCtx) {
+    ctx.var_types.remove(&format!("{name}.value"));
+    ctx.var_types.remove(&format!("{name}.error"));
+}
+
+/// Lower `expr` with Result variable `name` narrowed to one side, if any.
+pub(crate) fn lower_narrowed(expr: &Expression<'_>, narrowing: Option<(&str, bool)>, ctx: &mut LowerCtx) -> Expr {
+    let Some((name, ok)) = narrowing else { return lower_expr(expr, ctx) };
+    let saved_vars = ctx.var_types.clone();
+    narrow_result(name, ok, ctx);
+    let lowered = lower_expr(expr, ctx);
+    ctx.var_types = saved_vars;
+    lowered
+}
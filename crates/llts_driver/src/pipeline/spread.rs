@@ -0,0 +1,206 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, types::LltsType};
+
+use super::context::{LowerCtx, ParamShape};
+use super::utils::{binding_name, coerce_to_type, ir_expr_type};
+use super::{codegen_type_suffix, fill_omitted_args, lower_array_element, lower_type_annotation};
+
+// ---------------------------------------------------------------------------
+// Spread: `[...a, x]`, `f(...args)` and rest parameters
+// ---------------------------------------------------------------------------
+//
+// An array literal with spread elements concatenates its parts into one
+// allocation of their summed length, runs of plain elements forming literal
+// parts:
+//
+//     [...a, x, y, ...b]                 →   ArrayConcat [a, [x, y], b]
+//
+// A rest parameter (`...xs: T[]`) is an array parameter; call sites pack
+// the trailing arguments into it, spreading arrays as above:
+//
+//     sum(1, 2)                          →   sum([1, 2])
+//     sum(...xs, 3)                      →   sum(ArrayConcat [xs, [3]])
+//
+// Any other spread argument must be a tuple (an array literal, or a variable
+// with a tuple type), spread into one argument per element:
+//
+//     add(...pair)  with pair: [i32, i32]   →   add(pair[0], pair[1])
+
+/// The name and array type of the rest parameter in `params`, if any.
+pub(crate) fn rest_param(params: &FormalParameters<'_>, ctx: &mut LowerCtx) -> Option<(String, LltsType)> {
+    let rest = params.rest.as_ref()?;
+    let ty = rest
+        .type_annotation
+        .as_ref()
+        .map(|ann| lower_type_annotation(&ann.type_annotation, ctx))
+        .unwrap_or_else(|| LltsType::Array(Box::new(LltsType::F64)));
+    Some((binding_name(&rest.rest.argument, ctx), ty))
+}
+
+/// Remember the length of variable `name` if `ann` is a tuple type, so it
+/// can be spread into arguments.
+pub(crate) fn record_tuple_length(name: &str, ann: Option<&TSTypeAnnotation<'_>>, ctx: &mut LowerCtx) {
+    match ann.map(|a| &a.type_annotation) {
+        Some(TSType::TSTupleType(tuple)) => {
+            ctx.tuple_lengths.insert(name.to_string(), tuple.element_types.len());
+        }
+        _ => {
+            ctx.tuple_lengths.remove(name);
+        }
+    }
+}
+
+/// Lower an array literal with spread elements, or None if it has none.
+pub(crate) fn lower_array_spread(arr: &ArrayExpression<'_>, ctx: &mut LowerCtx) -> Option<Expr> {
+    if !arr.elements.iter().any(|el| matches!(el, ArrayExpressionElement::SpreadElement(_))) {
+        return None;
+    }
+    let parts: Vec<(bool, Expr)> = arr
+        .elements
+        .iter()
+        .filter(|el| !matches!(el, ArrayExpressionElement::Elision(_)))
+        .map(|el| {
+            let spread = matches!(el, ArrayExpressionElement::SpreadElement(_));
+            (spread, lower_array_element(el, ctx))
+        })
+        .collect();
+    // Spread arrays are typed; literal elements follow them.
+    let elem_type = parts
+        .iter()
+        .find_map(|(spread, e)| match (spread, ir_expr_type(e)) {
+            (true, LltsType::Array(elem)) => Some(*elem),
+            _ => None,
+        })
+        .or_else(|| parts.iter().find(|(spread, _)| !spread).map(|(_, e)| ir_expr_type(e)))
+        .unwrap_or(LltsType::F64);
+    Some(pack_array(parts, &elem_type, ctx))
+}
+
+/// Build an array of `elem_type` from `parts`: single elements, or arrays
+/// (`true`) whose elements are spread into it.
+fn pack_array(parts: Vec<(bool, Expr)>, elem_type: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    let literal = |elements: Vec<Expr>| Expr::ArrayLit { elem_type: elem_type.clone(), elements };
+    let mut arrays: Vec<Expr> = Vec::new();
+    let mut run: Vec<Expr> = Vec::new();
+    for (spread, part) in parts {
+        if !spread {
+            run.push(coerce_to_type(part, elem_type));
+            continue;
+        }
+        match ir_expr_type(&part) {
+            LltsType::Array(elem) if *elem == *elem_type => {}
+            other => ctx.errors.push(format!(
+                "cannot spread {} into an array of {}",
+                codegen_type_suffix(&other),
+                codegen_type_suffix(elem_type)
+            )),
+        }
+        if !run.is_empty() {
+            arrays.push(literal(std::mem::take(&mut run)));
+        }
+        arrays.push(part);
+    }
+    if arrays.is_empty() {
+        return literal(run);
+    }
+    if !run.is_empty() {
+        arrays.push(literal(run));
+    }
+    Expr::ArrayConcat { elem_type: elem_type.clone(), parts: arrays }
+}
+
+/// The parameter type each of `arg_count` arguments to `callee` is lowered
+/// against: a rest parameter's element type for the trailing ones.
+pub(crate) fn param_hints(callee: &str, arg_count: usize, ctx: &LowerCtx) -> Vec<LltsType> {
+    let mut params = ctx.fn_param_types.get(callee).cloned().unwrap_or_default();
+    if ctx.param_shapes.get(callee).is_some_and(|shape| shape.rest) {
+        if let Some(LltsType::Array(elem)) = params.pop() {
+            params.resize(arg_count.max(params.len()), *elem);
+        }
+    }
+    params
+}
+
+/// Resolve the arguments of a call to `target` (its parameter shape and
+/// types, see `call_target`): pack the trailing arguments for a rest
+/// parameter into its array, spread tuples into one argument per element,
+/// and pass `null` for omitted optional parameters.
+pub(crate) fn spread_call_args(
+    target: Option<&(ParamShape, Vec<LltsType>)>,
+    arguments: &[Argument<'_>],
+    args: Vec<Expr>,
+    ctx: &mut LowerCtx,
+) -> Vec<Expr> {
+    if target.is_none() && !arguments.iter().any(|a| matches!(a, Argument::SpreadElement(_))) {
+        return args;
+    }
+    // Fixed parameter count and rest element type
+    let rest = target.filter(|(shape, _)| shape.rest).and_then(|(_, params)| match params.last()? {
+        LltsType::Array(elem) => Some((params.len() - 1, *elem.clone())),
+        _ => None,
+    });
+
+    let mut fixed: Vec<Expr> = Vec::new();
+    let mut trailing: Vec<(bool, Expr)> = Vec::new();
+    let mut push = |spread: bool, arg: Expr, fixed: &mut Vec<Expr>| match &rest {
+        Some((count, _)) if fixed.len() >= *count => trailing.push((spread, arg)),
+        _ => fixed.push(arg),
+    };
+    for (argument, arg) in arguments.iter().zip(args) {
+        let Argument::SpreadElement(spread) = argument else {
+            push(false, arg, &mut fixed);
+            continue;
+        };
+        // Spread straight into the rest array when every fixed parameter has its argument.
+        if rest.as_ref().is_some_and(|(count, _)| fixed.len() >= *count) {
+            push(true, arg, &mut fixed);
+            continue;
+        }
+        match tuple_elements(&spread.argument, arg, ctx) {
+            Some(elements) => {
+                for element in elements {
+                    push(false, element, &mut fixed);
+                }
+            }
+            None => {
+                ctx.errors.push(
+                    "a spread argument must be a tuple or passed to a rest parameter".to_string(),
+                );
+            }
+        }
+    }
+    if let Some((shape, params)) = target {
+        fill_omitted_args(shape, params, &mut fixed, ctx);
+    }
+    if let Some((_, elem_type)) = &rest {
+        let packed = pack_array(trailing, elem_type, ctx);
+        fixed.push(packed);
+    }
+    fixed
+}
+
+/// The elements of spread source `source` (lowered to `value`) if it is a
+/// tuple: an array literal, or a variable with a tuple type.
+fn tuple_elements(source: &Expression<'_>, value: Expr, ctx: &LowerCtx) -> Option<Vec<Expr>> {
+    match (source.without_parentheses(), value) {
+        (Expression::ArrayExpression(_), Expr::ArrayLit { elements, .. }) => Some(elements),
+        (Expression::Identifier(id), value) => {
+            let len = *ctx.tuple_lengths.get(&ctx.var_name(id))?;
+            let elem_type = match ir_expr_type(&value) {
+                LltsType::Array(elem) => *elem,
+                _ => return None,
+            };
+            Some(
+                (0..len)
+                    .map(|i| Expr::ArrayIndex {
+                        array: Box::new(value.clone()),
+                        index: Box::new(Expr::IntLit { value: i as i64, ty: LltsType::I64 }),
+                        elem_type: elem_type.clone(),
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
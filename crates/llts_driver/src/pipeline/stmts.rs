@@ -6,12 +6,20 @@ use llts_codegen::{
 };
 
 use super::context::LowerCtx;
-use super::utils::{binding_name, coerce_to_type, detect_null_comparison, infer_expr_type, ir_expr_type, property_key_name};
-use super::{build_union_lit_from_object, lower_expr, lower_ts_type_with_enums, try_lower_as_assign, try_lower_discriminated_switch};
+use super::utils::{
+    binding_name, coerce_to_type, detect_null_comparison, infer_expr_type, ir_expr_type, takes_init_type,
+};
+use super::{
+    bind_catch_param, build_union_lit_from_object, detect_instanceof_narrowing, detect_result_narrowing,
+    generator_elem, generator_for_of, iterable_source, iteration_source, iterator_class, iterator_for_of,
+    lower_binding_pattern, lower_expr, lower_expr_expecting, lower_result_expecting, lower_throw,
+    lower_type_annotation, narrow_result, record_tuple_length, retarget_result, try_lower_as_assign,
+    try_lower_discriminated_switch, try_name_lambda,
+};
 
 /// After patching a StructLit's struct_type, coerce field values to match
 /// the declared field types (e.g. f64 literal → i64 IntLit).
-fn coerce_struct_fields(fields: &mut Vec<Expr>, struct_type: &LltsType) {
+pub(crate) fn coerce_struct_fields(fields: &mut Vec<Expr>, struct_type: &LltsType) {
     if let LltsType::Struct { fields: type_fields, .. } = struct_type {
         for (i, (_, field_ty)) in type_fields.iter().enumerate() {
             if i < fields.len() {
@@ -22,6 +30,71 @@ fn coerce_struct_fields(fields: &mut Vec<Expr>, struct_type: &LltsType) {
     }
 }
 
+/// Lower the initializer of a variable declared (or inferred) as `ty`,
+/// converting it to that type.
+fn lower_declared_init(init: &Expression<'_>, ty: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    // Check if the type is a discriminated union for object literal construction.
+    let du_name = match ty {
+        LltsType::Union { name: un, .. } if ctx.discriminated_unions.contains_key(un) => Some(un.clone()),
+        _ => None,
+    };
+    let union_lit = match (&du_name, init) {
+        (Some(du_n), Expression::ObjectExpression(obj)) => build_union_lit_from_object(obj, du_n, ctx),
+        _ => None,
+    };
+    let mut init = union_lit.unwrap_or_else(|| lower_expr_expecting(init, ty, ctx));
+    // Coerce init to the declared type (e.g. `const x: i64 = 1`)
+    init = coerce_to_type(init, ty);
+    if let Expr::StructLit { struct_type, fields } = &mut init {
+        if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
+            *struct_type = ty.clone();
+        }
+        coerce_struct_fields(fields, struct_type);
+    }
+    // Array element type coercion: when declared type is Array(T)
+    // and init is ArrayLit { elem_type: U } where T != U,
+    // patch elem_type to T and wrap each element in Cast.
+    if let LltsType::Array(declared_elem) = ty {
+        if let Expr::ArrayLit { elem_type, elements } = &mut init {
+            if *elem_type != **declared_elem {
+                let from = elem_type.clone();
+                *elem_type = *declared_elem.clone();
+                // Also patch StructLit elements inside array literals
+                if let LltsType::Struct { name: sname, .. } = &**declared_elem {
+                    for el in elements.iter_mut() {
+                        if let Expr::StructLit { struct_type, fields } = el {
+                            if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
+                                *struct_type = ctx.full_struct_type(sname);
+                            }
+                            coerce_struct_fields(fields, struct_type);
+                        }
+                    }
+                }
+                // For numeric type mismatches, coerce each element
+                if from != *elem_type && !matches!(*elem_type, LltsType::Struct { .. }) {
+                    let to = elem_type.clone();
+                    *elements = elements.drain(..).map(|e| {
+                        coerce_to_type(e, &to)
+                    }).collect();
+                }
+            }
+        }
+    }
+    // Option wrapping: when declared type is Option<T>,
+    // fix up null literals and wrap non-null values.
+    // Skip wrapping if the init already produces Option<T>.
+    if let LltsType::Option(inner) = ty {
+        let already_option = matches!(ir_expr_type(&init), LltsType::Option(_));
+        init = match init {
+            Expr::OptionNone { .. } => Expr::OptionNone { inner_type: *inner.clone() },
+            Expr::OptionSome { .. } => init,
+            _ if already_option => init,
+            _ => Expr::OptionSome { value: Box::new(init), inner_type: *inner.clone() },
+        };
+    }
+    init
+}
+
 pub(crate) fn lower_stmts(stmts: &[Statement<'_>], ctx: &mut LowerCtx) -> Vec<Stmt> {
     stmts.iter().flat_map(|s| lower_stmt(s, ctx)).collect()
 }
@@ -33,211 +106,47 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
             for declarator in &decl.declarations {
                 // Check for destructuring patterns before simple binding
                 match &declarator.id {
-                    BindingPattern::ObjectPattern(obj_pat) => {
-                        // Object destructuring: const { x, y } = expr;
-                        if let Some(init_expr) = &declarator.init {
-                            let init_lowered = lower_expr(init_expr, ctx);
-                            let init_ty = ir_expr_type(&init_lowered);
-                            let init_ty = match &init_ty {
-                                LltsType::Struct { name: sname, fields } if fields.is_empty() => {
-                                    ctx.full_struct_type(sname)
-                                }
-                                other => other.clone(),
-                            };
-                            let tmp_name = format!("__destructure_tmp_{}", result.len());
-                            let mut patched_init = init_lowered;
-                            if let Expr::StructLit { struct_type, fields } = &mut patched_init {
-                                if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                                    *struct_type = init_ty.clone();
-                                }
-                                coerce_struct_fields(fields, struct_type);
+                    BindingPattern::ObjectPattern(_) | BindingPattern::ArrayPattern(_) => {
+                        // `const { a, b: [c, d] } = e` destructures the value
+                        // of `e`, converted to the annotated type if any.
+                        let Some(init_expr) = &declarator.init else { continue };
+                        let value = match &declarator.type_annotation {
+                            Some(ann) => {
+                                let ty = lower_type_annotation(&ann.type_annotation, ctx);
+                                lower_declared_init(init_expr, &ty, ctx)
                             }
-                            ctx.var_types.insert(tmp_name.clone(), init_ty.clone());
-                            result.push(Stmt::VarDecl {
-                                name: tmp_name.clone(),
-                                ty: init_ty.clone(),
-                                init: Some(patched_init),
-                            });
-
-                            let struct_name = match &init_ty {
-                                LltsType::Struct { name, .. } => name.clone(),
-                                _ => String::new(),
-                            };
-                            for prop in &obj_pat.properties {
-                                let field_name = property_key_name(&prop.key);
-                                let var_name = binding_name(&prop.value);
-                                let var_name = if var_name == "_" { field_name.clone() } else { var_name };
-
-                                if let Some((field_index, field_type)) = ctx.lookup_field(&struct_name, &field_name) {
-                                    ctx.var_types.insert(var_name.clone(), field_type.clone());
-                                    result.push(Stmt::VarDecl {
-                                        name: var_name,
-                                        ty: field_type.clone(),
-                                        init: Some(Expr::FieldAccess {
-                                            object: Box::new(Expr::Var {
-                                                name: tmp_name.clone(),
-                                                ty: init_ty.clone(),
-                                            }),
-                                            object_type: init_ty.clone(),
-                                            field_index,
-                                            field_type,
-                                        }),
-                                    });
-                                } else {
-                                    ctx.var_types.insert(var_name.clone(), LltsType::F64);
-                                    result.push(Stmt::VarDecl {
-                                        name: var_name,
-                                        ty: LltsType::F64,
-                                        init: None,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                    BindingPattern::ArrayPattern(arr_pat) => {
-                        // Array destructuring: const [a, b] = expr;
-                        if let Some(init_expr) = &declarator.init {
-                            let init_lowered = lower_expr(init_expr, ctx);
-                            let init_ty = ir_expr_type(&init_lowered);
-                            let elem_type = match &init_ty {
-                                LltsType::Array(elem) => *elem.clone(),
-                                _ => LltsType::F64,
-                            };
-
-                            let tmp_name = format!("__destructure_tmp_{}", result.len());
-                            ctx.var_types.insert(tmp_name.clone(), init_ty.clone());
-                            result.push(Stmt::VarDecl {
-                                name: tmp_name.clone(),
-                                ty: init_ty.clone(),
-                                init: Some(init_lowered),
-                            });
-
-                            for (i, elem) in arr_pat.elements.iter().enumerate() {
-                                if let Some(binding) = elem {
-                                    let var_name = binding_name(binding);
-                                    if var_name == "_" {
-                                        continue;
-                                    }
-                                    ctx.var_types.insert(var_name.clone(), elem_type.clone());
-                                    result.push(Stmt::VarDecl {
-                                        name: var_name,
-                                        ty: elem_type.clone(),
-                                        init: Some(Expr::ArrayIndex {
-                                            array: Box::new(Expr::Var {
-                                                name: tmp_name.clone(),
-                                                ty: init_ty.clone(),
-                                            }),
-                                            index: Box::new(Expr::IntLit {
-                                                value: i as i64,
-                                                ty: LltsType::I64,
-                                            }),
-                                            elem_type: elem_type.clone(),
-                                        }),
-                                    });
-                                }
-                            }
-                        }
+                            None => lower_expr(init_expr, ctx),
+                        };
+                        result.extend(lower_binding_pattern(&declarator.id, value, ctx));
                     }
                     _ => {
                         // Simple binding (BindingIdentifier or AssignmentPattern)
-                        let name = binding_name(&declarator.id);
-                        let enum_names = ctx.enum_names();
-                        let ty = declarator
-                            .type_annotation
-                            .as_ref()
-                            .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                            .or_else(|| declarator.init.as_ref().map(|e| infer_expr_type(e)))
-                            .unwrap_or(LltsType::F64);
-                        let ty = match &ty {
-                            LltsType::Struct { name: sname, fields } if fields.is_empty() => {
-                                // Check if this is a discriminated union type.
-                                if let Some(du) = ctx.discriminated_unions.get(sname) {
-                                    du.union_type.clone()
-                                } else {
-                                    ctx.full_struct_type(sname)
-                                }
+                        let name = binding_name(&declarator.id, ctx);
+                        // Struct names resolve to their fields, discriminated
+                        // unions or `Dyn` views.
+                        let ty = match &declarator.type_annotation {
+                            Some(ann) => lower_type_annotation(&ann.type_annotation, ctx),
+                            None => {
+                                let inferred = declarator.init.as_ref().map(|e| infer_expr_type(e)).unwrap_or(LltsType::F64);
+                                ctx.resolve_annotation(inferred)
                             }
-                            other => other.clone(),
                         };
                         ctx.var_types.insert(name.clone(), ty.clone());
+                        record_tuple_length(&name, declarator.type_annotation.as_deref(), ctx);
 
-                        // Check if the type is a discriminated union for object literal construction.
-                        let du_name = match &ty {
-                            LltsType::Union { name: un, .. } if ctx.discriminated_unions.contains_key(un) => {
-                                Some(un.clone())
-                            }
-                            _ => None,
-                        };
-                        let mut init = if let (Some(du_n), Some(Expression::ObjectExpression(obj))) =
-                            (&du_name, declarator.init.as_ref())
-                        {
-                            build_union_lit_from_object(obj, du_n, ctx)
-                        } else {
-                            declarator.init.as_ref().map(|e| lower_expr(e, ctx))
-                        };
-                        // Coerce init to the declared type (e.g. `const x: i64 = 1`)
-                        init = init.map(|e| coerce_to_type(e, &ty));
-                        if let Some(Expr::StructLit { struct_type, fields }) = &mut init {
-                            if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                                *struct_type = ty.clone();
-                            }
-                            coerce_struct_fields(fields, struct_type);
+                        let init = declarator.init.as_ref().map(|e| lower_declared_init(e, &ty, ctx));
+                        if try_name_lambda(&init, &name, ctx) {
+                            continue;
                         }
-                        // Array element type coercion: when declared type is Array(T)
-                        // and init is ArrayLit { elem_type: U } where T != U,
-                        // patch elem_type to T and wrap each element in Cast.
-                        if let LltsType::Array(ref declared_elem) = ty {
-                            if let Some(Expr::ArrayLit { elem_type, elements }) = &mut init {
-                                if *elem_type != **declared_elem {
-                                    let from = elem_type.clone();
-                                    *elem_type = *declared_elem.clone();
-                                    // Also patch StructLit elements inside array literals
-                                    if let LltsType::Struct { name: sname, .. } = &**declared_elem {
-                                        for el in elements.iter_mut() {
-                                            if let Expr::StructLit { struct_type, fields } = el {
-                                                if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                                                    *struct_type = ctx.full_struct_type(sname);
-                                                }
-                                                coerce_struct_fields(fields, struct_type);
-                                            }
-                                        }
-                                    }
-                                    // For numeric type mismatches, coerce each element
-                                    if from != *elem_type && !matches!(*elem_type, LltsType::Struct { .. }) {
-                                        let to = elem_type.clone();
-                                        *elements = elements.drain(..).map(|e| {
-                                            coerce_to_type(e, &to)
-                                        }).collect();
-                                    }
-                                }
+                        // Without an annotation, function values and class instances
+                        // take the type of their initializer.
+                        let ty = match init.as_ref().map(ir_expr_type) {
+                            Some(init_ty) if declarator.type_annotation.is_none() && takes_init_type(&init_ty) => {
+                                ctx.var_types.insert(name.clone(), init_ty.clone());
+                                init_ty
                             }
-                        }
-                        // Option wrapping: when declared type is Option<T>,
-                        // fix up null literals and wrap non-null values.
-                        // Skip wrapping if the init already produces Option<T>.
-                        if let LltsType::Option(ref inner) = ty {
-                            init = init.map(|e| {
-                                let already_option = matches!(ir_expr_type(&e), LltsType::Option(_));
-                                match e {
-                                    Expr::OptionNone { .. } => Expr::OptionNone { inner_type: *inner.clone() },
-                                    Expr::OptionSome { .. } => e,
-                                    _ if already_option => e,
-                                    _ => Expr::OptionSome { value: Box::new(e), inner_type: *inner.clone() },
-                                }
-                            });
-                        }
-                        if let Some(Expr::Var { name: ref lambda_name, .. }) = init {
-                            if lambda_name.starts_with("__lambda_") {
-                                let lname = lambda_name.clone();
-                                if let Some(func) = ctx.pending_functions.iter_mut().find(|f| f.name == lname) {
-                                    func.name = name.clone();
-                                }
-                                if let Some(ret) = ctx.fn_ret_types.get(&lname).cloned() {
-                                    ctx.fn_ret_types.insert(name.clone(), ret);
-                                }
-                                continue;
-                            }
-                        }
+                            _ => ty,
+                        };
                         result.push(Stmt::VarDecl { name, ty, init });
                     }
                 }
@@ -252,22 +161,38 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
             }
         }
         Statement::ReturnStatement(ret) => {
-            let mut expr = ret.argument.as_ref().map(|e| lower_expr(e, ctx));
+            // A declared `Result<T, E>` returns `T` values, wrapped in `Ok`
+            // below, and Result values as they are; its errors come from
+            // throws and `Err(e)`.
+            let fn_ret = ctx.var_types.get("__fn_return_type__").cloned();
+            if let (Some(fn_ret), Some(arg)) = (&fn_ret, &ret.argument) {
+                if let Some(result) = lower_result_expecting(arg, fn_ret, ctx) {
+                    return vec![Stmt::Return(Some(result))];
+                }
+            }
+            let value_ret = match &fn_ret {
+                Some(LltsType::Result { ok, .. }) => Some(*ok.clone()),
+                other => other.clone(),
+            };
+            let mut expr = ret.argument.as_ref().map(|e| match &value_ret {
+                Some(t) => lower_expr_expecting(e, t, ctx),
+                None => lower_expr(e, ctx),
+            });
             // Coerce return value to function return type
-            if let Some(fn_ret) = ctx.var_types.get("__fn_return_type__").cloned() {
-                expr = expr.map(|e| coerce_to_type(e, &fn_ret));
+            if let Some(value_ret) = &value_ret {
+                expr = expr.map(|e| coerce_to_type(e, value_ret));
             }
             // Patch StructLit type from function return type
             if let Some(Expr::StructLit { struct_type, fields }) = &mut expr {
                 if matches!(struct_type, LltsType::Struct { name: n, .. } if n.is_empty()) {
-                    if let Some(fn_ret) = ctx.var_types.get("__fn_return_type__") {
-                        *struct_type = fn_ret.clone();
+                    if let Some(value_ret) = &value_ret {
+                        *struct_type = value_ret.clone();
                     }
                 }
                 coerce_struct_fields(fields, struct_type);
             }
             // Option wrapping for return values when function returns Option<T>
-            if let Some(fn_ret) = ctx.var_types.get("__fn_return_type__").cloned() {
+            if let Some(fn_ret) = fn_ret {
                 if let LltsType::Option(ref inner) = fn_ret {
                     expr = expr.map(|e| {
                         let already_option = matches!(ir_expr_type(&e), LltsType::Option(_));
@@ -279,12 +204,20 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
                         }
                     });
                 }
+                if let LltsType::Result { .. } = fn_ret {
+                    expr = Some(match expr {
+                        Some(e) if matches!(ir_expr_type(&e), LltsType::Result { .. }) => retarget_result(e, &fn_ret),
+                        value => Expr::ResultOk { value: value.map(Box::new), result_type: fn_ret },
+                    });
+                }
             }
             vec![Stmt::Return(expr)]
         }
         Statement::IfStatement(if_stmt) => {
             // Detect null comparison patterns for Option narrowing
             let null_narrow_info = detect_null_comparison(&if_stmt.test, ctx);
+            let instanceof_narrow_info = detect_instanceof_narrowing(&if_stmt.test, ctx);
+            let result_narrow_info = detect_result_narrowing(&if_stmt.test, ctx);
             let condition = lower_expr(&if_stmt.test, ctx);
 
             let then_body = {
@@ -293,10 +226,24 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
                 if let Some((ref var_name, ref inner_ty, true)) = null_narrow_info {
                     ctx.var_types.insert(var_name.clone(), inner_ty.clone());
                 }
+                // If `e instanceof C`, narrow e to the matching member
+                if let Some((ref var_name, ref narrowed_ty, _)) = instanceof_narrow_info {
+                    ctx.var_types.insert(var_name.clone(), narrowed_ty.clone());
+                }
+                // If `r.ok`, r.value is readable in the then-branch (r.error if `!r.ok`)
+                if let Some((ref var_name, ok)) = result_narrow_info {
+                    narrow_result(var_name, ok, ctx);
+                }
                 let mut stmts = match &if_stmt.consequent {
                     Statement::BlockStatement(block) => lower_stmts(&block.body, ctx),
                     other => lower_stmt(other, ctx),
                 };
+                // The narrowed binding is scoped to a block, so the union is
+                // visible again after the branch.
+                if let Some((var_name, narrowed_ty, value)) = instanceof_narrow_info {
+                    stmts.insert(0, Stmt::VarDecl { name: var_name, ty: narrowed_ty, init: Some(value) });
+                    stmts = vec![Stmt::Block(stmts)];
+                }
                 // Prepend an unwrap assignment if narrowing (x !== null in then)
                 if let Some((ref var_name, ref inner_ty, true)) = null_narrow_info {
                     let opt_ty = LltsType::Option(Box::new(inner_ty.clone()));
@@ -319,6 +266,9 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
                 if let Some((ref var_name, ref inner_ty, false)) = null_narrow_info {
                     ctx.var_types.insert(var_name.clone(), inner_ty.clone());
                 }
+                if let Some((ref var_name, ok)) = result_narrow_info {
+                    narrow_result(var_name, !ok, ctx);
+                }
                 let mut stmts = match alt {
                     Statement::BlockStatement(block) => lower_stmts(&block.body, ctx),
                     other => lower_stmt(other, ctx),
@@ -339,6 +289,21 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
                 stmts
             });
 
+            // `if (!r.ok) { return ...; }` leaves r.value readable after it.
+            if let (Some((var_name, ok)), None) = (&result_narrow_info, &else_body) {
+                let exits = |stmt: &Statement<'_>| {
+                    matches!(stmt, Statement::ReturnStatement(_) | Statement::ThrowStatement(_)
+                        | Statement::BreakStatement(_) | Statement::ContinueStatement(_))
+                };
+                let then_exits = match &if_stmt.consequent {
+                    Statement::BlockStatement(block) => block.body.last().is_some_and(exits),
+                    other => exits(other),
+                };
+                if then_exits {
+                    narrow_result(var_name, !ok, ctx);
+                }
+            }
+
             vec![Stmt::If {
                 condition,
                 then_body,
@@ -357,14 +322,13 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
             let init = for_stmt.init.as_ref().and_then(|i| match i {
                 ForStatementInit::VariableDeclaration(decl) => {
                     let declarator = &decl.declarations[0];
-                    let name = binding_name(&declarator.id);
-                    let ty = declarator
-                        .init
-                        .as_ref()
-                        .map(|e| infer_expr_type(e))
-                        .unwrap_or(LltsType::F64);
+                    let name = binding_name(&declarator.id, ctx);
+                    let ty = match &declarator.type_annotation {
+                        Some(ann) => lower_type_annotation(&ann.type_annotation, ctx),
+                        None => declarator.init.as_ref().map(|e| infer_expr_type(e)).unwrap_or(LltsType::F64),
+                    };
                     ctx.var_types.insert(name.clone(), ty.clone());
-                    let init_expr = declarator.init.as_ref().map(|e| lower_expr(e, ctx));
+                    let init_expr = declarator.init.as_ref().map(|e| coerce_to_type(lower_expr(e, ctx), &ty));
                     Some(Box::new(Stmt::VarDecl {
                         name,
                         ty,
@@ -389,15 +353,30 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
             }]
         }
         Statement::ForOfStatement(forof) => {
-            let elem_name = match &forof.left {
-                ForStatementLeft::VariableDeclaration(decl) => {
-                    binding_name(&decl.declarations[0].id)
+            let pattern = match &forof.left {
+                ForStatementLeft::VariableDeclaration(decl) => match &decl.declarations[0].id {
+                    BindingPattern::BindingIdentifier(_) => None,
+                    pattern => Some(pattern),
+                },
+                _ => None,
+            };
+            let elem_name = match (&forof.left, pattern) {
+                // `for (const [k, v] of ...)` binds a temporary and destructures it
+                (_, Some(_)) => format!("__forof_elem_{}", forof.span.start),
+                (ForStatementLeft::VariableDeclaration(decl), None) => {
+                    binding_name(&decl.declarations[0].id, ctx)
                 }
                 _ => "_".to_string(),
             };
+            // Maps iterate their entries, sets their values, iterables what
+            // `iter()` returns, generators and iterators what `next()` returns.
             let iterable = lower_expr(&forof.right, ctx);
-            let elem_type = match ir_expr_type(&iterable) {
-                LltsType::Array(inner) => *inner,
+            let iterable = iteration_source(iterable_source(iterable, ctx), ctx);
+            let generator_elem = generator_elem(&ir_expr_type(&iterable), ctx);
+            let iterator = iterator_class(&ir_expr_type(&iterable), ctx);
+            let elem_type = match (ir_expr_type(&iterable), &generator_elem, &iterator) {
+                (_, Some(elem), _) | (_, None, Some((_, elem))) => elem.clone(),
+                (LltsType::Array(inner), None, None) => *inner,
                 _ => LltsType::F64,
             };
             // Resolve empty struct types to full struct types
@@ -408,10 +387,23 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
                 other => other.clone(),
             };
             ctx.var_types.insert(elem_name.clone(), elem_type.clone());
-            let body = match &forof.body {
+            let mut body = match pattern {
+                Some(pattern) => {
+                    let elem = Expr::Var { name: elem_name.clone(), ty: elem_type.clone() };
+                    lower_binding_pattern(pattern, elem, ctx)
+                }
+                None => Vec::new(),
+            };
+            body.extend(match &forof.body {
                 Statement::BlockStatement(block) => lower_stmts(&block.body, ctx),
                 other => lower_stmt(other, ctx),
-            };
+            });
+            if generator_elem.is_some() {
+                return vec![generator_for_of(iterable, elem_name, elem_type, body, forof.span.start, ctx)];
+            }
+            if let Some((class, _)) = iterator {
+                return vec![iterator_for_of(iterable, &class, elem_name, elem_type, body, forof.span.start)];
+            }
             vec![Stmt::ForOf {
                 elem_name,
                 elem_type,
@@ -443,21 +435,38 @@ pub(crate) fn lower_stmt(stmt: &Statement<'_>, ctx: &mut LowerCtx) -> Vec<Stmt>
         Statement::ContinueStatement(_) => vec![Stmt::Continue],
         Statement::BlockStatement(block) => vec![Stmt::Block(lower_stmts(&block.body, ctx))],
         Statement::ThrowStatement(throw) => {
-            vec![Stmt::Throw(lower_expr(&throw.argument, ctx))]
+            let value = lower_expr(&throw.argument, ctx);
+            lower_throw(value, throw.span.start, ctx)
         }
         Statement::TryStatement(try_stmt) => {
             let try_body = lower_stmts(&try_stmt.block.body, ctx);
             let (catch_param, catch_body) = if let Some(handler) = &try_stmt.handler {
-                let param = handler.param.as_ref().map(|p| binding_name(&p.pattern));
-                let body = lower_stmts(&handler.body.body, ctx);
-                (param, body)
+                // The codegen binds the error struct; the user's parameter
+                // is bound to the thrown value itself.
+                let id = ctx.catch_counter;
+                ctx.catch_counter += 1;
+                let caught = format!("__caught_{id}");
+                let saved_vars = ctx.var_types.clone();
+                let mut body = Vec::new();
+                if let Some(param) = &handler.param {
+                    let name = binding_name(&param.pattern, ctx);
+                    body.push(bind_catch_param(&name, &caught, &try_body, id, ctx));
+                }
+                body.extend(lower_stmts(&handler.body.body, ctx));
+                ctx.var_types = saved_vars;
+                (Some(caught), Some(body))
             } else {
-                (None, Vec::new())
+                (None, None)
             };
+            let finally_body = try_stmt
+                .finalizer
+                .as_ref()
+                .map(|finalizer| lower_stmts(&finalizer.body, ctx));
             vec![Stmt::TryCatch {
                 try_body,
                 catch_param,
                 catch_body,
+                finally_body,
             }]
         }
         _ => vec![],
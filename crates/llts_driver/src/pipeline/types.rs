@@ -3,13 +3,16 @@ use std::collections::{HashMap, HashSet};
 use oxc_ast::ast::*;
 
 use llts_codegen::{
-    EnumDecl, FunctionDecl, StructDecl,
+    EnumDecl, Expr, FunctionDecl, Stmt, StructDecl,
     types::{LltsType, TypeRegistry},
 };
 
 use super::context::LowerCtx;
-use super::utils::{binding_name, enum_member_name, property_key_name, ts_type_name_string};
-use super::lower_stmts;
+use super::utils::{coerce_to_type, enum_member_name, field_assign, ir_expr_type, property_key_name, ts_type_name_string};
+use super::{
+    bind_params, iterator_step, lower_async_function, lower_expr_expecting, lower_stmts, lower_type_annotation, mangle_generic_name,
+    record_fn_throws, record_param_shape, signature_params,
+};
 
 // ---------------------------------------------------------------------------
 // Type lowering: TS type annotations → codegen LltsType
@@ -76,7 +79,34 @@ pub(crate) fn lower_ts_type_with_enums(ts_type: &TSType<'_>, enum_names: &HashSe
                         err: Box::new(err),
                     }
                 }
+                "Map" | "Set" if ref_type.type_arguments.is_some() => {
+                    let mut args = ref_type
+                        .type_arguments
+                        .iter()
+                        .flat_map(|a| a.params.iter().map(|t| lower_ts_type_with_enums(t, enum_names)));
+                    let first = args.next().unwrap_or(LltsType::F64);
+                    if name == "Set" {
+                        LltsType::Set(Box::new(first))
+                    } else {
+                        LltsType::Map {
+                            key: Box::new(first),
+                            value: Box::new(args.next().unwrap_or(LltsType::F64)),
+                        }
+                    }
+                }
                 _ if enum_names.contains(&name) => LltsType::I32,
+                // Generic type instance: `Pair<i32, string>` names `Pair$i32$string`.
+                _ if ref_type.type_arguments.is_some() => {
+                    let args: Vec<LltsType> = ref_type
+                        .type_arguments
+                        .iter()
+                        .flat_map(|a| a.params.iter().map(|t| lower_ts_type_with_enums(t, enum_names)))
+                        .collect();
+                    LltsType::Struct {
+                        name: mangle_generic_name(&name, &args),
+                        fields: vec![],
+                    }
+                }
                 _ => LltsType::Struct {
                     name,
                     fields: vec![],
@@ -87,6 +117,16 @@ pub(crate) fn lower_ts_type_with_enums(ts_type: &TSType<'_>, enum_names: &HashSe
             let elem = lower_ts_type_with_enums(&arr.element_type, enum_names);
             LltsType::Array(Box::new(elem))
         }
+        // Tuples of one element type are arrays of it.
+        TSType::TSTupleType(tuple) => {
+            let elem = tuple
+                .element_types
+                .first()
+                .and_then(|el| el.as_ts_type())
+                .map(|t| lower_ts_type_with_enums(t, enum_names))
+                .unwrap_or(LltsType::F64);
+            LltsType::Array(Box::new(elem))
+        }
         TSType::TSFunctionType(func) => {
             let params: Vec<LltsType> = func
                 .params
@@ -236,15 +276,37 @@ pub(crate) fn extract_string_literal_fields(iface: &TSInterfaceDeclaration<'_>)
     result
 }
 
-/// Extract struct fields from a class declaration (first pass).
-pub(crate) fn lower_class_struct(class: &Class<'_>) -> Option<StructDecl> {
-    let name = class
+fn class_name(class: &Class<'_>) -> String {
+    class
         .id
         .as_ref()
         .map(|id| id.name.to_string())
-        .unwrap_or_else(|| "<anonymous>".to_string());
+        .unwrap_or_else(|| "<anonymous>".to_string())
+}
+
+/// Name of the class a declaration `extends`, if any.
+fn class_parent_name(class: &Class<'_>) -> Option<String> {
+    match class.super_class.as_ref()? {
+        Expression::Identifier(id) => Some(id.name.to_string()),
+        _ => None,
+    }
+}
+
+/// Extract struct fields from a class declaration (first pass).
+///
+/// With `extends`, the parent's fields come first so a subclass value starts
+/// with its parent's layout. The parent must be lowered first (see
+/// `llts_analysis::declaration_order`).
+pub(crate) fn lower_class_struct(class: &Class<'_>, ctx: &mut LowerCtx) -> Option<StructDecl> {
+    let name = class_name(class);
 
     let mut fields = Vec::new();
+    if let Some(parent) = class_parent_name(class) {
+        if let Some(parent_fields) = ctx.struct_defs.get(&parent) {
+            fields.extend(parent_fields.iter().cloned());
+        }
+        ctx.class_parents.insert(name.clone(), parent);
+    }
     for element in &class.body.body {
         if let ClassElement::PropertyDefinition(prop) = element {
             if !prop.computed {
@@ -254,66 +316,207 @@ pub(crate) fn lower_class_struct(class: &Class<'_>) -> Option<StructDecl> {
                     .as_ref()
                     .map(|ann| lower_ts_type(&ann.type_annotation))
                     .unwrap_or(LltsType::F64);
-                fields.push((field_name, ty));
+                // A redeclared parent field keeps its slot.
+                match fields.iter_mut().find(|(n, _)| *n == field_name) {
+                    Some(existing) => existing.1 = ty,
+                    None => fields.push((field_name, ty)),
+                }
             }
         }
     }
 
-    if fields.is_empty() {
+    // Instances of a generic class need a struct even without fields.
+    if fields.is_empty() && class.type_parameters.is_none() {
         None
     } else {
         Some(StructDecl { name, fields })
     }
 }
 
-/// Lower class methods (second pass, with struct awareness).
-pub(crate) fn lower_class_methods(class: &Class<'_>, ctx: &mut LowerCtx) -> Vec<FunctionDecl> {
-    let name = class
-        .id
+fn lower_method_ret(func: &Function<'_>, ctx: &mut LowerCtx) -> LltsType {
+    func.return_type
         .as_ref()
-        .map(|id| id.name.to_string())
-        .unwrap_or_else(|| "<anonymous>".to_string());
+        .map(|r| lower_type_annotation(&r.type_annotation, ctx))
+        .unwrap_or(LltsType::Void)
+}
 
+/// Register the constructor (`{Class}_new`) and method (`{Class}_{method}`)
+/// signatures of a class (second pass), including methods inherited from its
+/// parent. A class without a constructor takes its parent's parameters.
+pub(crate) fn register_class_signatures(class: &Class<'_>, ctx: &mut LowerCtx) {
+    let name = class_name(class);
     let self_type = ctx.full_struct_type(&name);
-    let enum_names = ctx.enum_names();
-    let mut methods = Vec::new();
+    let parent = ctx.class_parents.get(&name).cloned();
 
+    let mut methods = Vec::new();
+    let mut ctor_params = None;
     for element in &class.body.body {
         if let ClassElement::MethodDefinition(method) = element {
+            let (param_types, shape) = signature_params(&method.value.params, ctx);
+            if matches!(method.kind, MethodDefinitionKind::Constructor) {
+                ctor_params = Some((param_types, shape));
+                continue;
+            }
             let method_name = property_key_name(&method.key);
             let mangled = format!("{name}_{method_name}");
+            let ret = lower_method_ret(&method.value, ctx);
+            ctx.fn_ret_types.insert(mangled.clone(), ret);
+            let mut all_params = vec![self_type.clone()];
+            all_params.extend(param_types);
+            record_param_shape(&mangled, shape, ctx);
+            ctx.fn_param_types.insert(mangled, all_params);
+            methods.push(method_name);
+        }
+    }
+
+    if let Some(parent) = &parent {
+        for method_name in ctx.class_methods.get(parent).cloned().unwrap_or_default() {
+            if methods.contains(&method_name) {
+                continue;
+            }
+            let parent_mangled = format!("{parent}_{method_name}");
+            let mangled = format!("{name}_{method_name}");
+            if let Some(ret) = ctx.fn_ret_types.get(&parent_mangled).cloned() {
+                ctx.fn_ret_types.insert(mangled.clone(), ret);
+            }
+            if let Some(mut param_types) = ctx.fn_param_types.get(&parent_mangled).cloned() {
+                param_types[0] = self_type.clone();
+                ctx.fn_param_types.insert(mangled.clone(), param_types);
+            }
+            let shape = ctx.param_shapes.get(&parent_mangled).cloned();
+            record_param_shape(&mangled, shape, ctx);
+            methods.push(method_name);
+        }
+    }
+
+    let (ctor_params, ctor_shape) = ctor_params
+        .or_else(|| {
+            let parent = parent.as_ref()?;
+            let parent_ctor = format!("{parent}_new");
+            let params = ctx.fn_param_types.get(&parent_ctor).cloned()?;
+            Some((params, ctx.param_shapes.get(&parent_ctor).cloned()))
+        })
+        .unwrap_or_default();
+    ctx.fn_ret_types.insert(format!("{name}_new"), self_type);
+    ctx.fn_param_types.insert(format!("{name}_new"), ctor_params);
+    record_param_shape(&format!("{name}_new"), ctor_shape, ctx);
+    ctx.class_methods.insert(name, methods);
+}
+
+/// View a subclass value as its parent: a struct literal of the parent's
+/// fields read from the (prefix-compatible) subclass value.
+pub(crate) fn upcast_expr(value: Expr, parent: &str, ctx: &LowerCtx) -> Expr {
+    let parent_type = ctx.full_struct_type(parent);
+    let from_type = ir_expr_type(&value);
+    let fields = match &parent_type {
+        LltsType::Struct { fields, .. } => fields
+            .iter()
+            .enumerate()
+            .map(|(i, (_, field_type))| Expr::FieldAccess {
+                object: Box::new(value.clone()),
+                object_type: from_type.clone(),
+                field_index: i as u32,
+                field_type: field_type.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Expr::StructLit { struct_type: parent_type, fields }
+}
+
+/// The parent of the class whose method is being lowered, if any.
+pub(crate) fn current_parent_class(ctx: &LowerCtx) -> Option<String> {
+    match ctx.var_types.get("self")? {
+        LltsType::Struct { name, .. } => ctx.class_parents.get(name).cloned(),
+        _ => None,
+    }
+}
+
+fn self_var(self_type: &LltsType) -> Expr {
+    Expr::Var { name: "self".to_string(), ty: self_type.clone() }
+}
+
+/// Lower class methods and the constructor (second pass, with struct awareness).
+///
+/// Methods become `{Class}_{method}(self, ...)`; the constructor becomes
+/// `{Class}_new(...)` returning the struct. Inherited methods that are not
+/// overridden get a `{Class}_{method}` wrapper forwarding the parent part of
+/// `self` to the parent's implementation.
+pub(crate) fn lower_class_methods(class: &Class<'_>, ctx: &mut LowerCtx) -> Vec<FunctionDecl> {
+    lower_class_methods_as(class, &class_name(class), ctx)
+}
+
+/// Lower the methods of `class` under the struct name `name`: the class
+/// itself, or one instance of a generic class (`Stack$i32`) while its type
+/// parameters are in scope.
+pub(crate) fn lower_class_methods_as(class: &Class<'_>, name: &str, ctx: &mut LowerCtx) -> Vec<FunctionDecl> {
+    let name = name.to_string();
+    let self_type = ctx.full_struct_type(&name);
+    let parent = ctx.class_parents.get(&name).cloned();
+    let mut methods = Vec::new();
+    let mut own_methods: Vec<String> = Vec::new();
+    let mut has_ctor = false;
+
+    // Field initializers (`x: f64 = 0`) become assignments on `self`.
+    let field_inits: Vec<Stmt> = {
+        let saved_vars = ctx.var_types.clone();
+        ctx.var_types.insert("self".to_string(), self_type.clone());
+        let mut inits = Vec::new();
+        for element in &class.body.body {
+            if let ClassElement::PropertyDefinition(prop) = element {
+                let Some(init) = &prop.value else { continue };
+                let field_name = property_key_name(&prop.key);
+                if let Some((field_index, field_type)) = ctx.lookup_field(&name, &field_name) {
+                    let value = coerce_to_type(lower_expr_expecting(init, &field_type, ctx), &field_type);
+                    inits.push(field_assign("self", &self_type, field_index, field_type, value));
+                }
+            }
+        }
+        ctx.var_types = saved_vars;
+        inits
+    };
+
+    for element in &class.body.body {
+        if let ClassElement::MethodDefinition(method) = element {
+            let is_ctor = matches!(method.kind, MethodDefinitionKind::Constructor);
+            let method_name = property_key_name(&method.key);
+            let mangled = if is_ctor { format!("{name}_new") } else { format!("{name}_{method_name}") };
 
             let saved_vars = ctx.var_types.clone();
             ctx.var_types.insert("self".to_string(), self_type.clone());
 
-            let mut params = vec![("self".to_string(), self_type.clone())];
-            for param in &method.value.params.items {
-                let pname = binding_name(&param.pattern);
-                let pty = param
-                    .type_annotation
-                    .as_ref()
-                    .map(|ann| lower_ts_type_with_enums(&ann.type_annotation, &enum_names))
-                    .unwrap_or(LltsType::F64);
-                ctx.var_types.insert(pname.clone(), pty.clone());
-                params.push((pname, pty));
-            }
+            let mut params = if is_ctor { Vec::new() } else { vec![("self".to_string(), self_type.clone())] };
+            let bound = bind_params(&method.value.params, &[], ctx);
+            params.extend(bound.params);
 
-            let ret_type = method
-                .value
-                .return_type
-                .as_ref()
-                .map(|r| lower_ts_type_with_enums(&r.type_annotation, &enum_names))
-                .unwrap_or(LltsType::Void);
+            let ret_type = if is_ctor { self_type.clone() } else { lower_method_ret(&method.value, ctx) };
+            if method.value.r#async && !is_ctor {
+                // The promise stores a copy of `self` with the other arguments.
+                let decl = lower_async_function(&method.value, mangled, params, bound.prologue, ret_type, ctx);
+                ctx.var_types = saved_vars;
+                own_methods.push(method_name);
+                methods.extend(decl);
+                continue;
+            }
+            if !is_ctor {
+                ctx.var_types.insert("__fn_return_type__".to_string(), ret_type.clone());
+            }
 
-            let body = method
-                .value
-                .body
-                .as_ref()
-                .map(|b| lower_stmts(&b.statements, ctx))
-                .unwrap_or_default();
+            let mut body = bound.prologue;
+            if let Some(b) = &method.value.body {
+                body.extend(lower_stmts(&b.statements, ctx));
+            }
 
             ctx.var_types = saved_vars;
 
+            let body = if is_ctor {
+                has_ctor = true;
+                constructor_body(&self_type, parent.as_deref(), body, field_inits.clone())
+            } else {
+                own_methods.push(method_name);
+                body
+            };
+
             methods.push(FunctionDecl {
                 name: mangled,
                 params,
@@ -323,9 +526,132 @@ pub(crate) fn lower_class_methods(class: &Class<'_>, ctx: &mut LowerCtx) -> Vec<
         }
     }
 
+    // Implicit constructor: forwards its arguments to the parent's.
+    if !has_ctor {
+        let param_types = ctx.fn_param_types.get(&format!("{name}_new")).cloned().unwrap_or_default();
+        let params: Vec<(String, LltsType)> = param_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| (format!("__arg{i}"), t))
+            .collect();
+        let mut body = Vec::new();
+        if let Some(parent) = &parent {
+            let callee = format!("{parent}_new");
+            body.push(Stmt::Expr(Expr::Call {
+                args: params.iter().map(|(n, t)| Expr::Var { name: n.clone(), ty: t.clone() }).collect(),
+                ret_type: ctx.full_struct_type(parent),
+                callee,
+            }));
+        }
+        methods.push(FunctionDecl {
+            name: format!("{name}_new"),
+            params,
+            ret_type: self_type.clone(),
+            body: constructor_body(&self_type, parent.as_deref(), body, field_inits),
+        });
+    }
+
+    // Inherited, non-overridden methods forward to the parent's implementation.
+    if let Some(parent) = &parent {
+        for method_name in ctx.class_methods.get(&name).cloned().unwrap_or_default() {
+            if own_methods.contains(&method_name) {
+                continue;
+            }
+            let mangled = format!("{name}_{method_name}");
+            let ret_type = ctx.fn_ret_types.get(&mangled).cloned().unwrap_or(LltsType::Void);
+            let param_types = ctx.fn_param_types.get(&mangled).cloned().unwrap_or_default();
+            let mut params = vec![("self".to_string(), self_type.clone())];
+            params.extend(
+                param_types
+                    .into_iter()
+                    .skip(1)
+                    .enumerate()
+                    .map(|(i, t)| (format!("__arg{i}"), t)),
+            );
+            let mut args = vec![upcast_expr(self_var(&self_type), parent, ctx)];
+            args.extend(params.iter().skip(1).map(|(n, t)| Expr::Var { name: n.clone(), ty: t.clone() }));
+            let call = Expr::Call {
+                callee: format!("{parent}_{method_name}"),
+                args,
+                ret_type: ret_type.clone(),
+            };
+            let body = if ret_type == LltsType::Void {
+                vec![Stmt::Expr(call)]
+            } else {
+                vec![Stmt::Return(Some(call))]
+            };
+            methods.push(FunctionDecl { name: mangled, params, ret_type, body });
+        }
+    }
+
+    // Iterators also get the variant of `next()` that `for...of` calls.
+    if let Some(step) = methods.iter().find_map(|method| iterator_step(method, &name)) {
+        methods.push(step);
+    }
+
+    for method in &methods {
+        record_fn_throws(method, ctx);
+    }
     methods
 }
 
+/// Assemble a constructor body around the lowered user statements.
+///
+/// `self` starts uninitialized; `super(...)` (lowered to a call to the
+/// parent's `_new`) copies the parent's fields in, and field initializers run
+/// right after it (or first, without a parent). Returns `self`.
+fn constructor_body(
+    self_type: &LltsType,
+    parent: Option<&str>,
+    body: Vec<Stmt>,
+    field_inits: Vec<Stmt>,
+) -> Vec<Stmt> {
+    let mut full = vec![Stmt::VarDecl {
+        name: "self".to_string(),
+        ty: self_type.clone(),
+        init: None,
+    }];
+    let super_callee = parent.map(|p| format!("{p}_new"));
+    let mut pending_inits = Some(field_inits);
+    if super_callee.is_none() {
+        full.extend(pending_inits.take().unwrap_or_default());
+    }
+
+    for stmt in body {
+        match stmt {
+            Stmt::Expr(Expr::Call { callee, args, ret_type }) if Some(&callee) == super_callee.as_ref() => {
+                let parent_type = ret_type.clone();
+                let call = Expr::Call { callee, args, ret_type };
+                full.push(Stmt::VarDecl {
+                    name: "__super".to_string(),
+                    ty: parent_type.clone(),
+                    init: Some(call),
+                });
+                if let LltsType::Struct { fields, .. } = &parent_type {
+                    for (i, (_, field_type)) in fields.iter().enumerate() {
+                        let inherited = Expr::FieldAccess {
+                            object: Box::new(Expr::Var {
+                                name: "__super".to_string(),
+                                ty: parent_type.clone(),
+                            }),
+                            object_type: parent_type.clone(),
+                            field_index: i as u32,
+                            field_type: field_type.clone(),
+                        };
+                        full.push(field_assign("self", self_type, i as u32, field_type.clone(), inherited));
+                    }
+                }
+                full.extend(pending_inits.take().unwrap_or_default());
+            }
+            Stmt::Return(None) => full.push(Stmt::Return(Some(self_var(self_type)))),
+            other => full.push(other),
+        }
+    }
+    full.extend(pending_inits.take().unwrap_or_default());
+    full.push(Stmt::Return(Some(self_var(self_type))));
+    full
+}
+
 /// Lower an enum declaration, returning the EnumDecl and the actual numeric
 /// values for each variant (respecting explicit initializers and auto-increment).
 pub(crate) fn lower_enum(decl: &TSEnumDeclaration<'_>) -> (EnumDecl, Vec<(String, i64)>) {
@@ -436,6 +762,10 @@ pub(crate) fn extract_generic_param_info(
                     let ref_name = ts_type_name_string(&ref_type.type_name);
                     if let Some(members) = type_alias_members.get(&ref_name) {
                         members.clone()
+                    } else if ref_type.type_arguments.is_some() {
+                        // A generic interface (`T extends Comparable<T>`) is
+                        // checked member-wise against its declaration.
+                        vec![LltsType::Struct { name: ref_name, fields: vec![] }]
                     } else {
                         vec![lower_ts_type(p.constraint.as_ref().unwrap())]
                     }
@@ -202,7 +202,7 @@ pub(crate) fn try_lower_discriminated_switch(
     // The discriminant must be a member expression: s.kind
     let (var_name, field_name) = match &switch.discriminant {
         Expression::StaticMemberExpression(member) => {
-            let obj = expr_to_name(&member.object);
+            let obj = expr_to_name(&member.object, ctx);
             let field = member.property.name.to_string();
             (obj, field)
         }
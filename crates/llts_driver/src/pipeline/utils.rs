@@ -1,13 +1,14 @@
 use oxc_ast::ast::*;
 
 use llts_codegen::{
-    Expr,
+    Expr, Stmt,
     expr::BinOp,
     expr::UnaryOp,
     types::LltsType,
 };
 
 use super::context::LowerCtx;
+use super::retarget_result;
 
 pub(crate) fn lower_binop(op: BinaryOperator) -> BinOp {
     match op {
@@ -92,8 +93,8 @@ pub(crate) fn detect_null_comparison(expr: &Expression<'_>, ctx: &LowerCtx) -> O
     }
     // Determine which side is null and which is the variable
     let var_name = match (&bin.left, &bin.right) {
-        (Expression::Identifier(id), Expression::NullLiteral(_)) => id.name.to_string(),
-        (Expression::NullLiteral(_), Expression::Identifier(id)) => id.name.to_string(),
+        (Expression::Identifier(id), Expression::NullLiteral(_)) => ctx.var_name(id),
+        (Expression::NullLiteral(_), Expression::Identifier(id)) => ctx.var_name(id),
         _ => return None,
     };
     // Check if the variable is Option<T>
@@ -121,6 +122,16 @@ pub(crate) fn coerce_to_type(expr: Expr, expected: &LltsType) -> Expr {
     if expr_ty == *expected {
         return expr;
     }
+    // An empty array literal takes the element type its context expects.
+    if let (Expr::ArrayLit { elements, .. }, LltsType::Array(elem)) = (&expr, expected) {
+        if elements.is_empty() {
+            return Expr::ArrayLit { elem_type: *elem.clone(), elements: Vec::new() };
+        }
+    }
+    // `Ok(x)` / `Err(e)` take the Result type their context expects.
+    if let LltsType::Result { .. } = expected {
+        return retarget_result(expr, expected);
+    }
     // Don't coerce non-numeric types
     if matches!(expected, LltsType::String | LltsType::Bool | LltsType::Void
                 | LltsType::Struct { .. } | LltsType::Array(_) | LltsType::Option(_)
@@ -152,6 +163,40 @@ pub(crate) fn coerce_to_type(expr: Expr, expected: &LltsType) -> Expr {
     }
 }
 
+/// `expr` as a value of `Option<inner>`: `null` takes the inner type, and
+/// a value that is not already nullable is wrapped.
+pub(crate) fn into_option(expr: Expr, inner: &LltsType) -> Expr {
+    match expr {
+        Expr::OptionNone { .. } => Expr::OptionNone { inner_type: inner.clone() },
+        expr if matches!(ir_expr_type(&expr), LltsType::Option(_)) => expr,
+        expr => Expr::OptionSome { value: Box::new(coerce_to_type(expr, inner)), inner_type: inner.clone() },
+    }
+}
+
+/// `object.field = value` on the struct variable `object`, storing through
+/// the field's place (see `Expr::Assign`).
+pub(crate) fn field_assign(
+    object: &str,
+    object_type: &LltsType,
+    field_index: u32,
+    field_type: LltsType,
+    value: Expr,
+) -> Stmt {
+    let place = Expr::FieldAccess {
+        object: Box::new(Expr::Var { name: object.to_string(), ty: object_type.clone() }),
+        object_type: object_type.clone(),
+        field_index,
+        field_type: field_type.clone(),
+    };
+    Stmt::Expr(Expr::Assign {
+        target: Box::new(place),
+        op: None,
+        current: None,
+        value: Box::new(value),
+        ty: field_type,
+    })
+}
+
 pub(crate) fn ir_expr_type(expr: &Expr) -> LltsType {
     match expr {
         Expr::IntLit { ty, .. } => ty.clone(),
@@ -165,23 +210,54 @@ pub(crate) fn ir_expr_type(expr: &Expr) -> LltsType {
         Expr::Cast { to, .. } => to.clone(),
         Expr::FieldAccess { field_type, .. } => field_type.clone(),
         Expr::StructLit { struct_type, .. } => struct_type.clone(),
-        Expr::ArrayLit { elem_type, .. } => LltsType::Array(Box::new(elem_type.clone())),
+        Expr::ArrayLit { elem_type, .. } | Expr::ArrayConcat { elem_type, .. } => {
+            LltsType::Array(Box::new(elem_type.clone()))
+        }
         Expr::ArrayIndex { elem_type, .. } => elem_type.clone(),
+        Expr::Assign { ty, .. } | Expr::Update { ty, .. } => ty.clone(),
         Expr::Ternary { ty, .. } => ty.clone(),
         Expr::StringConcat { .. } => LltsType::String,
         Expr::Logical { ty, .. } => ty.clone(),
+        Expr::Coalesce { ty, .. } => ty.clone(),
         Expr::UnionLit { union_type, .. } => union_type.clone(),
+        Expr::BoxValue { .. } => LltsType::Ptr,
+        Expr::UnboxValue { ty, .. } => ty.clone(),
+        Expr::ResultOk { result_type, .. } => result_type.clone(),
+        Expr::ResultPropagate { ok_type, .. } => ok_type.clone(),
         Expr::OptionNone { inner_type } => LltsType::Option(Box::new(inner_type.clone())),
         Expr::OptionSome { inner_type, .. } => LltsType::Option(Box::new(inner_type.clone())),
         Expr::OptionIsSome { .. } => LltsType::Bool,
         Expr::OptionUnwrap { inner_type, .. } => inner_type.clone(),
-        _ => LltsType::F64,
+        Expr::OptionExpect { inner_type, .. } => inner_type.clone(),
+        Expr::OptionAndThen { ty: LltsType::Void, .. } => LltsType::Void,
+        Expr::OptionAndThen { ty, .. } => LltsType::Option(Box::new(ty.clone())),
+        Expr::IndirectCall { ret_type, .. } => ret_type.clone(),
+        Expr::Closure { ty, .. } => ty.clone(),
+        Expr::DynCoerce { ty, .. } => ty.clone(),
+        Expr::DynCall { ret_type, .. } => ret_type.clone(),
+        Expr::MethodCall { ret_type, .. } | Expr::ConstructorCall { ret_type, .. } => ret_type.clone(),
+    }
+}
+
+/// Whether an unannotated binding should take its initializer's lowered type
+/// rather than the AST-level guess (which only knows literals).
+pub(crate) fn takes_init_type(ty: &LltsType) -> bool {
+    match ty {
+        LltsType::Function { .. }
+        | LltsType::Map { .. }
+        | LltsType::Set(_)
+        | LltsType::Option(_)
+        | LltsType::Result { .. }
+        | LltsType::Dyn { .. } => true,
+        LltsType::Struct { name, .. } => !name.is_empty(),
+        LltsType::Array(elem) => takes_init_type(elem) || !matches!(**elem, LltsType::Struct { .. }),
+        _ => false,
     }
 }
 
-pub(crate) fn binding_name(pattern: &BindingPattern<'_>) -> String {
+pub(crate) fn binding_name(pattern: &BindingPattern<'_>, ctx: &LowerCtx) -> String {
     match pattern {
-        BindingPattern::BindingIdentifier(id) => id.name.to_string(),
+        BindingPattern::BindingIdentifier(id) => ctx.binding_var_name(id),
         _ => "_".to_string(),
     }
 }
@@ -201,23 +277,25 @@ pub(crate) fn enum_member_name(name: &TSEnumMemberName<'_>) -> String {
     }
 }
 
-pub(crate) fn expr_to_name(expr: &Expression<'_>) -> String {
+pub(crate) fn expr_to_name(expr: &Expression<'_>, ctx: &LowerCtx) -> String {
     match expr {
-        Expression::Identifier(id) => id.name.to_string(),
+        Expression::Identifier(id) => ctx.var_name(id),
+        // Methods receive `this` as their `self` parameter.
+        Expression::ThisExpression(_) => "self".to_string(),
         _ => "<expr>".to_string(),
     }
 }
 
-pub(crate) fn assignment_target_name(target: &AssignmentTarget<'_>) -> String {
+pub(crate) fn assignment_target_name(target: &AssignmentTarget<'_>, ctx: &LowerCtx) -> String {
     match target {
-        AssignmentTarget::AssignmentTargetIdentifier(id) => id.name.to_string(),
+        AssignmentTarget::AssignmentTargetIdentifier(id) => ctx.var_name(id),
         _ => "_".to_string(),
     }
 }
 
-pub(crate) fn simple_target_name(target: &SimpleAssignmentTarget<'_>) -> String {
+pub(crate) fn simple_target_name(target: &SimpleAssignmentTarget<'_>, ctx: &LowerCtx) -> String {
     match target {
-        SimpleAssignmentTarget::AssignmentTargetIdentifier(id) => id.name.to_string(),
+        SimpleAssignmentTarget::AssignmentTargetIdentifier(id) => ctx.var_name(id),
         _ => "_".to_string(),
     }
 }
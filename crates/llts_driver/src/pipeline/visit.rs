@@ -0,0 +1,269 @@
+use llts_codegen::{Expr, Stmt};
+
+// ---------------------------------------------------------------------------
+// Generic IR traversal for post-lowering passes
+// ---------------------------------------------------------------------------
+
+/// Visit every statement in `stmts`, including nested bodies, in pre-order.
+pub(crate) fn for_each_stmt(stmts: &[Stmt], f: &mut impl FnMut(&Stmt)) {
+    for stmt in stmts {
+        f(stmt);
+        match stmt {
+            Stmt::If { then_body, else_body, .. } => {
+                for_each_stmt(then_body, f);
+                if let Some(els) = else_body {
+                    for_each_stmt(els, f);
+                }
+            }
+            Stmt::While { body, .. } | Stmt::ForOf { body, .. } | Stmt::Block(body) => {
+                for_each_stmt(body, f)
+            }
+            Stmt::For { init, update, body, .. } => {
+                if let Some(i) = init {
+                    for_each_stmt(std::slice::from_ref(i.as_ref()), f);
+                }
+                if let Some(u) = update {
+                    for_each_stmt(std::slice::from_ref(u.as_ref()), f);
+                }
+                for_each_stmt(body, f);
+            }
+            Stmt::Switch { cases, .. } => {
+                for (_, body) in cases {
+                    for_each_stmt(body, f);
+                }
+            }
+            Stmt::TryCatch { try_body, catch_body, finally_body, .. } => {
+                for_each_stmt(try_body, f);
+                for body in catch_body.iter().chain(finally_body) {
+                    for_each_stmt(body, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Mutable counterpart of [`for_each_stmt`]. The callback runs before the
+/// statement's children are visited, so it may replace the statement outright.
+pub(crate) fn for_each_stmt_mut(stmts: &mut [Stmt], f: &mut impl FnMut(&mut Stmt)) {
+    for stmt in stmts {
+        f(stmt);
+        match stmt {
+            Stmt::If { then_body, else_body, .. } => {
+                for_each_stmt_mut(then_body, f);
+                if let Some(els) = else_body {
+                    for_each_stmt_mut(els, f);
+                }
+            }
+            Stmt::While { body, .. } | Stmt::ForOf { body, .. } | Stmt::Block(body) => {
+                for_each_stmt_mut(body, f)
+            }
+            Stmt::For { init, update, body, .. } => {
+                if let Some(i) = init {
+                    for_each_stmt_mut(std::slice::from_mut(i.as_mut()), f);
+                }
+                if let Some(u) = update {
+                    for_each_stmt_mut(std::slice::from_mut(u.as_mut()), f);
+                }
+                for_each_stmt_mut(body, f);
+            }
+            Stmt::Switch { cases, .. } => {
+                for (_, body) in cases {
+                    for_each_stmt_mut(body, f);
+                }
+            }
+            Stmt::TryCatch { try_body, catch_body, finally_body, .. } => {
+                for_each_stmt_mut(try_body, f);
+                for body in catch_body.iter_mut().chain(finally_body) {
+                    for_each_stmt_mut(body, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The expressions a statement holds directly (not those of nested bodies).
+pub(crate) fn stmt_exprs(stmt: &Stmt) -> Vec<&Expr> {
+    match stmt {
+        Stmt::VarDecl { init, .. } | Stmt::BoxDecl { init, .. } => init.iter().collect(),
+        Stmt::Assign { value, .. } => vec![value],
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => vec![condition],
+        Stmt::For { condition, .. } => condition.iter().collect(),
+        Stmt::ForOf { iterable, .. } => vec![iterable],
+        Stmt::Return(e) => e.iter().collect(),
+        Stmt::Expr(e) | Stmt::Throw(e) => vec![e],
+        Stmt::Switch { discriminant, cases } => {
+            let mut out = vec![discriminant];
+            out.extend(cases.iter().filter_map(|(test, _)| test.as_ref()));
+            out
+        }
+        Stmt::Block(_)
+        | Stmt::Break
+        | Stmt::Continue
+        | Stmt::TryCatch { .. }
+        | Stmt::CaptureBind { .. } => Vec::new(),
+    }
+}
+
+fn stmt_exprs_mut(stmt: &mut Stmt) -> Vec<&mut Expr> {
+    match stmt {
+        Stmt::VarDecl { init, .. } | Stmt::BoxDecl { init, .. } => init.iter_mut().collect(),
+        Stmt::Assign { value, .. } => vec![value],
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => vec![condition],
+        Stmt::For { condition, .. } => condition.iter_mut().collect(),
+        Stmt::ForOf { iterable, .. } => vec![iterable],
+        Stmt::Return(e) => e.iter_mut().collect(),
+        Stmt::Expr(e) | Stmt::Throw(e) => vec![e],
+        Stmt::Switch { discriminant, cases } => {
+            let mut out = vec![discriminant];
+            out.extend(cases.iter_mut().filter_map(|(test, _)| test.as_mut()));
+            out
+        }
+        Stmt::Block(_)
+        | Stmt::Break
+        | Stmt::Continue
+        | Stmt::TryCatch { .. }
+        | Stmt::CaptureBind { .. } => Vec::new(),
+    }
+}
+
+/// The direct sub-expressions of `expr`.
+pub(crate) fn expr_children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::IntLit { .. }
+        | Expr::FloatLit { .. }
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::Var { .. }
+        | Expr::OptionNone { .. }
+        | Expr::Closure { .. } => Vec::new(),
+        Expr::Binary { lhs, rhs, .. } | Expr::Logical { lhs, rhs, .. } => vec![lhs, rhs],
+        Expr::Assign { target, value, .. } => vec![target, value],
+        Expr::Update { target, .. } => vec![target],
+        Expr::Unary { operand, .. } => vec![operand],
+        Expr::Call { args, .. } | Expr::ConstructorCall { args, .. } => args.iter().collect(),
+        Expr::MethodCall { receiver, args, .. } => {
+            let mut out = vec![receiver.as_ref()];
+            out.extend(args);
+            out
+        }
+        Expr::IndirectCall { callee, args, .. } => {
+            let mut out = vec![callee.as_ref()];
+            out.extend(args);
+            out
+        }
+        Expr::DynCall { receiver, args, .. } => {
+            let mut out = vec![receiver.as_ref()];
+            out.extend(args);
+            out
+        }
+        Expr::FieldAccess { object, .. } => vec![object],
+        Expr::ArrayIndex { array, index, .. } => vec![array, index],
+        Expr::Cast { value, .. }
+        | Expr::OptionSome { value, .. }
+        | Expr::OptionIsSome { value, .. }
+        | Expr::OptionUnwrap { value, .. }
+        | Expr::OptionExpect { value, .. }
+        | Expr::BoxValue { value, .. }
+        | Expr::UnboxValue { ptr: value, .. }
+        | Expr::ResultPropagate { value, .. }
+        | Expr::DynCoerce { value, .. } => vec![value],
+        Expr::OptionAndThen { value, body, .. } => vec![value, body],
+        Expr::Coalesce { value, fallback, .. } => vec![value, fallback],
+        Expr::StructLit { fields: items, .. }
+        | Expr::ArrayLit { elements: items, .. }
+        | Expr::ArrayConcat { parts: items, .. }
+        | Expr::StringConcat { parts: items } => items.iter().collect(),
+        Expr::Ternary { condition, then_expr, else_expr, .. } => {
+            vec![condition, then_expr, else_expr]
+        }
+        Expr::UnionLit { payload, .. } => vec![payload],
+        Expr::ResultOk { value, .. } => value.as_deref().into_iter().collect(),
+    }
+}
+
+/// Mutable counterpart of [`expr_children`].
+pub(crate) fn expr_children_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    match expr {
+        Expr::IntLit { .. }
+        | Expr::FloatLit { .. }
+        | Expr::BoolLit(_)
+        | Expr::StringLit(_)
+        | Expr::Var { .. }
+        | Expr::OptionNone { .. }
+        | Expr::Closure { .. } => Vec::new(),
+        Expr::Binary { lhs, rhs, .. } | Expr::Logical { lhs, rhs, .. } => vec![lhs, rhs],
+        Expr::Assign { target, value, .. } => vec![target, value],
+        Expr::Update { target, .. } => vec![target],
+        Expr::Unary { operand, .. } => vec![operand],
+        Expr::Call { args, .. } | Expr::ConstructorCall { args, .. } => args.iter_mut().collect(),
+        Expr::MethodCall { receiver, args, .. } => {
+            let mut out = vec![receiver.as_mut()];
+            out.extend(args);
+            out
+        }
+        Expr::IndirectCall { callee, args, .. } => {
+            let mut out = vec![callee.as_mut()];
+            out.extend(args);
+            out
+        }
+        Expr::DynCall { receiver, args, .. } => {
+            let mut out = vec![receiver.as_mut()];
+            out.extend(args);
+            out
+        }
+        Expr::FieldAccess { object, .. } => vec![object],
+        Expr::ArrayIndex { array, index, .. } => vec![array, index],
+        Expr::Cast { value, .. }
+        | Expr::OptionSome { value, .. }
+        | Expr::OptionIsSome { value, .. }
+        | Expr::OptionUnwrap { value, .. }
+        | Expr::OptionExpect { value, .. }
+        | Expr::BoxValue { value, .. }
+        | Expr::UnboxValue { ptr: value, .. }
+        | Expr::ResultPropagate { value, .. }
+        | Expr::DynCoerce { value, .. } => vec![value],
+        Expr::OptionAndThen { value, body, .. } => vec![value, body],
+        Expr::Coalesce { value, fallback, .. } => vec![value, fallback],
+        Expr::StructLit { fields: items, .. }
+        | Expr::ArrayLit { elements: items, .. }
+        | Expr::ArrayConcat { parts: items, .. }
+        | Expr::StringConcat { parts: items } => items.iter_mut().collect(),
+        Expr::Ternary { condition, then_expr, else_expr, .. } => {
+            vec![condition, then_expr, else_expr]
+        }
+        Expr::UnionLit { payload, .. } => vec![payload],
+        Expr::ResultOk { value, .. } => value.as_deref_mut().into_iter().collect(),
+    }
+}
+
+/// Visit every expression in `stmts` (including sub-expressions) in pre-order.
+pub(crate) fn for_each_expr(stmts: &[Stmt], f: &mut impl FnMut(&Expr)) {
+    fn walk(expr: &Expr, f: &mut impl FnMut(&Expr)) {
+        f(expr);
+        for child in expr_children(expr) {
+            walk(child, f);
+        }
+    }
+    for_each_stmt(stmts, &mut |stmt| {
+        for e in stmt_exprs(stmt) {
+            walk(e, f);
+        }
+    });
+}
+
+/// Mutable counterpart of [`for_each_expr`].
+pub(crate) fn for_each_expr_mut(stmts: &mut [Stmt], f: &mut impl FnMut(&mut Expr)) {
+    fn walk(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+        f(expr);
+        for child in expr_children_mut(expr) {
+            walk(child, f);
+        }
+    }
+    for_each_stmt_mut(stmts, &mut |stmt| {
+        for e in stmt_exprs_mut(stmt) {
+            walk(e, f);
+        }
+    });
+}
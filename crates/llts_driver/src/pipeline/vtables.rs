@@ -0,0 +1,252 @@
+use oxc_ast::ast::*;
+
+use llts_codegen::{Expr, FunctionDecl, Stmt, types::LltsType};
+
+use super::closures::ENV_PARAM;
+use super::context::{DynSlot, LowerCtx};
+use super::types::lower_ts_type_with_enums;
+use super::utils::{coerce_to_type, ir_expr_type, property_key_name};
+
+// ---------------------------------------------------------------------------
+// Dynamic dispatch: interface- and base-class-typed values
+// ---------------------------------------------------------------------------
+
+/// Register the types whose values are dynamically dispatched (after the
+/// first pass): interfaces that declare methods and classes that are
+/// extended. Interface slots are laid out here; base-class slots need the
+/// class signatures, see [`layout_base_class_slots`].
+///
+/// Classes never viewed through one of these types keep their plain struct
+/// layout; only `Dyn`-typed bindings carry the `{ data_ptr, vtable_ptr }` pair.
+pub(crate) fn register_dyn_types(program: &Program<'_>, ctx: &mut LowerCtx) {
+    let interfaces: Vec<&TSInterfaceDeclaration<'_>> = program
+        .body
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::TSInterfaceDeclaration(iface) => Some(&**iface),
+            Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                Some(Declaration::TSInterfaceDeclaration(iface)) => Some(&**iface),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|iface| {
+            iface
+                .body
+                .body
+                .iter()
+                .any(|member| matches!(member, TSSignature::TSMethodSignature(_)))
+        })
+        .collect();
+
+    // Register every name first so slot types can refer to each other.
+    for iface in &interfaces {
+        ctx.dyn_types.insert(iface.id.name.to_string(), Vec::new());
+    }
+    let bases: Vec<String> = ctx.class_parents.values().cloned().collect();
+    for base in bases {
+        ctx.dyn_types.entry(base).or_default();
+    }
+
+    let enum_names = ctx.enum_names();
+    let lower = |ann: Option<&TSTypeAnnotation<'_>>, default: LltsType, ctx: &LowerCtx| {
+        ann.map(|a| ctx.resolve_annotation(lower_ts_type_with_enums(&a.type_annotation, &enum_names)))
+            .unwrap_or(default)
+    };
+    for iface in interfaces {
+        let mut slots = Vec::new();
+        for member in &iface.body.body {
+            match member {
+                TSSignature::TSPropertySignature(prop) => slots.push(DynSlot {
+                    name: property_key_name(&prop.key),
+                    params: None,
+                    ret: lower(prop.type_annotation.as_deref(), LltsType::F64, ctx),
+                }),
+                TSSignature::TSMethodSignature(method) => slots.push(DynSlot {
+                    name: property_key_name(&method.key),
+                    params: Some(
+                        method
+                            .params
+                            .items
+                            .iter()
+                            .map(|p| lower(p.type_annotation.as_deref(), LltsType::F64, ctx))
+                            .collect(),
+                    ),
+                    ret: lower(method.return_type.as_deref(), LltsType::Void, ctx),
+                }),
+                _ => {}
+            }
+        }
+        ctx.dyn_types.insert(iface.id.name.to_string(), slots);
+    }
+}
+
+/// Lay out the vtable of each extended class (after the second pass): one
+/// getter slot per field, then one slot per method, inherited ones included.
+pub(crate) fn layout_base_class_slots(ctx: &mut LowerCtx) {
+    let bases: Vec<String> = ctx
+        .dyn_types
+        .keys()
+        .filter(|name| ctx.class_methods.contains_key(*name))
+        .cloned()
+        .collect();
+    for base in bases {
+        let mut slots: Vec<DynSlot> = ctx
+            .struct_defs
+            .get(&base)
+            .map(|fields| {
+                fields
+                    .iter()
+                    .map(|(name, ty)| DynSlot { name: name.clone(), params: None, ret: ty.clone() })
+                    .collect()
+            })
+            .unwrap_or_default();
+        for method in &ctx.class_methods[&base] {
+            let mangled = format!("{base}_{method}");
+            let params = ctx
+                .fn_param_types
+                .get(&mangled)
+                .map(|p| p[1..].to_vec())
+                .unwrap_or_default();
+            let ret = ctx.fn_ret_types.get(&mangled).cloned().unwrap_or(LltsType::Void);
+            slots.push(DynSlot { name: method.clone(), params: Some(params), ret });
+        }
+        ctx.dyn_types.insert(base, slots);
+    }
+}
+
+/// View a class value as the interface or base-class type its context
+/// expects (binding, parameter, return value, array element). Array literals
+/// and option payloads are viewed element-wise. Anything else, including
+/// classes that do not provide every slot, is returned unchanged.
+pub(crate) fn coerce_to_dyn(expr: Expr, expected: &LltsType, ctx: &mut LowerCtx) -> Expr {
+    match (expected, expr) {
+        (LltsType::Dyn { name }, expr) => {
+            let LltsType::Struct { name: class, .. } = ir_expr_type(&expr) else {
+                return expr;
+            };
+            match vtable_slots(&class, name, ctx) {
+                Some(slots) => Expr::DynCoerce {
+                    value: Box::new(expr),
+                    vtable: format!("__vtable_{class}_as_{name}"),
+                    slots,
+                    ty: expected.clone(),
+                },
+                None => expr,
+            }
+        }
+        (LltsType::Option(inner), expr) if matches!(**inner, LltsType::Dyn { .. }) => {
+            coerce_to_dyn(expr, inner, ctx)
+        }
+        (LltsType::Array(elem), Expr::ArrayLit { elements, .. }) if matches!(**elem, LltsType::Dyn { .. }) => {
+            let elements = elements.into_iter().map(|e| coerce_to_dyn(e, elem, ctx)).collect();
+            Expr::ArrayLit { elem_type: *elem.clone(), elements }
+        }
+        (_, expr) => expr,
+    }
+}
+
+/// Call vtable slot `index` of a `Dyn` receiver. Field slots are getters
+/// taking no arguments.
+pub(crate) fn dyn_call(receiver: Expr, index: u32, slot: &DynSlot, mut args: Vec<Expr>, ctx: &mut LowerCtx) -> Expr {
+    let param_types = slot.params.clone().unwrap_or_default();
+    for (arg, pty) in args.iter_mut().zip(&param_types) {
+        let old = std::mem::replace(arg, Expr::BoolLit(false));
+        *arg = coerce_to_dyn(coerce_to_type(old, pty), pty, ctx);
+    }
+    Expr::DynCall {
+        receiver: Box::new(receiver),
+        slot: index,
+        args,
+        param_types,
+        ret_type: slot.ret.clone(),
+    }
+}
+
+/// The vtable functions of `class` viewed as `dyn_name`, generating the
+/// `__vt_{Class}_as_{Type}_{member}` thunks on first use. `None` if the
+/// class lacks one of the members.
+fn vtable_slots(class: &str, dyn_name: &str, ctx: &mut LowerCtx) -> Option<Vec<String>> {
+    let slots = ctx.dyn_types.get(dyn_name)?.clone();
+    let methods = ctx.class_methods.get(class)?.clone();
+    let mut names = Vec::with_capacity(slots.len());
+    for slot in &slots {
+        let thunk = format!("__vt_{class}_as_{dyn_name}_{}", slot.name);
+        if !ctx.fn_thunks.contains(&thunk) {
+            let func = vtable_thunk(&thunk, class, slot, &methods, ctx)?;
+            ctx.fn_thunks.insert(thunk.clone());
+            ctx.pending_functions.push(func);
+        }
+        names.push(thunk);
+    }
+    Some(names)
+}
+
+/// Build a vtable entry: it takes the data pointer in the env position,
+/// binds `self` to the class value stored there and forwards to
+/// `{Class}_{method}` (or reads the field, for getter slots).
+fn vtable_thunk(
+    thunk: &str,
+    class: &str,
+    slot: &DynSlot,
+    methods: &[String],
+    ctx: &LowerCtx,
+) -> Option<FunctionDecl> {
+    let class_type = ctx.full_struct_type(class);
+    let self_var = Expr::Var { name: "self".to_string(), ty: class_type.clone() };
+    let mut params = vec![(ENV_PARAM.to_string(), LltsType::Ptr)];
+
+    let value = match &slot.params {
+        Some(slot_params) => {
+            if !methods.contains(&slot.name) {
+                return None;
+            }
+            let args: Vec<(String, LltsType)> = slot_params
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (format!("__arg{i}"), t.clone()))
+                .collect();
+            let mut call_args = vec![self_var];
+            call_args.extend(args.iter().map(|(n, t)| Expr::Var { name: n.clone(), ty: t.clone() }));
+            params.extend(args);
+            let callee = format!("{class}_{}", slot.name);
+            Expr::Call {
+                ret_type: ctx.fn_ret_types.get(&callee).cloned().unwrap_or(LltsType::Void),
+                callee,
+                args: call_args,
+            }
+        }
+        None => {
+            let (field_index, field_type) = ctx.lookup_field(class, &slot.name)?;
+            Expr::FieldAccess {
+                object: Box::new(self_var),
+                object_type: class_type.clone(),
+                field_index,
+                field_type,
+            }
+        }
+    };
+
+    // The data cell holds exactly one class value.
+    let bind_self = Stmt::CaptureBind {
+        name: "self".to_string(),
+        ty: class_type.clone(),
+        env_type: LltsType::Struct {
+            name: format!("__dyn_{class}"),
+            fields: vec![("self".to_string(), class_type)],
+        },
+        field_index: 0,
+        by_ref: false,
+    };
+    let body = if slot.ret == LltsType::Void {
+        vec![bind_self, Stmt::Expr(value)]
+    } else {
+        vec![bind_self, Stmt::Return(Some(coerce_to_type(value, &slot.ret)))]
+    };
+    Some(FunctionDecl {
+        name: thunk.to_string(),
+        params,
+        ret_type: slot.ret.clone(),
+        body,
+    })
+}
@@ -1,6 +1,7 @@
 use oxc_ast::ast::Program;
 use oxc_diagnostics::OxcDiagnostic;
-use oxc_semantic::{Semantic, SemanticBuilder, SemanticBuilderReturn};
+use oxc_semantic::{ScopeId, Scoping, Semantic, SemanticBuilder, SemanticBuilderReturn, SymbolFlags};
+use oxc_span::GetSpan;
 
 /// Result of semantic analysis on a parsed program.
 pub struct SemanticResult<'a> {
@@ -22,11 +23,51 @@ impl<'a> SemanticResult<'a> {
 /// `program` must be a reference into allocator-owned memory (i.e.
 /// `&'a Program<'a>` where the allocator outlives both). This performs scope
 /// and symbol resolution, reference binding, and optional syntax error
-/// checking.
+/// checking, and reports `let`/`const` bindings used before their
+/// declaration.
 pub fn analyze_semantics<'a>(program: &'a Program<'a>) -> SemanticResult<'a> {
-    let SemanticBuilderReturn { semantic, errors } = SemanticBuilder::new()
+    let SemanticBuilderReturn { semantic, mut errors } = SemanticBuilder::new()
         .with_check_syntax_error(true)
         .build(program);
+    errors.extend(use_before_declaration(&semantic));
 
     SemanticResult { semantic, errors }
 }
+
+/// The innermost function scope containing `scope`, or the root scope.
+fn enclosing_function(scoping: &Scoping, scope: ScopeId) -> ScopeId {
+    scoping
+        .scope_ancestors(scope)
+        .find(|&s| scoping.scope_flags(s).is_function())
+        .unwrap_or_else(|| scoping.root_scope_id())
+}
+
+/// Reads of `let` and `const` bindings before their declaration (the
+/// temporal dead zone). Only reads from the declaring function are errors:
+/// a nested function may run after the declaration.
+fn use_before_declaration(semantic: &Semantic<'_>) -> Vec<OxcDiagnostic> {
+    let scoping = semantic.scoping();
+    let mut errors = Vec::new();
+    for symbol in scoping.symbol_ids() {
+        if !scoping.symbol_flags(symbol).contains(SymbolFlags::BlockScopedVariable) {
+            continue;
+        }
+        // The declarator, so `let x = x + 1` reads `x` too early.
+        let declared = semantic.symbol_declaration(symbol).kind().span();
+        let function = enclosing_function(scoping, scoping.symbol_scope_id(symbol));
+        for reference in scoping.get_resolved_references(symbol).filter(|r| r.is_value()) {
+            let node = semantic.nodes().get_node(reference.node_id());
+            let span = node.kind().span();
+            if span.start < declared.end && enclosing_function(scoping, node.scope_id()) == function {
+                errors.push(
+                    OxcDiagnostic::error(format!(
+                        "Block-scoped variable '{}' used before its declaration",
+                        scoping.symbol_name(symbol)
+                    ))
+                    .with_label(span),
+                );
+            }
+        }
+    }
+    errors
+}
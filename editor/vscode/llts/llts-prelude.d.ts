@@ -19,3 +19,11 @@ type u8 = number & { readonly [__u8]: true };
 
 // Built-in functions
 declare function print(...args: unknown[]): void;
+declare function sleep(ms: number): Promise<void>;
+declare function readFile(path: string): Promise<string | null>;
+
+// Result values: construct with Ok/Err, narrow on `ok`
+type Result<T, E> = { ok: true; value: T } | { ok: false; error: E };
+declare function Ok<T>(value: T): { ok: true; value: T };
+declare function Ok(): { ok: true; value: void };
+declare function Err<E>(error: E): { ok: false; error: E };
@@ -24,3 +24,7 @@ declare type Weak<T> = T;
 
 // Built-in print function
 declare function print(...args: any[]): void;
+
+// Built-in promises (`Promise<T>` is the standard lib's; see docs/features/async.md)
+declare function sleep(ms: i64): Promise<void>;
+declare function readFile(path: string): Promise<string | null>;
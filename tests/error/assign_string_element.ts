@@ -0,0 +1,7 @@
+// Should error: cannot assign to an element of string
+
+function main(): void {
+  const s: string = "cat";
+  s[0] = "b";
+  print(s);
+}
@@ -0,0 +1,14 @@
+// Should error: cannot assign to 'x' of a temporary Point
+
+interface Point {
+  x: f64;
+  y: f64;
+}
+
+function origin(): Point {
+  return { x: 0, y: 0 };
+}
+
+function main(): void {
+  origin().x = 1;
+}
@@ -0,0 +1,11 @@
+// Should error: `await` in a loop condition or update is not supported (in async function 'main')
+
+async function ready(): Promise<boolean> {
+  return true;
+}
+
+async function main(): Promise<void> {
+  while (!(await ready())) {
+    print("waiting");
+  }
+}
@@ -0,0 +1,9 @@
+// Should error: `await` inside switch or a try with `finally` is not supported (in async function 'main')
+
+async function main(): Promise<void> {
+  try {
+    await sleep(1);
+  } finally {
+    print("done");
+  }
+}
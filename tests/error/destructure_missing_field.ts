@@ -0,0 +1,12 @@
+// Should error: 'Point' has no field 'z' to destructure
+
+interface Point {
+  x: i32;
+  y: i32;
+}
+
+function main(): void {
+  const p: Point = { x: 1, y: 2 };
+  const { x, z } = p;
+  print(x + z);
+}
@@ -0,0 +1,12 @@
+// Should error: `yield` must be a statement of its own (`yield value;`) in generator 'numbers'
+
+function* numbers(): Generator<i32> {
+  const x: i32 = yield 1;
+  print(x);
+}
+
+function main(): void {
+  for (const n of numbers()) {
+    print(n);
+  }
+}
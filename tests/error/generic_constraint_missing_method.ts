@@ -0,0 +1,24 @@
+// Should error: type Version does not satisfy constraint Comparable for parameter 'T' in maxOf (missing compareTo())
+
+interface Comparable<T> {
+  compareTo(other: T): f64;
+}
+
+class Version {
+  major: f64;
+
+  constructor(major: f64) {
+    this.major = major;
+  }
+}
+
+function maxOf<T extends Comparable<T>>(a: T, b: T): T {
+  if (a.compareTo(b) >= 0) {
+    return a;
+  }
+  return b;
+}
+
+function main(): void {
+  print(maxOf<Version>(new Version(3), new Version(1)).major);
+}
@@ -0,0 +1,10 @@
+// Should error: ambiguous type argument for 'T' — inferred both i32 and string.
+
+function pick<T>(a: T, b: T): T {
+  return a;
+}
+
+function main(): void {
+  const n: i32 = 1;
+  print(pick(n, "one"));
+}
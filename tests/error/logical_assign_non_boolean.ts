@@ -0,0 +1,7 @@
+// Should error: `||=` is only supported on booleans, got i32
+
+function main(): void {
+  let retries: i32 = 0;
+  retries ||= 3;
+  print(retries);
+}
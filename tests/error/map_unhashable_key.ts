@@ -0,0 +1,6 @@
+// Should error: Map key type arr_f64 is not hashable.
+
+function main(): void {
+  const m = new Map<f64[], i32>();
+  print(m.size);
+}
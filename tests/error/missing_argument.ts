@@ -0,0 +1,9 @@
+// Should error: missing arguments: expected at least 1, got 0
+
+function add(a: i32, b: i32 = 1): i32 {
+  return a + b;
+}
+
+function main(): void {
+  print(add());
+}
@@ -0,0 +1,13 @@
+// Should error: `r.value` can only be read where `r.ok` is known to be true
+
+function half(n: i32): Result<i32, string> {
+  if (n % 2 != 0) {
+    return Err("odd");
+  }
+  return Ok(n / 2);
+}
+
+function main(): void {
+  const r = half(3);
+  print(r.value);
+}
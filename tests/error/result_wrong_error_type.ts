@@ -0,0 +1,12 @@
+// Should error: `parse` returns `Result<_, Error>` but can throw string
+
+function parse(input: string): Result<i32, Error> {
+  if (input === "") {
+    throw "empty input";
+  }
+  return 1;
+}
+
+function main(): void {
+  parse("");
+}
@@ -0,0 +1,10 @@
+// Should error: a spread argument must be a tuple or passed to a rest parameter
+
+function add(a: i32, b: i32): i32 {
+  return a + b;
+}
+
+function main(): void {
+  const xs: i32[] = [1, 2];
+  print(add(...xs));
+}
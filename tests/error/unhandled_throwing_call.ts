@@ -0,0 +1,20 @@
+// Should error: `checkAge` can throw, but `nextAge` calls it outside `try`/`catch`
+
+function checkAge(age: i32): i32 {
+  if (age < 0) {
+    throw new Error("negative age");
+  }
+  return age;
+}
+
+function nextAge(age: i32): i32 {
+  return checkAge(age) + 1;
+}
+
+function main(): void {
+  try {
+    print(nextAge(41));
+  } catch (e) {
+    print(e.message);
+  }
+}
@@ -0,0 +1,14 @@
+// Should error: `slot` can throw, but `main` calls it outside `try`/`catch`
+
+function slot(i: i32): i32 {
+  if (i < 0) {
+    throw new Error("negative slot");
+  }
+  return i;
+}
+
+function main(): void {
+  const values: i32[] = [1, 2, 3];
+  values[slot(1)] = 5;
+  print(values[1]);
+}
@@ -0,0 +1,31 @@
+// Should error: `Account.withdraw` can throw, but `drain` calls it outside `try`/`catch`
+
+class Account {
+  balance: i32;
+
+  constructor(balance: i32) {
+    this.balance = balance;
+  }
+
+  withdraw(amount: i32): i32 {
+    if (amount > this.balance) {
+      throw new Error("insufficient funds");
+    }
+    this.balance = this.balance - amount;
+    return this.balance;
+  }
+}
+
+function drain(accounts: Account[]): void {
+  for (let i: i32 = 0; i < accounts.length; i++) {
+    accounts[i].withdraw(20);
+  }
+}
+
+function main(): void {
+  try {
+    drain([new Account(10)]);
+  } catch (e) {
+    print(e.message);
+  }
+}
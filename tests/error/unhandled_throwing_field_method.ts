@@ -0,0 +1,38 @@
+// Should error: `Account.withdraw` can throw, but `Bank.close` calls it outside `try`/`catch`
+
+class Account {
+  balance: i32;
+
+  constructor(balance: i32) {
+    this.balance = balance;
+  }
+
+  withdraw(amount: i32): i32 {
+    if (amount > this.balance) {
+      throw new Error("insufficient funds");
+    }
+    this.balance = this.balance - amount;
+    return this.balance;
+  }
+}
+
+class Bank {
+  main: Account;
+
+  constructor(main: Account) {
+    this.main = main;
+  }
+
+  close(): i32 {
+    return this.main.withdraw(this.main.balance);
+  }
+}
+
+function main(): void {
+  const bank = new Bank(new Account(10));
+  try {
+    print(bank.close());
+  } catch (e) {
+    print(e.message);
+  }
+}
@@ -0,0 +1,22 @@
+// Should error: `Account.withdraw` can throw, but `main` calls it outside `try`/`catch`
+
+class Account {
+  balance: i32;
+
+  constructor(balance: i32) {
+    this.balance = balance;
+  }
+
+  withdraw(amount: i32): i32 {
+    if (amount > this.balance) {
+      throw new Error("insufficient funds");
+    }
+    this.balance = this.balance - amount;
+    return this.balance;
+  }
+}
+
+function main(): void {
+  const account = new Account(10);
+  print(account.withdraw(20));
+}
@@ -0,0 +1,42 @@
+// Should error: cannot tell which `withdraw` `pay` calls, and `Account.withdraw` can throw
+
+class Account {
+  balance: i32;
+
+  constructor(balance: i32) {
+    this.balance = balance;
+  }
+
+  withdraw(amount: i32): i32 {
+    if (amount > this.balance) {
+      throw new Error("insufficient funds");
+    }
+    this.balance = this.balance - amount;
+    return this.balance;
+  }
+}
+
+class Wallet {
+  cash: i32;
+
+  constructor(cash: i32) {
+    this.cash = cash;
+  }
+
+  withdraw(amount: i32): i32 {
+    this.cash = this.cash - amount;
+    return this.cash;
+  }
+}
+
+function pay(from: Account | Wallet, amount: i32): void {
+  from.withdraw(amount);
+}
+
+function main(): void {
+  try {
+    pay(new Wallet(10), 20);
+  } catch (e) {
+    print(e.message);
+  }
+}
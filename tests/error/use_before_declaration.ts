@@ -0,0 +1,7 @@
+// Should error: Block-scoped variable 'total' used before its declaration
+
+function main(): void {
+  print(total);
+  let total: i32 = 3;
+  print(total);
+}
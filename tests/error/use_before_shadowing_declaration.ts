@@ -0,0 +1,11 @@
+// Should error: Block-scoped variable 'limit' used before its declaration
+
+function main(): void {
+  const limit: i32 = 10;
+  if (limit > 0) {
+    // The inner `limit` shadows the outer one for the whole block
+    print(limit);
+    const limit: i32 = 5;
+    print(limit);
+  }
+}
@@ -0,0 +1,6 @@
+// Should error: Block-scoped variable 'count' used before its declaration
+
+function main(): void {
+  let count: i32 = count + 1;
+  print(count);
+}
@@ -0,0 +1,50 @@
+// Expected output: start\n6\ncreated\n5\nfast\nslow\nslow\nfast\nhare\nhare\nslept\nmissing\ndone
+
+async function delayed(label: string, ms: i64): Promise<string> {
+  await sleep(ms);
+  print(label);
+  return label;
+}
+
+async function add(a: i32, b: i32): Promise<i32> {
+  await sleep(1);
+  return a + b;
+}
+
+async function sum(n: i32): Promise<i32> {
+  let total: i32 = 0;
+  for (let i: i32 = 0; i < n; i++) {
+    const next = await add(total, i);
+    total = next;
+  }
+  return total;
+}
+
+async function main(): Promise<void> {
+  print("start");
+  const s = await sum(4);
+  print(s);
+
+  // Promises are lazy: the body runs once awaited
+  const p = add(2, 3);
+  print("created");
+  print(await p);
+
+  // Awaited together, the shorter sleep finishes first
+  const labels = await Promise.all([delayed("slow", 30), delayed("fast", 10)]);
+  print(labels[0]);
+  print(labels[1]);
+
+  // The loser is dropped without finishing
+  const winner = await Promise.race([delayed("tortoise", 40), delayed("hare", 5)]);
+  print(winner);
+
+  await Promise.all([sleep(1), sleep(2)]);
+  print("slept");
+
+  const contents = await readFile("/nonexistent/llts/async_await.txt");
+  if (contents === null) {
+    print("missing");
+  }
+  print("done");
+}
@@ -0,0 +1,35 @@
+// Expected output: fetching 3\n30\n31\n2
+
+class Fetcher {
+  scale: i32;
+
+  constructor(scale: i32) {
+    this.scale = scale;
+  }
+
+  async fetch(id: i32): Promise<i32> {
+    print(`fetching ${id}`);
+    await sleep(1);
+    return id * this.scale;
+  }
+
+  async fetchBoth(a: i32, b: i32): Promise<i32> {
+    // Awaiting another method of the same class
+    const first = await this.fetchQuiet(a);
+    const second = await this.fetchQuiet(b);
+    return first + second;
+  }
+
+  async fetchQuiet(id: i32): Promise<i32> {
+    await sleep(1);
+    return id * this.scale;
+  }
+}
+
+async function main(): Promise<void> {
+  const fetcher = new Fetcher(10);
+  print(await fetcher.fetch(3));
+  print(await new Fetcher(1).fetchBoth(10, 21));
+  const results = await Promise.all([fetcher.fetchQuiet(0), new Fetcher(2).fetchQuiet(1)]);
+  print(results[0] + results[1]);
+}
@@ -0,0 +1,52 @@
+// Expected output: before\ncaught bad input\nafter\n7\nfailed at 2\nrecovered\n-1
+
+async function parse(text: string): Promise<i32> {
+  await sleep(1);
+  return text === "" ? 0 : 7;
+}
+
+function check(step: i32): void {
+  if (step === 2) {
+    throw step;
+  }
+}
+
+async function steps(): Promise<i32> {
+  let step: i32 = 0;
+  try {
+    while (step < 5) {
+      await sleep(1);
+      step += 1;
+      check(step);
+    }
+  } catch (e) {
+    // The catch body can await too
+    print(`failed at ${e}`);
+    await sleep(1);
+    print("recovered");
+    return -1;
+  }
+  return step;
+}
+
+async function main(): Promise<void> {
+  try {
+    print("before");
+    const length = await parse("");
+    if (length === 0) {
+      throw "bad input";
+    }
+    print(length);
+  } catch (e) {
+    print(`caught ${e}`);
+  }
+  print("after");
+
+  try {
+    print(await parse("seven"));
+  } catch (e) {
+    print("unreachable");
+  }
+
+  print(await steps());
+}
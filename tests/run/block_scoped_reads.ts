@@ -0,0 +1,14 @@
+// Expected output: 1\n2\n1
+
+function main(): void {
+  const total: i32 = 1;
+  {
+    // Reads the outer `total`: the inner one is declared in another block
+    print(total);
+  }
+  {
+    const total: i32 = 2;
+    print(total);
+  }
+  print(total);
+}
@@ -0,0 +1,51 @@
+// Expected output: 4\n1\n4\n8\n11\n3
+
+class Animal {
+  legs: f64;
+  id: f64 = 1;
+
+  constructor(legs: f64) {
+    this.legs = legs;
+  }
+
+  describe(): f64 {
+    return this.legs;
+  }
+
+  weight(): f64 {
+    return this.legs * 2;
+  }
+}
+
+class Dog extends Animal {
+  tricks: f64;
+
+  constructor(tricks: f64) {
+    super(4);
+    this.tricks = tricks;
+  }
+
+  // Overrides Animal.weight and builds on it
+  weight(): f64 {
+    return super.weight() + this.tricks;
+  }
+}
+
+class Puppy extends Dog {}
+
+function main(): void {
+  const d = new Dog(3);
+  print(d.legs);
+  print(d.id);
+
+  // Inherited method
+  print(d.describe());
+
+  const a = new Animal(4);
+  print(a.weight());
+  print(d.weight());
+
+  // Implicit constructor forwards to the parent's
+  const p = new Puppy(3);
+  print(p.tricks);
+}
@@ -0,0 +1,28 @@
+// Expected output: 10\n15\n6
+
+function apply(f: (x: f64) => f64, val: f64): f64 {
+  return f(val);
+}
+
+function main(): void {
+  // Non-escaping capture of an enclosing const
+  const scale: f64 = 2.0;
+  print(apply((x: f64): f64 => x * scale, 5.0));
+
+  // Writes through the capture reach the enclosing frame
+  let total: f64 = 0;
+  const add = (n: f64): void => {
+    total += n;
+  };
+  add(5);
+  add(10);
+  print(total);
+
+  // Nested lambdas capture through their parent's environment
+  const offset: i32 = 1;
+  const nested = (a: i32): i32 => {
+    const inner = (b: i32): i32 => b + offset;
+    return inner(a) * 2;
+  };
+  print(nested(2));
+}
@@ -0,0 +1,70 @@
+// Expected output: Hello, world\nHi, Ann\n7\n12\nnone\none\n1\n5\n11\n11\n6\n10\n102\n15
+
+function greet(name: string = "world", greeting: string = "Hello"): string {
+  return greeting + ", " + name;
+}
+
+// A default can use the parameters before it
+function add(a: i32, b: i32 = a + 2): i32 {
+  return a + b;
+}
+
+function describe(n: i32, label?: string): string {
+  if (label !== null) {
+    return label;
+  }
+  return "none";
+}
+
+class Counter {
+  count: i32;
+
+  constructor(start: i32 = 0) {
+    this.count = start;
+  }
+
+  add(step: i32 = 1): i32 {
+    this.count = this.count + step;
+    return this.count;
+  }
+
+  addAll(...steps: i32[]): i32 {
+    for (const step of steps) {
+      this.count = this.count + step;
+    }
+    return this.count;
+  }
+}
+
+function main(): void {
+  print(greet());
+  print(greet("Ann", "Hi"));
+  print(add(5, 2));
+  print(add(5));
+  print(describe(1));
+  print(describe(1, "one"));
+
+  const c = new Counter();
+  print(c.add());
+  print(c.add(4));
+  print(c.addAll(1, 2, 3));
+  const d = new Counter(10);
+  print(d.add());
+
+  const scale = (x: i32, factor: i32 = 3): i32 => x * factor;
+  print(scale(2));
+  print(scale(2, 5));
+
+  const base: i32 = 100;
+  const offset = (x: i32, extra: i32 = 1): i32 => base + x + extra;
+  print(offset(1));
+
+  const total = (...xs: i32[]): i32 => {
+    let sum: i32 = 0;
+    for (const x of xs) {
+      sum = sum + x;
+    }
+    return sum;
+  };
+  print(total(4, 5, 6));
+}
@@ -0,0 +1,84 @@
+// Expected output: ann\n7\nanon\n7\nann\n3\n1\n3\n1\n4\n5\n20\n7\n13\n42\n2\n30\n3\n7\n2\n1\n34
+
+interface Point {
+  x: i32;
+  y: i32;
+}
+
+class Player {
+  name: string;
+  pos: Point;
+  nickname: string | null;
+  score: i32;
+
+  constructor(name: string, pos: Point, nickname: string | null, score: i32) {
+    this.name = name;
+    this.pos = pos;
+    this.nickname = nickname;
+    this.score = score;
+  }
+}
+
+function manhattan({ x, y }: Point): i32 {
+  return x + y;
+}
+
+function headAndCount([head, ...rest]: i32[]): i32 {
+  return head * 10 + rest.length;
+}
+
+function main(): void {
+  const origin: Point = { x: 3, y: 4 };
+  const player = new Player("ann", origin, null, 7);
+
+  // Nested patterns and defaults
+  const { name, pos: { x, y }, nickname = "anon" } = player;
+  print(name);
+  print(x + y);
+  print(nickname);
+
+  // Object rest
+  const { score, ...others } = player;
+  print(score);
+  print(others.name);
+  print(others.pos.x);
+
+  // Holes, array rest and defaults
+  const xs: i32[] = [1, 2, 3, 4];
+  const [first, , third, ...tail] = xs;
+  print(first);
+  print(third);
+  print(tail.length);
+  print(tail[0]);
+  const short: i32[] = [5];
+  const [p, q = 20] = short;
+  print(p);
+  print(q);
+
+  // Parameters
+  print(manhattan(origin));
+  print(headAndCount(xs));
+  const pairSum = ([a, b]: i32[]): i32 => a + b;
+  print(pairSum([20, 22]));
+
+  // for...of heads
+  const points: Point[] = [{ x: 1, y: 2 }, { x: 5, y: 6 }];
+  for (const { x: px, y: py } of points) {
+    print(px * py);
+  }
+  const pairs: i32[][] = [[1, 2], [3, 4]];
+  for (const [i, v] of pairs) {
+    print(i + v);
+  }
+
+  // Assignment
+  let a: i32 = 1;
+  let b: i32 = 2;
+  [a, b] = [b, a];
+  print(a);
+  print(b);
+  let u: i32 = 0;
+  let w: i32 = 0;
+  ({ x: u, y: w } = origin);
+  print(u * 10 + w);
+}
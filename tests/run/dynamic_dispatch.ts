@@ -0,0 +1,101 @@
+// Expected output: 3\n12\n4\n15\n1\n2\n4\n25
+
+interface Shape {
+  area(): f64;
+  sides(): f64;
+}
+
+class Square implements Shape {
+  size: f64;
+
+  constructor(size: f64) {
+    this.size = size;
+  }
+
+  area(): f64 {
+    return this.size * this.size;
+  }
+
+  sides(): f64 {
+    return 4;
+  }
+}
+
+class Tri implements Shape {
+  base: f64;
+  height: f64;
+
+  constructor(base: f64, height: f64) {
+    this.base = base;
+    this.height = height;
+  }
+
+  area(): f64 {
+    return (this.base * this.height) / 2;
+  }
+
+  sides(): f64 {
+    return 3;
+  }
+}
+
+class Animal {
+  legs: f64;
+
+  constructor(legs: f64) {
+    this.legs = legs;
+  }
+
+  speak(): f64 {
+    return 1;
+  }
+}
+
+class Dog extends Animal {
+  constructor() {
+    super(4);
+  }
+
+  speak(): f64 {
+    return 2;
+  }
+}
+
+function totalArea(shapes: Shape[]): f64 {
+  let total: f64 = 0;
+  for (const s of shapes) {
+    total += s.area();
+  }
+  return total;
+}
+
+function describe(s: Shape): f64 {
+  return s.area() + s.sides();
+}
+
+function loudness(a: Animal): f64 {
+  return a.speak();
+}
+
+function legsOf(a: Animal): f64 {
+  return a.legs;
+}
+
+function main(): void {
+  const shapes: Shape[] = [new Tri(2, 3), new Square(3)];
+  print(shapes[0].area());
+  print(totalArea(shapes));
+
+  const s: Shape = new Square(2);
+  print(s.area());
+  print(describe(new Tri(4, 6)));
+
+  // Base-class parameters dispatch to the override
+  print(loudness(new Animal(2)));
+  print(loudness(new Dog()));
+  print(legsOf(new Dog()));
+
+  // Concrete receivers still call the method directly
+  const sq = new Square(5);
+  print(sq.area());
+}
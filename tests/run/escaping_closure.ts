@@ -0,0 +1,29 @@
+// Expected output: 1\n2\n3\n1\n15\n25
+
+function makeCounter(): () => i32 {
+  let count: i32 = 0;
+  return (): i32 => {
+    count += 1;
+    return count;
+  };
+}
+
+function makeAdder(base: f64): (x: f64) => f64 {
+  // Never mutated: copied into the closure's environment
+  return (x: f64): f64 => x + base;
+}
+
+function main(): void {
+  const c = makeCounter();
+  print(c());
+  print(c());
+  print(c());
+
+  // Each call gets its own box
+  const d = makeCounter();
+  print(d());
+
+  const add10 = makeAdder(10.0);
+  print(add10(5.0));
+  print(add10(15.0));
+}
@@ -0,0 +1,41 @@
+// Expected output: 11\n3\n5\n1\n2\n20\n7
+
+interface Handler {
+  name: string;
+  run: (x: i32) => i32;
+}
+
+function makeHandler(offset: i32): Handler {
+  // Stored in a struct field, called after this frame returns
+  return { name: "add", run: (x: i32): i32 => x + offset };
+}
+
+function makeSteps(): ((x: i32) => i32)[] {
+  // Stored in array elements; `calls` is shared through a box
+  let calls: i32 = 0;
+  const steps: ((x: i32) => i32)[] = [];
+  steps.push((x: i32): i32 => {
+    calls += 1;
+    return x + calls;
+  });
+  steps.push((x: i32): i32 => x * 5);
+  return steps;
+}
+
+function main(): void {
+  let handler = makeHandler(10);
+  print(handler.run(1));
+
+  const steps = makeSteps();
+  print(steps[0](2));
+  print(steps[1](1));
+
+  // Overwriting releases the closure it replaces
+  handler = makeHandler(-1);
+  print(handler.run(2));
+  handler.run = (x: i32): i32 => x * 2;
+  print(handler.run(1));
+  steps[1] = handler.run;
+  print(steps[1](10));
+  print(steps[0](5));
+}
@@ -0,0 +1,74 @@
+// Expected output: body\ncleanup 1\nreturning\ncleanup 2\n10\n0\nfinally\nfinally\n2\nfinally\nfinally\ncaught bad input\ncleanup 3\ninner cleanup\nouter: rethrown\nfinally ran\nmain: from helper
+
+function plain(): void {
+  try {
+    print("body");
+  } finally {
+    print("cleanup 1");
+  }
+}
+
+function compute(): i32 {
+  let x: i32 = 10;
+  try {
+    print("returning");
+    return x;
+  } finally {
+    // The returned value was computed before this runs
+    x = 20;
+    print("cleanup 2");
+  }
+}
+
+function helper(): void {
+  try {
+    throw new Error("from helper");
+  } finally {
+    print("finally ran");
+  }
+}
+
+function main(): void {
+  plain();
+  print(compute());
+
+  for (let i: i32 = 0; i < 5; i++) {
+    try {
+      if (i == 1) {
+        continue;
+      }
+      if (i == 3) {
+        break;
+      }
+      print(i);
+    } finally {
+      print("finally");
+    }
+  }
+
+  try {
+    throw "bad input";
+  } catch (e) {
+    print("caught " + e);
+  } finally {
+    print("cleanup 3");
+  }
+
+  try {
+    try {
+      throw new Error("rethrown");
+    } catch (e) {
+      throw e;
+    } finally {
+      print("inner cleanup");
+    }
+  } catch (e) {
+    print("outer: " + e.message);
+  }
+
+  try {
+    helper();
+  } catch (e) {
+    print("main: " + e.message);
+  }
+}
@@ -0,0 +1,51 @@
+// Expected output: 6\n9\n4\n12\n7\n30
+
+interface Handler {
+  name: string;
+  callback: (x: f64) => f64;
+}
+
+function double(x: f64): f64 {
+  return x * 2;
+}
+
+function triple(x: f64): f64 {
+  return x * 3;
+}
+
+function pick(useDouble: boolean): (x: f64) => f64 {
+  if (useDouble) {
+    return double;
+  }
+  return triple;
+}
+
+function apply(f: (x: f64) => f64, value: f64): f64 {
+  return f(value);
+}
+
+function main(): void {
+  // Named functions as values
+  const f: (x: f64) => f64 = double;
+  print(f(3));
+
+  // Arrays of callbacks
+  const handlers: ((x: f64) => f64)[] = [double, triple];
+  print(handlers[1](3));
+
+  // Function-typed struct fields
+  const h: Handler = { name: "halve", callback: (x: f64): f64 => x / 2 };
+  print(h.callback(8));
+
+  // Function-returning functions
+  print(pick(false)(4));
+
+  // Lambda parameter types from the call's parameter type
+  print(apply((x) => x + 1, 6));
+
+  let total: f64 = 0;
+  for (const g of handlers) {
+    total += g(5);
+  }
+  print(total);
+}
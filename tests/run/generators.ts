@@ -0,0 +1,93 @@
+// Expected output: 0\n1\n2\n10\n20\ndone\n1\n1\n2\n3\n5\n8\n0\n2\n4\n6\nfirst\nsecond\nab\nab
+
+function* range(start: i32, end: i32): Generator<i32> {
+  for (let i: i32 = start; i < end; i++) {
+    yield i;
+  }
+}
+
+function* pair(a: i32, b: i32): Generator<i32> {
+  yield a;
+  yield b;
+}
+
+function* fibonacci(): Generator<i32> {
+  let a: i32 = 1;
+  let b: i32 = 1;
+  while (true) {
+    yield a;
+    const next: i32 = a + b;
+    a = b;
+    b = next;
+  }
+}
+
+function* evensUpTo(limit: i32): Generator<i32> {
+  let n: i32 = 0;
+  while (true) {
+    if (n > limit) {
+      return;
+    }
+    if (n % 2 === 0) {
+      yield n;
+    }
+    n++;
+  }
+}
+
+function* nonEmpty(items: string[]): Generator<string> {
+  for (const item of items) {
+    if (item.length === 0) {
+      continue;
+    }
+    yield item;
+  }
+}
+
+function* repeat<T>(value: T, times: i32): Generator<T> {
+  for (let i: i32 = 0; i < times; i++) {
+    yield value;
+  }
+}
+
+function main(): void {
+  for (const i of range(0, 3)) {
+    print(i);
+  }
+
+  // Manual next() calls
+  const p = pair(10, 20);
+  const first = p.next();
+  if (first !== null) {
+    print(first);
+  }
+  const second = p.next();
+  if (second !== null) {
+    print(second);
+  }
+  if (p.next() === null) {
+    print("done");
+  }
+
+  // An infinite generator, left early
+  let count: i32 = 0;
+  for (const f of fibonacci()) {
+    if (count === 6) {
+      break;
+    }
+    print(f);
+    count++;
+  }
+
+  for (const n of evensUpTo(7)) {
+    print(n);
+  }
+
+  for (const word of nonEmpty(["first", "", "second"])) {
+    print(word);
+  }
+
+  for (const s of repeat("ab", 2)) {
+    print(s);
+  }
+}
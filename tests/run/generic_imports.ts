@@ -0,0 +1,21 @@
+// Expected output: 42\nhi\n6\n3\nb
+
+import { identity, map, last, Stack } from "./generic_lib";
+
+function main(): void {
+  print(identity(42));
+  print(identity<string>("hi"));
+
+  const xs: i32[] = [1, 2, 3];
+  const doubled = map(xs, (x: i32): i32 => x * 2);
+  print(last(doubled));
+
+  const ints = new Stack<i32>();
+  ints.push(3);
+  print(ints.peek());
+
+  const strs = new Stack<string>();
+  strs.push("a");
+  strs.push("b");
+  print(strs.peek());
+}
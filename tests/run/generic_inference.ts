@@ -0,0 +1,65 @@
+// Expected output: 42\n7\n10\n3\n5\n2\n4\n6\n9\n-1
+
+interface Box<T> {
+  value: T;
+}
+
+function identity<T>(x: T): T {
+  return x;
+}
+
+function max<T>(a: T, b: T): T {
+  return a > b ? a : b;
+}
+
+function first<T>(xs: T[]): T {
+  return xs[0];
+}
+
+function orElse<T>(x: T | null, fallback: T): T {
+  if (x !== null) {
+    return x;
+  }
+  return fallback;
+}
+
+function map<T, U>(xs: T[], f: (x: T) => U): U[] {
+  const out: U[] = [];
+  for (const x of xs) {
+    out.push(f(x));
+  }
+  return out;
+}
+
+function unbox<T>(b: Box<T>): T {
+  return b.value;
+}
+
+function sum<T = i32>(xs: T[]): i32 {
+  return xs.length as i32;
+}
+
+function main(): void {
+  print(identity(42));
+
+  // A typed argument decides over a bare literal: T = i32
+  const n: i32 = 7;
+  print(max(n, 0));
+
+  const values: i32[] = [10, 20, 30];
+  print(first(values));
+  print(orElse(null, 3));
+  print(orElse(5, 0));
+
+  // T = i32 from the array, U = i32 from the lambda body
+  const doubled = map(values, (x) => x / 10 * 2);
+  print(doubled[0]);
+  print(doubled[1]);
+  print(doubled[2]);
+
+  const b: Box<i32> = { value: 9 };
+  print(unbox(b));
+
+  // T falls back to its default
+  print(sum([]) - 1);
+}
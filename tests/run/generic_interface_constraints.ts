@@ -0,0 +1,76 @@
+// Expected output: 5\n6\n3\n9\n7
+
+interface HasLength {
+  length: f64;
+}
+
+interface Comparable<T> {
+  compareTo(other: T): f64;
+}
+
+class Rope {
+  length: f64;
+
+  constructor(length: f64) {
+    this.length = length;
+  }
+}
+
+class Version implements Comparable<Version> {
+  major: f64;
+
+  constructor(major: f64) {
+    this.major = major;
+  }
+
+  compareTo(other: Version): f64 {
+    return this.major - other.major;
+  }
+}
+
+function longer<T extends HasLength>(a: T, b: T): T {
+  if (a.length >= b.length) {
+    return a;
+  }
+  return b;
+}
+
+function totalLength<T extends HasLength>(items: T[]): f64 {
+  let total: f64 = 0;
+  for (const item of items) {
+    total += item.length;
+  }
+  return total;
+}
+
+function maxOf<T extends Comparable<T>>(a: T, b: T): T {
+  if (a.compareTo(b) >= 0) {
+    return a;
+  }
+  return b;
+}
+
+function largest<T extends Comparable<T>>(items: T[]): T {
+  let best: T = items[0];
+  for (const item of items) {
+    if (item.compareTo(best) > 0) {
+      best = item;
+    }
+  }
+  return best;
+}
+
+function main(): void {
+  print(longer<Rope>(new Rope(5), new Rope(3)).length);
+
+  const ropes: Rope[] = [new Rope(1), new Rope(2), new Rope(3)];
+  print(totalLength<Rope>(ropes));
+
+  print(maxOf<Version>(new Version(3), new Version(1)).major);
+
+  const versions: Version[] = [new Version(1), new Version(4), new Version(9)];
+  print(largest<Version>(versions).major);
+
+  const v = maxOf<Version>(new Version(2), new Version(7));
+  print(v.major);
+}
@@ -0,0 +1,27 @@
+export function identity<T>(x: T): T {
+  return x;
+}
+
+export function map<T, U>(xs: T[], f: (x: T) => U): U[] {
+  const out: U[] = [];
+  for (const x of xs) {
+    out.push(f(x));
+  }
+  return out;
+}
+
+export function last<T>(xs: T[]): T {
+  return xs[xs.length - 1];
+}
+
+export class Stack<T> {
+  items: T[] = [];
+
+  push(item: T): void {
+    this.items.push(item);
+  }
+
+  peek(): T {
+    return last(this.items);
+  }
+}
@@ -0,0 +1,56 @@
+// Expected output: 42\nhello\n7\n3\n2\n2\ntwo\n1\n2
+
+interface Pair<A, B> {
+  first: A;
+  second: B;
+}
+
+type Box<T> = { value: T };
+
+class Stack<T> {
+  items: T[] = [];
+
+  push(item: T): void {
+    this.items.push(item);
+  }
+
+  peek(): T {
+    return this.items[this.items.length - 1];
+  }
+
+  size(): i32 {
+    return this.items.length as i32;
+  }
+}
+
+function unbox(b: Box<i32>): i32 {
+  return b.value;
+}
+
+function main(): void {
+  const p: Pair<i32, string> = { first: 42, second: "hello" };
+  print(p.first);
+  print(p.second);
+
+  const b: Box<i32> = { value: 7 };
+  print(unbox(b));
+
+  const ints = new Stack<i32>();
+  ints.push(1);
+  ints.push(2);
+  ints.push(3);
+  print(ints.peek());
+  print(ints.size() - 1);
+
+  const pairs: Stack<Pair<i32, string>> = new Stack();
+  pairs.push({ first: 1, second: "one" });
+  pairs.push({ first: 2, second: "two" });
+  const top = pairs.peek();
+  print(top.first);
+  print(top.second);
+
+  const flags = new Stack<bool>();
+  flags.push(true);
+  print(flags.size());
+  print(pairs.size());
+}
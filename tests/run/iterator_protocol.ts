@@ -0,0 +1,88 @@
+// Expected output: 3\n2\n1\n10\n20\n30\n1\n4\n9\nh\né\n中\n😀\n4\n5\n3
+
+// An iterator: `next()` returns null when done
+class Countdown {
+  n: i32;
+  constructor(n: i32) {
+    this.n = n;
+  }
+  next(): i32 | null {
+    if (this.n <= 0) {
+      return null;
+    }
+    this.n -= 1;
+    return this.n + 1;
+  }
+}
+
+// A lazy sequence
+class Squares {
+  i: i32;
+  limit: i32;
+  constructor(limit: i32) {
+    this.i = 0;
+    this.limit = limit;
+  }
+  next(): i32 | null {
+    if (this.i >= this.limit) {
+      return null;
+    }
+    this.i += 1;
+    return this.i * this.i;
+  }
+}
+
+// Iterables: `iter()` returns what the loop walks
+class Bag {
+  items: i32[];
+  constructor(items: i32[]) {
+    this.items = items;
+  }
+  iter(): i32[] {
+    return this.items;
+  }
+}
+
+class SquareRange {
+  n: i32;
+  constructor(n: i32) {
+    this.n = n;
+  }
+  iter(): Squares {
+    return new Squares(this.n);
+  }
+}
+
+function main(): void {
+  for (const n of new Countdown(3)) {
+    print(n);
+  }
+
+  const items: i32[] = [10, 20, 30];
+  const bag = new Bag(items);
+  for (const x of bag) {
+    print(x);
+  }
+
+  for (const sq of new SquareRange(3)) {
+    print(sq);
+  }
+
+  // Strings iterate by code point
+  let count: i32 = 0;
+  for (const ch of "hé中😀") {
+    print(ch);
+    count += 1;
+  }
+  print(count);
+
+  for (const n of new Countdown(5)) {
+    if (n == 4) {
+      continue;
+    }
+    if (n == 2) {
+      break;
+    }
+    print(n);
+  }
+}
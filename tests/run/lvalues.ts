@@ -0,0 +1,72 @@
+// Expected output: 3\n5\n0\n6\n7\n0\n4\n-1\n9\n9\n3\n3\n1\n1\n2\n3\n3
+
+interface Vec2 {
+  x: f64;
+  y: f64;
+}
+
+interface Particle {
+  pos: Vec2;
+  vel: Vec2;
+}
+
+class Cursor {
+  pos: Vec2;
+  constructor() {
+    this.pos = { x: 4, y: 6 };
+  }
+  home(): void {
+    this.pos.x = 0;
+  }
+}
+
+function main(): void {
+  // Compound assignment and updates on array elements
+  const arr: i32[] = [1, 2, 3];
+  arr[0] += 2;
+  console.log(arr[0]);
+  arr[1]++;
+  ++arr[1];
+  arr[1]++;
+  console.log(arr[1]);
+
+  // Nested fields
+  const cursor = new Cursor();
+  cursor.home();
+  console.log(cursor.pos.x);
+  console.log(cursor.pos.y);
+
+  // Elements of nested arrays
+  const grid: i32[][] = [[0, 0], [0, 0]];
+  const r = 1;
+  const c = 0;
+  grid[r][c] = 7;
+  console.log(grid[1][0]);
+  console.log(grid[0][0]);
+
+  const p: Particle = { pos: { x: 0, y: 0 }, vel: { x: 8, y: -2 } };
+  p.vel.x *= 0.5;
+  p.vel.y /= 2;
+  console.log(p.vel.x);
+  console.log(p.vel.y);
+
+  // Assignments are expressions yielding the stored value
+  const stored: i32 = (arr[2] = 9);
+  console.log(stored);
+  console.log(arr[2]);
+  let x: f64 = 0;
+  let y: f64 = 0;
+  x = y = 3;
+  console.log(x);
+  console.log(y);
+
+  // Postfix updates yield the old value, prefix ones the new value
+  let i: i32 = 0;
+  const first = arr[i++];
+  console.log(i);
+  console.log(first - 2);
+  const pre = ++i;
+  console.log(pre);
+  console.log(arr[1] - 2);
+  console.log(arr[0]);
+}
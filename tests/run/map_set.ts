@@ -0,0 +1,87 @@
+// Expected output: 3\n26\nno dave\ntrue\ntrue\nfalse\nfalse\nbob\n26\ncarol\n41\nalice\n5\n100\n5929\n50\nfalse\n100\n8725\n1\n149\n2\na\nb\n0\nfalse\nfirst\nfalse\nfirst\nsecond
+
+type Point = { x: i32; y: i32 };
+
+function main(): void {
+  const ages = new Map<string, i32>();
+  ages.set("alice", 30);
+  ages.set("bob", 25);
+  ages.set("carol", 41);
+  ages.set("bob", 26);
+  print(ages.size);
+
+  const bob = ages.get("bob");
+  if (bob !== null) {
+    print(bob);
+  }
+  const dave = ages.get("dave");
+  if (dave === null) {
+    print("no dave");
+  }
+
+  print(ages.has("alice"));
+  print(ages.delete("alice"));
+  print(ages.delete("alice"));
+  print(ages.has("alice"));
+
+  // Re-added keys go to the end
+  ages.set("alice", 5);
+  for (const [name, age] of ages) {
+    print(name);
+    print(age);
+  }
+
+  // Growth, and compaction of deleted entries
+  const squares: Map<i32, i32> = new Map();
+  for (let i: i32 = 0; i < 100; i++) {
+    squares.set(i, i * i);
+  }
+  print(squares.size);
+  const sq = squares.get(77);
+  if (sq !== null) {
+    print(sq);
+  }
+  for (let i: i32 = 0; i < 100; i += 2) {
+    squares.delete(i);
+  }
+  print(squares.size);
+  print(squares.has(4));
+  for (let i: i32 = 100; i < 150; i++) {
+    squares.set(i, i * i);
+  }
+  print(squares.size);
+  let total: i32 = 0;
+  for (const k of squares.keys()) {
+    total += k;
+  }
+  print(total);
+  const ks = squares.keys();
+  print(ks[0]);
+  print(ks[ks.length - 1]);
+
+  const seen = new Set<string>();
+  seen.add("a");
+  seen.add("b");
+  seen.add("a");
+  print(seen.size);
+  for (const s of seen) {
+    print(s);
+  }
+  seen.clear();
+  print(seen.size);
+  print(seen.has("a"));
+
+  // Struct keys compare by value
+  const grid = new Map<Point, string>();
+  grid.set({ x: 1, y: 2 }, "first");
+  grid.set({ x: 2, y: 1 }, "second");
+  const p: Point = { x: 1, y: 2 };
+  const hit = grid.get(p);
+  if (hit !== null) {
+    print(hit);
+  }
+  print(grid.has({ x: 3, y: 3 }));
+  for (const v of grid.values()) {
+    print(v);
+  }
+}
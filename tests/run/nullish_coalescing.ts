@@ -0,0 +1,78 @@
+// Expected output: 0\n7\nfalse\n0\nanon\nAnn\n1\nfallback\n5\n9\ntrue\nfalse\n3\n1\n4
+
+function find(n: i32): i32 | null {
+  if (n < 0) {
+    return null;
+  }
+  return n;
+}
+
+function lookup(key: string): string | null {
+  if (key === "ann") {
+    return "Ann";
+  }
+  return null;
+}
+
+function fallback(): i32 {
+  print("fallback");
+  return 5;
+}
+
+class Config {
+  retries: i32 | null;
+
+  constructor(retries: i32 | null) {
+    this.retries = retries;
+  }
+}
+
+function main(): void {
+  // Only null is replaced: 0, false and "" are kept
+  const zero: i32 = find(0) ?? 7;
+  print(zero);
+  const seven: i32 = find(-1) ?? 7;
+  print(seven);
+  const flag: boolean | null = false;
+  const f: boolean = flag ?? true;
+  print(f);
+  const empty: string | null = "";
+  const e: string = empty ?? "default";
+  print(e.length);
+
+  const name: string = lookup("bob") ?? "anon";
+  print(name);
+  const first: string = lookup("bob") ?? lookup("ann") ?? "nobody";
+  print(first);
+
+  // The fallback only runs when the value is null
+  const a: i32 = find(1) ?? fallback();
+  print(a);
+  const b: i32 = find(-1) ?? fallback();
+  print(b);
+
+  // Logical assignment
+  let x: i32 | null = null;
+  x ??= 9;
+  x ??= 10;
+  if (x !== null) {
+    print(x);
+  }
+  let ready: boolean = false;
+  ready ||= true;
+  print(ready);
+  ready &&= false;
+  print(ready);
+
+  const config = new Config(find(-1));
+  config.retries ??= 3;
+  const retries: i32 = config.retries ?? 0;
+  print(retries);
+
+  // The target's array and index are evaluated once
+  const configs: Config[] = [new Config(null), new Config(2)];
+  let i: i32 = 0;
+  configs[i++].retries ??= 4;
+  print(i);
+  print(configs[0].retries ?? 0);
+}
@@ -0,0 +1,97 @@
+// Expected output: Ann\ntrue\nOslo\ntrue\ntrue\n3\nhi Ann\ntrue\nlog Ann\n8\ntrue\nAnn\ncaught
+
+interface Address {
+  city: string;
+}
+
+class User {
+  name: string;
+  address: Address | null;
+
+  constructor(name: string, address: Address | null) {
+    this.name = name;
+    this.address = address;
+  }
+
+  greet(): string {
+    return "hi " + this.name;
+  }
+
+  log(): void {
+    print("log " + this.name);
+  }
+}
+
+function address(city: string): Address | null {
+  if (city === "") {
+    return null;
+  }
+  const a: Address = { city: city };
+  return a;
+}
+
+function findUser(id: i32): User | null {
+  if (id === 1) {
+    return new User("Ann", address("Oslo"));
+  }
+  if (id === 2) {
+    return new User("Bob", address(""));
+  }
+  return null;
+}
+
+function scores(): i32[] | null {
+  const s: i32[] = [7, 8];
+  return s;
+}
+
+function main(): void {
+  const ann = findUser(1);
+  const bob = findUser(2);
+  const nobody = findUser(0);
+
+  // Fields
+  const name = ann?.name;
+  if (name !== null) {
+    print(name);
+  }
+  print(nobody?.name === null);
+
+  // Chains short-circuit at the first null
+  const city = ann?.address?.city;
+  if (city !== null) {
+    print(city);
+  }
+  print(bob?.address?.city === null);
+  print(nobody?.address?.city === null);
+
+  const len = ann?.name.length;
+  if (len !== null) {
+    print(len);
+  }
+
+  // Methods
+  const greeting = ann?.greet();
+  if (greeting !== null) {
+    print(greeting);
+  }
+  print(nobody?.greet() === null);
+  ann?.log();
+  nobody?.log();
+
+  // Indexing
+  const second = scores()?.[1];
+  if (second !== null) {
+    print(second);
+  }
+  const none: i32[] | null = null;
+  print(none?.[0] === null);
+
+  // Non-null assertions
+  print(ann!.name);
+  try {
+    print(nobody!.name);
+  } catch (e) {
+    print("caught");
+  }
+}
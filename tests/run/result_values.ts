@@ -0,0 +1,65 @@
+// Expected output: 5\ndivision by zero\nnegative\nok\n3\ntoo big\n-1\n42\nreset\n7
+
+function divide(a: i32, b: i32): Result<i32, string> {
+  if (b == 0) {
+    return Err("division by zero");
+  }
+  return Ok(a / b);
+}
+
+function parsePositive(n: i32): Result<i32, string> {
+  if (n < 0) {
+    return { ok: false, error: "negative" };
+  }
+  return { ok: true, value: n };
+}
+
+// Thrown strings come back as Err, plain returns as Ok
+function checked(n: i32): Result<i32, string> {
+  if (n > 100) {
+    throw "too big";
+  }
+  return n * 2;
+}
+
+function valueOr(r: Result<i32, string>, fallback: i32): i32 {
+  if (!r.ok) {
+    print(r.error);
+    return fallback;
+  }
+  return r.value;
+}
+
+function main(): void {
+  const a = divide(10, 2);
+  if (a.ok) {
+    print(a.value);
+  } else {
+    print(a.error);
+  }
+
+  const b = divide(1, 0);
+  if (!b.ok) {
+    print(b.error);
+  }
+
+  const c = parsePositive(-5);
+  print(c.ok ? "ok" : c.error);
+  const d = parsePositive(3);
+  if (d.ok) {
+    print("ok");
+    print(d.value);
+  }
+
+  print(valueOr(checked(500), -1));
+  print(valueOr(checked(21), -1));
+
+  let r: Result<i32, string> = Ok(1);
+  r = Err("reset");
+  if (r.ok) {
+    print(r.value);
+  } else {
+    print(r.error);
+  }
+  print(valueOr(Ok(7), 0));
+}
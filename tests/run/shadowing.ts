@@ -0,0 +1,59 @@
+// Expected output: 0\n2\n4\n1.5\ninner\n7\n11\nlocal\n3\n2.5\noops\n42\n100
+
+const limit: i32 = 3;
+
+function scaled(limit: f64): f64 {
+  // The parameter shadows the global
+  return limit / 2;
+}
+
+function main(): void {
+  // A loop-body `let` shadows an outer variable of another type
+  let x: f64 = 1.5;
+  for (let i: i32 = 0; i < limit; i++) {
+    let x: i32 = i * 2;
+    print(x);
+  }
+  print(x);
+
+  // Same-named locals in sibling blocks
+  if (limit > 0) {
+    const label: string = "inner";
+    print(label);
+  }
+  {
+    const label: i32 = 7;
+    print(label);
+  }
+
+  // Same-named locals in different closures
+  const counter = (): i32 => {
+    let n: i32 = 10;
+    return n + 1;
+  };
+  const name = (): string => {
+    let n: string = "local";
+    return n;
+  };
+  print(counter());
+  print(name());
+
+  // A closure parameter shadowing a captured variable
+  const n: i32 = 100;
+  const identity = (n: i32): i32 => n;
+  print(identity(limit));
+  print(scaled(5));
+
+  // Catch bindings in sibling `try` statements
+  try {
+    throw "oops";
+  } catch (e) {
+    print(e);
+  }
+  try {
+    throw 42;
+  } catch (e) {
+    print(e);
+  }
+  print(n);
+}
@@ -0,0 +1,36 @@
+// Expected output: 6\n1\n4\n6\n0\n6\n15\n3\n3\n7
+
+function sum(...nums: i32[]): i32 {
+  let total: i32 = 0;
+  for (const n of nums) {
+    total = total + n;
+  }
+  return total;
+}
+
+function scaled(factor: i32, ...nums: i32[]): i32 {
+  return factor * sum(...nums);
+}
+
+function add(a: i32, b: i32): i32 {
+  return a + b;
+}
+
+function main(): void {
+  const a: i32[] = [1, 2];
+  const b: i32[] = [5, 6];
+  const all = [...a, 3, 4, ...b];
+  print(all.length);
+  print(all[0]);
+  print(all[3]);
+  print(all[5]);
+
+  print(sum());
+  print(sum(1, 2, 3));
+  print(sum(...all, -6));
+  print(scaled(1, ...a));
+
+  const pair: [i32, i32] = [1, 2];
+  print(add(...pair));
+  print(add(...[3, 4]));
+}
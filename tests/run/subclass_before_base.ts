@@ -0,0 +1,59 @@
+// Expected output: 4\n5\n4\n10\n4\n9
+
+// Declared before the classes it extends
+class Square extends Rect {
+  label: i32;
+
+  constructor(side: i32) {
+    super(side, side);
+    this.label = 9;
+  }
+
+  area(): i32 {
+    return super.area() + this.label;
+  }
+}
+
+class Rect extends Shape {
+  w: i32;
+  h: i32;
+
+  constructor(w: i32, h: i32) {
+    super(4);
+    this.w = w;
+    this.h = h;
+  }
+
+  area(): i32 {
+    return this.w * this.h;
+  }
+
+  perimeter(): i32 {
+    return 2 * (this.w + this.h);
+  }
+}
+
+class Shape {
+  sides: i32;
+
+  constructor(sides: i32) {
+    this.sides = sides;
+  }
+
+  id(): i32 {
+    return this.sides;
+  }
+}
+
+function main(): void {
+  const r = new Rect(2, 5);
+  print(r.sides);
+  print(r.h);
+
+  const s = new Square(1);
+  // Inherited through two levels
+  print(s.perimeter());
+  print(s.area());
+  print(s.id());
+  print(s.label);
+}
@@ -0,0 +1,98 @@
+// Expected output: 42\nnegative age\nloadAge: negative age\nmain: negative age\nvalid ann\nempty name\n7\nfailed: insufficient funds\n7\nbad negative age\n0\n1\nnegative balance
+
+function checkAge(age: i32): i32 {
+  if (age < 0) {
+    throw new Error("negative age");
+  }
+  return age;
+}
+
+// Not caught here: the error goes back to the caller
+function nextAge(age: i32): i32 {
+  return checkAge(age) + 1;
+}
+
+function loadAge(age: i32): i32 {
+  try {
+    return checkAge(age);
+  } catch (e) {
+    print("loadAge: " + e.message);
+    throw e;
+  }
+}
+
+function validate(name: string): void {
+  if (name === "") {
+    throw "empty name";
+  }
+  print("valid " + name);
+}
+
+class Account {
+  balance: i32;
+
+  constructor(balance: i32) {
+    if (balance < 0) {
+      throw new Error("negative balance");
+    }
+    this.balance = balance;
+  }
+
+  withdraw(amount: i32): i32 {
+    if (amount > this.balance) {
+      throw new Error("insufficient funds");
+    }
+    this.balance = this.balance - amount;
+    return this.balance;
+  }
+}
+
+function main(): void {
+  print(nextAge(41));
+  try {
+    print(nextAge(-1));
+    print("not reached");
+  } catch (e) {
+    print(e.message);
+  }
+
+  try {
+    loadAge(-2);
+  } catch (e) {
+    print("main: " + e.message);
+  }
+
+  try {
+    validate("ann");
+    validate("");
+    print("not reached");
+  } catch (e) {
+    print(e);
+  }
+
+  // Methods
+  const account = new Account(10);
+  try {
+    print(account.withdraw(3));
+    print(account.withdraw(20));
+  } catch (e) {
+    print("failed: " + e.message);
+  }
+  print(account.balance);
+
+  for (let i: i32 = -1; i < 2; i++) {
+    try {
+      print(checkAge(i));
+    } catch (e) {
+      print("bad " + e.message);
+    }
+  }
+
+  // Constructors
+  try {
+    const broken = new Account(-5);
+    print(broken.balance);
+  } catch (e) {
+    print(e.message);
+  }
+}
@@ -0,0 +1,52 @@
+// Expected output: 42\nnegative age\nnegative amount\n5
+
+function checkAge(age: i32): i32 {
+  if (age < 0) {
+    throw new Error("negative age");
+  }
+  return age;
+}
+
+// Rethrown: the error goes back to the caller
+function nextAge(age: i32): i32 {
+  try {
+    return checkAge(age) + 1;
+  } catch (e) {
+    throw e;
+  }
+}
+
+class Loan {
+  amount: i32;
+
+  constructor(amount: i32) {
+    if (amount < 0) {
+      throw new Error("negative amount");
+    }
+    this.amount = amount;
+  }
+}
+
+function main(): void {
+  try {
+    print(nextAge(41));
+    print(nextAge(-1));
+    print("not reached");
+  } catch (e) {
+    print(e.message);
+  }
+
+  // Constructors
+  try {
+    const broken = new Loan(-5);
+    print(broken.amount);
+  } catch (e) {
+    print(e.message);
+  }
+  try {
+    const loan = new Loan(5);
+    print(loan.amount);
+  } catch (e) {
+    print(e.message);
+  }
+}
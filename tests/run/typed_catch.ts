@@ -0,0 +1,101 @@
+// Expected output: boom\n42\nbad input\nParseError at 3: bad token\nerror: disk full\ntimeout 7\nother\ninner failed\nrethrown: inner failed\ntimeout 5\nnot a timeout\nTypeError: non-null assertion on a null value
+
+class ParseError extends Error {
+  line: i32;
+
+  constructor(message: string, line: i32) {
+    super(message);
+    this.line = line;
+  }
+}
+
+class Timeout {
+  ms: i32;
+
+  constructor(ms: i32) {
+    this.ms = ms;
+  }
+}
+
+function main(): void {
+  // The catch parameter has the type of what the try block throws
+  try {
+    throw "boom";
+  } catch (e) {
+    print(e);
+  }
+  try {
+    throw 42;
+  } catch (e) {
+    print(e);
+  }
+  try {
+    throw new Error("bad input");
+  } catch (e) {
+    print(e.message);
+  }
+
+  // Several thrown types: narrow with instanceof
+  for (let i: i32 = 0; i < 4; i++) {
+    try {
+      if (i === 0) {
+        throw new ParseError("bad token", 3);
+      }
+      if (i === 1) {
+        throw new Error("disk full");
+      }
+      if (i === 2) {
+        throw new Timeout(7);
+      }
+      throw "other";
+    } catch (e) {
+      if (e instanceof ParseError) {
+        print(`ParseError at ${e.line}: ${e.message}`);
+      } else if (e instanceof Error) {
+        print(`error: ${e.message}`);
+      } else if (e instanceof Timeout) {
+        print(`timeout ${e.ms}`);
+      } else {
+        print("other");
+      }
+    }
+  }
+
+  // Rethrowing keeps the original type
+  try {
+    try {
+      throw new Error("inner failed");
+    } catch (e) {
+      print(e.message);
+      throw e;
+    }
+  } catch (e) {
+    print(`rethrown: ${e.message}`);
+  }
+  for (let i: i32 = 0; i < 2; i++) {
+    try {
+      try {
+        if (i === 0) {
+          throw new Timeout(5);
+        }
+        throw "late";
+      } catch (e) {
+        throw e;
+      }
+    } catch (e) {
+      if (e instanceof Timeout) {
+        print(`timeout ${e.ms}`);
+      } else {
+        print("not a timeout");
+      }
+    }
+  }
+
+  // A failed non-null assertion throws a TypeError string
+  const missing: string | null = null;
+  try {
+    print(missing!);
+  } catch (e) {
+    print(e);
+  }
+}